@@ -1,6 +1,7 @@
 use actix_web::web::{Json, ServiceConfig};
 use actix_web::HttpResponse;
 use actix_web::{delete, get, post, put, web, Responder};
+use std::time::SystemTime;
 use todo_shared::{CreateTodoItemRequest, TodoItem, UpdateTodoItemRequest};
 use uuid::Uuid;
 
@@ -33,7 +34,11 @@ async fn delete_todo(_id: web::Path<Uuid>) -> impl Responder {
 
 #[put("/todo/{id}")]
 async fn update_todo(_id: web::Path<Uuid>, todo: Json<UpdateTodoItemRequest>) -> impl Responder {
-    HttpResponse::Ok().json(TodoItem::new(&todo.new_title, &todo.new_description))
+    HttpResponse::Ok().json(TodoItem {
+        completed: todo.completed,
+        completed_at: todo.completed.then(SystemTime::now),
+        ..TodoItem::new(&todo.new_title, &todo.new_description)
+    })
 }
 
 pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
@@ -17,7 +17,7 @@ pub struct TodoItem {
     pub completed: bool,
 
     // Epoch timestamp when the todo item was completed
-    pub completed_at: SystemTime,
+    pub completed_at: Option<SystemTime>,
 
     // Epoch timestamp when the todo item was created
     pub created_at: SystemTime,
@@ -51,7 +51,7 @@ impl TodoItem {
             title: title.to_string(),
             description: description.to_string(),
             completed: false,
-            completed_at: SystemTime::now(),
+            completed_at: None,
             created_at: SystemTime::now(),
         }
     }
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE todos (
+  id TEXT PRIMARY KEY NOT NULL,
+  title TEXT NOT NULL,
+  description TEXT NOT NULL,
+  completed BOOLEAN NOT NULL DEFAULT 0,
+  completed_at TIMESTAMP,
+  created_at TIMESTAMP
+)
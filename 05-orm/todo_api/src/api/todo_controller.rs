@@ -3,9 +3,8 @@ use actix_web::HttpResponse;
 use actix_web::{delete, get, post, put, web, Error};
 use todo_shared::{CreateTodoItemRequest, TodoItem, UpdateTodoItemRequest};
 
-use crate::data::repository::Repository;
-use crate::data::todo_repository::TodoEntityRepository;
-use crate::entities::todo_entity::TodoEntity;
+use crate::data::repository::RepositoryError;
+use crate::data::todo_repository::{TodoEntityRepository, TodoRepository};
 use actix_web::web::Data;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -13,7 +12,7 @@ use uuid::Uuid;
 use log::{error, warn};
 
 #[get("/todo")]
-async fn get_todos(repository: Data<dyn Repository<TodoEntity>>) -> Result<HttpResponse, Error> {
+async fn get_todos(repository: Data<TodoRepository>) -> Result<HttpResponse, Error> {
     // Get entities from the datastore
     let entities = web::block(move || repository.get_all())
         .await
@@ -28,8 +27,8 @@ async fn get_todos(repository: Data<dyn Repository<TodoEntity>>) -> Result<HttpR
 
 #[get("/todo/{id}")]
 async fn get_todo_by_id(
-    id: web::Path<Uuid>, // The identifier of the item to retrieve
-    repository: Data<dyn Repository<TodoEntity>>, // The todo item repository, injected from app_data
+    id: web::Path<Uuid>,              // The identifier of the item to retrieve
+    repository: Data<TodoRepository>, // The todo item repository, injected from app_data
 ) -> Result<HttpResponse, Error> {
     let uuid = id.into_inner();
 
@@ -56,7 +55,7 @@ async fn get_todo_by_id(
 #[post("/todo")]
 async fn create_todo(
     todo: Json<CreateTodoItemRequest>,
-    repository: Data<dyn Repository<TodoEntity>>, // The todo item repository, injected from app_data
+    repository: Data<TodoRepository>, // The todo item repository, injected from app_data
 ) -> Result<HttpResponse, Error> {
     let request_body = todo.into_inner();
     let result = web::block(move || repository.insert(request_body.into()))
@@ -77,7 +76,7 @@ async fn create_todo(
 #[delete("/todo/{id}")]
 async fn delete_todo(
     id: web::Path<Uuid>,
-    repository: Data<dyn Repository<TodoEntity>>, // The todo item repository, injected from app_data
+    repository: Data<TodoRepository>, // The todo item repository, injected from app_data
 ) -> Result<HttpResponse, Error> {
     let result = web::block(move || repository.delete(id.into_inner()))
         .await
@@ -92,16 +91,26 @@ async fn delete_todo(
 async fn update_todo(
     id: web::Path<Uuid>,
     todo: Json<UpdateTodoItemRequest>,
-    repository: Data<dyn Repository<TodoEntity>>, // The todo item repository, injected from app_data
+    repository: Data<TodoRepository>, // The todo item repository, injected from app_data
 ) -> Result<HttpResponse, Error> {
     let request_body = todo.into_inner();
     let uuid = id.into_inner();
-    let entity = web::block(move || repository.update(uuid, request_body.into()))
-        .await?
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let result = web::block(move || repository.update(uuid, request_body.into())).await?;
 
-    let result: TodoItem = entity.into();
-    Ok(HttpResponse::Ok().json(result))
+    match result {
+        Ok(entity) => {
+            let result: TodoItem = entity.into();
+            Ok(HttpResponse::Ok().json(result))
+        }
+        Err(RepositoryError::NotFound) => {
+            warn!("Todo item with id {} was not found in the data store", uuid);
+            Ok(HttpResponse::NotFound().finish())
+        }
+        Err(error) => {
+            error!("Unable to update todo item with id {}: {}", uuid, error);
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
 }
 
 pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
@@ -112,7 +121,7 @@ pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
         // Todo entity repository is unsized, so we need to wrap this in a Atomic Reference Counter
         // "For types that are unsized, most commonly dyn T, Data can wrap these types by first constructing an Arc<dyn T> and using the From implementation to convert it."
         // https://docs.rs/actix-web/latest/actix_web/web/struct.Data.html
-        let repository_arc: Arc<dyn Repository<TodoEntity>> = Arc::new(repository);
+        let repository_arc: Arc<TodoRepository> = Arc::new(repository);
 
         config
             // Register our repository for data injection;
@@ -138,7 +147,7 @@ mod tests {
     use std::sync::Mutex;
 
     use crate::data::repository::Repository;
-    use crate::entities::todo_entity::TodoEntity;
+    use crate::entities::todo_entity::{TodoChanges, TodoEntity};
 
     use super::*;
 
@@ -149,6 +158,9 @@ mod tests {
 
     // Implement our repository pattern for the mock.
     impl Repository<TodoEntity> for TodoEntityRepositoryMock {
+        type Key = Uuid;
+        type Changes = TodoChanges;
+
         fn get_all(&self) -> Vec<TodoEntity> {
             self.db
                 .lock()
@@ -167,9 +179,18 @@ mod tests {
             Ok(entity)
         }
 
-        fn update(&self, todo_id: Uuid, entity: TodoEntity) -> Result<TodoEntity, String> {
-            *self.db.lock().unwrap().get_mut(&todo_id).unwrap() = entity.clone();
-            Ok(entity)
+        fn update(
+            &self,
+            todo_id: Uuid,
+            changes: TodoChanges,
+        ) -> Result<TodoEntity, RepositoryError> {
+            let mut db = self.db.lock().unwrap();
+            let existing = db.get_mut(&todo_id).ok_or(RepositoryError::NotFound)?;
+            existing.title = changes.title;
+            existing.description = changes.description;
+            existing.completed = changes.completed;
+            existing.completed_at = changes.completed_at;
+            Ok(existing.clone())
         }
 
         fn delete(&self, todo_id: Uuid) -> Result<bool, String> {
@@ -178,7 +199,7 @@ mod tests {
         }
     }
 
-    fn get_repository_mock_with_data() -> Arc<dyn Repository<TodoEntity>> {
+    fn get_repository_mock_with_data() -> Arc<TodoRepository> {
         // Create our repository
         let repository = TodoEntityRepositoryMock {
             db: Arc::new(Mutex::new(HashMap::new())),
@@ -205,7 +226,7 @@ mod tests {
             })
             .unwrap();
 
-        let repository_arc: Arc<dyn Repository<TodoEntity>> = Arc::new(repository);
+        let repository_arc: Arc<TodoRepository> = Arc::new(repository);
         repository_arc
     }
 
@@ -295,6 +316,59 @@ mod tests {
         assert_eq!(resp.completed, true);
     }
 
+    #[actix_web::test]
+    async fn test_update_todo_keeps_created_at() {
+        let repository = get_repository_mock_with_data();
+        let todo_id = Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap();
+        let created_at = repository.get_by_id(todo_id).unwrap().created_at;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .service(update_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874")
+            .set_json(&UpdateTodoItemRequest {
+                new_title: "Test update".to_string(),
+                new_description: "We should keep the creation time".to_string(),
+                completed: false,
+            })
+            .to_request();
+
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.id, todo_id);
+        assert_eq!(resp.created_at, created_at);
+        assert_eq!(
+            repository.get_by_id(todo_id).unwrap().created_at,
+            created_at
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_update_todo_not_found() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(update_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/todo/{}", Uuid::new_v4()))
+            .set_json(&UpdateTodoItemRequest {
+                new_title: "Test update".to_string(),
+                new_description: "We should not find this todo item".to_string(),
+                completed: true,
+            })
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
     #[actix_web::test]
     async fn test_delete_todo() {
         let repository = get_repository_mock_with_data();
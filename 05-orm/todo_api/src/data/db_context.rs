@@ -1,17 +1,28 @@
-use diesel::pg::PgConnection;
 use diesel::r2d2::ConnectionManager;
 use dotenv::dotenv;
 use r2d2::Pool;
 use std::env;
 
-// The Postgres-specific connection pool managing all database connections.
-pub type PostgresPool = Pool<ConnectionManager<PgConnection>>;
+#[cfg(all(feature = "postgres", feature = "sqlite"))]
+compile_error!("the `postgres` and `sqlite` features are mutually exclusive");
 
-pub fn get_pool() -> PostgresPool {
+#[cfg(not(any(feature = "postgres", feature = "sqlite")))]
+compile_error!("enable either the `postgres` or the `sqlite` feature");
+
+// The connection of the database backend selected by the active feature.
+#[cfg(feature = "postgres")]
+pub type DbConnection = diesel::pg::PgConnection;
+#[cfg(feature = "sqlite")]
+pub type DbConnection = diesel::sqlite::SqliteConnection;
+
+// The connection pool managing all database connections.
+pub type DbPool = Pool<ConnectionManager<DbConnection>>;
+
+pub fn get_pool() -> DbPool {
     // it from the environment within this function
     dotenv().ok();
     let url = env::var("DATABASE_URL").expect("no DB URL");
-    let migr = ConnectionManager::<PgConnection>::new(url);
+    let migr = ConnectionManager::<DbConnection>::new(url);
     r2d2::Pool::builder()
         .build(migr)
         .expect("could not build connection pool")
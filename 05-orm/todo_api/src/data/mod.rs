@@ -1,10 +1,16 @@
 pub mod db_context;
 pub mod repository;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_types;
 pub mod todo_repository;
 
 use crate::Error;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+#[cfg(feature = "postgres")]
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+// SQLite has its own syntax and column types, its migrations are kept apart
+#[cfg(feature = "sqlite")]
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations_sqlite");
 
 pub fn run_migrations() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
     // This will run the necessary migrations.
@@ -1,4 +1,45 @@
+use std::fmt;
+
+/// Errors returned by a `Repository`.
+#[derive(Debug)]
+pub enum RepositoryError {
+    /// No instance exists with the given identifier
+    NotFound,
+
+    /// The data store failed to execute the query
+    Database(diesel::result::Error),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepositoryError::NotFound => write!(f, "Record not found"),
+            RepositoryError::Database(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+// Convert from the Diesel error, keeping a missing record apart from other failures
+impl From<diesel::result::Error> for RepositoryError {
+    fn from(error: diesel::result::Error) -> Self {
+        match error {
+            diesel::result::Error::NotFound => RepositoryError::NotFound,
+            error => RepositoryError::Database(error),
+        }
+    }
+}
+
+/// The data store operations on instances of `<T>`, identified by a `Key`.
+///
+/// Implementations choose the type of the key, e.g. `Uuid` for the todo items, so the trait is
+/// reused for entities that are keyed differently.
 pub trait Repository<T>: Send + Sync {
+    /// The identifier of an instance of `<T>`
+    type Key;
+
+    /// The changes an update applies to an instance of `<T>`
+    type Changes;
+
     /// Returns all availble instances of `<T>`
     fn get_all(&self) -> Vec<T>;
 
@@ -7,7 +48,7 @@ pub trait Repository<T>: Send + Sync {
     ///  # Arguments
     ///  
     ///  * `id` - The identifier of the item to find in the data store.
-    fn get_by_id(&self, id: uuid::Uuid) -> Option<T>;
+    fn get_by_id(&self, id: Self::Key) -> Option<T>;
 
     /// Inserts a single instance of `<T>` in the data store
     ///
@@ -21,13 +62,13 @@ pub trait Repository<T>: Send + Sync {
     ///  # Arguments
     ///  
     ///  * `id` - The unique identifier of the entity to update
-    ///  * `entity` - An updated version of the entity with the latest values.
-    fn update(&self, id: uuid::Uuid, entity: T) -> Result<T, String>;
+    ///  * `changes` - The latest values of the changed fields.
+    fn update(&self, id: Self::Key, changes: Self::Changes) -> Result<T, RepositoryError>;
 
     /// Deletes a single instance of `<T>` from the data store with the given `id`
     ///
     ///  # Arguments
     ///  
     ///  * `id` - The identifier of the item to delete from the data store.
-    fn delete(&self, id: uuid::Uuid) -> Result<bool, String>;
+    fn delete(&self, id: Self::Key) -> Result<bool, String>;
 }
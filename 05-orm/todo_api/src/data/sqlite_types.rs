@@ -0,0 +1,114 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::deserialize::{self, FromSql};
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::{Nullable, Text, Timestamp};
+use diesel::sqlite::{Sqlite, SqliteValue};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+// Diesel only maps `Uuid` and `SystemTime` to the column types of Postgres. The types below wrap
+// them for SQLite, so the entities keep the same fields for both backends.
+
+/// A `Uuid` stored in a `Text` column, in its hyphenated form.
+#[derive(Debug, Clone, Copy, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+pub struct SqliteUuid(pub Uuid);
+
+impl From<Uuid> for SqliteUuid {
+    fn from(uuid: Uuid) -> Self {
+        SqliteUuid(uuid)
+    }
+}
+
+impl From<SqliteUuid> for Uuid {
+    fn from(uuid: SqliteUuid) -> Self {
+        uuid.0
+    }
+}
+
+impl ToSql<Text, Sqlite> for SqliteUuid {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(self.0.to_string());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for SqliteUuid {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let text = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        Ok(SqliteUuid(text.parse()?))
+    }
+}
+
+/// A `SystemTime` stored in a `Timestamp` column, as UTC.
+#[derive(Debug, Clone, Copy, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Timestamp)]
+pub struct SqliteTimestamp(pub SystemTime);
+
+impl From<SystemTime> for SqliteTimestamp {
+    fn from(time: SystemTime) -> Self {
+        SqliteTimestamp(time)
+    }
+}
+
+impl From<SqliteTimestamp> for SystemTime {
+    fn from(timestamp: SqliteTimestamp) -> Self {
+        timestamp.0
+    }
+}
+
+impl ToSql<Timestamp, Sqlite> for SqliteTimestamp {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        // The same format Diesel writes a `NaiveDateTime` in, so SQLite's date functions accept it
+        let timestamp = DateTime::<Utc>::from(self.0).naive_utc();
+        out.set_value(timestamp.format("%F %T%.f").to_string());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Timestamp, Sqlite> for SqliteTimestamp {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let timestamp = <NaiveDateTime as FromSql<Timestamp, Sqlite>>::from_sql(value)?;
+        Ok(SqliteTimestamp(timestamp.and_utc().into()))
+    }
+}
+
+/// An optional `SystemTime` stored in a nullable `Timestamp` column, as UTC.
+#[derive(Debug, Clone, Copy, FromSqlRow)]
+pub struct SqliteNullableTimestamp(pub Option<SystemTime>);
+
+impl From<Option<SystemTime>> for SqliteNullableTimestamp {
+    fn from(time: Option<SystemTime>) -> Self {
+        SqliteNullableTimestamp(time)
+    }
+}
+
+impl From<SqliteNullableTimestamp> for Option<SystemTime> {
+    fn from(timestamp: SqliteNullableTimestamp) -> Self {
+        timestamp.0
+    }
+}
+
+impl diesel::expression::AsExpression<Nullable<Timestamp>> for SqliteNullableTimestamp {
+    type Expression = diesel::dsl::AsExprOf<Option<SqliteTimestamp>, Nullable<Timestamp>>;
+
+    fn as_expression(self) -> Self::Expression {
+        diesel::expression::AsExpression::<Nullable<Timestamp>>::as_expression(
+            self.0.map(SqliteTimestamp),
+        )
+    }
+}
+
+impl FromSql<Nullable<Timestamp>, Sqlite> for SqliteNullableTimestamp {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let timestamp = <SqliteTimestamp as FromSql<Timestamp, Sqlite>>::from_sql(value)?;
+        Ok(SqliteNullableTimestamp(Some(timestamp.0)))
+    }
+
+    fn from_nullable_sql(value: Option<SqliteValue<'_, '_, '_>>) -> deserialize::Result<Self> {
+        match value {
+            Some(value) => Self::from_sql(value),
+            None => Ok(SqliteNullableTimestamp(None)),
+        }
+    }
+}
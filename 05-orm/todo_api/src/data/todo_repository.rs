@@ -1,14 +1,23 @@
 use uuid::Uuid;
 
 use crate::data::db_context;
-use crate::data::repository::Repository;
+use crate::data::repository::{Repository, RepositoryError};
+#[cfg(feature = "sqlite")]
+use crate::data::sqlite_types::SqliteUuid as IdValue;
 use crate::diesel::prelude::*;
-use crate::entities::todo_entity::TodoEntity;
+use crate::entities::todo_entity::{TodoChanges, TodoEntity};
 use crate::schema::todos;
 use crate::schema::todos::dsl::*;
 
+// The value bound to the `id` column, SQLite stores it as text
+#[cfg(feature = "postgres")]
+type IdValue = Uuid;
+
+/// The repository of the todo items, as the handlers receive it.
+pub type TodoRepository = dyn Repository<TodoEntity, Key = Uuid, Changes = TodoChanges>;
+
 pub struct TodoEntityRepository {
-    db_context: db_context::PostgresPool,
+    db_context: db_context::DbPool,
 }
 
 impl TodoEntityRepository {
@@ -20,6 +29,9 @@ impl TodoEntityRepository {
 }
 
 impl Repository<TodoEntity> for TodoEntityRepository {
+    type Key = Uuid;
+    type Changes = TodoChanges;
+
     fn get_all(&self) -> Vec<TodoEntity> {
         let mut connection = self.db_context.get().unwrap();
         todos
@@ -29,7 +41,7 @@ impl Repository<TodoEntity> for TodoEntityRepository {
 
     fn get_by_id(&self, todo_id: Uuid) -> Option<TodoEntity> {
         let mut connection = self.db_context.get().unwrap();
-        let item = todos.find(todo_id).first(&mut connection);
+        let item = todos.find(IdValue::from(todo_id)).first(&mut connection);
         if item.is_ok() {
             Some(item.unwrap())
         } else {
@@ -46,24 +58,18 @@ impl Repository<TodoEntity> for TodoEntityRepository {
         Ok(result)
     }
 
-    fn update(&self, todo_id: Uuid, entity: TodoEntity) -> Result<TodoEntity, String> {
+    fn update(&self, todo_id: Uuid, changes: TodoChanges) -> Result<TodoEntity, RepositoryError> {
         let mut connection = self.db_context.get().unwrap();
-        let todo_item = diesel::update(todos.find(todo_id))
-            .set((
-                completed_at.eq(entity.completed_at),
-                completed.eq(entity.completed),
-                title.eq(entity.title),
-                description.eq(entity.description),
-            ))
-            .get_result::<TodoEntity>(&mut connection)
-            .expect("Unable to update todo entity");
+        let todo_item = diesel::update(todos.find(IdValue::from(todo_id)))
+            .set(changes)
+            .get_result::<TodoEntity>(&mut connection)?;
 
         Ok(todo_item)
     }
 
     fn delete(&self, todo_id: Uuid) -> Result<bool, String> {
         let mut connection = self.db_context.get().unwrap();
-        let num_deleted = diesel::delete(todos.find(todo_id))
+        let num_deleted = diesel::delete(todos.find(IdValue::from(todo_id)))
             .execute(&mut connection)
             .expect("Error deleting todo item with id {}");
         Ok(num_deleted > 0)
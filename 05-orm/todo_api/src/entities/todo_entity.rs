@@ -1,4 +1,9 @@
+#[cfg(feature = "sqlite")]
+use crate::data::sqlite_types::{SqliteNullableTimestamp, SqliteTimestamp, SqliteUuid};
 use crate::schema::todos;
+// The changeset of a `serialize_as` field calls `eq` as a method
+#[cfg(feature = "sqlite")]
+use diesel::ExpressionMethods;
 use std::time::SystemTime;
 use todo_shared::{CreateTodoItemRequest, TodoItem, UpdateTodoItemRequest};
 use uuid::Uuid;
@@ -8,6 +13,10 @@ use uuid::Uuid;
 #[diesel(primary_key(id))]
 pub struct TodoEntity {
     /// The unique identifier of the todo item
+    #[cfg_attr(
+        feature = "sqlite",
+        diesel(serialize_as = SqliteUuid, deserialize_as = SqliteUuid)
+    )]
     pub id: Uuid,
 
     /// The title of the todo item
@@ -20,9 +29,20 @@ pub struct TodoEntity {
     pub completed: bool,
 
     /// Timestamp when the todo item was completed
+    #[cfg_attr(
+        feature = "sqlite",
+        diesel(
+            serialize_as = SqliteNullableTimestamp,
+            deserialize_as = SqliteNullableTimestamp
+        )
+    )]
     pub completed_at: Option<SystemTime>,
 
     /// Timestamp when the todo item was created
+    #[cfg_attr(
+        feature = "sqlite",
+        diesel(serialize_as = SqliteTimestamp, deserialize_as = SqliteTimestamp)
+    )]
     pub created_at: SystemTime,
 }
 
@@ -54,18 +74,38 @@ impl From<CreateTodoItemRequest> for TodoEntity {
     }
 }
 
-// Convert from UpdateTodoItemRequest to TodoEntity
-impl From<UpdateTodoItemRequest> for TodoEntity {
+/// The columns an update of a todo item changes.
+///
+/// The id and creation time are left out, so an update can never change the identity of a todo
+/// item.
+#[derive(AsChangeset)]
+#[diesel(table_name = todos)]
+#[diesel(treat_none_as_null = true)]
+pub struct TodoChanges {
+    /// The new title of the todo item
+    pub title: String,
+
+    /// The new description of the todo item
+    pub description: String,
+
+    /// Whether the todo item is completed
+    pub completed: bool,
+
+    /// Timestamp when the todo item was completed, cleared when it is not completed
+    #[cfg_attr(
+        feature = "sqlite",
+        diesel(serialize_as = SqliteNullableTimestamp)
+    )]
+    pub completed_at: Option<SystemTime>,
+}
+
+// Convert from UpdateTodoItemRequest to TodoChanges
+impl From<UpdateTodoItemRequest> for TodoChanges {
     fn from(request: UpdateTodoItemRequest) -> Self {
-        TodoEntity {
-            id: Uuid::new_v4(),
+        TodoChanges {
             title: request.new_title,
             description: request.new_description,
-            created_at: SystemTime::now(),
-            completed_at: match request.completed {
-                true => Some(SystemTime::now()),
-                _ => None,
-            },
+            completed_at: request.completed.then(SystemTime::now),
             completed: request.completed,
         }
     }
@@ -1,3 +1,4 @@
+#[cfg(feature = "postgres")]
 table! {
     todos (id) {
         id -> Uuid,
@@ -8,3 +9,16 @@ table! {
         created_at -> Timestamp,
     }
 }
+
+// SQLite has no uuid type, the identifiers are stored as text
+#[cfg(feature = "sqlite")]
+table! {
+    todos (id) {
+        id -> Text,
+        title -> Text,
+        description -> Text,
+        completed -> Bool,
+        completed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
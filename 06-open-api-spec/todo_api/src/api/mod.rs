@@ -1,6 +1,8 @@
 pub mod todo_controller;
 pub use todo_controller::configure;
-use todo_shared::{CreateTodoItemRequest, TodoItem, UpdateTodoItemRequest};
+use todo_shared::{
+    CreateTodoItemRequest, ErrorResponse, TodoCount, TodoItem, UpdateTodoItemRequest,
+};
 use utoipa::OpenApi;
 
 pub fn register_open_api_spec() -> utoipa::openapi::OpenApi {
@@ -8,13 +10,14 @@ pub fn register_open_api_spec() -> utoipa::openapi::OpenApi {
     #[openapi(
         paths(
             todo_controller::get_todos,
+            todo_controller::get_todo_count,
             todo_controller::get_todo_by_id,
             todo_controller::create_todo,
             todo_controller::update_todo,
             todo_controller::delete_todo,
         ),
         components(
-            schemas(TodoItem, UpdateTodoItemRequest, CreateTodoItemRequest)
+            schemas(TodoItem, UpdateTodoItemRequest, CreateTodoItemRequest, TodoCount, ErrorResponse)
         ),
         tags(
             (name = "todo", description = "Todo management endpoints.")
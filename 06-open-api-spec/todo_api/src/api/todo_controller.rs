@@ -1,38 +1,134 @@
+use actix_web::error::InternalError;
+use actix_web::http::StatusCode;
 use actix_web::web::{Json, ServiceConfig};
 use actix_web::HttpResponse;
 use actix_web::{delete, get, post, put, web, Error};
-use todo_shared::{CreateTodoItemRequest, TodoItem, UpdateTodoItemRequest};
-
-use crate::data::repository::Repository;
-use crate::data::todo_repository::TodoEntityRepository;
-use crate::entities::todo_entity::TodoEntity;
+use todo_shared::{
+    CreateTodoItemRequest, ErrorResponse, PageQuery, TodoCount, TodoItem, UpdateTodoItemRequest,
+    DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+};
+
+use crate::data;
+use crate::data::in_memory_repository::InMemoryRepository;
+use crate::data::repository::RepositoryError;
+use crate::data::todo_repository::{TodoEntityRepository, TodoRepository};
 use actix_web::web::Data;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use log::{error, warn};
 
+/// Header containing the total number of todo items, regardless of the requested page.
+const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+
+/// Returns the limit and offset for the requested page, rejecting pages out of range.
+///
+/// A page holds `DEFAULT_PAGE_SIZE` todo items unless `per_page` is given, which has to be between
+/// 1 and `MAX_PAGE_SIZE`. Pages out of range are rejected with 400 bad request.
+fn page_bounds(page: &PageQuery) -> Result<(i64, i64), Error> {
+    let per_page = page.per_page.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&per_page) {
+        return Err(actix_web::error::ErrorBadRequest(format!(
+            "per_page must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+
+    let page = page.page.unwrap_or(1);
+    if page < 1 {
+        return Err(actix_web::error::ErrorBadRequest("page must be at least 1"));
+    }
+
+    // Pages beyond the range of the offset can't be requested from the data store
+    let offset = (page - 1)
+        .checked_mul(per_page)
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("page is out of range"))?;
+
+    Ok((per_page, offset))
+}
+
+/// Builds a response with the given status code and an `ErrorResponse` as json body.
+fn error_response(status: StatusCode, message: &str) -> HttpResponse {
+    HttpResponse::build(status).json(ErrorResponse {
+        code: status.as_u16(),
+        message: message.to_string(),
+    })
+}
+
+/// Maps a failure to a 500 response with an `ErrorResponse` as json body, logging its cause.
+fn internal_error<E>(message: &'static str) -> impl FnOnce(E) -> Error
+where
+    E: std::fmt::Debug + std::fmt::Display + 'static,
+{
+    move |cause| {
+        error!("{}: {}", message, cause);
+        let response = error_response(StatusCode::INTERNAL_SERVER_ERROR, message);
+        InternalError::from_response(cause, response).into()
+    }
+}
+
 /// Get list of todos.
 ///
-/// List todos from in-memory todo store.
+/// List a page of todos from the todo store, e.g. `GET /todo?page=2&per_page=50`.
 /// One could call the api endpoit with following curl.
 #[utoipa::path(
     responses(
-        (status = 200, description = "List current todo items", body = [TodoItem])
-    )
+        (status = 200, description = "List current todo items, with the total number of todo items in the `X-Total-Count` header", body = [TodoItem]),
+        (status = 400, description = "The requested page is out of range"),
+        (status = 500, description = "Unable to load the todo items", body = ErrorResponse)
+    ),
+    params(PageQuery)
 )]
 #[get("/todo")]
-async fn get_todos(repository: Data<dyn Repository<TodoEntity>>) -> Result<HttpResponse, Error> {
-    // Get entities from the datastore
-    let entities = web::block(move || repository.get_all())
-        .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+async fn get_todos(
+    page: web::Query<PageQuery>,
+    repository: Data<TodoRepository>,
+) -> Result<HttpResponse, Error> {
+    let (limit, offset) = page_bounds(&page)?;
+
+    // Get a page of entities from the datastore, along with the total number of entities
+    let (entities, total) =
+        web::block(move || (repository.get_paginated(limit, offset), repository.count()))
+            .await
+            .map_err(internal_error("Unable to load the todo items"))?;
+    let total = total.map_err(internal_error("Unable to count the todo items"))?;
 
     // Map our entities to our public struct TodoItem
     let response: Vec<TodoItem> = entities.into_iter().map(|entity| entity.into()).collect();
 
     // Send the response
-    Ok(HttpResponse::Ok().json(response))
+    Ok(HttpResponse::Ok()
+        .insert_header((TOTAL_COUNT_HEADER, total))
+        .json(response))
+}
+
+/// Get the total number of todos.
+///
+/// Returns the number of todo items in the data store, e.g. `{ "count": 42 }`.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The total number of todo items", body = TodoCount),
+        (status = 500, description = "Unable to count the todo items", body = ErrorResponse)
+    )
+)]
+#[get("/todo/count")]
+async fn get_todo_count(
+    repository: Data<TodoRepository>, // The todo item repository, injected from app_data
+) -> Result<HttpResponse, Error> {
+    let result = web::block(move || repository.count())
+        .await
+        .map_err(internal_error("Unable to count the todo items"))?;
+
+    match result {
+        Ok(count) => Ok(HttpResponse::Ok().json(TodoCount { count })),
+        Err(error) => {
+            error!("Unable to count the todo items: {}", error);
+            Ok(error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Unable to count the todo items",
+            ))
+        }
+    }
 }
 
 /// Get Todo by given todo id.
@@ -43,6 +139,7 @@ async fn get_todos(repository: Data<dyn Repository<TodoEntity>>) -> Result<HttpR
         (status = 200, description = "Todo found from storage", body = TodoItem),
         (status = 400, description = "The given identifier was not a correct uuid"),
         (status = 404, description = "Todo item was not found with the given identifier"),
+        (status = 500, description = "Unable to load the todo item", body = ErrorResponse)
     ),
     params(
         ("id", description = "Unique storage id of Todo")
@@ -50,15 +147,15 @@ async fn get_todos(repository: Data<dyn Repository<TodoEntity>>) -> Result<HttpR
 )]
 #[get("/todo/{id}")]
 async fn get_todo_by_id(
-    id: web::Path<Uuid>, // The identifier of the item to retrieve
-    repository: Data<dyn Repository<TodoEntity>>, // The todo item repository, injected from app_data
+    id: web::Path<Uuid>,              // The identifier of the item to retrieve
+    repository: Data<TodoRepository>, // The todo item repository, injected from app_data
 ) -> Result<HttpResponse, Error> {
     let uuid = id.into_inner();
 
     // Query our entity from the data store.
     let entity = web::block(move || repository.get_by_id(uuid))
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(internal_error("Unable to load the todo item"))?;
 
     match entity {
         Some(item) => {
@@ -78,31 +175,34 @@ async fn get_todo_by_id(
 /// Create new Todo to the data source.
 ///
 /// Post a new `Todo` in request body as json to store it. Api will return
-/// created `Todo` on success or `ErrorResponse::InternalServerError` if a problem occured whilst creating the todo item.
+/// created `Todo` on success or an `ErrorResponse` with status 500 if a problem occured whilst creating the todo item.
 #[utoipa::path(
     request_body = CreateTodoItemRequest,
     responses(
-        (status = 201, description = "Todo created successfully", body = Todo),
+        (status = 201, description = "Todo created successfully", body = TodoItem),
         (status = 500, description = "Unable to insert new todo item", body = ErrorResponse)
     )
 )]
 #[post("/todo")]
 async fn create_todo(
     todo: Json<CreateTodoItemRequest>,
-    repository: Data<dyn Repository<TodoEntity>>, // The todo item repository, injected from app_data
+    repository: Data<TodoRepository>, // The todo item repository, injected from app_data
 ) -> Result<HttpResponse, Error> {
     let request_body = todo.into_inner();
     let result = web::block(move || repository.insert(request_body.into()))
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(internal_error("Unable to insert new todo item"))?;
     match result {
         Ok(entity) => {
             let result: TodoItem = entity.into();
             Ok(HttpResponse::Ok().json(result))
         }
-        _ => {
-            error!("Unable to insert new todo item");
-            Ok(HttpResponse::InternalServerError().finish())
+        Err(error) => {
+            error!("Unable to insert new todo item: {}", error);
+            Ok(error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Unable to insert new todo item",
+            ))
         }
     }
 }
@@ -125,29 +225,37 @@ async fn create_todo(
 #[delete("/todo/{id}")]
 async fn delete_todo(
     id: web::Path<Uuid>,
-    repository: Data<dyn Repository<TodoEntity>>, // The todo item repository, injected from app_data
+    repository: Data<TodoRepository>, // The todo item repository, injected from app_data
 ) -> Result<HttpResponse, Error> {
-    let result = web::block(move || repository.delete(id.into_inner()))
+    let uuid = id.into_inner();
+    let result = web::block(move || repository.delete(uuid))
         .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+        .map_err(internal_error("Unable to delete todo item"))?;
     match result {
         Ok(true) => Ok(HttpResponse::Ok().finish()),
-        _ => Ok(HttpResponse::NotFound().finish()),
+        Ok(false) => Ok(HttpResponse::NotFound().finish()),
+        Err(error) => {
+            error!("Unable to delete todo item with id {}: {}", uuid, error);
+            Ok(error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Unable to delete todo item",
+            ))
+        }
     }
 }
 
 /// Update Todo with given id.
 ///
 /// Tries to update `Todo` by given id as path variable. If todo is found by id values are
-/// updated according `TodoUpdateRequest` and updated `Todo` is returned with status 200.
+/// updated according `UpdateTodoItemRequest` and updated `Todo` is returned with status 200.
 /// If todo is not found then 404 not found is returned.
 #[utoipa::path(
-    request_body = TodoUpdateRequest,
+    request_body = UpdateTodoItemRequest,
     responses(
         (status = 200, description = "Todo updated successfully", body = TodoItem),
         (status = 400, description = "The given identifier was not a correct uuid"),
         (status = 404, description = "Todo item was not found with the given identifier"),
-        (status = 500, description = "Unable to delete todo item", body = ErrorResponse)
+        (status = 500, description = "Unable to update todo item", body = ErrorResponse)
     ),
     params(
         ("id", description = "Unique storage id of Todo")
@@ -157,27 +265,43 @@ async fn delete_todo(
 async fn update_todo(
     id: web::Path<Uuid>,
     todo: Json<UpdateTodoItemRequest>,
-    repository: Data<dyn Repository<TodoEntity>>, // The todo item repository, injected from app_data
+    repository: Data<TodoRepository>, // The todo item repository, injected from app_data
 ) -> Result<HttpResponse, Error> {
     let request_body = todo.into_inner();
     let uuid = id.into_inner();
-    let entity = web::block(move || repository.update(uuid, request_body.into()))
-        .await?
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let result = web::block(move || repository.update(uuid, request_body.into()))
+        .await
+        .map_err(internal_error("Unable to update todo item"))?;
 
-    let result: TodoItem = entity.into();
-    Ok(HttpResponse::Ok().json(result))
+    match result {
+        Ok(entity) => {
+            let result: TodoItem = entity.into();
+            Ok(HttpResponse::Ok().json(result))
+        }
+        Err(RepositoryError::NotFound) => {
+            warn!("Todo item with id {} was not found in the data store", uuid);
+            Ok(HttpResponse::NotFound().finish())
+        }
+        Err(error) => {
+            error!("Unable to update todo item with id {}: {}", uuid, error);
+            Ok(error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Unable to update todo item",
+            ))
+        }
+    }
 }
 
 pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
     |config: &mut ServiceConfig| {
-        // Create our repository
-        let repository = TodoEntityRepository::new();
-
         // Todo entity repository is unsized, so we need to wrap this in a Atomic Reference Counter
         // "For types that are unsized, most commonly dyn T, Data can wrap these types by first constructing an Arc<dyn T> and using the From implementation to convert it."
         // https://docs.rs/actix-web/latest/actix_web/web/struct.Data.html
-        let repository_arc: Arc<dyn Repository<TodoEntity>> = Arc::new(repository);
+        let repository_arc: Arc<TodoRepository> = match data::use_in_memory_db() {
+            // Shared by the worker threads, as every worker runs this configuration
+            true => Arc::new(InMemoryRepository::shared()),
+            false => Arc::new(TodoEntityRepository::new()),
+        };
 
         config
             // Register our repository for data injection;
@@ -186,6 +310,8 @@ pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
             .service(get_todos)
             .service(create_todo)
             .service(delete_todo)
+            // before get_todo_by_id, so `count` is not mistaken for an id
+            .service(get_todo_count)
             .service(get_todo_by_id)
             .service(update_todo);
     }
@@ -198,56 +324,16 @@ mod tests {
     use actix_web::{test, App};
     use uuid::Uuid;
 
-    use std::collections::HashMap;
     use std::sync::Arc;
-    use std::sync::Mutex;
 
     use crate::data::repository::Repository;
-    use crate::entities::todo_entity::TodoEntity;
+    use crate::entities::todo_entity::{TodoChanges, TodoEntity};
 
     use super::*;
 
-    // Define a mock for Repository<TodoEntity>
-    pub struct TodoEntityRepositoryMock {
-        db: Arc<Mutex<HashMap<Uuid, TodoEntity>>>,
-    }
-
-    // Implement our repository pattern for the mock.
-    impl Repository<TodoEntity> for TodoEntityRepositoryMock {
-        fn get_all(&self) -> Vec<TodoEntity> {
-            self.db
-                .lock()
-                .unwrap()
-                .values()
-                .map(|v| v.clone())
-                .collect()
-        }
-
-        fn get_by_id(&self, todo_id: Uuid) -> Option<TodoEntity> {
-            self.db.lock().unwrap().get(&todo_id).map(|f| f.clone())
-        }
-
-        fn insert<'a>(&self, entity: TodoEntity) -> Result<TodoEntity, String> {
-            self.db.lock().unwrap().insert(entity.id, entity.clone());
-            Ok(entity)
-        }
-
-        fn update(&self, todo_id: Uuid, entity: TodoEntity) -> Result<TodoEntity, String> {
-            *self.db.lock().unwrap().get_mut(&todo_id).unwrap() = entity.clone();
-            Ok(entity)
-        }
-
-        fn delete(&self, todo_id: Uuid) -> Result<bool, String> {
-            self.db.lock().unwrap().remove(&todo_id);
-            Ok(true)
-        }
-    }
-
-    fn get_repository_mock_with_data() -> Arc<dyn Repository<TodoEntity>> {
+    fn get_repository_mock_with_data() -> Arc<TodoRepository> {
         // Create our repository
-        let repository = TodoEntityRepositoryMock {
-            db: Arc::new(Mutex::new(HashMap::new())),
-        };
+        let repository = InMemoryRepository::new();
 
         // insert some mock data
         let _ = repository.insert(TodoEntity {
@@ -270,7 +356,7 @@ mod tests {
             })
             .unwrap();
 
-        let repository_arc: Arc<dyn Repository<TodoEntity>> = Arc::new(repository);
+        let repository_arc: Arc<TodoRepository> = Arc::new(repository);
         repository_arc
     }
 
@@ -288,6 +374,131 @@ mod tests {
         assert_eq!(resp.len(), 2);
     }
 
+    #[actix_web::test]
+    async fn test_get_paginated() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todos),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .uri("/todo?page=1&per_page=1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        // The total reflects all todo items, not the size of the page.
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "2");
+        let first_page: Vec<TodoItem> = test::read_body_json(resp).await;
+        assert_eq!(first_page.len(), 1);
+
+        let req = test::TestRequest::default()
+            .uri("/todo?page=2&per_page=1")
+            .to_request();
+        let second_page: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(second_page.len(), 1);
+        assert_ne!(first_page[0].id, second_page[0].id);
+
+        let req = test::TestRequest::default()
+            .uri("/todo?page=3&per_page=1")
+            .to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_get_paginated_rejects_large_pages() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todos),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .uri("/todo?per_page=201")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::default()
+            .uri("/todo?page=9223372036854775807&per_page=200")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_get_todo_count() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todo_count),
+        )
+        .await;
+        let req = test::TestRequest::default().uri("/todo/count").to_request();
+
+        let resp: TodoCount = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.count, 2);
+    }
+
+    // A data store that is unable to count its todo items.
+    struct UncountableRepository;
+
+    impl Repository<TodoEntity> for UncountableRepository {
+        type Key = Uuid;
+        type Changes = TodoChanges;
+
+        fn get_all(&self) -> Vec<TodoEntity> {
+            Vec::new()
+        }
+
+        fn get_paginated(&self, _: i64, _: i64) -> Vec<TodoEntity> {
+            Vec::new()
+        }
+
+        fn count(&self) -> Result<i64, String> {
+            Err("The data store is unavailable".to_string())
+        }
+
+        fn get_by_id(&self, _: Uuid) -> Option<TodoEntity> {
+            None
+        }
+
+        fn insert(&self, entity: TodoEntity) -> Result<TodoEntity, String> {
+            Ok(entity)
+        }
+
+        fn update(&self, _: Uuid, _: TodoChanges) -> Result<TodoEntity, RepositoryError> {
+            Err(RepositoryError::NotFound)
+        }
+
+        fn delete(&self, _: Uuid) -> Result<bool, String> {
+            Ok(false)
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_failure_is_error_response() {
+        let repository: Arc<TodoRepository> = Arc::new(UncountableRepository);
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(get_todos),
+        )
+        .await;
+        let req = test::TestRequest::default().uri("/todo").to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR
+        );
+        let error: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(error.code, 500);
+        assert_eq!(error.message, "Unable to count the todo items");
+    }
+
     #[actix_web::test]
     async fn test_get_by_id() {
         let repository = get_repository_mock_with_data();
@@ -360,6 +571,59 @@ mod tests {
         assert_eq!(resp.completed, true);
     }
 
+    #[actix_web::test]
+    async fn test_update_todo_keeps_created_at() {
+        let repository = get_repository_mock_with_data();
+        let todo_id = Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap();
+        let created_at = repository.get_by_id(todo_id).unwrap().created_at;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .service(update_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874")
+            .set_json(&UpdateTodoItemRequest {
+                new_title: "Test update".to_string(),
+                new_description: "We should keep the creation time".to_string(),
+                completed: false,
+            })
+            .to_request();
+
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.id, todo_id);
+        assert_eq!(resp.created_at, created_at);
+        assert_eq!(
+            repository.get_by_id(todo_id).unwrap().created_at,
+            created_at
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_update_todo_not_found() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(update_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/todo/{}", Uuid::new_v4()))
+            .set_json(&UpdateTodoItemRequest {
+                new_title: "Test update".to_string(),
+                new_description: "We should not find this todo item".to_string(),
+                completed: true,
+            })
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
     #[actix_web::test]
     async fn test_delete_todo() {
         let repository = get_repository_mock_with_data();
@@ -386,4 +650,11 @@ mod tests {
         let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, validation_req).await;
         assert_eq!(resp.len(), 1);
     }
+
+    #[actix_web::test]
+    async fn test_error_response_schema_is_registered() {
+        let openapi = crate::api::register_open_api_spec();
+        let schemas = openapi.components.unwrap().schemas;
+        assert!(schemas.contains_key("ErrorResponse"));
+    }
 }
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use uuid::Uuid;
+
+use crate::data::repository::{Repository, RepositoryError};
+use crate::entities::todo_entity::{TodoChanges, TodoEntity};
+
+/// Keeps the instances of `<T>` in memory, so the API runs without a Postgres database, e.g. during
+/// local development or in integration tests.
+///
+/// Clones share the same instances. Nothing is persisted, the instances are gone after a restart.
+pub struct InMemoryRepository<T> {
+    items: Arc<RwLock<HashMap<Uuid, T>>>,
+}
+
+impl<T> InMemoryRepository<T> {
+    /// Creates an empty repository.
+    pub fn new() -> Self {
+        InMemoryRepository {
+            items: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+impl<T> Default for InMemoryRepository<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for InMemoryRepository<T> {
+    fn clone(&self) -> Self {
+        InMemoryRepository {
+            items: self.items.clone(),
+        }
+    }
+}
+
+impl InMemoryRepository<TodoEntity> {
+    /// Returns the repository shared by all worker threads, so they all see the same todo items.
+    pub fn shared() -> Self {
+        static SHARED: OnceLock<InMemoryRepository<TodoEntity>> = OnceLock::new();
+        SHARED.get_or_init(InMemoryRepository::new).clone()
+    }
+}
+
+// Behaves like `TodoEntityRepository`, so handlers can't tell the two apart
+impl Repository<TodoEntity> for InMemoryRepository<TodoEntity> {
+    type Key = Uuid;
+    type Changes = TodoChanges;
+
+    fn get_all(&self) -> Vec<TodoEntity> {
+        let mut items: Vec<_> = self.items.read().unwrap().values().cloned().collect();
+        // Ties are broken on the id, so pages never overlap or skip items created at once
+        items.sort_by_key(|item| (item.created_at, item.id));
+        items
+    }
+
+    fn get_paginated(&self, limit: i64, offset: i64) -> Vec<TodoEntity> {
+        self.get_all()
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect()
+    }
+
+    fn count(&self) -> Result<i64, String> {
+        Ok(self.items.read().unwrap().len() as i64)
+    }
+
+    fn get_by_id(&self, todo_id: Uuid) -> Option<TodoEntity> {
+        self.items.read().unwrap().get(&todo_id).cloned()
+    }
+
+    fn insert(&self, entity: TodoEntity) -> Result<TodoEntity, String> {
+        let mut items = self.items.write().unwrap();
+        if items.contains_key(&entity.id) {
+            return Err(format!("A todo item with id {} already exists", entity.id));
+        }
+        items.insert(entity.id, entity.clone());
+        Ok(entity)
+    }
+
+    fn update(&self, todo_id: Uuid, changes: TodoChanges) -> Result<TodoEntity, RepositoryError> {
+        let mut items = self.items.write().unwrap();
+        let existing = items.get_mut(&todo_id).ok_or(RepositoryError::NotFound)?;
+
+        existing.title = changes.title;
+        existing.description = changes.description;
+        existing.completed = changes.completed;
+        existing.completed_at = changes.completed_at;
+        Ok(existing.clone())
+    }
+
+    fn delete(&self, todo_id: Uuid) -> Result<bool, String> {
+        Ok(self.items.write().unwrap().remove(&todo_id).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    fn todo(title: &str, created_at: SystemTime) -> TodoEntity {
+        TodoEntity {
+            id: Uuid::new_v4(),
+            title: title.to_string(),
+            description: String::new(),
+            completed: false,
+            completed_at: None,
+            created_at,
+        }
+    }
+
+    fn changes(title: &str) -> TodoChanges {
+        TodoChanges {
+            title: title.to_string(),
+            description: String::new(),
+            completed: false,
+            completed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_crud() {
+        let repository = InMemoryRepository::new();
+        let now = SystemTime::now();
+        let second = repository.insert(todo("Second", now)).unwrap();
+        let first = repository
+            .insert(todo("First", now - Duration::from_secs(60)))
+            .unwrap();
+        assert!(repository.insert(first.clone()).is_err());
+
+        // Pages are ordered by creation time, like the database returns them
+        let titles: Vec<_> = repository
+            .get_paginated(10, 0)
+            .into_iter()
+            .map(|item| item.title)
+            .collect();
+        assert_eq!(titles, ["First", "Second"]);
+        assert_eq!(repository.get_paginated(1, 1)[0].id, second.id);
+        assert_eq!(repository.get_all().len(), 2);
+        assert_eq!(repository.count(), Ok(2));
+
+        let updated = repository.update(first.id, changes("Updated")).unwrap();
+        assert_eq!(updated.id, first.id);
+        assert_eq!(updated.created_at, first.created_at);
+        assert_eq!(repository.get_by_id(first.id).unwrap().title, "Updated");
+        assert!(matches!(
+            repository.update(Uuid::new_v4(), changes("Missing")),
+            Err(RepositoryError::NotFound)
+        ));
+
+        assert_eq!(repository.delete(first.id), Ok(true));
+        assert_eq!(repository.delete(first.id), Ok(false));
+        assert!(repository.get_by_id(first.id).is_none());
+    }
+
+    #[test]
+    fn test_pages_of_items_created_at_once() {
+        let repository = InMemoryRepository::new();
+        let now = SystemTime::now();
+        let mut ids: Vec<_> = (0..10)
+            .map(|index| repository.insert(todo(&index.to_string(), now)).unwrap().id)
+            .collect();
+        ids.sort();
+
+        // Every page continues where the previous one ended, in the order of the ids
+        let paged: Vec<_> = (0..5)
+            .flat_map(|page| repository.get_paginated(2, page * 2))
+            .map(|item| item.id)
+            .collect();
+        assert_eq!(paged, ids);
+    }
+}
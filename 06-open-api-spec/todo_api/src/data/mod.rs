@@ -1,11 +1,20 @@
 pub mod db_context;
+pub mod in_memory_repository;
 pub mod repository;
 pub mod todo_repository;
 
 use crate::Error;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use std::env;
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
 
+/// Indicates whether the todo items are kept in memory rather than in Postgres, when
+/// `USE_IN_MEMORY_DB` is `true` or `1`.
+pub fn use_in_memory_db() -> bool {
+    env::var("USE_IN_MEMORY_DB")
+        .is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+}
+
 pub fn run_migrations() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
     // This will run the necessary migrations.
     //
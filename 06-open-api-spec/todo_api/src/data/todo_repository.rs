@@ -1,12 +1,15 @@
 use uuid::Uuid;
 
 use crate::data::db_context;
-use crate::data::repository::Repository;
+use crate::data::repository::{Repository, RepositoryError};
 use crate::diesel::prelude::*;
-use crate::entities::todo_entity::TodoEntity;
+use crate::entities::todo_entity::{TodoChanges, TodoEntity};
 use crate::schema::todos;
 use crate::schema::todos::dsl::*;
 
+/// The repository of the todo items, as the handlers receive it.
+pub type TodoRepository = dyn Repository<TodoEntity, Key = Uuid, Changes = TodoChanges>;
+
 pub struct TodoEntityRepository {
     db_context: db_context::PostgresPool,
 }
@@ -20,6 +23,9 @@ impl TodoEntityRepository {
 }
 
 impl Repository<TodoEntity> for TodoEntityRepository {
+    type Key = Uuid;
+    type Changes = TodoChanges;
+
     fn get_all(&self) -> Vec<TodoEntity> {
         let mut connection = self.db_context.get().unwrap();
         todos
@@ -27,6 +33,24 @@ impl Repository<TodoEntity> for TodoEntityRepository {
             .expect("Error loading todo items")
     }
 
+    fn get_paginated(&self, limit: i64, offset: i64) -> Vec<TodoEntity> {
+        let mut connection = self.db_context.get().unwrap();
+        todos
+            .order((created_at.asc(), id.asc()))
+            .limit(limit)
+            .offset(offset)
+            .load::<TodoEntity>(&mut connection)
+            .expect("Error loading todo items")
+    }
+
+    fn count(&self) -> Result<i64, String> {
+        let mut connection = self.db_context.get().unwrap();
+        todos
+            .count()
+            .get_result(&mut connection)
+            .map_err(|error| error.to_string())
+    }
+
     fn get_by_id(&self, todo_id: Uuid) -> Option<TodoEntity> {
         let mut connection = self.db_context.get().unwrap();
         let item = todos.find(todo_id).first(&mut connection);
@@ -46,17 +70,11 @@ impl Repository<TodoEntity> for TodoEntityRepository {
         Ok(result)
     }
 
-    fn update(&self, todo_id: Uuid, entity: TodoEntity) -> Result<TodoEntity, String> {
+    fn update(&self, todo_id: Uuid, changes: TodoChanges) -> Result<TodoEntity, RepositoryError> {
         let mut connection = self.db_context.get().unwrap();
         let todo_item = diesel::update(todos.find(todo_id))
-            .set((
-                completed_at.eq(entity.completed_at),
-                completed.eq(entity.completed),
-                title.eq(entity.title),
-                description.eq(entity.description),
-            ))
-            .get_result::<TodoEntity>(&mut connection)
-            .expect("Unable to update todo entity");
+            .set(changes)
+            .get_result::<TodoEntity>(&mut connection)?;
 
         Ok(todo_item)
     }
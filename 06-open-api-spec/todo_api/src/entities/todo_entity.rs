@@ -54,18 +54,34 @@ impl From<CreateTodoItemRequest> for TodoEntity {
     }
 }
 
-// Convert from UpdateTodoItemRequest to TodoEntity
-impl From<UpdateTodoItemRequest> for TodoEntity {
+/// The columns an update of a todo item changes.
+///
+/// The id and creation time are left out, so an update can never change the identity of a todo
+/// item.
+#[derive(AsChangeset)]
+#[diesel(table_name = todos)]
+#[diesel(treat_none_as_null = true)]
+pub struct TodoChanges {
+    /// The new title of the todo item
+    pub title: String,
+
+    /// The new description of the todo item
+    pub description: String,
+
+    /// Whether the todo item is completed
+    pub completed: bool,
+
+    /// Timestamp when the todo item was completed, cleared when it is not completed
+    pub completed_at: Option<SystemTime>,
+}
+
+// Convert from UpdateTodoItemRequest to TodoChanges
+impl From<UpdateTodoItemRequest> for TodoChanges {
     fn from(request: UpdateTodoItemRequest) -> Self {
-        TodoEntity {
-            id: Uuid::new_v4(),
+        TodoChanges {
             title: request.new_title,
             description: request.new_description,
-            created_at: SystemTime::now(),
-            completed_at: match request.completed {
-                true => Some(SystemTime::now()),
-                _ => None,
-            },
+            completed_at: request.completed.then(SystemTime::now),
             completed: request.completed,
         }
     }
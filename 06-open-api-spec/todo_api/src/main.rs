@@ -20,9 +20,13 @@ async fn main() -> std::io::Result<()> {
     env_logger::init();
 
     // Apply any ending database migrations upon startup of our application.
-    match data::run_migrations() {
-        Ok(()) => info!("Succesfully applied pending migrations (if any)"),
-        Err(_) => error!("Unable to apply pending migrations"),
+    if data::use_in_memory_db() {
+        info!("Keeping the todo items in memory, they are lost when the application stops");
+    } else {
+        match data::run_migrations() {
+            Ok(()) => info!("Succesfully applied pending migrations (if any)"),
+            Err(_) => error!("Unable to apply pending migrations"),
+        }
     }
 
     // Make instance variable of ApiDoc so all worker threads gets the same instance.
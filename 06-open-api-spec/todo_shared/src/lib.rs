@@ -1,4 +1,9 @@
 pub mod models;
+pub use models::error_response::ErrorResponse;
+pub use models::todo_count::TodoCount;
 pub use models::todo_item::CreateTodoItemRequest;
 pub use models::todo_item::TodoItem;
 pub use models::todo_item::UpdateTodoItemRequest;
+pub use models::todo_query::PageQuery;
+pub use models::todo_query::DEFAULT_PAGE_SIZE;
+pub use models::todo_query::MAX_PAGE_SIZE;
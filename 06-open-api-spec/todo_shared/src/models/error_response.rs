@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ErrorResponse {
+    // The HTTP status code of the response
+    pub code: u16,
+
+    // A human readable description of the error
+    pub message: String,
+}
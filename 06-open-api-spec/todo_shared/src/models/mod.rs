@@ -1 +1,4 @@
+pub mod error_response;
+pub mod todo_count;
 pub mod todo_item;
+pub mod todo_query;
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+/// The number of todo items in a page when no page size is given.
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+
+/// The maximum number of todo items in a page, larger pages are rejected with 400 bad request.
+pub const MAX_PAGE_SIZE: i64 = 200;
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    // The (1-based) number of the page to return, defaults to the first page
+    pub page: Option<i64>,
+
+    // The number of todo items per page, `DEFAULT_PAGE_SIZE` by default and at most
+    // `MAX_PAGE_SIZE`
+    pub per_page: Option<i64>,
+}
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE todos ADD COLUMN deleted_at TIMESTAMP
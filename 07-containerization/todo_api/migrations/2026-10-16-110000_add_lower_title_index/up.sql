@@ -0,0 +1,2 @@
+-- Your SQL goes here
+CREATE INDEX todos_lower_title_idx ON todos (lower(title))
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE todos DROP COLUMN actual_minutes;
+ALTER TABLE todos DROP COLUMN estimated_minutes;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE todos ADD COLUMN estimated_minutes INTEGER;
+ALTER TABLE todos ADD COLUMN actual_minutes INTEGER;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX todos_search_trgm_idx;
+DROP EXTENSION IF EXISTS pg_trgm
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+CREATE INDEX todos_search_trgm_idx ON todos USING GIN ((title || ' ' || description) gin_trgm_ops)
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE todo_history;
+DROP INDEX todos_owner_idx;
+ALTER TABLE todos DROP COLUMN owner
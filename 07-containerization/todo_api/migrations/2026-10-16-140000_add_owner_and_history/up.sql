@@ -0,0 +1,13 @@
+-- Your SQL goes here
+ALTER TABLE todos ADD COLUMN owner TEXT;
+CREATE INDEX todos_owner_idx ON todos (owner);
+
+CREATE TABLE todo_history (
+  id UUID PRIMARY KEY,
+  todo_id UUID NOT NULL,
+  actor TEXT NOT NULL,
+  action TEXT NOT NULL,
+  details TEXT,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX todo_history_created_at_idx ON todo_history (created_at)
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE todos DROP COLUMN reminded_at;
+ALTER TABLE todos DROP COLUMN remind_before_minutes;
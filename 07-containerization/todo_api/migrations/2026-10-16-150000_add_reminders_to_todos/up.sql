@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE todos ADD COLUMN remind_before_minutes INTEGER;
+ALTER TABLE todos ADD COLUMN reminded_at TIMESTAMP;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX todos_tag_namespaces_idx;
+DROP INDEX todos_tags_idx;
+ALTER TABLE todos DROP COLUMN tag_namespaces;
+ALTER TABLE todos DROP COLUMN tags;
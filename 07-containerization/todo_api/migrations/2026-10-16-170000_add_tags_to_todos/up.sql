@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE todos ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+-- Every namespace of the tags, so namespace filters are prefix lookups on an index
+ALTER TABLE todos ADD COLUMN tag_namespaces TEXT[] NOT NULL DEFAULT '{}';
+CREATE INDEX todos_tags_idx ON todos USING GIN (tags);
+CREATE INDEX todos_tag_namespaces_idx ON todos USING GIN (tag_namespaces);
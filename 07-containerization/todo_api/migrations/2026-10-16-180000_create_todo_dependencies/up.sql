@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE todo_dependencies (
+  todo_id UUID NOT NULL REFERENCES todos (id) ON DELETE CASCADE,
+  blocked_by UUID NOT NULL REFERENCES todos (id) ON DELETE CASCADE,
+  PRIMARY KEY (todo_id, blocked_by),
+  CHECK (todo_id <> blocked_by)
+);
+CREATE INDEX todo_dependencies_blocked_by_idx ON todo_dependencies (blocked_by)
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX todos_parent_id_idx;
+ALTER TABLE todos DROP CONSTRAINT todos_parent_is_other;
+ALTER TABLE todos DROP COLUMN parent_id;
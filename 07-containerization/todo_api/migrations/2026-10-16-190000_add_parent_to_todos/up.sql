@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE todos ADD COLUMN parent_id UUID REFERENCES todos (id) ON DELETE CASCADE;
+ALTER TABLE todos ADD CONSTRAINT todos_parent_is_other CHECK (parent_id <> id);
+
+-- Subtasks are looked up by their parent when it completes
+CREATE INDEX todos_parent_id_idx ON todos (parent_id);
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- An iCalendar style recurrence rule, e.g. FREQ=WEEKLY;INTERVAL=1
+ALTER TABLE todos ADD COLUMN recurrence TEXT;
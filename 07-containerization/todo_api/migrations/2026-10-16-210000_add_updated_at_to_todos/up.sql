@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE todos ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT now();
+
+-- The last known change of the existing todo items
+UPDATE todos SET updated_at = GREATEST(created_at, completed_at);
@@ -0,0 +1,319 @@
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderMap, AUTHORIZATION, WWW_AUTHENTICATE};
+use actix_web::http::Method;
+use actix_web::{Error, HttpMessage, HttpResponse};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use todo_shared::ErrorResponse;
+
+use crate::auth::authenticated_user::AuthenticatedUser;
+
+// The routes that require authentication for their mutating methods.
+const PROTECTED_PREFIX: &str = "/todo";
+
+// The other routes that require authentication for their mutating methods, e.g. GraphQL mutations.
+const PROTECTED_PATHS: [&str; 1] = ["/graphql"];
+
+// The routes that are posted to for reading only, e.g. to send a query in the request body.
+const READ_ONLY_PATHS: [&str; 1] = ["/todo/stats/tags"];
+
+// The routes that require authentication for every method, as they depend on the caller.
+const PRIVATE_PREFIXES: [&str; 2] = ["/admin", "/activity"];
+
+// The claims of a bearer token this API relies on.
+#[derive(Deserialize)]
+struct Claims {
+    // The subject (user identifier) the token was issued to
+    sub: String,
+
+    // The roles granted to the subject
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+/// Middleware authenticating requests with a JWT bearer token.
+///
+/// `POST`, `PUT`, `PATCH` and `DELETE` requests of the todo and GraphQL routes, and all requests of
+/// the admin and activity routes, need an `Authorization: Bearer <token>` header with an unexpired
+/// token, signed with the configured secret using HS256. Other requests are public, but a bearer
+/// token sent along is still checked, so e.g. admins can include deleted todo items. The `sub` and
+/// optional `roles` claims are exposed to the handlers as an `AuthenticatedUser` in the request
+/// extensions. Requests without a valid token are rejected with 401 unauthorized, unless an outer
+/// middleware authenticated them already.
+pub struct JwtAuth {
+    key: DecodingKey,
+}
+
+impl JwtAuth {
+    /// Creates the middleware.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `secret` - The secret the bearer tokens are signed with.
+    pub fn new(secret: &str) -> Self {
+        JwtAuth {
+            key: DecodingKey::from_secret(secret.as_bytes()),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for JwtAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = JwtAuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(JwtAuthMiddleware {
+            service,
+            key: self.key.clone(),
+        }))
+    }
+}
+
+pub struct JwtAuthMiddleware<S> {
+    service: S,
+    key: DecodingKey,
+}
+
+impl<S, B> Service<ServiceRequest> for JwtAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, request: ServiceRequest) -> Self::Future {
+        // Requests authenticated otherwise, e.g. by their signature, don't need a bearer token
+        let authenticated = request.extensions().contains::<AuthenticatedUser>();
+        let required = requires_authentication(request.method(), request.path());
+        if !authenticated && (required || bearer_token(request.headers()).is_some()) {
+            match authenticate(request.headers(), &self.key) {
+                Ok(user) => {
+                    request.extensions_mut().insert(user);
+                }
+                Err(message) => {
+                    let response = HttpResponse::Unauthorized()
+                        .insert_header((WWW_AUTHENTICATE, "Bearer"))
+                        .json(ErrorResponse {
+                            status: 401,
+                            message: message.to_string(),
+                            error_code: "UNAUTHORIZED".to_string(),
+                        });
+                    let response = request.into_response(response).map_into_right_body();
+                    return Box::pin(ready(Ok(response)));
+                }
+            }
+        }
+
+        let response = self.service.call(request);
+        Box::pin(async move { Ok(response.await?.map_into_left_body()) })
+    }
+}
+
+// Indicates whether a request changes todo items or depends on the caller, which is only allowed
+// for authenticated callers.
+fn requires_authentication(method: &Method, path: &str) -> bool {
+    if PRIVATE_PREFIXES
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+    {
+        return true;
+    }
+
+    let mutating = [Method::POST, Method::PUT, Method::PATCH, Method::DELETE].contains(method);
+    let protected = path.starts_with(PROTECTED_PREFIX) || PROTECTED_PATHS.contains(&path);
+    mutating && protected && !READ_ONLY_PATHS.contains(&path)
+}
+
+// Returns the bearer token of a request, when its authorization header holds one.
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+// Validates the bearer token of a request, returning the reason to reject it otherwise.
+fn authenticate(headers: &HeaderMap, key: &DecodingKey) -> Result<AuthenticatedUser, &'static str> {
+    if !headers.contains_key(AUTHORIZATION) {
+        return Err("A bearer token is required");
+    }
+    let token = bearer_token(headers).ok_or("The authorization header is not a bearer token")?;
+
+    let claims = decode::<Claims>(token.trim(), key, &Validation::new(Algorithm::HS256))
+        .map_err(|error| match error.kind() {
+            ErrorKind::ExpiredSignature => "The bearer token has expired",
+            _ => "The bearer token is invalid",
+        })?
+        .claims;
+
+    Ok(AuthenticatedUser {
+        subject: claims.sub,
+        roles: claims.roles,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{get, post, test, App, HttpResponse, Responder};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    const SECRET: &str = "test-secret";
+
+    #[get("/todo")]
+    async fn list(user: Option<AuthenticatedUser>) -> impl Responder {
+        let subject = user.map(|user| user.subject);
+        HttpResponse::Ok().body(subject.unwrap_or_default())
+    }
+
+    #[get("/activity")]
+    async fn activity(user: AuthenticatedUser) -> impl Responder {
+        HttpResponse::Ok().body(user.subject)
+    }
+
+    #[post("/todo")]
+    async fn create(user: AuthenticatedUser) -> impl Responder {
+        HttpResponse::Ok().body(user.subject)
+    }
+
+    // Signs a token for the given subject, expiring the given number of seconds from now.
+    fn token(secret: &str, subject: &str, expires_in: i64) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let claims = json!({ "sub": subject, "exp": now + expires_in });
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn test_bearer_token_required_for_mutations() {
+        let app = test::init_service(
+            App::new()
+                .wrap(JwtAuth::new(SECRET))
+                .service(list)
+                .service(create),
+        )
+        .await;
+
+        // Reading stays public
+        let req = test::TestRequest::get().uri("/todo").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .insert_header((
+                AUTHORIZATION,
+                format!("Bearer {}", token(SECRET, "user-1", 600)),
+            ))
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, "user-1");
+
+        let rejected = [
+            None,
+            Some("Basic dXNlcjpwYXNzd29yZA==".to_string()),
+            Some("Bearer not-a-token".to_string()),
+            Some(format!("Bearer {}", token("other-secret", "user-1", 600))),
+            Some(format!("Bearer {}", token(SECRET, "user-1", -600))),
+        ];
+        for authorization in rejected {
+            let mut req = test::TestRequest::post().uri("/todo");
+            if let Some(authorization) = authorization {
+                req = req.insert_header((AUTHORIZATION, authorization));
+            }
+            let resp = test::call_service(&app, req.to_request()).await;
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(resp.headers().get(WWW_AUTHENTICATE).unwrap(), "Bearer");
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_bearer_token_optional_for_reads() {
+        let app = test::init_service(
+            App::new()
+                .wrap(JwtAuth::new(SECRET))
+                .service(list)
+                .service(activity),
+        )
+        .await;
+
+        // A token sent along with a public request identifies the caller
+        let req = test::TestRequest::get()
+            .uri("/todo")
+            .insert_header((
+                AUTHORIZATION,
+                format!("Bearer {}", token(SECRET, "user-1", 600)),
+            ))
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, "user-1");
+
+        let req = test::TestRequest::get().uri("/todo").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, "");
+
+        // But it is never ignored when invalid
+        let req = test::TestRequest::get()
+            .uri("/todo")
+            .insert_header((AUTHORIZATION, "Bearer not-a-token"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // Reading the activity requires a token
+        let req = test::TestRequest::get().uri("/activity").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/activity")
+            .insert_header((
+                AUTHORIZATION,
+                format!("Bearer {}", token(SECRET, "user-1", 600)),
+            ))
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, "user-1");
+    }
+
+    #[actix_web::test]
+    async fn test_requires_authentication() {
+        assert!(requires_authentication(&Method::POST, "/todo"));
+        assert!(requires_authentication(&Method::POST, "/graphql"));
+        assert!(!requires_authentication(&Method::GET, "/graphql"));
+        assert!(!requires_authentication(&Method::POST, "/todo/stats/tags"));
+        assert!(requires_authentication(&Method::GET, "/activity"));
+        assert!(requires_authentication(
+            &Method::POST,
+            "/admin/todo/reassign-by-owner"
+        ));
+    }
+}
@@ -0,0 +1,130 @@
+use actix_web::web::{Data, ServiceConfig};
+use actix_web::{get, HttpResponse};
+use todo_shared::{Capabilities, CapabilityLimits, MAX_PAGE_SIZE};
+
+use crate::api::json_body::JsonOptions;
+use crate::settings::Settings;
+
+/// Get the capabilities of this deployment.
+///
+/// Lists the optional features that are enabled and the limits that are enforced, so clients
+/// can adapt to differently configured backends.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Capabilities of this deployment", body = Capabilities)
+    )
+)]
+#[get("/capabilities")]
+async fn get_capabilities(
+    settings: Data<Settings>, // The deployment settings, injected from app_data
+    json_options: Option<Data<JsonOptions>>, // The request body options, injected from app_data
+) -> HttpResponse {
+    let strict_json = json_options.map(|options| options.strict).unwrap_or(false);
+
+    let features = [
+        ("auth", settings.jwt_secret.is_some()),
+        ("search", true),
+        ("search_fuzzy", settings.fuzzy_search),
+        ("soft_delete", true),
+        ("strict_json", strict_json),
+        ("sync", true),
+        ("tags", true),
+        ("unique_titles", settings.unique_titles),
+        ("websocket", true),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(feature, _)| feature.to_string())
+    .collect();
+
+    HttpResponse::Ok().json(Capabilities {
+        features,
+        limits: CapabilityLimits {
+            max_batch_size: settings.max_batch_size,
+            max_page_size: MAX_PAGE_SIZE,
+            soft_delete_retention_days: settings.soft_delete_retention_days,
+        },
+    })
+}
+
+pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        // The settings are registered for data injection by the todo controller
+        config.service(get_capabilities);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_get_capabilities() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(Settings {
+                    max_batch_size: 42,
+                    unique_titles: true,
+                    ..Settings::default()
+                }))
+                .app_data(Data::new(JsonOptions { strict: false }))
+                .service(get_capabilities),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .uri("/capabilities")
+            .to_request();
+
+        let resp: Capabilities = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            resp.features,
+            vec![
+                "search",
+                "soft_delete",
+                "sync",
+                "tags",
+                "unique_titles",
+                "websocket"
+            ]
+        );
+        assert_eq!(resp.limits.max_batch_size, 42);
+        assert_eq!(resp.limits.max_page_size, MAX_PAGE_SIZE);
+    }
+
+    #[actix_web::test]
+    async fn test_get_capabilities_of_configured_features() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(Settings {
+                    jwt_secret: Some("secret".to_string()),
+                    fuzzy_search: true,
+                    ..Settings::default()
+                }))
+                .app_data(Data::new(JsonOptions { strict: true }))
+                .service(get_capabilities),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .uri("/capabilities")
+            .to_request();
+
+        let resp: Capabilities = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            resp.features,
+            vec![
+                "auth",
+                "search",
+                "search_fuzzy",
+                "soft_delete",
+                "strict_json",
+                "sync",
+                "tags",
+                "websocket"
+            ]
+        );
+    }
+}
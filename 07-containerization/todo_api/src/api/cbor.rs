@@ -0,0 +1,195 @@
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, Accept, Header, HeaderValue, CONTENT_TYPE, VARY};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpRequest};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// The media type of CBOR (RFC 8949) encoded bodies.
+pub const CBOR: &str = "application/cbor";
+
+// The CBOR tag of a binary UUID.
+const UUID_TAG: u64 = 37;
+
+/// Indicates whether the caller prefers a CBOR response, i.e. `application/cbor` is ranked first
+/// in its `Accept` header.
+pub fn accepts_cbor(request: &HttpRequest) -> bool {
+    Accept::parse(request)
+        .ok()
+        .and_then(|accept| accept.ranked().into_iter().next())
+        .is_some_and(|preferred| preferred.essence_str() == CBOR)
+}
+
+/// Encodes a value as CBOR.
+pub fn to_cbor<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    ciborium::ser::into_writer(value, &mut bytes).map_err(|error| error.to_string())?;
+    Ok(bytes)
+}
+
+/// Decodes a CBOR encoded value.
+pub fn from_cbor<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    ciborium::de::from_reader(bytes).map_err(|error| error.to_string())
+}
+
+/// Decodes a CBOR encoded body into JSON, so it is deserialized just like a JSON body.
+///
+/// UUIDs tagged as such (tag 37) become their text form, other byte strings become arrays of
+/// numbers and other tags are dropped. Maps need text keys, as JSON objects do.
+pub fn cbor_to_json(bytes: &[u8]) -> Result<serde_json::Value, String> {
+    to_json(from_cbor(bytes)?)
+}
+
+// Converts a CBOR value to the JSON value it represents.
+fn to_json(value: ciborium::Value) -> Result<serde_json::Value, String> {
+    use ciborium::Value as Cbor;
+    use serde_json::Value as Json;
+
+    Ok(match value {
+        Cbor::Null => Json::Null,
+        Cbor::Bool(value) => Json::Bool(value),
+        Cbor::Integer(value) => {
+            let value = i128::from(value);
+            match (i64::try_from(value), u64::try_from(value)) {
+                (Ok(value), _) => Json::from(value),
+                (_, Ok(value)) => Json::from(value),
+                _ => return Err(format!("Integer {} is out of range", value)),
+            }
+        }
+        Cbor::Float(value) => Json::from(value),
+        Cbor::Text(value) => Json::String(value),
+        Cbor::Bytes(bytes) => Json::Array(bytes.into_iter().map(Json::from).collect()),
+        Cbor::Tag(UUID_TAG, value) => match *value {
+            Cbor::Bytes(bytes) => Uuid::from_slice(&bytes)
+                .map(|uuid| Json::String(uuid.to_string()))
+                .map_err(|error| error.to_string())?,
+            value => to_json(value)?,
+        },
+        Cbor::Tag(_, value) => to_json(*value)?,
+        Cbor::Array(values) => {
+            Json::Array(values.into_iter().map(to_json).collect::<Result<_, _>>()?)
+        }
+        Cbor::Map(entries) => Json::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| match key {
+                    Cbor::Text(key) => Ok((key, to_json(value)?)),
+                    _ => Err("Map keys must be text".to_string()),
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        _ => return Err("Unsupported CBOR value".to_string()),
+    })
+}
+
+/// Middleware encoding the JSON responses as CBOR for callers accepting `application/cbor`.
+///
+/// Constrained clients get a compact binary representation of every endpoint this way, while the
+/// handlers keep responding with JSON. The data model stays the same, e.g. identifiers are text
+/// strings. Request bodies are decoded by `JsonBody`.
+pub async fn cbor_responses(
+    request: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let cbor = accepts_cbor(request.request());
+
+    let mut response = next.call(request).await?.map_into_boxed_body();
+    response
+        .headers_mut()
+        .append(VARY, HeaderValue::from_static("accept"));
+
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !cbor || !is_json {
+        return Ok(response);
+    }
+
+    let (request, response) = response.into_parts();
+    let (mut response, body) = response.into_parts();
+    let bytes = body::to_bytes(body)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let value: serde_json::Value =
+        serde_json::from_slice(&bytes).map_err(actix_web::error::ErrorInternalServerError)?;
+    let encoded = to_cbor(&value).map_err(actix_web::error::ErrorInternalServerError)?;
+
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(CBOR));
+    response.headers_mut().remove(header::CONTENT_LENGTH);
+    let response = response.set_body(BoxBody::new(encoded));
+    Ok(ServiceResponse::new(request, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::json_body::JsonBody;
+    use actix_web::http::header::ACCEPT;
+    use actix_web::middleware::from_fn;
+    use actix_web::{post, test, App, HttpResponse, Responder};
+    use chrono::DateTime;
+    use todo_shared::TodoItem;
+
+    #[post("/echo")]
+    async fn echo(item: JsonBody<TodoItem>) -> impl Responder {
+        HttpResponse::Ok().json(item.into_inner())
+    }
+
+    #[actix_web::test]
+    async fn test_todo_item_round_trips_through_cbor() {
+        let created_at = DateTime::from_timestamp(1_664_452_800, 123_000).unwrap();
+        let item: TodoItem = serde_json::from_value(serde_json::json!({
+            "id": Uuid::new_v4(),
+            "title": "Encode as CBOR",
+            "description": "For constrained clients",
+            "created_at": created_at,
+            "updated_at": created_at,
+            "version": 1,
+            "completed": false,
+            "tags": ["transport/cbor"],
+        }))
+        .unwrap();
+
+        let decoded: TodoItem = from_cbor(&to_cbor(&item).unwrap()).unwrap();
+        assert_eq!(
+            serde_json::to_value(decoded).unwrap(),
+            serde_json::to_value(&item).unwrap()
+        );
+
+        let app = test::init_service(App::new().wrap(from_fn(cbor_responses)).service(echo)).await;
+
+        let req = test::TestRequest::post()
+            .uri("/echo")
+            .insert_header((CONTENT_TYPE, CBOR))
+            .insert_header((ACCEPT, "application/cbor, application/json;q=0.5"))
+            .set_payload(to_cbor(&serde_json::to_value(&item).unwrap()).unwrap())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), CBOR);
+        let body = test::read_body(resp).await;
+        let decoded: TodoItem = serde_json::from_value(cbor_to_json(&body).unwrap()).unwrap();
+        assert_eq!(
+            serde_json::to_value(decoded).unwrap(),
+            serde_json::to_value(&item).unwrap()
+        );
+
+        // JSON stays the default representation
+        let req = test::TestRequest::post()
+            .uri("/echo")
+            .set_json(&item)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let echoed: TodoItem = test::read_body_json(resp).await;
+        assert_eq!(echoed.title, item.title);
+    }
+}
@@ -0,0 +1,99 @@
+use actix_cors::Cors;
+use actix_web::http::header::{AUTHORIZATION, CONTENT_TYPE};
+
+// The number of seconds browsers may cache the result of a preflight request.
+const MAX_AGE_SECS: usize = 3600;
+
+/// Creates the CORS middleware, allowing browsers to call the API from the given origins.
+///
+/// Without any allowed origins only pages served from localhost are allowed, so a deployment stays
+/// locked down until its front-end origin is configured.
+///
+///  # Arguments
+///
+///  * `allowed_origins` - The origins to allow, e.g. `https://todo.example.com`.
+pub fn cors(allowed_origins: &[String]) -> Cors {
+    let cors = Cors::default()
+        .allowed_methods(["GET", "POST", "PUT", "DELETE", "PATCH"])
+        .allowed_headers([CONTENT_TYPE, AUTHORIZATION])
+        .max_age(MAX_AGE_SECS);
+
+    if allowed_origins.is_empty() {
+        return cors
+            .allowed_origin_fn(|origin, _| origin.to_str().map(is_localhost).unwrap_or(false));
+    }
+    allowed_origins
+        .iter()
+        .fold(cors, |cors, origin| cors.allowed_origin(origin))
+}
+
+// Indicates whether an origin, e.g. `http://localhost:3000`, is served from the local machine.
+fn is_localhost(origin: &str) -> bool {
+    let host = origin
+        .strip_prefix("http://")
+        .or_else(|| origin.strip_prefix("https://"))
+        .unwrap_or_default();
+    let host = match host.rsplit_once(':') {
+        // The colons of an IPv6 address are not followed by a port
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => host,
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, ORIGIN};
+    use actix_web::{get, test, App, HttpResponse, Responder};
+
+    #[get("/todo")]
+    async fn list() -> impl Responder {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn test_allowed_origins() {
+        let origins = vec!["https://todo.example.com".to_string()];
+        let app = test::init_service(App::new().wrap(cors(&origins)).service(list)).await;
+        let localhost = test::init_service(App::new().wrap(cors(&[])).service(list)).await;
+
+        let req = test::TestRequest::get()
+            .uri("/todo")
+            .insert_header((ORIGIN, "https://todo.example.com"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://todo.example.com"
+        );
+
+        for origin in [
+            "http://localhost:3000",
+            "http://127.0.0.1",
+            "http://[::1]:8080",
+        ] {
+            let req = test::TestRequest::get()
+                .uri("/todo")
+                .insert_header((ORIGIN, origin))
+                .to_request();
+            let resp = test::call_service(&localhost, req).await;
+            assert_eq!(
+                resp.headers().get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+                origin
+            );
+        }
+
+        for origin in ["https://evil.example.com", "http://localhost.evil.com"] {
+            let req = test::TestRequest::get()
+                .uri("/todo")
+                .insert_header((ORIGIN, origin))
+                .to_request();
+            let resp = test::try_call_service(&localhost, req).await;
+            assert!(resp.map_or(true, |resp| resp
+                .headers()
+                .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+                .is_none()));
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use actix_web::web::Bytes;
+use actix_web::Error;
+use chrono::{DateTime, Utc};
+use futures::Stream;
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::data::todo_repository::TodoRepository;
+use crate::entities::todo_entity::TodoEntity;
+use crate::services::cursor::Cursor;
+use crate::telemetry;
+
+/// The columns of an exported todo item, in order.
+const HEADER: &[u8] = b"id,title,description,completed,completed_at,created_at\n";
+
+// A todo item as a CSV row, with the timestamps in seconds since the unix epoch.
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    id: Uuid,
+    title: &'a str,
+    description: &'a str,
+    completed: bool,
+    completed_at: Option<i64>,
+    created_at: i64,
+}
+
+/// Streams the todo items as CSV, starting with a header row, oldest first.
+///
+/// The todo items are loaded in keyset pages of `page_size`, the next page only once the rows of
+/// the previous one have been sent, so neither the todo items nor the CSV document as a whole are
+/// held in memory. A failure after the header has been sent ends the response early.
+///
+///  # Arguments
+///
+///  * `repository` - The repository to load the todo items from.
+///  * `page_size` - The number of todo items to load per query.
+pub fn csv_body(
+    repository: Arc<dyn TodoRepository>,
+    page_size: i64,
+) -> impl Stream<Item = Result<Bytes, Error>> {
+    let header = futures::stream::once(async { Ok(Bytes::from_static(HEADER)) });
+    let rows = futures::stream::try_unfold(
+        (repository, None, false),
+        move |(repository, after, last): (_, Option<Cursor>, _)| async move {
+            if last {
+                return Ok(None);
+            }
+            let pages = repository.clone();
+            let entities = telemetry::block(move || pages.get_after(after, page_size)).await??;
+            let Some(next) = entities.last().map(Cursor::after) else {
+                return Ok(None);
+            };
+            // A partial page is the last one
+            let last = (entities.len() as i64) < page_size;
+            let page = to_rows(&entities).map_err(actix_web::error::ErrorInternalServerError)?;
+            Ok(Some((page, (repository, Some(next), last))))
+        },
+    );
+    futures::StreamExt::chain(header, rows)
+}
+
+// Serializes the todo items as CSV rows, quoting fields where needed.
+fn to_rows(entities: &[TodoEntity]) -> Result<Bytes, csv::Error> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new());
+    for entity in entities {
+        writer.serialize(CsvRow {
+            id: entity.id,
+            title: &entity.title,
+            description: &entity.description,
+            completed: entity.completed,
+            completed_at: entity.completed_at.map(epoch_seconds),
+            created_at: epoch_seconds(entity.created_at),
+        })?;
+    }
+    let rows = writer
+        .into_inner()
+        .map_err(|error| csv::Error::from(error.into_error()))?;
+    Ok(Bytes::from(rows))
+}
+
+// Whole seconds since the unix epoch.
+fn epoch_seconds(time: DateTime<Utc>) -> i64 {
+    time.timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use todo_shared::CreateTodoItemRequest;
+
+    #[test]
+    fn test_csv_rows() {
+        let entity: TodoEntity = CreateTodoItemRequest {
+            title: "Export, as CSV".to_string(),
+            description: "Quote \"special\" characters".to_string(),
+            due_date: None,
+            estimated_minutes: None,
+            actual_minutes: None,
+            remind_before_minutes: None,
+            tags: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+        }
+        .into();
+        let entity = TodoEntity {
+            id: Uuid::parse_str("cdce7fda-909e-41cb-8507-abceb316a5b4").unwrap(),
+            created_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            ..entity
+        };
+
+        let body = to_rows(&[entity]).unwrap();
+        assert_eq!(
+            body,
+            "cdce7fda-909e-41cb-8507-abceb316a5b4,\"Export, as CSV\",\"Quote \"\"special\"\" characters\",false,,1700000000\n"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::Method;
+use actix_web::middleware::Next;
+use actix_web::Error;
+
+const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+/// An endpoint that is superseded and will be removed at the sunset date.
+pub struct DeprecatedEndpoint {
+    /// The method of the endpoint
+    pub method: Method,
+
+    /// The route pattern of the endpoint, as registered with the handler
+    pub pattern: &'static str,
+
+    /// The HTTP-date after which the endpoint may be removed
+    pub sunset: &'static str,
+}
+
+/// The registry of deprecated endpoints.
+pub const DEPRECATED_ENDPOINTS: [DeprecatedEndpoint; 1] = [
+    // Superseded by the partial update of `PATCH /todo/{id}`
+    DeprecatedEndpoint {
+        method: Method::PUT,
+        pattern: "/todo/{id}",
+        sunset: "Sat, 01 May 2027 00:00:00 GMT",
+    },
+];
+
+/// Returns the deprecation of the endpoint matching the given method and route pattern, if any.
+pub fn deprecation_of(method: &Method, pattern: &str) -> Option<&'static DeprecatedEndpoint> {
+    DEPRECATED_ENDPOINTS
+        .iter()
+        .find(|endpoint| endpoint.method == method && endpoint.pattern == pattern)
+}
+
+/// Middleware adding the `Deprecation` and `Sunset` (RFC 8594) headers to the responses of the
+/// deprecated endpoints, warning clients before the endpoints are removed.
+pub async fn deprecation_headers(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let deprecation = request
+        .match_pattern()
+        .and_then(|pattern| deprecation_of(request.method(), &pattern));
+
+    let mut response = next.call(request).await?;
+    if let Some(deprecation) = deprecation {
+        let headers = response.headers_mut();
+        headers.insert(DEPRECATION, HeaderValue::from_static("true"));
+        headers.insert(SUNSET, HeaderValue::from_static(deprecation.sunset));
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::{patch, put, test, App, HttpResponse, Responder};
+
+    #[put("/todo/{id}")]
+    async fn replace() -> impl Responder {
+        HttpResponse::Ok().finish()
+    }
+
+    #[patch("/todo/{id}")]
+    async fn modify() -> impl Responder {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn test_deprecation_headers() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(deprecation_headers))
+                .service(replace)
+                .service(modify),
+        )
+        .await;
+
+        let req = test::TestRequest::put().uri("/todo/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(DEPRECATION).unwrap(), "true");
+        assert_eq!(
+            resp.headers().get(SUNSET).unwrap(),
+            "Sat, 01 May 2027 00:00:00 GMT"
+        );
+
+        let req = test::TestRequest::patch().uri("/todo/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.headers().get(DEPRECATION).is_none());
+        assert!(resp.headers().get(SUNSET).is_none());
+    }
+}
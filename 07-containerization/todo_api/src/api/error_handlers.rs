@@ -0,0 +1,117 @@
+use actix_web::dev::ServiceResponse;
+use actix_web::error::BlockingError;
+use actix_web::http::StatusCode;
+use actix_web::middleware::{ErrorHandlerResponse, ErrorHandlers};
+use actix_web::{HttpResponse, Result};
+use log::error;
+use std::panic::{self, PanicHookInfo};
+use todo_shared::ErrorResponse;
+
+use crate::api::i18n::{Locale, Message};
+
+/// Installs a panic hook logging the location and payload of every panic.
+///
+/// Panics in `web::block` closures happen on the blocking thread pool, without the hook they would
+/// only show up as a failed blocking call.
+pub fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        let thread = std::thread::current();
+        error!(
+            "Thread '{}' panicked at {}: {}",
+            thread.name().unwrap_or("<unnamed>"),
+            info.location()
+                .map(|location| location.to_string())
+                .unwrap_or_else(|| "an unknown location".to_string()),
+            panic_message(info)
+        );
+    }));
+}
+
+/// Maps a failed `web::block` call, e.g. because its closure panicked, to a 503 with an
+/// `ErrorResponse`, logging the request that was being handled.
+///
+/// Other internal server errors are passed through unchanged.
+pub fn blocking_error_handlers<B: 'static>() -> ErrorHandlers<B> {
+    ErrorHandlers::new().handler(StatusCode::INTERNAL_SERVER_ERROR, handle_blocking_error)
+}
+
+fn handle_blocking_error<B>(response: ServiceResponse<B>) -> Result<ErrorHandlerResponse<B>> {
+    let blocking_error = response
+        .response()
+        .error()
+        .and_then(|error| error.as_error::<BlockingError>())
+        .is_some();
+    if !blocking_error {
+        return Ok(ErrorHandlerResponse::Response(
+            response.map_into_left_body(),
+        ));
+    }
+
+    let (request, _) = response.into_parts();
+    let locale = Locale::negotiate(&request);
+    error!(
+        "A blocking task failed while handling {} {}",
+        request.method(),
+        request.path()
+    );
+
+    let status = StatusCode::SERVICE_UNAVAILABLE;
+    let body = HttpResponse::build(status).json(ErrorResponse {
+        status: status.as_u16(),
+        message: Message::TryAgainLater.localize(locale).to_string(),
+        error_code: "UNAVAILABLE".to_string(),
+    });
+    Ok(ErrorHandlerResponse::Response(
+        ServiceResponse::new(request, body).map_into_right_body(),
+    ))
+}
+
+// The message a panic was raised with, which is a `&str` or `String` for `panic!` and `expect`.
+fn panic_message(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{get, test, web, App, Error};
+
+    use super::*;
+
+    #[get("/panic")]
+    async fn panicking() -> Result<HttpResponse, Error> {
+        web::block(|| panic!("Error loading todo items")).await?;
+        Ok(HttpResponse::Ok().finish())
+    }
+
+    #[get("/failure")]
+    async fn failing() -> Result<HttpResponse, Error> {
+        Err(actix_web::error::ErrorInternalServerError("Unexpected"))
+    }
+
+    #[actix_web::test]
+    async fn test_panicking_block_is_service_unavailable() {
+        let app = test::init_service(
+            App::new()
+                .wrap(blocking_error_handlers())
+                .service(panicking)
+                .service(failing),
+        )
+        .await;
+
+        let req = test::TestRequest::default().uri("/panic").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(body.status, 503);
+
+        // Other errors are left alone
+        let req = test::TestRequest::default().uri("/failure").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
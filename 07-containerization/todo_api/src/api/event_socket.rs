@@ -0,0 +1,68 @@
+use actix_ws::{CloseCode, CloseReason, Message, MessageStream, Session};
+use std::time::Duration;
+use todo_shared::TodoEvent;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::time::{interval_at, Instant, MissedTickBehavior};
+
+/// Forwards the received events to a WebSocket as JSON text frames, until either side closes it.
+///
+/// Pings of the client are answered with a pong, and the socket is pinged every keep-alive
+/// interval. Clients that didn't send anything for two intervals are disconnected. The
+/// subscription is dropped along with the socket, clients that fall behind skip the events they
+/// missed.
+///
+///  # Arguments
+///
+///  * `session` - The sending half of the socket.
+///  * `messages` - The messages received from the client.
+///  * `receiver` - The subscription to the published events.
+///  * `keep_alive` - The interval between the pings.
+pub async fn forward_events(
+    mut session: Session,
+    mut messages: MessageStream,
+    mut receiver: Receiver<TodoEvent>,
+    keep_alive: Duration,
+) {
+    let mut ticks = interval_at(Instant::now() + keep_alive, keep_alive);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_heard = Instant::now();
+
+    let reason = loop {
+        tokio::select! {
+            message = messages.recv() => {
+                last_heard = Instant::now();
+                match message {
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Ok(Message::Close(reason))) => break reason,
+                    // Clients only listen, anything else they send is ignored
+                    Some(Ok(_)) => {}
+                    Some(Err(_)) => break Some(CloseCode::Protocol.into()),
+                    None => return,
+                }
+            }
+            event = receiver.recv() => match event {
+                Ok(event) => {
+                    let frame = serde_json::to_string(&event).unwrap_or_default();
+                    if session.text(frame).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break Some(CloseCode::Away.into()),
+            },
+            _ = ticks.tick() => {
+                if last_heard.elapsed() > keep_alive * 2 {
+                    break Some(CloseReason::from((CloseCode::Away, "No response to pings")));
+                }
+                if session.ping(b"").await.is_err() {
+                    return;
+                }
+            }
+        }
+    };
+    let _ = session.close(reason).await;
+}
@@ -0,0 +1,83 @@
+use actix_web::web::Bytes;
+use actix_web::Error;
+use futures::Stream;
+use std::time::Duration;
+use todo_shared::TodoEvent;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tokio::time::{interval_at, Instant, MissedTickBehavior};
+
+/// The comment sent to idle connections, so proxies don't close them.
+const KEEP_ALIVE: &[u8] = b": keep-alive\n\n";
+
+/// Streams the received events as `text/event-stream` frames, until the channel closes.
+///
+/// Every event is a single `data` frame holding the event as JSON. A keep-alive comment is sent
+/// whenever no frame was sent within the keep-alive interval. Subscribers that fall behind skip
+/// the events they missed.
+///
+///  # Arguments
+///
+///  * `receiver` - The subscription to the published events.
+///  * `keep_alive` - The interval between the keep-alive comments.
+pub fn event_stream(
+    receiver: Receiver<TodoEvent>,
+    keep_alive: Duration,
+) -> impl Stream<Item = Result<Bytes, Error>> {
+    let mut ticks = interval_at(Instant::now() + keep_alive, keep_alive);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    futures::stream::unfold((receiver, ticks), |(mut receiver, mut ticks)| async move {
+        let frame = loop {
+            tokio::select! {
+                event = receiver.recv() => match event {
+                    Ok(event) => break to_frame(&event),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                },
+                _ = ticks.tick() => break Bytes::from_static(KEEP_ALIVE),
+            }
+        };
+        ticks.reset();
+        Some((Ok(frame), (receiver, ticks)))
+    })
+}
+
+// Formats an event as a `data` frame.
+fn to_frame(event: &TodoEvent) -> Bytes {
+    let data = serde_json::to_string(event).unwrap_or_default();
+    Bytes::from(format!("data: {}\n\n", data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use todo_shared::TodoEventKind;
+    use tokio::sync::broadcast;
+    use uuid::Uuid;
+
+    #[actix_web::test]
+    async fn test_events_and_keep_alive_frames() {
+        let (sender, receiver) = broadcast::channel(4);
+        let stream = event_stream(receiver, Duration::from_millis(50));
+        futures::pin_mut!(stream);
+
+        let id = Uuid::parse_str("cdce7fda-909e-41cb-8507-abceb316a5b4").unwrap();
+        sender
+            .send(TodoEvent {
+                kind: TodoEventKind::Updated,
+                id,
+            })
+            .unwrap();
+        assert_eq!(
+            stream.next().await.unwrap().unwrap(),
+            "data: {\"kind\":\"updated\",\"id\":\"cdce7fda-909e-41cb-8507-abceb316a5b4\"}\n\n"
+        );
+
+        // Idle connections get a comment
+        assert_eq!(stream.next().await.unwrap().unwrap(), KEEP_ALIVE);
+
+        // The stream ends with the channel
+        drop(sender);
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -0,0 +1,442 @@
+use actix_web::http::header::ContentType;
+use actix_web::web::{self, Data, ServiceConfig};
+use actix_web::{get, post, HttpResponse};
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{
+    Context, EmptySubscription, ErrorExtensions, InputObject, MaybeUndefined, Object, Schema,
+};
+use chrono::{DateTime, Utc};
+use log::error;
+use std::sync::Arc;
+use todo_shared::{
+    CreateTodoItemRequest, PageQuery, PatchTodoItemRequest, TodoEventKind, TodoItem,
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::i18n::Locale;
+use crate::api::todo_controller::{page_bounds, validate_minutes};
+use crate::auth::authenticated_user::AuthenticatedUser;
+use crate::data::repository::RepositoryError;
+use crate::data::todo_repository::{ListQuery, TodoRepository};
+use crate::entities::todo_entity::TodoEntity;
+use crate::entities::todo_patch::TodoPatch;
+use crate::services::activity;
+use crate::services::todo_events::TodoEvents;
+use crate::telemetry;
+
+/// The GraphQL schema of the todo items, served next to the REST API.
+pub type TodoSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+/// Builds the schema.
+///
+/// The repository, the event stream, the authenticated caller and the language of the client are
+/// attached to every request by `post_graphql`, so one schema serves all worker threads.
+pub fn schema() -> TodoSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription).finish()
+}
+
+/// The queries reading the todo items.
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// A page of the todo items, optionally only the completed or the incomplete ones.
+    ///
+    /// Paged like `GET /todo`, by default the first `DEFAULT_PAGE_SIZE` todo items are returned
+    /// and a limit beyond `MAX_PAGE_SIZE` or a negative offset is rejected as bad user input.
+    async fn todos(
+        &self,
+        ctx: &Context<'_>,
+        completed: Option<bool>,
+        offset: Option<i64>,
+        limit: Option<i64>,
+    ) -> async_graphql::Result<Vec<TodoItem>> {
+        let repository = repository(ctx)?.clone();
+        let page = page_bounds(&PageQuery { offset, limit })
+            .map_err(|error| bad_input(error.to_string()))?;
+        let listing = ListQuery {
+            completed,
+            ..Default::default()
+        };
+        let entities = telemetry::block(move || repository.get_listed(&listing, Some(page)))
+            .await
+            .map_err(|error| {
+                error!("Unable to list the todo items: {}", error);
+                async_graphql::Error::new("Unable to list the todo items")
+            })?
+            .map_err(repository_error)?;
+        Ok(entities.into_iter().map(TodoItem::from).collect())
+    }
+
+    /// The todo item with the given id, null when it doesn't exist.
+    async fn todo(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<Option<TodoItem>> {
+        let entity = repository(ctx)?
+            .get_by_id(id)
+            .await
+            .map_err(repository_error)?;
+        Ok(entity.map(TodoItem::from))
+    }
+}
+
+/// The changes to a todo item, absent fields are left untouched.
+#[derive(InputObject)]
+pub struct UpdateTodoInput {
+    /// The new title of the todo item
+    title: Option<String>,
+
+    /// The new description of the todo item
+    description: Option<String>,
+
+    /// Indicates whether the todo item is completed
+    completed: Option<bool>,
+
+    /// Timestamp when the todo item is due, `null` removes the deadline
+    due_date: MaybeUndefined<DateTime<Utc>>,
+}
+
+/// The mutations changing the todo items, published to the event stream like the REST changes.
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    /// Creates a todo item, owned by the authenticated caller if any.
+    ///
+    /// Validated like `POST /todo`, fails with the `DUPLICATE_TITLE` code when another todo item
+    /// has the same title while unique titles are enabled.
+    async fn create_todo(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateTodoItemRequest,
+    ) -> async_graphql::Result<TodoItem> {
+        validate(&input)?;
+        validate_effort(
+            ctx,
+            input.estimated_minutes,
+            input.actual_minutes,
+            input.remind_before_minutes,
+        )?;
+        let repository = repository(ctx)?;
+        if let Some(parent) = input.parent_id {
+            let existing = repository
+                .get_by_id(parent)
+                .await
+                .map_err(repository_error)?;
+            if existing.is_none() {
+                return Err(bad_input(format!(
+                    "The parent todo item {} doesn't exist",
+                    parent
+                )));
+            }
+        }
+
+        let mut entity: TodoEntity = input.into();
+        entity.owner = ctx
+            .data_opt::<AuthenticatedUser>()
+            .map(|user| user.subject.clone());
+        let entity = repository.insert(entity).await.map_err(repository_error)?;
+        record(ctx, "create", [entity.id]).await;
+        publish(ctx, TodoEventKind::Created, entity.id);
+        Ok(entity.into())
+    }
+
+    /// Updates the given fields of a todo item.
+    ///
+    /// The fields are changed within a single write, like `PATCH /todo/{id}`. Fails with the
+    /// `DUPLICATE_TITLE` code when another todo item has the new title while unique titles are
+    /// enabled, and with the `SUBTASKS_INCOMPLETE` code when completing it while its subtasks have
+    /// to be done first.
+    async fn update_todo(
+        &self,
+        ctx: &Context<'_>,
+        id: Uuid,
+        input: UpdateTodoInput,
+    ) -> async_graphql::Result<TodoItem> {
+        // The changed fields are held to the rules of a patch
+        let request = PatchTodoItemRequest {
+            title: input.title,
+            description: input.description,
+            completed: input.completed,
+            ..Default::default()
+        };
+        validate(&request)?;
+        let patch = TodoPatch {
+            due_date: match input.due_date {
+                MaybeUndefined::Value(due_date) => Some(Some(due_date)),
+                MaybeUndefined::Null => Some(None),
+                MaybeUndefined::Undefined => None,
+            },
+            ..request.into()
+        };
+
+        // Returns the subtasks completed along with the todo item, to publish them as well
+        let repository = repository(ctx)?.clone();
+        let changed = telemetry::block(move || repository.patch_with_previous(id, patch, None))
+            .await
+            .map_err(|error| {
+                error!("Unable to update todo item {}: {}", id, error);
+                async_graphql::Error::new("Unable to update the todo item")
+            })?
+            .map_err(repository_error)?
+            .ok_or_else(|| repository_error(RepositoryError::NotFound))?;
+        record(ctx, "update", [id]).await;
+        record(ctx, "complete", changed.completed_subtasks.iter().copied()).await;
+        publish(ctx, TodoEventKind::Updated, id);
+        for subtask in changed.completed_subtasks {
+            publish(ctx, TodoEventKind::Updated, subtask);
+        }
+        Ok(changed.updated.into())
+    }
+
+    /// Deletes a todo item, returns whether it existed.
+    async fn delete_todo(&self, ctx: &Context<'_>, id: Uuid) -> async_graphql::Result<bool> {
+        let deleted = repository(ctx)?
+            .delete(id)
+            .await
+            .map_err(repository_error)?;
+        if deleted {
+            record(ctx, "delete", [id]).await;
+            publish(ctx, TodoEventKind::Deleted, id);
+        }
+        Ok(deleted)
+    }
+}
+
+// The todo item repository, attached to the request by `post_graphql`.
+fn repository<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a Arc<dyn TodoRepository>> {
+    ctx.data::<Arc<dyn TodoRepository>>()
+}
+
+// Publishes a change to the event stream, when registered.
+fn publish(ctx: &Context<'_>, kind: TodoEventKind, id: Uuid) {
+    if let Some(events) = ctx.data_opt::<Arc<TodoEvents>>() {
+        events.publish(kind, id);
+    }
+}
+
+// Records a change made by the authenticated caller in the history, like the REST API does.
+async fn record(ctx: &Context<'_>, action: &str, ids: impl IntoIterator<Item = Uuid>) {
+    if let (Ok(repository), Some(user)) = (repository(ctx), ctx.data_opt::<AuthenticatedUser>()) {
+        activity::record(repository.clone(), &user.subject, action, ids).await;
+    }
+}
+
+// Rejects an input violating the validation rules of the REST requests.
+fn validate(input: &impl Validate) -> async_graphql::Result<()> {
+    input
+        .validate()
+        .map_err(|errors| bad_input(errors.to_string()))
+}
+
+// Rejects negative effort and reminder lead time values like the REST requests, explained in the
+// language of the client.
+fn validate_effort(
+    ctx: &Context<'_>,
+    estimated: Option<i32>,
+    actual: Option<i32>,
+    remind_before: Option<i32>,
+) -> async_graphql::Result<()> {
+    let locale = ctx.data_opt::<Locale>().copied().unwrap_or_default();
+    validate_minutes(estimated, actual, remind_before, locale)
+        .map_err(|error| bad_input(error.to_string()))
+}
+
+// An error caused by the input of the client.
+fn bad_input(message: String) -> async_graphql::Error {
+    async_graphql::Error::new(message).extend_with(|_, extensions| {
+        extensions.set("code", "BAD_USER_INPUT");
+    })
+}
+
+// Maps a repository error to a GraphQL error, with the `code` extension of the REST `error_code`.
+fn repository_error(error: RepositoryError) -> async_graphql::Error {
+    if let RepositoryError::PoolTimeout | RepositoryError::Database(_) = error {
+        error!("Unable to access the data store: {}", error);
+    }
+    let code = error.error_code();
+    async_graphql::Error::new(error.client_message())
+        .extend_with(|_, extensions| extensions.set("code", code))
+}
+
+/// Execute a GraphQL query or mutation.
+///
+/// Like the mutating REST endpoints, requests need a bearer token when authentication is enabled.
+/// Errors are returned in the `errors` of the response, with status 200.
+#[post("/graphql")]
+async fn post_graphql(
+    schema: Data<TodoSchema>,
+    request: web::Json<async_graphql::Request>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the changes to the event stream, when registered
+    locale: Locale,                  // The language of the error messages
+) -> HttpResponse {
+    let mut request = request
+        .into_inner()
+        .data(repository.into_inner())
+        .data(locale);
+    if let Some(events) = events {
+        request = request.data(events.into_inner());
+    }
+    if let Some(user) = user {
+        request = request.data(user);
+    }
+    HttpResponse::Ok().json(schema.execute(request).await)
+}
+
+/// Get the GraphiQL playground, to explore the schema and send queries from the browser.
+#[get("/graphql")]
+async fn get_graphiql() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+pub fn configure(schema: Data<TodoSchema>) -> impl FnOnce(&mut ServiceConfig) {
+    |config: &mut ServiceConfig| {
+        // The repository is registered for data injection by the todo controller
+        config
+            .app_data(schema)
+            .service(post_graphql)
+            .service(get_graphiql);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::todo_controller::tests::{
+        get_repository_mock_with_data, get_repository_mock_with_unique_titles,
+    };
+    use actix_web::{test, App};
+    use serde_json::{json, Value};
+    use todo_shared::MAX_PAGE_SIZE;
+
+    async fn execute(query: &str) -> Value {
+        execute_with(get_repository_mock_with_data(), query).await
+    }
+
+    async fn execute_with(repository: Arc<dyn TodoRepository>, query: &str) -> Value {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .configure(configure(Data::new(schema()))),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/graphql")
+            .set_json(json!({ "query": query }))
+            .to_request();
+        test::call_and_read_body_json(&app, req).await
+    }
+
+    #[actix_web::test]
+    async fn test_query_todos() {
+        let response = execute("{ todos(completed: true) { id title completed } }").await;
+        let todos = response["data"]["todos"].as_array().unwrap();
+        assert!(!todos.is_empty());
+        assert!(todos.iter().all(|todo| todo["completed"] == true));
+
+        let response =
+            execute(r#"{ todo(id: "cdce7fda-909e-41cb-8507-abceb316a5b4") { title } }"#).await;
+        assert_eq!(response["data"]["todo"]["title"], "Test the microservice");
+    }
+
+    #[actix_web::test]
+    async fn test_query_todos_paged() {
+        let repository = get_repository_mock_with_data();
+        for index in 0..MAX_PAGE_SIZE {
+            repository
+                .insert(
+                    CreateTodoItemRequest {
+                        title: format!("Paged item {}", index),
+                        description: "We should return a page of the items".to_string(),
+                        due_date: None,
+                        estimated_minutes: None,
+                        actual_minutes: None,
+                        remind_before_minutes: None,
+                        tags: Vec::new(),
+                        parent_id: None,
+                        recurrence: None,
+                    }
+                    .into(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let query = format!("{{ todos(limit: {}) {{ id }} }}", MAX_PAGE_SIZE);
+        let response = execute_with(repository.clone(), &query).await;
+        let todos = response["data"]["todos"].as_array().unwrap();
+        assert_eq!(todos.len(), MAX_PAGE_SIZE as usize);
+
+        let query = format!("{{ todos(offset: {}, limit: 10) {{ id }} }}", MAX_PAGE_SIZE);
+        let response = execute_with(repository.clone(), &query).await;
+        assert_eq!(response["data"]["todos"].as_array().unwrap().len(), 2);
+
+        // Pages out of range are rejected like the REST requests
+        for query in [
+            "{ todos(limit: 1000) { id } }",
+            "{ todos(offset: -1) { id } }",
+        ] {
+            let response = execute_with(repository.clone(), query).await;
+            assert_eq!(
+                response["errors"][0]["extensions"]["code"], "BAD_USER_INPUT",
+                "{}",
+                query
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_mutation() {
+        let response = execute(
+            r#"mutation { createTodo(input: { title: "Try GraphQL", description: "" }) { title completed tags } }"#,
+        )
+        .await;
+        assert_eq!(response["data"]["createTodo"]["title"], "Try GraphQL");
+        assert_eq!(response["data"]["createTodo"]["completed"], false);
+
+        // The validation rules of the REST requests apply
+        let response =
+            execute(r#"mutation { createTodo(input: { title: "", description: "" }) { id } }"#)
+                .await;
+        assert_eq!(
+            response["errors"][0]["extensions"]["code"],
+            "BAD_USER_INPUT"
+        );
+        let response = execute(
+            r#"mutation { createTodo(input: { title: "Negative", description: "", estimatedMinutes: -5 }) { id } }"#,
+        )
+        .await;
+        assert_eq!(
+            response["errors"][0]["extensions"]["code"],
+            "BAD_USER_INPUT"
+        );
+        assert!(response["errors"][0]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("estimated_minutes"));
+
+        // Like the REST requests, titles have to be unique while unique titles are enabled
+        let response = execute_with(
+            get_repository_mock_with_unique_titles(),
+            r#"mutation { createTodo(input: { title: "Test the microservice", description: "" }) { id } }"#,
+        )
+        .await;
+        assert_eq!(
+            response["errors"][0]["extensions"]["code"],
+            "DUPLICATE_TITLE"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_update_missing_todo_mutation() {
+        let response = execute(
+            r#"mutation { updateTodo(id: "00000000-0000-0000-0000-000000000000", input: { completed: true }) { id } }"#,
+        )
+        .await;
+        assert_eq!(response["errors"][0]["extensions"]["code"], "NOT_FOUND");
+    }
+}
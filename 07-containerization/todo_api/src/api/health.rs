@@ -0,0 +1,185 @@
+use actix_web::web::{Data, ServiceConfig};
+use actix_web::{get, HttpResponse};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use todo_shared::HealthStatus;
+
+use crate::data::db_context::DatabaseProbe;
+use crate::services::clock::Clock;
+use crate::telemetry;
+
+// The time to wait for a connection from the pool before reporting the database as unavailable.
+const DATABASE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Tracks when the instance may report it is ready to receive traffic.
+///
+/// Readiness is delayed for a while after startup, giving load balancers time to register the
+/// instance gradually while its caches and pools warm up.
+pub struct Readiness {
+    ready_at: DateTime<Utc>,
+    reported: AtomicBool,
+}
+
+impl Readiness {
+    /// Creates the readiness of an instance.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `started_at` - The point in time the instance started.
+    ///  * `delay` - The time to wait after startup before reporting readiness.
+    pub fn new(started_at: DateTime<Utc>, delay: Duration) -> Self {
+        Readiness {
+            ready_at: started_at + delay,
+            reported: AtomicBool::new(false),
+        }
+    }
+
+    /// Indicates whether the delay has elapsed, logging the first time it has.
+    pub fn is_ready(&self, now: DateTime<Utc>) -> bool {
+        let ready = now >= self.ready_at;
+        if ready && !self.reported.swap(true, Ordering::Relaxed) {
+            info!("Reporting readiness");
+        }
+        ready
+    }
+}
+
+/// Get the liveness of this instance.
+///
+/// Always returns 200 OK while the process is able to handle requests, regardless of the state of
+/// the database.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The instance is alive", body = HealthStatus)
+    )
+)]
+#[get("/health")]
+async fn get_health() -> HttpResponse {
+    HttpResponse::Ok().json(HealthStatus {
+        status: "ok".to_string(),
+    })
+}
+
+/// Get the readiness of this instance.
+///
+/// Returns 503 service unavailable until the `READINESS_DELAY_SECS` after startup have elapsed, so
+/// orchestrators only route traffic to the instance once it has warmed up, or when the database
+/// can't hand out a connection to run `SELECT 1` on within a short timeout.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The instance is ready to receive traffic", body = HealthStatus),
+        (status = 503, description = "The instance is still warming up or the database is unavailable", body = HealthStatus)
+    )
+)]
+#[get("/ready")]
+async fn get_ready(
+    readiness: Data<Readiness>, // The readiness of this instance, injected from app_data
+    clock: Data<dyn Clock>, // The clock to compare the readiness time with, injected from app_data
+    probe: Data<dyn DatabaseProbe>, // The probe checking the database, injected from app_data
+) -> HttpResponse {
+    if !readiness.is_ready(clock.now()) {
+        return HttpResponse::ServiceUnavailable().json(HealthStatus {
+            status: "warming_up".to_string(),
+        });
+    }
+
+    let available = match telemetry::block(move || probe.probe(DATABASE_TIMEOUT)).await {
+        Ok(result) => result.map_err(|error| error.to_string()),
+        Err(error) => Err(error.to_string()),
+    };
+    if let Err(error) = available {
+        warn!("The database is unavailable: {}", error);
+        return HttpResponse::ServiceUnavailable().json(HealthStatus {
+            status: "database_unavailable".to_string(),
+        });
+    }
+
+    HttpResponse::Ok().json(HealthStatus {
+        status: "ready".to_string(),
+    })
+}
+
+pub fn configure(readiness: Data<Readiness>) -> impl FnOnce(&mut ServiceConfig) {
+    move |config: &mut ServiceConfig| {
+        // The clock and database probe are registered for data injection by the todo controller
+        config
+            .app_data(readiness)
+            .service(get_health)
+            .service(get_ready);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::repository::RepositoryError;
+    use crate::services::clock::FixedClock;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+    use std::sync::Arc;
+
+    // A database probe with a fixed outcome.
+    struct DatabaseProbeMock(bool);
+
+    impl DatabaseProbe for DatabaseProbeMock {
+        fn probe(&self, _timeout: Duration) -> Result<(), RepositoryError> {
+            match self.0 {
+                true => Ok(()),
+                false => Err(RepositoryError::PoolTimeout),
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_health_ignores_database() {
+        let readiness = Data::new(Readiness::new(DateTime::UNIX_EPOCH, Duration::ZERO));
+        let clock: Arc<dyn Clock> = Arc::new(FixedClock(DateTime::UNIX_EPOCH));
+        let probe: Arc<dyn DatabaseProbe> = Arc::new(DatabaseProbeMock(false));
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(clock))
+                .app_data(Data::from(probe))
+                .configure(configure(readiness)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/health").to_request();
+        let status: HealthStatus = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(status.status, "ok");
+
+        let req = test::TestRequest::get().uri("/ready").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let status: HealthStatus = test::read_body_json(resp).await;
+        assert_eq!(status.status, "database_unavailable");
+    }
+
+    #[actix_web::test]
+    async fn test_ready_after_delay() {
+        let started_at = DateTime::from_timestamp(1_664_452_800, 0).unwrap();
+        let readiness = Data::new(Readiness::new(started_at, Duration::from_secs(30)));
+
+        for (elapsed, expected) in [
+            (0, StatusCode::SERVICE_UNAVAILABLE),
+            (29, StatusCode::SERVICE_UNAVAILABLE),
+            (30, StatusCode::OK),
+        ] {
+            let clock: Arc<dyn Clock> =
+                Arc::new(FixedClock(started_at + Duration::from_secs(elapsed)));
+            let probe: Arc<dyn DatabaseProbe> = Arc::new(DatabaseProbeMock(true));
+            let app = test::init_service(
+                App::new()
+                    .app_data(Data::from(clock))
+                    .app_data(Data::from(probe))
+                    .configure(configure(readiness.clone())),
+            )
+            .await;
+
+            let req = test::TestRequest::get().uri("/ready").to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), expected, "after {} seconds", elapsed);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use actix_web::dev::Payload;
+use actix_web::http::header::{AcceptLanguage, Header, Preference};
+use actix_web::{Error, FromRequest, HttpRequest};
+use std::future::{ready, Ready};
+
+/// The languages error messages are available in, English when the client prefers none of them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    Dutch,
+}
+
+impl Locale {
+    /// Picks the supported language ranked highest by the `Accept-Language` header of a request.
+    ///
+    /// Only the primary language subtag is compared, so `nl-BE` selects Dutch. Falls back to
+    /// English when the header is absent, invalid or lists no supported language.
+    pub fn negotiate(request: &HttpRequest) -> Self {
+        let ranked = AcceptLanguage::parse(request)
+            .map(|header| header.ranked())
+            .unwrap_or_default();
+        ranked
+            .iter()
+            .find_map(|preference| match preference {
+                Preference::Any => Some(Locale::English),
+                Preference::Specific(tag) => Locale::from_language(tag.primary_language()),
+            })
+            .unwrap_or_default()
+    }
+
+    // Maps an ISO 639 language code to the supported locale.
+    fn from_language(language: &str) -> Option<Self> {
+        match language.to_ascii_lowercase().as_str() {
+            "en" => Some(Locale::English),
+            "nl" => Some(Locale::Dutch),
+            _ => None,
+        }
+    }
+}
+
+impl FromRequest for Locale {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(Locale::negotiate(req)))
+    }
+}
+
+/// The error messages that are returned in the language of the client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message {
+    /// An effort or reminder lead time is negative, preceded by the name of the field
+    NegativeMinutes,
+
+    /// A batch request doesn't contain any todo item
+    EmptyBatch,
+
+    /// A blocking task failed and the request can be retried
+    TryAgainLater,
+}
+
+impl Message {
+    /// Returns the message in the given language.
+    pub fn localize(self, locale: Locale) -> &'static str {
+        match (self, locale) {
+            (Message::NegativeMinutes, Locale::English) => "must not be negative",
+            (Message::NegativeMinutes, Locale::Dutch) => "mag niet negatief zijn",
+            (Message::EmptyBatch, Locale::English) => "At least one todo item is required",
+            (Message::EmptyBatch, Locale::Dutch) => "Er is minstens één todo item nodig",
+            (Message::TryAgainLater, Locale::English) => {
+                "The request could not be completed, please try again later"
+            }
+            (Message::TryAgainLater, Locale::Dutch) => {
+                "Het verzoek kon niet worden afgerond, probeer het later opnieuw"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::ACCEPT_LANGUAGE;
+    use actix_web::test::TestRequest;
+
+    fn negotiate(accept_language: &str) -> Locale {
+        let request = TestRequest::default()
+            .insert_header((ACCEPT_LANGUAGE, accept_language))
+            .to_http_request();
+        Locale::negotiate(&request)
+    }
+
+    #[test]
+    fn test_negotiate_locale() {
+        assert_eq!(negotiate("nl-NL,nl;q=0.9,en;q=0.8"), Locale::Dutch);
+        assert_eq!(negotiate("de-DE,en;q=0.5,nl;q=0.7"), Locale::Dutch);
+        assert_eq!(negotiate("en-GB,nl;q=0.9"), Locale::English);
+
+        // Unsupported and missing languages fall back to English
+        assert_eq!(negotiate("fr-FR,de;q=0.8"), Locale::English);
+        assert_eq!(
+            Locale::negotiate(&TestRequest::default().to_http_request()),
+            Locale::English
+        );
+    }
+}
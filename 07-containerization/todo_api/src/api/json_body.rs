@@ -0,0 +1,121 @@
+use actix_web::dev::Payload;
+use actix_web::error::{InternalError, JsonPayloadError};
+use actix_web::web::{Bytes, Data, Json, JsonConfig};
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use serde::de::DeserializeOwned;
+use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use todo_shared::ErrorResponse;
+
+use crate::api::cbor::{self, CBOR};
+
+/// Options controlling how JSON request bodies are deserialized.
+#[derive(Clone, Copy, Default)]
+pub struct JsonOptions {
+    /// When set, request bodies containing fields that are not part of the model are rejected.
+    pub strict: bool,
+}
+
+impl JsonOptions {
+    /// Reads the options from the environment.
+    ///
+    /// Strict mode is enabled by setting `STRICT_JSON=true`, lenient mode is the default.
+    pub fn from_env() -> Self {
+        JsonOptions {
+            strict: env::var("STRICT_JSON")
+                .map(|value| value.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Configures the JSON extraction, which `JsonBody` builds on, to accept bodies of at most `limit`
+/// bytes.
+///
+/// Larger bodies are rejected with a 413 `ErrorResponse` stating the limit, other errors keep the
+/// default responses of actix.
+///
+///  # Arguments
+///
+///  * `limit` - The maximum number of bytes of a JSON request body.
+pub fn json_config(limit: usize) -> JsonConfig {
+    JsonConfig::default()
+        .limit(limit)
+        .error_handler(|error, _| match error {
+            JsonPayloadError::Overflow { limit }
+            | JsonPayloadError::OverflowKnownLength { limit, .. } => payload_too_large(limit),
+            error => error.into(),
+        })
+}
+
+/// Rejects a request body larger than `limit` bytes with a 413 `ErrorResponse`.
+pub fn payload_too_large(limit: usize) -> Error {
+    let message = format!(
+        "The request body is larger than the limit of {} bytes",
+        limit
+    );
+    let response = HttpResponse::PayloadTooLarge().json(ErrorResponse {
+        status: 413,
+        message: message.clone(),
+        error_code: "PAYLOAD_TOO_LARGE".to_string(),
+    });
+    InternalError::from_response(message, response).into()
+}
+
+/// Json extractor honouring the registered `JsonOptions`.
+///
+/// Bodies sent with `Content-Type: application/cbor` are decoded from CBOR instead, with the same
+/// options applied. In lenient mode unknown fields are ignored, just like `web::Json<T>` does.
+/// In strict mode the request is rejected with 422 naming the first unexpected field.
+pub struct JsonBody<T>(pub T);
+
+impl<T> JsonBody<T> {
+    /// Unwrap into the inner deserialized value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for JsonBody<T> {
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let strict = req
+            .app_data::<Data<JsonOptions>>()
+            .map(|options| options.strict)
+            .unwrap_or(false);
+        let value: Pin<Box<dyn Future<Output = Result<serde_json::Value, Error>>>> =
+            match req.content_type() == CBOR {
+                true => {
+                    let bytes = Bytes::from_request(req, payload);
+                    Box::pin(async move {
+                        cbor::cbor_to_json(&bytes.await?).map_err(actix_web::error::ErrorBadRequest)
+                    })
+                }
+                false => {
+                    let json = Json::<serde_json::Value>::from_request(req, payload);
+                    Box::pin(async move { Ok(json.await?.into_inner()) })
+                }
+            };
+
+        Box::pin(async move {
+            let value = value.await?;
+
+            // Deserialize while keeping track of every field serde had to skip.
+            let mut unknown_fields = Vec::new();
+            let body =
+                serde_ignored::deserialize(value, |path| unknown_fields.push(path.to_string()))
+                    .map_err(actix_web::error::ErrorBadRequest)?;
+
+            match unknown_fields.first() {
+                Some(field) if strict => Err(actix_web::error::ErrorUnprocessableEntity(format!(
+                    "Unknown field `{}` in request body",
+                    field
+                ))),
+                _ => Ok(JsonBody(body)),
+            }
+        })
+    }
+}
@@ -0,0 +1,235 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::web::{Data, ServiceConfig};
+use actix_web::{get, Error, HttpResponse};
+use log::error;
+use prometheus::core::{Collector, Desc};
+use prometheus::proto::MetricFamily;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::data::db_context::PostgresPool;
+
+// The path of requests that matched no route, so unknown paths don't add a series each.
+const UNMATCHED: &str = "unmatched";
+
+/// The metrics of the requests handled by all worker threads, and of their connection pools.
+///
+/// Requests are counted and timed by method, route pattern and status, the requests in flight by
+/// method. The metrics are kept in a registry of their own, rather than the global one.
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    durations: HistogramVec,
+    in_flight: IntGaugeVec,
+    pools: Arc<Mutex<Vec<PostgresPool>>>,
+}
+
+// Reports the connections of the watched pools when the metrics are gathered, summed over all
+// pools.
+struct PoolCollector {
+    pools: Arc<Mutex<Vec<PostgresPool>>>,
+    connections: IntGauge,
+    idle: IntGauge,
+}
+
+impl Collector for PoolCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        let mut descs = self.connections.desc();
+        descs.extend(self.idle.desc());
+        descs
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let (connections, idle) = self
+            .pools
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|pool| pool.state())
+            .fold((0, 0), |(connections, idle), state| {
+                (
+                    connections + state.connections,
+                    idle + state.idle_connections,
+                )
+            });
+        self.connections.set(connections.into());
+        self.idle.set(idle.into());
+
+        let mut families = self.connections.collect();
+        families.extend(self.idle.collect());
+        families
+    }
+}
+
+impl Metrics {
+    /// Creates the metrics and registers them.
+    pub fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new();
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "The number of handled requests"),
+            &["method", "path", "status"],
+        )?;
+        let durations = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "The time it took to handle a request",
+            ),
+            &["method", "path", "status"],
+        )?;
+        let in_flight = IntGaugeVec::new(
+            Opts::new(
+                "http_requests_in_flight",
+                "The number of requests being handled",
+            ),
+            &["method"],
+        )?;
+        let pools = Arc::new(Mutex::new(Vec::new()));
+        let pool_collector = PoolCollector {
+            pools: pools.clone(),
+            connections: IntGauge::new(
+                "db_pool_connections",
+                "The number of open database connections",
+            )?,
+            idle: IntGauge::new(
+                "db_pool_idle_connections",
+                "The number of open database connections that are not checked out",
+            )?,
+        };
+
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(durations.clone()))?;
+        registry.register(Box::new(in_flight.clone()))?;
+        registry.register(Box::new(pool_collector))?;
+        Ok(Metrics {
+            registry,
+            requests,
+            durations,
+            in_flight,
+            pools,
+        })
+    }
+
+    /// Includes the connections of a pool in the pool metrics, e.g. the pool of a worker thread.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `pool` - The connection pool to watch.
+    pub fn watch_pool(&self, pool: PostgresPool) {
+        self.pools.lock().unwrap().push(pool);
+    }
+
+    /// Returns the current value of all metrics, in the Prometheus text format.
+    pub fn encode(&self) -> prometheus::Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        String::from_utf8(buffer).map_err(|error| prometheus::Error::Msg(error.to_string()))
+    }
+}
+
+/// Middleware counting and timing the requests, by method, route pattern and status.
+pub async fn record_metrics(
+    metrics: Data<Metrics>,
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let method = request.method().to_string();
+    let in_flight = metrics.in_flight.with_label_values(&[&method]);
+    in_flight.inc();
+    let started = Instant::now();
+    let result = next.call(request).await;
+    in_flight.dec();
+
+    // The route pattern is only known once a route matched, failed middleware never got there
+    let (path, status) = match &result {
+        Ok(response) => (
+            response.request().match_pattern(),
+            response.status().as_u16(),
+        ),
+        Err(error) => (None, error.as_response_error().status_code().as_u16()),
+    };
+    let path = path.as_deref().unwrap_or(UNMATCHED);
+    let labels = [method.as_str(), path, &status.to_string()];
+    metrics.requests.with_label_values(&labels).inc();
+    metrics
+        .durations
+        .with_label_values(&labels)
+        .observe(started.elapsed().as_secs_f64());
+    result
+}
+
+/// Get the metrics of this instance.
+///
+/// Returns the request and connection pool metrics in the Prometheus text format, to be scraped.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The metrics in the Prometheus text format", body = String, content_type = "text/plain"),
+        (status = 500, description = "The metrics could not be encoded")
+    )
+)]
+#[get("/metrics")]
+async fn get_metrics(
+    metrics: Data<Metrics>, // The metrics of all worker threads, injected from app_data
+) -> HttpResponse {
+    match metrics.encode() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type(TextEncoder::new().format_type())
+            .body(body),
+        Err(error) => {
+            error!("Unable to encode the metrics: {}", error);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+pub fn configure(metrics: Data<Metrics>) -> impl FnOnce(&mut ServiceConfig) {
+    move |config: &mut ServiceConfig| {
+        config.app_data(metrics).service(get_metrics);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::{test, App, Responder};
+
+    #[get("/todo/{id}")]
+    async fn get_todo() -> impl Responder {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn test_requests_are_recorded_by_route() {
+        let metrics = Data::new(Metrics::new().unwrap());
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(record_metrics))
+                .configure(configure(metrics.clone()))
+                .service(get_todo),
+        )
+        .await;
+
+        for uri in ["/todo/1", "/todo/2", "/unknown"] {
+            test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        }
+
+        let resp =
+            test::call_service(&app, test::TestRequest::get().uri("/metrics").to_request()).await;
+        assert!(resp.status().is_success());
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body
+            .contains("http_requests_total{method=\"GET\",path=\"/todo/{id}\",status=\"200\"} 2"));
+        assert!(body
+            .contains("http_requests_total{method=\"GET\",path=\"unmatched\",status=\"404\"} 1"));
+        assert!(body.contains("http_request_duration_seconds_count{method=\"GET\",path=\"/todo/{id}\",status=\"200\"} 2"));
+        // Only the scrape itself is in flight
+        assert!(body.contains("http_requests_in_flight{method=\"GET\"} 1"));
+        assert!(body.contains("db_pool_connections 0"));
+    }
+}
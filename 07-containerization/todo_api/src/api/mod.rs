@@ -1,28 +1,274 @@
+pub mod auth;
+pub mod capabilities_controller;
+pub mod cbor;
+pub mod cors;
+pub mod csv_body;
+pub mod deprecation;
+pub mod error_handlers;
+pub mod event_socket;
+pub mod event_stream;
+pub mod graphql;
+pub mod health;
+pub mod i18n;
+pub mod json_body;
+pub mod metrics;
+pub mod postman;
+pub mod rate_limit;
+pub mod repository_error;
+pub mod request_id;
+pub mod signature;
 pub mod todo_controller;
 pub use todo_controller::configure;
-use todo_shared::{CreateTodoItemRequest, TodoItem, UpdateTodoItemRequest};
-use utoipa::OpenApi;
+use todo_shared::{
+    ActivityEntry, AddChecklistItemRequest, AddDependencyRequest, AddTagRequest, Capabilities,
+    CapabilityLimits, ChecklistItem, ChecklistProgress, CreateTodoItemRequest,
+    DeleteTodoItemsRequest, DeleteTodoItemsResponse, EffortSummary, ErrorResponse, HealthStatus,
+    ImportError, ImportSummary, PatchTodoItemRequest, PurgeDeletedResponse, ReassignOwnerRequest,
+    ReassignOwnerResponse, SyncOperation, SyncOperationKind, SyncOperationResult, TagStats,
+    TagStatsRequest, TodoCount, TodoEvent, TodoEventKind, TodoGraph, TodoGraphEdge, TodoGraphNode,
+    TodoItem, TodoOccurrences, TodoPage, TodoStats, TodoStatus, TodoStatusResponse,
+    UpdateTodoItemRequest,
+};
+use utoipa::openapi::path::Operation;
+use utoipa::openapi::schema::{OneOfBuilder, RefOr, Schema};
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+/// Adds the `bearer_auth` security scheme, which the mutating endpoints require, to the spec.
+///
+/// Swagger UI renders an Authorize button for it, to send a bearer token along with the requests.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+        );
+    }
+}
 
 pub fn register_open_api_spec() -> utoipa::openapi::OpenApi {
     #[derive(OpenApi)]
     #[openapi(
         paths(
+            capabilities_controller::get_capabilities,
+            health::get_health,
+            health::get_ready,
+            metrics::get_metrics,
             todo_controller::get_todos,
             todo_controller::get_todo_by_id,
+            todo_controller::head_todo,
+            todo_controller::get_todo_status,
+            todo_controller::get_occurrences,
+            todo_controller::get_todo_stats,
+            todo_controller::get_effort_summary,
+            todo_controller::get_tag_stats,
+            todo_controller::export_todos,
+            todo_controller::export_todos_csv,
+            todo_controller::import_todos,
+            todo_controller::search_todos,
+            todo_controller::count_todos,
             todo_controller::create_todo,
+            todo_controller::create_todos,
+            todo_controller::sync_todos,
+            todo_controller::reassign_by_owner,
             todo_controller::update_todo,
+            todo_controller::patch_todo,
+            todo_controller::complete_todo,
+            todo_controller::incomplete_todo,
+            todo_controller::restore_todo,
+            todo_controller::add_checklist_item,
+            todo_controller::toggle_checklist_item,
+            todo_controller::add_tag,
+            todo_controller::remove_tag,
+            todo_controller::get_todo_graph,
+            todo_controller::get_todo_events,
+            todo_controller::get_todo_events_socket,
+            todo_controller::add_dependency,
+            todo_controller::remove_dependency,
+            todo_controller::get_activity,
             todo_controller::delete_todo,
+            todo_controller::delete_todos,
+            todo_controller::purge_deleted_todos,
         ),
         components(
-            schemas(TodoItem, UpdateTodoItemRequest, CreateTodoItemRequest)
+            schemas(
+                Capabilities, CapabilityLimits, ErrorResponse, HealthStatus,
+                TodoItem, TodoPage, UpdateTodoItemRequest, PatchTodoItemRequest, CreateTodoItemRequest,
+                EffortSummary, TodoCount, TodoStats, TagStatsRequest, TagStats,
+                TodoStatus, TodoStatusResponse, TodoOccurrences,
+                SyncOperation, SyncOperationKind, SyncOperationResult,
+                TodoEvent, TodoEventKind,
+                ReassignOwnerRequest, ReassignOwnerResponse,
+                DeleteTodoItemsRequest, DeleteTodoItemsResponse, PurgeDeletedResponse,
+                ImportSummary, ImportError,
+                ActivityEntry,
+                ChecklistItem, ChecklistProgress, AddChecklistItemRequest, AddTagRequest,
+                TodoGraph, TodoGraphNode, TodoGraphEdge, AddDependencyRequest
+            )
         ),
         tags(
             (name = "todo", description = "Todo management endpoints.")
-        )
+        ),
+        modifiers(&SecurityAddon)
     )]
 
     struct ApiDoc;
+
+    // The handlers on the same path and method as a handler above, selected by a guard instead
+    #[derive(OpenApi)]
+    #[openapi(paths(
+        todo_controller::get_todos_after,
+        todo_controller::merge_patch_todo,
+        todo_controller::import_todos_upload,
+    ))]
+    struct GuardedApiDoc;
+
     // Make instance variable of ApiDoc so all worker threads gets the same instance.
-    let openapi = ApiDoc::openapi();
+    let mut openapi = ApiDoc::openapi();
+    merge_guarded(&mut openapi, GuardedApiDoc::openapi());
     openapi
 }
+
+/// Adds the operations of the guarded handlers to the operations on the same path and method.
+///
+/// utoipa keeps a single operation per path and method, so listing a guarded handler along with
+/// the other one would replace it. Instead the parameters, request body content types and
+/// responses of the guarded handler are added to the operation, and a response with another
+/// schema for the same status and content type becomes a `oneOf` of both.
+fn merge_guarded(openapi: &mut utoipa::openapi::OpenApi, guarded: utoipa::openapi::OpenApi) {
+    for (path, item) in guarded.paths.paths {
+        let Some(known) = openapi.paths.paths.get_mut(&path) else {
+            openapi.paths.paths.insert(path, item);
+            continue;
+        };
+        for (method, operation) in item.operations {
+            match known.operations.get_mut(&method) {
+                Some(known) => merge_operation(known, operation),
+                None => {
+                    known.operations.insert(method, operation);
+                }
+            }
+        }
+    }
+}
+
+fn merge_operation(operation: &mut Operation, guarded: Operation) {
+    let parameters = operation.parameters.get_or_insert_with(Vec::new);
+    for parameter in guarded.parameters.into_iter().flatten() {
+        if !parameters.iter().any(|known| known.name == parameter.name) {
+            parameters.push(parameter);
+        }
+    }
+
+    if let Some(request_body) = guarded.request_body {
+        match &mut operation.request_body {
+            Some(known) => known.content.extend(request_body.content),
+            None => operation.request_body = Some(request_body),
+        }
+    }
+
+    for (status, response) in guarded.responses.responses {
+        let Some(known) = operation.responses.responses.get_mut(&status) else {
+            operation.responses.responses.insert(status, response);
+            continue;
+        };
+        let (RefOr::T(known), RefOr::T(response)) = (known, response) else {
+            continue;
+        };
+        for (content_type, content) in response.content {
+            match known.content.get_mut(&content_type) {
+                Some(existing) if same_schema(&existing.schema, &content.schema) => {}
+                Some(existing) => {
+                    existing.schema = one_of(existing.schema.clone(), content.schema);
+                    known.description =
+                        format!("{}, or {}", known.description, response.description);
+                }
+                None => {
+                    known.content.insert(content_type, content);
+                }
+            }
+        }
+    }
+}
+
+fn same_schema(schema: &RefOr<Schema>, other: &RefOr<Schema>) -> bool {
+    serde_json::to_value(schema).ok() == serde_json::to_value(other).ok()
+}
+
+fn one_of(schema: RefOr<Schema>, other: RefOr<Schema>) -> RefOr<Schema> {
+    match schema {
+        RefOr::T(Schema::OneOf(mut one_of)) => {
+            one_of.items.push(other);
+            RefOr::T(Schema::OneOf(one_of))
+        }
+        schema => RefOr::T(Schema::OneOf(
+            OneOfBuilder::new().item(schema).item(other).build(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_bearer_auth_security_scheme() {
+        let openapi = register_open_api_spec();
+        let spec = serde_json::to_value(&openapi).unwrap();
+
+        let scheme = &spec["components"]["securitySchemes"]["bearer_auth"];
+        assert_eq!(scheme["type"], "http");
+        assert_eq!(scheme["scheme"], "bearer");
+
+        for (path, method) in [
+            ("/todo", "post"),
+            ("/todo/{id}", "put"),
+            ("/todo/{id}", "delete"),
+        ] {
+            let security = &spec["paths"][path][method]["security"];
+            assert!(
+                security[0].get("bearer_auth").is_some(),
+                "{} {}",
+                method,
+                path
+            );
+        }
+        assert!(spec["paths"]["/todo"]["get"]["security"].is_null());
+    }
+
+    #[actix_web::test]
+    async fn test_guarded_handlers_are_documented() {
+        let openapi = register_open_api_spec();
+        let spec = serde_json::to_value(&openapi).unwrap();
+
+        // The cursor pages are documented along with the listed todo items
+        let list = &spec["paths"]["/todo"]["get"];
+        let schemas = &list["responses"]["200"]["content"]["application/json"]["schema"]["oneOf"];
+        assert_eq!(schemas.as_array().map(Vec::len), Some(2));
+        assert_eq!(schemas[1]["$ref"], "#/components/schemas/TodoPage");
+        let parameters = list["parameters"].as_array().unwrap();
+        assert_eq!(
+            parameters
+                .iter()
+                .filter(|parameter| parameter["name"] == "after")
+                .count(),
+            1
+        );
+
+        let patch = &spec["paths"]["/todo/{id}"]["patch"]["requestBody"]["content"];
+        assert!(patch.get("application/json").is_some());
+        assert!(patch.get("application/merge-patch+json").is_some());
+
+        let import = &spec["paths"]["/todo/import"]["post"];
+        assert!(import["requestBody"]["content"]
+            .get("application/json")
+            .is_some());
+        assert!(import["requestBody"]["content"]
+            .get("multipart/form-data")
+            .is_some());
+        assert!(import["security"][0].get("bearer_auth").is_some());
+    }
+}
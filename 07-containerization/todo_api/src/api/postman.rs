@@ -0,0 +1,226 @@
+use actix_web::web::{Data, ServiceConfig};
+use actix_web::{get, HttpResponse};
+use serde_json::{json, Map, Value};
+use utoipa::openapi::OpenApi;
+
+// The HTTP methods an OpenAPI path item can describe an operation for.
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+// The tag to group operations without any tags under.
+const DEFAULT_TAG: &str = "default";
+
+/// A Postman v2.1 collection, converted from the OpenAPI specification.
+pub struct PostmanCollection(pub Value);
+
+/// Get the Postman collection of this API.
+///
+/// Contains a folder per tag with a request per operation, so the API can be imported in Postman
+/// without a third-party converter.
+#[get("/api-doc/postman.json")]
+async fn get_postman_collection(
+    collection: Data<PostmanCollection>, // The converted collection, injected from app_data
+) -> HttpResponse {
+    HttpResponse::Ok().json(&collection.0)
+}
+
+pub fn configure(collection: Data<PostmanCollection>) -> impl FnOnce(&mut ServiceConfig) {
+    move |config: &mut ServiceConfig| {
+        config.app_data(collection).service(get_postman_collection);
+    }
+}
+
+/// Converts an OpenAPI specification to a Postman v2.1 collection.
+///
+/// The requests use a `baseUrl` collection variable, path parameters become Postman path
+/// variables and JSON request bodies get an example generated from their schema.
+///
+///  # Arguments
+///
+///  * `openapi` - The OpenAPI specification to convert.
+pub fn to_collection(openapi: &OpenApi) -> Value {
+    let spec = serde_json::to_value(openapi).unwrap_or_default();
+    let schemas = &spec["components"]["schemas"];
+
+    // Folders in the order their tag is first encountered, requests in the order of the paths.
+    let mut folders: Vec<(String, Vec<Value>)> = Vec::new();
+    let paths = spec["paths"].as_object().cloned().unwrap_or_default();
+    for (path, item) in &paths {
+        for method in METHODS {
+            let operation = &item[method];
+            if operation.is_null() {
+                continue;
+            }
+
+            let tag = operation["tags"][0].as_str().unwrap_or(DEFAULT_TAG);
+            let request = to_request(path, method, operation, schemas);
+            match folders.iter_mut().find(|(name, _)| name == tag) {
+                Some((_, requests)) => requests.push(request),
+                None => folders.push((tag.to_string(), vec![request])),
+            }
+        }
+    }
+
+    json!({
+        "info": {
+            "name": spec["info"]["title"],
+            "description": spec["info"]["description"],
+            "version": spec["info"]["version"],
+            "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json",
+        },
+        "item": folders
+            .into_iter()
+            .map(|(name, requests)| json!({ "name": name, "item": requests }))
+            .collect::<Vec<_>>(),
+        "variable": [{ "key": "baseUrl", "value": "http://localhost:8080" }],
+    })
+}
+
+// Converts a single operation to a Postman request item.
+fn to_request(path: &str, method: &str, operation: &Value, schemas: &Value) -> Value {
+    // Postman marks path variables with a colon, rather than braces.
+    let segments: Vec<String> = path
+        .trim_start_matches('/')
+        .split('/')
+        .map(|segment| match segment.strip_prefix('{') {
+            Some(name) => format!(":{}", name.trim_end_matches('}')),
+            None => segment.to_string(),
+        })
+        .collect();
+    let parameters = operation["parameters"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let parameter = |location: &str| {
+        parameters
+            .iter()
+            .filter(|parameter| parameter["in"] == location)
+            .map(|parameter| {
+                json!({
+                    "key": parameter["name"],
+                    "value": "",
+                    "description": parameter["description"],
+                    "disabled": location == "query" && parameter["required"] != true,
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut request = json!({
+        "method": method.to_uppercase(),
+        "header": [],
+        "url": {
+            "raw": format!("{{{{baseUrl}}}}/{}", segments.join("/")),
+            "host": ["{{baseUrl}}"],
+            "path": segments,
+            "query": parameter("query"),
+            "variable": parameter("path"),
+        },
+        "description": operation["description"],
+    });
+
+    let body_schema = &operation["requestBody"]["content"]["application/json"]["schema"];
+    if !body_schema.is_null() {
+        let example = example(body_schema, schemas, 0);
+        request["header"] = json!([{ "key": "Content-Type", "value": "application/json" }]);
+        request["body"] = json!({
+            "mode": "raw",
+            "raw": serde_json::to_string_pretty(&example).unwrap_or_default(),
+            "options": { "raw": { "language": "json" } },
+        });
+    }
+
+    let name = operation["summary"]
+        .as_str()
+        .or_else(|| operation["operationId"].as_str())
+        .unwrap_or(path);
+    json!({ "name": name, "request": request })
+}
+
+// Generates an example value for a schema, resolving references to the component schemas.
+fn example(schema: &Value, schemas: &Value, depth: usize) -> Value {
+    // Stop at recursive schemas, rather than expanding them forever.
+    if depth > 8 {
+        return Value::Null;
+    }
+    if let Some(example) = schema.get("example") {
+        return example.clone();
+    }
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.trim_start_matches("#/components/schemas/");
+        return example(&schemas[name], schemas, depth + 1);
+    }
+    if let Some(value) = schema["enum"].get(0) {
+        return value.clone();
+    }
+    if let Some(variant) = schema["oneOf"].get(0).or_else(|| schema["allOf"].get(0)) {
+        return example(variant, schemas, depth + 1);
+    }
+
+    match schema["type"].as_str() {
+        Some("string") => match schema["format"].as_str() {
+            Some("uuid") => json!("00000000-0000-0000-0000-000000000000"),
+            Some("date-time") => json!("1970-01-01T00:00:00Z"),
+            _ => json!(""),
+        },
+        Some("integer") | Some("number") => json!(0),
+        Some("boolean") => json!(false),
+        Some("array") => json!([example(&schema["items"], schemas, depth + 1)]),
+        Some("object") | None if schema.get("properties").is_some() => {
+            let properties = schema["properties"]
+                .as_object()
+                .cloned()
+                .unwrap_or_default();
+            let object: Map<String, Value> = properties
+                .iter()
+                .map(|(name, property)| (name.clone(), example(property, schemas, depth + 1)))
+                .collect();
+            Value::Object(object)
+        }
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_collection_contains_every_operation() {
+        let openapi = crate::api::register_open_api_spec();
+        let collection = to_collection(&openapi);
+
+        let operations: usize = openapi
+            .paths
+            .paths
+            .values()
+            .map(|item| item.operations.len())
+            .sum();
+        let requests: Vec<&Value> = collection["item"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|folder| folder["item"].as_array().unwrap())
+            .collect();
+        assert!(operations > 0);
+        assert_eq!(requests.len(), operations);
+
+        let create = requests
+            .iter()
+            .find(|request| {
+                request["request"]["method"] == "POST"
+                    && request["request"]["url"]["raw"] == "{{baseUrl}}/todo"
+            })
+            .unwrap();
+        let body: Value =
+            serde_json::from_str(create["request"]["body"]["raw"].as_str().unwrap()).unwrap();
+        assert!(body.get("title").is_some());
+
+        let by_id = requests
+            .iter()
+            .find(|request| request["request"]["url"]["raw"] == "{{baseUrl}}/todo/:id")
+            .unwrap();
+        assert_eq!(by_id["request"]["url"]["variable"][0]["key"], "id");
+    }
+}
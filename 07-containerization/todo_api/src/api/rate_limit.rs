@@ -0,0 +1,207 @@
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER};
+use actix_web::middleware::Next;
+use actix_web::web::Data;
+use actix_web::{Error, HttpResponse};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use todo_shared::ErrorResponse;
+
+const LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+const REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+const RESET: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+
+/// Limits the number of requests per client using a token bucket per client address.
+///
+/// Every bucket holds up to `capacity` tokens and is refilled at `capacity` tokens per `window`, so
+/// a client can burst the full capacity and then sustain the configured rate.
+pub struct RateLimiter {
+    capacity: u32,
+    window: Duration,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+// The tokens left for a single client, as of the last time it made a request.
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// The state of a bucket after taking a token for a request, exposed as rate limit headers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitStatus {
+    /// The maximum number of requests within a window
+    pub limit: u32,
+
+    /// The number of requests the client can still make right away
+    pub remaining: u32,
+
+    /// The number of seconds until the bucket is full again
+    pub reset_secs: u64,
+
+    /// The number of seconds until the next request is allowed, 0 when the request was allowed
+    pub retry_after_secs: u64,
+
+    /// Indicates whether the request was allowed
+    pub allowed: bool,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `capacity` - The number of requests a client can make within a window.
+    ///  * `window` - The time it takes to refill an empty bucket.
+    pub fn new(capacity: u32, window: Duration) -> Self {
+        RateLimiter {
+            capacity,
+            window,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token from the bucket of the given client, when there is one left.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `client` - The key identifying the client, e.g. its address.
+    ///  * `now` - The current point in time.
+    pub fn acquire(&self, client: &str, now: Instant) -> RateLimitStatus {
+        let capacity = f64::from(self.capacity);
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated_at);
+        let refilled = capacity * elapsed.as_secs_f64() / self.window.as_secs_f64();
+        bucket.tokens = (bucket.tokens + refilled).min(capacity);
+        bucket.updated_at = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        // The seconds it takes to refill the given number of tokens.
+        let refill_secs =
+            |tokens: f64| (tokens / capacity * self.window.as_secs_f64()).ceil() as u64;
+        RateLimitStatus {
+            limit: self.capacity,
+            remaining: bucket.tokens.floor() as u32,
+            reset_secs: refill_secs(capacity - bucket.tokens),
+            retry_after_secs: match allowed {
+                true => 0,
+                false => refill_secs(1.0 - bucket.tokens),
+            },
+            allowed,
+        }
+    }
+}
+
+/// Middleware rejecting requests beyond the rate limit with 429 too many requests, adding the
+/// `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset` headers to every response.
+pub async fn rate_limit(
+    limiter: Data<RateLimiter>,
+    request: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let client = request
+        .peer_addr()
+        .map(|address| address.ip().to_string())
+        .unwrap_or_default();
+    let status = limiter.acquire(&client, Instant::now());
+
+    let mut response = if status.allowed {
+        next.call(request).await?.map_into_boxed_body()
+    } else {
+        let body = HttpResponse::TooManyRequests()
+            .insert_header((RETRY_AFTER, status.retry_after_secs))
+            .json(ErrorResponse {
+                status: 429,
+                message: "Too many requests, please try again later".to_string(),
+                error_code: "RATE_LIMITED".to_string(),
+            });
+        request.into_response(body)
+    };
+
+    insert_headers(response.headers_mut(), status);
+    Ok(response)
+}
+
+fn insert_headers(headers: &mut HeaderMap, status: RateLimitStatus) {
+    headers.insert(LIMIT, HeaderValue::from(status.limit));
+    headers.insert(REMAINING, HeaderValue::from(status.remaining));
+    headers.insert(RESET, HeaderValue::from(status.reset_secs));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::{get, test, App, HttpResponse, Responder};
+
+    #[get("/")]
+    async fn index() -> impl Responder {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn test_remaining_decrements() {
+        let limiter = Data::new(RateLimiter::new(2, Duration::from_secs(3600)));
+        let app = test::init_service(
+            App::new()
+                .app_data(limiter)
+                .wrap(from_fn(rate_limit))
+                .service(index),
+        )
+        .await;
+
+        let header = |response: &ServiceResponse, name: &HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+
+        let response = test::call_service(&app, test::TestRequest::get().to_request()).await;
+        assert!(response.status().is_success());
+        assert_eq!(header(&response, &LIMIT), "2");
+        assert_eq!(header(&response, &REMAINING), "1");
+        assert_eq!(header(&response, &RESET), "1800");
+
+        let response = test::call_service(&app, test::TestRequest::get().to_request()).await;
+        assert!(response.status().is_success());
+        assert_eq!(header(&response, &REMAINING), "0");
+
+        let response = test::call_service(&app, test::TestRequest::get().to_request()).await;
+        assert_eq!(
+            response.status(),
+            actix_web::http::StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(header(&response, &REMAINING), "0");
+        assert_eq!(header(&response, &RETRY_AFTER), "1800");
+    }
+
+    #[actix_web::test]
+    async fn test_bucket_refills() {
+        let limiter = RateLimiter::new(60, Duration::from_secs(60));
+        let start = Instant::now();
+        for _ in 0..60 {
+            assert!(limiter.acquire("client", start).allowed);
+        }
+        assert!(!limiter.acquire("client", start).allowed);
+        assert!(limiter.acquire("other", start).allowed);
+
+        let status = limiter.acquire("client", start + Duration::from_secs(2));
+        assert!(status.allowed);
+        assert_eq!(status.remaining, 1);
+    }
+}
@@ -0,0 +1,121 @@
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use log::error;
+use todo_shared::ErrorResponse;
+
+use crate::data::repository::RepositoryError;
+
+/// The number of seconds to wait before retrying a request the data store was too busy for.
+const POOL_RETRY_AFTER_SECS: u64 = 1;
+
+impl RepositoryError {
+    /// The stable code of the error, for clients to branch on rather than the message.
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            RepositoryError::NotFound => "NOT_FOUND",
+            RepositoryError::Conflict => "CONFLICT",
+            RepositoryError::DuplicateTitle => "DUPLICATE_TITLE",
+            RepositoryError::SubtasksIncomplete(_) => "SUBTASKS_INCOMPLETE",
+            RepositoryError::PoolTimeout => "UNAVAILABLE",
+            RepositoryError::Database(_) => "INTERNAL",
+        }
+    }
+
+    /// A description of the error that is safe to return, it never contains SQL or schema details.
+    pub fn client_message(&self) -> &'static str {
+        match self {
+            RepositoryError::NotFound => "The todo item was not found",
+            RepositoryError::Conflict => "The todo item was changed concurrently",
+            RepositoryError::DuplicateTitle => "A todo item with the same title already exists",
+            RepositoryError::SubtasksIncomplete(_) => {
+                "Subtasks of the todo item are not completed yet"
+            }
+            RepositoryError::PoolTimeout => "The data store is busy, please try again later",
+            RepositoryError::Database(_) => "Unable to access the data store",
+        }
+    }
+}
+
+/// A missing record becomes 404, a concurrent change, duplicate title or incomplete subtasks 409, an
+/// exhausted connection pool 503 with a `Retry-After` header so callers can retry later, and any
+/// other failure of the data store 500.
+///
+/// The body is an `ErrorResponse` with the generic message and code of the error, the underlying
+/// error of the data store is only logged.
+impl ResponseError for RepositoryError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RepositoryError::NotFound => StatusCode::NOT_FOUND,
+            RepositoryError::Conflict
+            | RepositoryError::DuplicateTitle
+            | RepositoryError::SubtasksIncomplete(_) => StatusCode::CONFLICT,
+            RepositoryError::PoolTimeout => StatusCode::SERVICE_UNAVAILABLE,
+            RepositoryError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        if status.is_server_error() {
+            error!("Unable to access the data store: {}", self);
+        }
+
+        let mut response = HttpResponse::build(status);
+        if let RepositoryError::PoolTimeout = self {
+            response.insert_header((RETRY_AFTER, POOL_RETRY_AFTER_SECS));
+        }
+        response.json(ErrorResponse {
+            status: status.as_u16(),
+            message: self.client_message().to_string(),
+            error_code: self.error_code().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_exhausted_pool_is_unavailable() {
+        let response = RepositoryError::PoolTimeout.error_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "1");
+
+        let response = RepositoryError::NotFound.error_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_repository_error_status() {
+        assert_eq!(
+            RepositoryError::NotFound.status_code(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            RepositoryError::PoolTimeout.status_code(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            RepositoryError::Database(diesel::result::Error::RollbackTransaction).status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_database_error_is_not_leaked() {
+        let error = RepositoryError::Database(diesel::result::Error::DatabaseError(
+            diesel::result::DatabaseErrorKind::UniqueViolation,
+            Box::new("duplicate key value violates unique constraint \"todos_pkey\"".to_string()),
+        ));
+
+        let body = actix_web::body::to_bytes(error.error_response().into_body())
+            .await
+            .unwrap();
+        let body: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.status, 500);
+        assert_eq!(body.error_code, "INTERNAL");
+        assert_eq!(body.message, "Unable to access the data store");
+    }
+}
@@ -0,0 +1,114 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest};
+use log::info;
+use std::fmt;
+use std::future::{ready, Ready};
+use std::time::Instant;
+use uuid::Uuid;
+
+/// The header carrying the correlation id of a request, in both the request and the response.
+pub const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+// The maximum length of a request id sent by a client, longer ones are replaced.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// The correlation id of a request, to find the log lines of a single request.
+///
+/// The request id middleware stores it in the request extensions. Handlers can take it as a
+/// parameter to include it in their log lines, a new id is generated when the middleware isn't
+/// installed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let request_id = req.extensions().get::<RequestId>().cloned();
+        let request_id = request_id.unwrap_or_else(|| RequestId(Uuid::new_v4().to_string()));
+        ready(Ok(request_id))
+    }
+}
+
+/// Middleware assigning a correlation id to every request and logging it once completed.
+///
+/// An `X-Request-Id` sent by the client is reused, so a request can be traced across services,
+/// otherwise a new UUID is generated. The id is returned in the `X-Request-Id` response header,
+/// and the method, path, status and elapsed time of the request are logged along with it.
+pub async fn request_id(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let started = Instant::now();
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty() && value.len() <= MAX_REQUEST_ID_LENGTH)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    request
+        .extensions_mut()
+        .insert(RequestId(request_id.clone()));
+    let (method, path) = (request.method().clone(), request.path().to_string());
+
+    let mut response = next.call(request).await?;
+    info!(
+        "{} {} {} {}ms request_id={}",
+        method,
+        path,
+        response.status().as_u16(),
+        started.elapsed().as_millis(),
+        request_id
+    );
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID, value);
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::{get, test, App, HttpResponse, Responder};
+
+    #[get("/todo")]
+    async fn list(request_id: RequestId) -> impl Responder {
+        HttpResponse::Ok().body(request_id.0)
+    }
+
+    #[actix_web::test]
+    async fn test_request_id() {
+        let app = test::init_service(App::new().wrap(from_fn(request_id)).service(list)).await;
+
+        // A new id is generated and shared with the handler
+        let req = test::TestRequest::get().uri("/todo").to_request();
+        let resp = test::call_service(&app, req).await;
+        let header = resp.headers().get(REQUEST_ID).unwrap().clone();
+        assert!(Uuid::parse_str(header.to_str().unwrap()).is_ok());
+        let body = test::read_body(resp).await;
+        assert_eq!(body, header.as_bytes());
+
+        // The id of the client is reused
+        let req = test::TestRequest::get()
+            .uri("/todo")
+            .insert_header((REQUEST_ID, "client-trace-1"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(REQUEST_ID).unwrap(), "client-trace-1");
+        let body = test::read_body(resp).await;
+        assert_eq!(body, "client-trace-1");
+    }
+}
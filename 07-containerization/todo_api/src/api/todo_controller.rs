@@ -1,71 +1,626 @@
-use actix_web::web::{Json, ServiceConfig};
-use actix_web::HttpResponse;
-use actix_web::{delete, get, post, put, web, Error};
-use todo_shared::{CreateTodoItemRequest, TodoItem, UpdateTodoItemRequest};
+use actix_multipart::form::{MultipartForm, MultipartFormConfig};
+use actix_web::guard::GuardContext;
+use actix_web::http::header::{
+    CacheControl, CacheDirective, ContentDisposition, ContentEncoding, ContentType,
+    DispositionParam, DispositionType, ETag, EntityTag, Header, HttpDate, IfNoneMatch,
+    CONTENT_RANGE, CONTENT_TYPE, LOCATION, RANGE,
+};
+use actix_web::web::ServiceConfig;
+use actix_web::{delete, get, head, patch, post, put, web, Error};
+use actix_web::{HttpRequest, HttpResponse};
+use todo_shared::{
+    ActivityEntry, AddChecklistItemRequest, AddDependencyRequest, AddTagRequest, CompletedQuery,
+    CountMode, CountQuery, CreateTodoItemRequest, CursorQuery, DeleteQuery, DeleteTodoItemsRequest,
+    DeleteTodoItemsResponse, EffortSummary, ErrorResponse, ExportQuery, FieldsQuery, ImportError,
+    ImportQuery, ImportSummary, IncludeDeletedQuery, OccurrencesQuery, PageQuery,
+    PatchTodoItemRequest, PurgeDeletedResponse, PurgeQuery, ReassignOwnerRequest,
+    ReassignOwnerResponse, Recurrence, ReturnQuery, ReturnRepresentation, SearchQuery, SortQuery,
+    SyncOperation, SyncOperationKind, SyncOperationResult, TagFilter, TagQuery, TagStats,
+    TagStatsRequest, TodoCount, TodoEventKind, TodoGraphEdge, TodoItem, TodoOccurrences, TodoPage,
+    TodoStats, TodoStatusResponse, UpdateTodoItemRequest, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+};
 
-use crate::data::repository::Repository;
-use crate::data::todo_repository::TodoEntityRepository;
+use crate::api::csv_body::csv_body;
+use crate::api::event_socket::forward_events;
+use crate::api::event_stream::event_stream;
+use crate::api::i18n::{Locale, Message};
+use crate::api::json_body::{json_config, payload_too_large, JsonBody, JsonOptions};
+use crate::api::metrics::Metrics;
+use crate::api::request_id::RequestId;
+use crate::auth::authenticated_user::AuthenticatedUser;
+use crate::data::db_context::DatabaseProbe;
+use crate::data::repository::RepositoryError;
+use crate::data::shard::Shard;
+use crate::data::todo_repository::{Changed, ListQuery, TodoEntityRepository, TodoRepository};
+use crate::entities::todo_change::{negative_minutes, ChangeError, TodoChange};
 use crate::entities::todo_entity::TodoEntity;
+use crate::entities::todo_patch::TodoPatch;
+use crate::services::activity;
+use crate::services::clock::{Clock, SystemClock};
+use crate::services::create_batcher::{BatchError, CreateBatcher};
+use crate::services::cursor::Cursor;
+use crate::services::dependency_graph;
+use crate::services::idempotency::{Claim, IdempotencyKeys};
+use crate::services::recurrence::Occurrences;
+use crate::services::todo_events::TodoEvents;
+use crate::services::todo_service;
+use crate::settings::Settings;
+use crate::telemetry;
 use actix_web::web::Data;
+use chrono::{DateTime, Days, Utc};
+use serde_json::Value;
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use uuid::Uuid;
+use validator::Validate;
 
 use log::{error, warn};
 
+/// Header containing the total number of items matching a (paged) query.
+const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+
+/// Header indicating the total number of items is an estimate, rather than an exact count.
+const TOTAL_IS_ESTIMATE_HEADER: &str = "X-Total-Is-Estimate";
+
+/// Header with a client chosen key, to safely retry a create without creating a duplicate.
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Header indicating the response is the replay of an earlier create with the same key.
+const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+/// The maximum length of an idempotency key.
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+/// Header containing the HTTP-date until which a deleted todo item is kept as a tombstone.
+const RETENTION_UNTIL_HEADER: &str = "X-Retention-Until";
+
+/// The content type of a JSON merge patch (RFC 7396) document.
+const MERGE_PATCH: &str = "application/merge-patch+json";
+
+/// The number of days in which todo items count as recently completed in the stats.
+const RECENTLY_COMPLETED_DAYS: i64 = 7;
+
+/// The number of todo items loaded per query while exporting them as CSV.
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// The number of upcoming occurrences of a recurring todo item to preview when no count is given.
+const DEFAULT_OCCURRENCES: u32 = 5;
+
+/// The maximum number of upcoming occurrences of a recurring todo item to preview.
+const MAX_OCCURRENCES: u32 = 100;
+
+/// The number of days a soft-deleted todo item is kept when purging without `older_than_days`.
+const DEFAULT_PURGE_AGE_DAYS: u64 = 30;
+
+/// Checks whether soft-deleted items should be included, which is only allowed for admins.
+fn include_deleted(
+    query: &IncludeDeletedQuery,
+    user: &Option<AuthenticatedUser>,
+) -> Result<bool, Error> {
+    if !query.include_deleted {
+        return Ok(false);
+    }
+
+    match user {
+        Some(user) if user.is_admin() => Ok(true),
+        _ => Err(actix_web::error::ErrorForbidden(
+            "Only admins are allowed to include deleted todo items",
+        )),
+    }
+}
+
+/// Returns the offset and limit to use for the given page, rejecting pages out of range.
+///
+/// A page holds `DEFAULT_PAGE_SIZE` todo items unless a `limit` is given, which has to be between
+/// 1 and `MAX_PAGE_SIZE`, and starts at the first todo item unless a non-negative `offset` is
+/// given. Pages out of range are rejected with 400 bad request.
+pub(crate) fn page_bounds(page: &PageQuery) -> Result<(i64, i64), Error> {
+    let limit = page.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(actix_web::error::ErrorBadRequest(format!(
+            "limit must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+
+    let offset = page.offset.unwrap_or(0);
+    if offset < 0 {
+        return Err(actix_web::error::ErrorBadRequest(
+            "offset must be at least 0",
+        ));
+    }
+
+    Ok((offset, limit))
+}
+
+/// Parses the tag to filter the todo items by, rejecting malformed tags with 400 bad request.
+fn tag_filter(query: TagQuery) -> Result<Option<TagFilter>, Error> {
+    query
+        .tag
+        .map(|tag| tag.parse::<TagFilter>())
+        .transpose()
+        .map_err(actix_web::error::ErrorBadRequest)
+}
+
+/// Returns the offset and limit of a `Range: items=<first>-<last>` header.
+///
+/// Returns `None` when the header is absent or not a valid items range, in which case the range
+/// is ignored. The limit is bound to the maximum page size.
+fn item_range(request: &HttpRequest) -> Option<(i64, i64)> {
+    let value = request.headers().get(RANGE)?.to_str().ok()?;
+    let (first, last) = value.trim().strip_prefix("items=")?.split_once('-')?;
+    let first = first.trim().parse::<i64>().ok()?;
+    let last = last.trim().parse::<i64>().ok()?;
+    if first < 0 {
+        return None;
+    }
+    // Huge ranges, e.g. `items=0-9223372036854775807`, are bound without overflowing
+    let length = last
+        .checked_sub(first)
+        .filter(|difference| *difference >= 0)?;
+    Some((first, length.saturating_add(1).min(MAX_PAGE_SIZE)))
+}
+
+/// Returns the entity tag of a todo item, which changes with every update of the item.
+fn entity_tag(entity: &TodoEntity) -> EntityTag {
+    EntityTag::new_strong(format!(
+        "{}-{}",
+        entity.version,
+        entity.updated_at.timestamp_micros()
+    ))
+}
+
+/// Indicates whether the `If-None-Match` header of a request matches the given entity tag.
+///
+/// Tags are compared weakly, as RFC 7232 requires for `If-None-Match`. An absent or invalid header
+/// never matches.
+fn none_match_fails(request: &HttpRequest, tag: &EntityTag) -> bool {
+    match IfNoneMatch::parse(request) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|other| other.weak_eq(tag)),
+        Err(_) => false,
+    }
+}
+
+/// Records a change of the given todo items made by the authenticated caller in the history,
+/// anonymous changes are not recorded.
+async fn record_changes(
+    repository: Data<dyn TodoRepository>,
+    user: &Option<AuthenticatedUser>,
+    action: &str,
+    ids: impl IntoIterator<Item = Uuid>,
+) {
+    if let Some(user) = user {
+        activity::record(repository.into_inner(), &user.subject, action, ids).await;
+    }
+}
+
+/// Publishes a change of the given todo items to the event stream, when it is registered.
+fn publish_changes(
+    events: &Option<Data<TodoEvents>>,
+    kind: TodoEventKind,
+    ids: impl IntoIterator<Item = Uuid>,
+) {
+    if let Some(events) = events {
+        for id in ids {
+            events.publish(kind, id);
+        }
+    }
+}
+
+/// Rejects batches containing more operations than the configured maximum.
+fn check_batch_size(size: usize, settings: &Settings) -> Result<(), Error> {
+    match size > settings.max_batch_size {
+        true => Err(actix_web::error::ErrorUnprocessableEntity(format!(
+            "The batch contains {} operations, the maximum is {}",
+            size, settings.max_batch_size
+        ))),
+        false => Ok(()),
+    }
+}
+
+/// Rejects requests violating their validation rules with 400 bad request, describing which fields
+/// failed in the JSON body.
+fn validate_request(request: &impl Validate) -> Result<(), Error> {
+    request.validate().map_err(|errors| {
+        let response = HttpResponse::BadRequest().json(&errors);
+        actix_web::error::InternalError::from_response(errors, response).into()
+    })
+}
+
+/// Rejects negative effort and reminder lead time values, explained in the language of the client.
+pub(crate) fn validate_minutes(
+    estimated: Option<i32>,
+    actual: Option<i32>,
+    remind_before: Option<i32>,
+    locale: Locale,
+) -> Result<(), Error> {
+    match negative_minutes(estimated, actual, remind_before) {
+        Some(field) => Err(actix_web::error::ErrorBadRequest(format!(
+            "{} {}",
+            field,
+            Message::NegativeMinutes.localize(locale)
+        ))),
+        None => Ok(()),
+    }
+}
+
 /// Get list of todos.
 ///
 /// List todos from in-memory todo store.
 /// One could call the api endpoit with following curl.
+///
+/// When a search term `q` is given, only matching todo items are returned, paged by `offset` and
+/// `limit`, with the total number of matches in the `X-Total-Count` header.
+///
+/// Without a search term, `completed=true` or `completed=false` only lists the finished or open
+/// todo items, and `sort=created_at&order=desc` sorts the todo items by `created_at`, `title` or
+/// `completed_at` before they are paged. Unknown sort fields, or sorting search results, are
+/// rejected with 400 bad request.
+///
+/// Without a search term, `tag=project/alpha` only lists the todo items with that tag and
+/// `tag=project/*` the todo items with any tag in the `project` namespace. Malformed tags are
+/// rejected with 400 bad request.
+///
+/// Without a search term, `offset` and `limit` page the filtered todo items as well, with their
+/// total number in the `X-Total-Count` header. A page holds `DEFAULT_PAGE_SIZE` todo items unless
+/// a `limit` of at most `MAX_PAGE_SIZE` is given, pages out of range are rejected with 400 bad
+/// request. Listing more than `MAX_UNPAGED_ITEMS` todo items without paging is rejected with 413
+/// payload too large.
+///
+/// Alternatively a slice of the todo items can be requested with a `Range: items=0-49` header,
+/// which is answered with 206 Partial Content and a `Content-Range: items 0-49/1000` header.
+///
+/// Counting every todo item is slow on huge tables, with `count=estimate` the total of an
+/// unfiltered page is estimated from the table statistics instead, which is indicated by a
+/// `X-Total-Is-Estimate: true` header. Filtered pages, e.g. searches, are still counted exactly.
+///
+/// With `fields=id,title` only the given fields of the todo items are returned, e.g. to leave out
+/// the descriptions in list views. Unknown fields are rejected with 400 bad request.
+///
+/// Clients paging through all todo items can use a cursor instead of an offset, which neither
+/// skips nor repeats todo items when others are created or deleted meanwhile. `after=` with an
+/// empty cursor returns the first `limit` todo items as a `TodoPage`, which contains the cursor to
+/// pass as `after` for the next page. The other filters don't apply to cursor pages.
 #[utoipa::path(
     responses(
-        (status = 200, description = "List current todo items", body = [TodoItem])
-    )
+        (status = 200, description = "List current todo items", body = [TodoItem]),
+        (status = 206, description = "The requested range of the todo items", body = [TodoItem]),
+        (status = 400, description = "An unknown sort field, tag or field to return, an invalid cursor or a page out of range was given"),
+        (status = 403, description = "Deleted todo items were requested by a non-admin caller"),
+        (status = 413, description = "Too many todo items to list without paging", body = ErrorResponse),
+        (status = 416, description = "The requested range starts beyond the last todo item"),
+    ),
+    params(IncludeDeletedQuery, SearchQuery, PageQuery, CompletedQuery, SortQuery, TagQuery, CountQuery, FieldsQuery, CursorQuery)
 )]
 #[get("/todo")]
-async fn get_todos(repository: Data<dyn Repository<TodoEntity>>) -> Result<HttpResponse, Error> {
-    // Get entities from the datastore
-    let entities = web::block(move || repository.get_all())
-        .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+async fn get_todos(
+    query: web::Query<IncludeDeletedQuery>,
+    search: web::Query<SearchQuery>,
+    filter: web::Query<CompletedQuery>,
+    page: web::Query<PageQuery>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    request: HttpRequest,
+    repository: Data<dyn TodoRepository>,
+) -> Result<HttpResponse, Error> {
+    let include_deleted = include_deleted(&query, &user)?;
+    let paged = page.offset.is_some() || page.limit.is_some();
+    let range = item_range(&request);
+    let (offset, limit) = match range {
+        Some(range) => range,
+        None => page_bounds(&page)?,
+    };
+    let term = search.into_inner().q;
+    let completed = filter.into_inner().completed;
+    // Read from the query string directly, as the handler already takes enough extractors
+    let sort = web::Query::<SortQuery>::from_query(request.query_string())
+        .map_err(|error| actix_web::error::ErrorBadRequest(error.to_string()))?
+        .into_inner();
+    let tag = web::Query::<TagQuery>::from_query(request.query_string())
+        .map_err(|error| actix_web::error::ErrorBadRequest(error.to_string()))?
+        .into_inner();
+    let tag = tag_filter(tag)?;
+    let estimate = web::Query::<CountQuery>::from_query(request.query_string())
+        .map_err(|error| actix_web::error::ErrorBadRequest(error.to_string()))?
+        .count
+        == CountMode::Estimate;
+    let fields = selected_fields(&request)?;
+    // Search results are ordered by creation time only
+    if term.is_some() && sort.sort.is_some() {
+        return Err(actix_web::error::ErrorBadRequest(
+            "Search results can't be sorted, leave out the sort parameter",
+        ));
+    }
+    let listing = ListQuery {
+        completed,
+        tag,
+        include_deleted,
+        sort: sort.sort,
+        order: sort.order,
+    };
+
+    // Rather than loading and serializing a huge payload, nudge the client towards paging
+    let max_unpaged_items = request
+        .app_data::<Data<Settings>>()
+        .map(|settings| settings.max_unpaged_items)
+        .unwrap_or_else(|| Settings::default().max_unpaged_items);
+    // One more than the maximum is loaded to tell whether there are too many todo items
+    let unpaged_page = (
+        0,
+        i64::try_from(max_unpaged_items).unwrap_or(i64::MAX - 1) + 1,
+    );
+
+    // Get entities from the datastore, along with the total number of matches when paging and
+    // whether that total is an estimate
+    let (entities, total, total_is_estimate) = telemetry::block(move || {
+        // Only the total of all live todo items can be estimated, filtered ones are counted
+        let estimate = estimate && listing.is_unfiltered();
+        Ok::<_, RepositoryError>(match term {
+            Some(term) => (
+                repository.search_paged(&term, offset, limit)?,
+                Some(repository.search_count(&term)?),
+                false,
+            ),
+            None if paged || range.is_some() => (
+                repository.get_listed(&listing, Some((offset, limit)))?,
+                Some(match estimate {
+                    true => repository.estimated_count()?,
+                    false => repository.count_listed(&listing)?,
+                }),
+                estimate,
+            ),
+            None => (
+                repository.get_listed(&listing, Some(unpaged_page))?,
+                None,
+                false,
+            ),
+        })
+    })
+    .await??;
+
+    if total.is_none() && entities.len() > max_unpaged_items {
+        let message = format!(
+            "More than {} todo items, use the offset and limit parameters to paginate",
+            max_unpaged_items
+        );
+        return Ok(HttpResponse::PayloadTooLarge().json(ErrorResponse {
+            status: 413,
+            message,
+            error_code: "TOO_MANY_ITEMS".to_string(),
+        }));
+    }
 
     // Map our entities to our public struct TodoItem
     let response: Vec<TodoItem> = entities.into_iter().map(|entity| entity.into()).collect();
 
     // Send the response
-    Ok(HttpResponse::Ok().json(response))
+    let mut builder = match (range, total) {
+        (Some(_), Some(total)) if response.is_empty() && offset > 0 => {
+            return Ok(HttpResponse::RangeNotSatisfiable()
+                .insert_header((CONTENT_RANGE, format!("items */{}", total)))
+                .finish());
+        }
+        (Some(_), Some(total)) if !response.is_empty() => {
+            let last = offset + response.len() as i64 - 1;
+            let mut builder = HttpResponse::PartialContent();
+            builder.insert_header((
+                CONTENT_RANGE,
+                format!("items {}-{}/{}", offset, last, total),
+            ));
+            builder
+        }
+        _ => HttpResponse::Ok(),
+    };
+    if let Some(total) = total {
+        builder.insert_header((TOTAL_COUNT_HEADER, total));
+    }
+    if total_is_estimate {
+        builder.insert_header((TOTAL_IS_ESTIMATE_HEADER, "true"));
+    }
+    match fields {
+        Some(fields) => Ok(builder.json(select_fields(response, &fields)?)),
+        None => Ok(builder.json(response)),
+    }
+}
+
+/// Get a page of todos after a cursor.
+///
+/// Answers `GET /todo` when an `after` cursor is given, see `get_todos`. The page contains at most
+/// `limit` todo items, ordered by creation time, along with the cursor of the next page unless
+/// this is the last one.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The todo items after the cursor, with the cursor of the next page", body = TodoPage),
+        (status = 400, description = "The cursor is not valid, or the page is out of range"),
+    ),
+    params(CursorQuery, PageQuery)
+)]
+#[get("/todo", guard = "has_cursor")]
+async fn get_todos_after(
+    cursor: web::Query<CursorQuery>,
+    page: web::Query<PageQuery>,
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+) -> Result<HttpResponse, Error> {
+    let after = cursor
+        .into_inner()
+        .after
+        .filter(|after| !after.is_empty())
+        .map(|after| after.parse::<Cursor>())
+        .transpose()
+        .map_err(actix_web::error::ErrorBadRequest)?;
+    let (_, limit) = page_bounds(&page)?;
+
+    let entities = telemetry::block(move || repository.get_after(after, limit)).await??;
+
+    // A full page may be followed by more todo items
+    let next_cursor = match entities.len() as i64 == limit {
+        true => entities
+            .last()
+            .map(|entity| Cursor::after(entity).to_string()),
+        false => None,
+    };
+    Ok(HttpResponse::Ok().json(TodoPage {
+        items: entities.into_iter().map(TodoItem::from).collect(),
+        next_cursor,
+    }))
+}
+
+// Matches the requests paging with a cursor.
+fn has_cursor(context: &GuardContext) -> bool {
+    web::Query::<CursorQuery>::from_query(context.head().uri.query().unwrap_or_default())
+        .is_ok_and(|query| query.after.is_some())
+}
+
+/// Returns the fields of the todo items requested with `fields=id,title`, `None` for all fields.
+///
+/// An unknown field is rejected with 400 bad request before anything is loaded.
+fn selected_fields(request: &HttpRequest) -> Result<Option<Vec<String>>, Error> {
+    let fields = web::Query::<FieldsQuery>::from_query(request.query_string())
+        .map_err(|error| actix_web::error::ErrorBadRequest(error.to_string()))?
+        .into_inner()
+        .fields;
+    let Some(fields) = fields else {
+        return Ok(None);
+    };
+
+    let fields: Vec<String> = fields
+        .split(',')
+        .map(|field| field.trim().to_string())
+        .collect();
+    if let Some(unknown) = fields
+        .iter()
+        .find(|field| !TodoItem::FIELDS.contains(&field.as_str()))
+    {
+        return Err(actix_web::error::ErrorBadRequest(format!(
+            "Unknown field `{}`, expected one of {}",
+            unknown,
+            TodoItem::FIELDS.join(", ")
+        )));
+    }
+    Ok(Some(fields))
+}
+
+/// Serializes the todo items with only the given fields, as Diesel always loads the whole row.
+fn select_fields(items: Vec<TodoItem>, fields: &[String]) -> Result<Vec<Value>, Error> {
+    items
+        .into_iter()
+        .map(|item| {
+            let mut value = serde_json::to_value(item)?;
+            if let Value::Object(object) = &mut value {
+                object.retain(|key, _| fields.contains(key));
+            }
+            Ok(value)
+        })
+        .collect::<Result<_, serde_json::Error>>()
+        .map_err(actix_web::error::ErrorInternalServerError)
+}
+
+/// Stream the changes of Todos.
+///
+/// Keeps the connection open and sends a Server-Sent Event with a `TodoEvent` whenever a todo item
+/// is created, updated or deleted, so front-ends can refresh without polling. Only the kind of
+/// change and the id are sent, `GET /todo/{id}` returns the todo item itself. Idle connections
+/// receive a keep-alive comment. Reassigning owners and purging tombstones are not streamed.
+///
+/// The stream is never compressed, as the compression would hold back the events.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Stream of changes of todo items, as `data` frames", body = TodoEvent, content_type = "text/event-stream"),
+    )
+)]
+#[get("/todo/events")]
+async fn get_todo_events(
+    events: Data<TodoEvents>, // The published changes, shared by all worker threads
+    settings: Data<Settings>,
+) -> HttpResponse {
+    let keep_alive = Duration::from_secs(settings.event_keep_alive_secs.max(1));
+    HttpResponse::Ok()
+        .insert_header((CONTENT_TYPE, "text/event-stream"))
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .insert_header(ContentEncoding::Identity)
+        .streaming(event_stream(events.subscribe(), keep_alive))
+}
+
+/// Stream the changes of Todos over a WebSocket.
+///
+/// Upgrades the connection and sends a `TodoEvent` as a JSON text frame whenever a todo item is
+/// created, updated or deleted, like `GET /todo/events`. Only the changes made after the upgrade
+/// are sent. The socket is pinged to detect lost connections, clients can ping as well and close
+/// the socket to unsubscribe.
+#[utoipa::path(
+    responses(
+        (status = 101, description = "Switched to the WebSocket protocol, changes of todo items are sent as text frames", body = TodoEvent),
+        (status = 400, description = "The request is not a valid WebSocket handshake"),
+    )
+)]
+#[get("/todo/ws")]
+async fn get_todo_events_socket(
+    request: HttpRequest,
+    body: web::Payload,
+    events: Data<TodoEvents>, // The published changes, shared by all worker threads
+    settings: Data<Settings>,
+) -> Result<HttpResponse, Error> {
+    let (response, session, messages) = actix_ws::handle(&request, body)?;
+    let keep_alive = Duration::from_secs(settings.event_keep_alive_secs.max(1));
+    actix_web::rt::spawn(forward_events(
+        session,
+        messages,
+        events.subscribe(),
+        keep_alive,
+    ));
+    Ok(response)
 }
 
 /// Get Todo by given todo id.
 ///
 /// Return found `Todo` with status 200 or 404 not found if `Todo` is not found in the data store.
+/// The response carries an `ETag` header, a request with a matching `If-None-Match` header gets
+/// 304 not modified without a body instead.
 #[utoipa::path(
     responses(
         (status = 200, description = "Todo found from storage", body = TodoItem),
+        (status = 304, description = "Todo item matches the entity tag in the If-None-Match header"),
         (status = 400, description = "The given identifier was not a correct uuid"),
+        (status = 403, description = "Deleted todo items were requested by a non-admin caller"),
         (status = 404, description = "Todo item was not found with the given identifier"),
     ),
     params(
-        ("id", description = "Unique storage id of Todo")
+        ("id", description = "Unique storage id of Todo"),
+        IncludeDeletedQuery
     )
 )]
 #[get("/todo/{id}")]
 async fn get_todo_by_id(
     id: web::Path<Uuid>, // The identifier of the item to retrieve
-    repository: Data<dyn Repository<TodoEntity>>, // The todo item repository, injected from app_data
+    query: web::Query<IncludeDeletedQuery>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    request: HttpRequest,            // The request, for its If-None-Match header
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
 ) -> Result<HttpResponse, Error> {
     let uuid = id.into_inner();
+    let include_deleted = include_deleted(&query, &user)?;
 
     // Query our entity from the data store.
-    let entity = web::block(move || repository.get_by_id(uuid))
-        .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let entity = match include_deleted {
+        true => repository.get_by_id_including_deleted(uuid).await,
+        false => repository.get_by_id(uuid).await,
+    }?;
 
     match entity {
         Some(item) => {
+            // Skip the body when the caller already has the current version
+            let tag = entity_tag(&item);
+            if none_match_fails(&request, &tag) {
+                return Ok(HttpResponse::NotModified()
+                    .insert_header(ETag(tag))
+                    .finish());
+            }
+
             // If we found one, use the From<T> trait to convert to a TodoItem
             let response: TodoItem = item.into();
             // Send the response
-            Ok(HttpResponse::Ok().json(response))
+            Ok(HttpResponse::Ok().insert_header(ETag(tag)).json(response))
         }
         _ => {
             warn!("Todo item with id {} was not found in the data store", uuid);
@@ -75,272 +630,5622 @@ async fn get_todo_by_id(
     }
 }
 
-/// Create new Todo to the data source.
+/// Check whether a Todo exists by given todo id.
 ///
-/// Post a new `Todo` in request body as json to store it. Api will return
-/// created `Todo` on success or `ErrorResponse::InternalServerError` if a problem occured whilst creating the todo item.
+/// Returns status 200 or 404 not found, both without a body, so the todo item isn't loaded or
+/// serialized just to check its presence.
 #[utoipa::path(
-    request_body = CreateTodoItemRequest,
     responses(
-        (status = 201, description = "Todo created successfully", body = Todo),
-        (status = 500, description = "Unable to insert new todo item", body = ErrorResponse)
+        (status = 200, description = "Todo item exists in the data store"),
+        (status = 400, description = "The given identifier was not a correct uuid"),
+        (status = 404, description = "Todo item was not found with the given identifier"),
+    ),
+    params(
+        ("id", description = "Unique storage id of Todo")
     )
 )]
-#[post("/todo")]
-async fn create_todo(
-    todo: Json<CreateTodoItemRequest>,
-    repository: Data<dyn Repository<TodoEntity>>, // The todo item repository, injected from app_data
+#[head("/todo/{id}")]
+async fn head_todo(
+    id: web::Path<Uuid>,                  // The identifier of the item to check
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
 ) -> Result<HttpResponse, Error> {
-    let request_body = todo.into_inner();
-    let result = web::block(move || repository.insert(request_body.into()))
-        .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    match result {
-        Ok(entity) => {
-            let result: TodoItem = entity.into();
-            Ok(HttpResponse::Ok().json(result))
-        }
-        _ => {
-            error!("Unable to insert new todo item");
-            Ok(HttpResponse::InternalServerError().finish())
-        }
+    match repository.exists(id.into_inner()).await? {
+        true => Ok(HttpResponse::Ok().finish()),
+        false => Ok(HttpResponse::NotFound().finish()),
     }
 }
 
-/// Delete Todo by given path variable id.
+/// Get the status of a Todo by given todo id.
 ///
-/// Api will delete todo from datasource by the provided id and return success 200.
-/// If storage does not contain `Todo` with given id 404 not found will be returned.
+/// Returns the derived status (`overdue`, `due today`, `completed`, `upcoming` or `no due date`)
+/// with status 200 or 404 not found if `Todo` is not found in the data store.
 #[utoipa::path(
     responses(
-        (status = 200, description = "Todo deleted successfully"),
+        (status = 200, description = "Status of the todo item", body = TodoStatusResponse),
         (status = 400, description = "The given identifier was not a correct uuid"),
         (status = 404, description = "Todo item was not found with the given identifier"),
-        (status = 500, description = "Unable to delete todo item", body = ErrorResponse)
     ),
     params(
         ("id", description = "Unique storage id of Todo")
-    ),
+    )
 )]
-#[delete("/todo/{id}")]
-async fn delete_todo(
+#[get("/todo/{id}/status")]
+async fn get_todo_status(
     id: web::Path<Uuid>,
-    repository: Data<dyn Repository<TodoEntity>>, // The todo item repository, injected from app_data
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    clock: Data<dyn Clock>, // The clock to compare the due date with, injected from app_data
 ) -> Result<HttpResponse, Error> {
-    let result = web::block(move || repository.delete(id.into_inner()))
-        .await
-        .map_err(actix_web::error::ErrorInternalServerError)?;
-    match result {
-        Ok(true) => Ok(HttpResponse::Ok().finish()),
-        _ => Ok(HttpResponse::NotFound().finish()),
+    let uuid = id.into_inner();
+
+    // Only query the fields needed to derive the status.
+    let fields = telemetry::block(move || repository.get_status_fields(uuid)).await??;
+
+    match fields {
+        Some((completed, due_date)) => {
+            let response = TodoStatusResponse {
+                id: uuid,
+                status: todo_service::derive_status(completed, due_date, clock.now()),
+            };
+            Ok(HttpResponse::Ok().json(response))
+        }
+        _ => {
+            warn!("Todo item with id {} was not found in the data store", uuid);
+            Ok(HttpResponse::NotFound().finish())
+        }
     }
 }
 
-/// Update Todo with given id.
+/// Preview the upcoming occurrences of a recurring Todo by given todo id.
 ///
-/// Tries to update `Todo` by given id as path variable. If todo is found by id values are
-/// updated according `TodoUpdateRequest` and updated `Todo` is returned with status 200.
-/// If todo is not found then 404 not found is returned.
+/// Computes the next `count` due dates following the current due date, or the creation time when
+/// there is no due date, from the recurrence rule of the todo item without creating them. Returns
+/// 400 bad request when the todo item doesn't recur or the count is out of range.
 #[utoipa::path(
-    request_body = TodoUpdateRequest,
     responses(
-        (status = 200, description = "Todo updated successfully", body = TodoItem),
-        (status = 400, description = "The given identifier was not a correct uuid"),
+        (status = 200, description = "The upcoming occurrences of the todo item", body = TodoOccurrences),
+        (status = 400, description = "The todo item has no recurrence rule, or the count is not between 1 and 100"),
         (status = 404, description = "Todo item was not found with the given identifier"),
-        (status = 500, description = "Unable to delete todo item", body = ErrorResponse)
     ),
     params(
-        ("id", description = "Unique storage id of Todo")
-    ),
+        ("id", description = "Unique storage id of Todo"),
+        OccurrencesQuery
+    )
 )]
-#[put("/todo/{id}")]
-async fn update_todo(
+#[get("/todo/{id}/occurrences")]
+async fn get_occurrences(
     id: web::Path<Uuid>,
-    todo: Json<UpdateTodoItemRequest>,
-    repository: Data<dyn Repository<TodoEntity>>, // The todo item repository, injected from app_data
+    query: web::Query<OccurrencesQuery>,
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
 ) -> Result<HttpResponse, Error> {
-    let request_body = todo.into_inner();
     let uuid = id.into_inner();
-    let entity = web::block(move || repository.update(uuid, request_body.into()))
-        .await?
-        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let count = query.count.unwrap_or(DEFAULT_OCCURRENCES);
+    if !(1..=MAX_OCCURRENCES).contains(&count) {
+        return Err(actix_web::error::ErrorBadRequest(format!(
+            "The count must be between 1 and {}",
+            MAX_OCCURRENCES
+        )));
+    }
 
-    let result: TodoItem = entity.into();
-    Ok(HttpResponse::Ok().json(result))
+    let entity = repository.get_by_id(uuid).await?;
+    let Some(entity) = entity else {
+        warn!("Todo item with id {} was not found in the data store", uuid);
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    let rule: Recurrence = entity
+        .recurrence
+        .as_deref()
+        .ok_or_else(|| {
+            actix_web::error::ErrorBadRequest(format!("Todo item {} doesn't recur", uuid))
+        })?
+        .parse()
+        .map_err(actix_web::error::ErrorBadRequest)?;
+
+    let start = entity.due_date.unwrap_or(entity.created_at);
+    let response = TodoOccurrences {
+        id: uuid,
+        recurrence: rule.to_string(),
+        occurrences: Occurrences::new(rule, start).take(count as usize).collect(),
+    };
+    Ok(HttpResponse::Ok().json(response))
 }
 
-pub fn configure() -> impl FnOnce(&mut ServiceConfig) {
-    |config: &mut ServiceConfig| {
-        // Create our repository
-        let repository = TodoEntityRepository::new();
+/// Export the todos, optionally limited to a single shard.
+///
+/// With `?shard=2/5` only the second of five disjoint partitions is exported, so multiple workers
+/// can export all todo items concurrently.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The exported todo items", body = [TodoItem]),
+        (status = 400, description = "The shard is not formatted as `<index>/<count>`"),
+    ),
+    params(ExportQuery)
+)]
+#[get("/todo/export")]
+async fn export_todos(
+    query: web::Query<ExportQuery>,
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+) -> Result<HttpResponse, Error> {
+    let shard = query
+        .into_inner()
+        .shard
+        .map(|spec| spec.parse::<Shard>())
+        .transpose()
+        .map_err(actix_web::error::ErrorBadRequest)?;
 
-        // Todo entity repository is unsized, so we need to wrap this in a Atomic Reference Counter
-        // "For types that are unsized, most commonly dyn T, Data can wrap these types by first constructing an Arc<dyn T> and using the From implementation to convert it."
-        // https://docs.rs/actix-web/latest/actix_web/web/struct.Data.html
-        let repository_arc: Arc<dyn Repository<TodoEntity>> = Arc::new(repository);
+    let entities = telemetry::block(move || repository.export(shard)).await??;
 
-        config
-            // Register our repository for data injection;
-            .app_data(Data::from(repository_arc))
-            // register our endpoints
-            .service(get_todos)
-            .service(create_todo)
-            .service(delete_todo)
-            .service(get_todo_by_id)
-            .service(update_todo);
-    }
+    let response: Vec<TodoItem> = entities.into_iter().map(|entity| entity.into()).collect();
+    Ok(HttpResponse::Ok().json(response))
 }
 
-#[cfg(test)]
-mod tests {
-    use std::time::SystemTime;
-
-    use actix_web::{test, App};
-    use uuid::Uuid;
+/// Export all todos as CSV.
+///
+/// Returns a `todos.csv` attachment with a header row and the `id`, `title`, `description`,
+/// `completed`, `completed_at` and `created_at` of every todo item, oldest first. The timestamps are
+/// in seconds since the unix epoch. The todo items are loaded in pages of `EXPORT_PAGE_SIZE` while
+/// the response is sent, rather than loading all of them and building the document up front.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The todo items as CSV", content_type = "text/csv", body = String),
+        (status = 500, description = "Unable to load the todo items", body = ErrorResponse)
+    )
+)]
+#[get("/todo/export.csv")]
+async fn export_todos_csv(
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+) -> Result<HttpResponse, Error> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename("todos.csv".to_string())],
+        })
+        .streaming(csv_body(repository.into_inner(), EXPORT_PAGE_SIZE)))
+}
 
-    use std::collections::HashMap;
-    use std::sync::Arc;
-    use std::sync::Mutex;
+/// Search the todos.
+///
+/// Returns the todo items whose title or description contains the term `q`, ignoring case, with
+/// the newest todo items first.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The matching todo items", body = [TodoItem]),
+        (status = 400, description = "No search term was given"),
+    ),
+    params(SearchQuery)
+)]
+#[get("/todo/search")]
+async fn search_todos(
+    query: web::Query<SearchQuery>,
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+) -> Result<HttpResponse, Error> {
+    let term = match query.into_inner().q {
+        Some(term) if !term.trim().is_empty() => term,
+        _ => return Ok(HttpResponse::BadRequest().body("The search term `q` is required")),
+    };
 
-    use crate::data::repository::Repository;
-    use crate::entities::todo_entity::TodoEntity;
+    let entities = telemetry::block(move || repository.search(&term)).await??;
 
-    use super::*;
+    let response: Vec<TodoItem> = entities.into_iter().map(|entity| entity.into()).collect();
+    Ok(HttpResponse::Ok().json(response))
+}
 
-    // Define a mock for Repository<TodoEntity>
-    pub struct TodoEntityRepositoryMock {
-        db: Arc<Mutex<HashMap<Uuid, TodoEntity>>>,
-    }
+/// Count the todos.
+///
+/// Returns the number of live todo items matching the same `q`, `completed` and `tag` filters as
+/// the list endpoint, without fetching them. The count equals the `X-Total-Count` header of the
+/// list, so like there a search term `q` takes precedence over the other filters.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The number of matching todo items", body = TodoCount),
+        (status = 400, description = "A malformed tag was given")
+    ),
+    params(SearchQuery, CompletedQuery, TagQuery)
+)]
+#[get("/todo/count")]
+async fn count_todos(
+    search: web::Query<SearchQuery>,
+    filter: web::Query<CompletedQuery>,
+    tag: web::Query<TagQuery>,
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+) -> Result<HttpResponse, Error> {
+    let term = search.into_inner().q;
+    let listing = ListQuery {
+        completed: filter.into_inner().completed,
+        tag: tag_filter(tag.into_inner())?,
+        ..ListQuery::default()
+    };
 
-    // Implement our repository pattern for the mock.
-    impl Repository<TodoEntity> for TodoEntityRepositoryMock {
-        fn get_all(&self) -> Vec<TodoEntity> {
-            self.db
-                .lock()
-                .unwrap()
-                .values()
-                .map(|v| v.clone())
-                .collect()
-        }
+    let count = telemetry::block(move || match term {
+        Some(term) => repository.search_count(&term),
+        None => repository.count_listed(&listing),
+    })
+    .await??;
 
-        fn get_by_id(&self, todo_id: Uuid) -> Option<TodoEntity> {
-            self.db.lock().unwrap().get(&todo_id).map(|f| f.clone())
-        }
+    Ok(HttpResponse::Ok().json(TodoCount { count }))
+}
 
-        fn insert<'a>(&self, entity: TodoEntity) -> Result<TodoEntity, String> {
-            self.db.lock().unwrap().insert(entity.id, entity.clone());
-            Ok(entity)
-        }
+/// Get the stats of the Todos.
+///
+/// Returns the number of todo items, how many of them are completed or still pending, and how many
+/// were completed within the last 7 days, counted by the data store in a single call.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The stats of the todo items", body = TodoStats),
+        (status = 500, description = "Unable to count the todo items", body = ErrorResponse)
+    )
+)]
+#[get("/todo/stats")]
+async fn get_todo_stats(
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    clock: Data<dyn Clock>, // The clock to determine the recently completed todo items, injected from app_data
+) -> Result<HttpResponse, Error> {
+    let completed_since = clock.now() - chrono::Duration::days(RECENTLY_COMPLETED_DAYS);
+    let (total, completed, completed_last_7_days) =
+        telemetry::block(move || repository.stats(completed_since)).await??;
 
-        fn update(&self, todo_id: Uuid, entity: TodoEntity) -> Result<TodoEntity, String> {
-            *self.db.lock().unwrap().get_mut(&todo_id).unwrap() = entity.clone();
-            Ok(entity)
-        }
+    Ok(HttpResponse::Ok().json(TodoStats {
+        total,
+        completed,
+        pending: total - completed,
+        completed_last_7_days,
+    }))
+}
 
-        fn delete(&self, todo_id: Uuid) -> Result<bool, String> {
-            self.db.lock().unwrap().remove(&todo_id);
-            Ok(true)
-        }
-    }
+/// Get the effort summary of the completed todos.
+///
+/// Returns the number of completed todo items with their total estimated and actual effort.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Effort summary of the completed todo items", body = EffortSummary)
+    )
+)]
+#[get("/todo/stats/effort")]
+async fn get_effort_summary(
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+) -> Result<HttpResponse, Error> {
+    let (completed_items, total_estimated_minutes, total_actual_minutes) =
+        telemetry::block(move || repository.effort_summary()).await??;
 
-    fn get_repository_mock_with_data() -> Arc<dyn Repository<TodoEntity>> {
-        // Create our repository
-        let repository = TodoEntityRepositoryMock {
-            db: Arc::new(Mutex::new(HashMap::new())),
-        };
+    Ok(HttpResponse::Ok().json(EffortSummary {
+        completed_items,
+        total_estimated_minutes,
+        total_actual_minutes,
+    }))
+}
 
-        // insert some mock data
-        let _ = repository.insert(TodoEntity {
-            id: Uuid::parse_str("cdce7fda-909e-41cb-8507-abceb316a5b4").unwrap(),
+/// Get the stats of multiple tags at once.
+///
+/// Returns the number of total, completed and overdue todo items for each of the tags in the
+/// request body, in the requested order, counted in a single grouped query. Tags are matched
+/// exactly, tags without todo items get zero counts.
+#[utoipa::path(
+    request_body = TagStatsRequest,
+    responses(
+        (status = 200, description = "The stats of every requested tag", body = [TagStats]),
+        (status = 400, description = "No tags were given, or one of them is not a valid tag"),
+        (status = 500, description = "Unable to count the todo items", body = ErrorResponse)
+    )
+)]
+#[post("/todo/stats/tags")]
+async fn get_tag_stats(
+    request: JsonBody<TagStatsRequest>,
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    clock: Data<dyn Clock>, // The clock to determine which todo items are overdue, injected from app_data
+) -> Result<HttpResponse, Error> {
+    let request = request.into_inner();
+    validate_request(&request)?;
+
+    let mut requested = request.tags;
+    let mut seen = HashSet::new();
+    requested.retain(|tag| seen.insert(tag.clone()));
+
+    let overdue_before = todo_service::start_of_day(clock.now());
+    let tags = requested.clone();
+    let counts = telemetry::block(move || repository.tag_stats(&tags, overdue_before)).await??;
+
+    let response: Vec<TagStats> = requested
+        .into_iter()
+        .map(|tag| {
+            let (total, completed, overdue) = counts
+                .iter()
+                .find(|(counted, ..)| *counted == tag)
+                .map(|(_, total, completed, overdue)| (*total, *completed, *overdue))
+                .unwrap_or_default();
+            TagStats {
+                tag,
+                total,
+                completed,
+                overdue,
+            }
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Create new Todo to the data source.
+///
+/// The authenticated caller, if any, becomes the owner of the todo item. When create batching is
+/// enabled, the todo item is inserted together with the other todo items created at the same time.
+/// A create retried with the same `Idempotency-Key` header returns the todo item created the first
+/// time, with the `Idempotent-Replayed` header, rather than inserting it again.
+/// Post a new `Todo` in request body as json to store it. Api will return the created `Todo`,
+/// exactly as it was persisted, with a `Location` header referencing it on success or
+/// `ErrorResponse::InternalServerError` if a problem occured whilst creating the todo item.
+#[utoipa::path(
+    request_body = CreateTodoItemRequest,
+    responses(
+        (status = 201, description = "Todo created successfully, or created before with the same idempotency key", body = TodoItem),
+        (status = 400, description = "The estimated or actual effort is negative, the parent todo item doesn't exist or the idempotency key is too long"),
+        (status = 409, description = "A todo item with the same title already exists, while unique titles are enabled, or a create with the same idempotency key is in progress"),
+        (status = 422, description = "The request body contains an unknown field while strict mode is enabled"),
+        (status = 500, description = "Unable to insert new todo item", body = ErrorResponse)
+    ),
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Key to safely retry the create, the todo item is only created once within the configured window")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/todo")]
+#[allow(clippy::too_many_arguments)] // Every extractor is an argument
+async fn create_todo(
+    todo: JsonBody<CreateTodoItemRequest>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+    batcher: Option<Data<CreateBatcher>>, // Buffers creates when batching is enabled
+    idempotency: Option<Data<IdempotencyKeys>>, // Replays retried creates, when registered
+    request: HttpRequest,            // The request, for its Idempotency-Key header
+    request_id: RequestId,           // The correlation id of the request, for the log lines
+    locale: Locale,                  // The language of the error messages
+) -> Result<HttpResponse, Error> {
+    let request_body = todo.into_inner();
+    validate_request(&request_body)?;
+    validate_minutes(
+        request_body.estimated_minutes,
+        request_body.actual_minutes,
+        request_body.remind_before_minutes,
+        locale,
+    )?;
+
+    // Keys are scoped to the caller, so nobody else's todo item is ever replayed
+    let idempotency_key = match request.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => {
+            let key = value
+                .to_str()
+                .ok()
+                .filter(|key| (1..=MAX_IDEMPOTENCY_KEY_LENGTH).contains(&key.len()))
+                .ok_or_else(|| {
+                    actix_web::error::ErrorBadRequest(format!(
+                        "The idempotency key must be 1 to {} visible ASCII characters",
+                        MAX_IDEMPOTENCY_KEY_LENGTH
+                    ))
+                })?;
+            let caller = user.as_ref().map_or("", |user| user.subject.as_str());
+            Some(format!("{}:{}", caller, key))
+        }
+        None => None,
+    };
+    let pending = match (&idempotency, &idempotency_key) {
+        (Some(keys), Some(key)) => match keys.claim(key, Instant::now()) {
+            Claim::New(pending) => Some(pending),
+            Claim::Created(todo) => {
+                return Ok(HttpResponse::Created()
+                    .insert_header((LOCATION, format!("/todo/{}", todo.id)))
+                    .insert_header((IDEMPOTENT_REPLAYED_HEADER, "true"))
+                    .json(todo));
+            }
+            Claim::InProgress => {
+                return Err(actix_web::error::ErrorConflict(
+                    "A todo item with the same idempotency key is being created",
+                ));
+            }
+        },
+        _ => None,
+    };
+
+    if let Some(parent) = request_body.parent_id {
+        let existing = repository.get_by_id(parent).await?;
+        if existing.is_none() {
+            return Err(actix_web::error::ErrorBadRequest(format!(
+                "The parent todo item {} doesn't exist",
+                parent
+            )));
+        }
+    }
+
+    let mut entity: TodoEntity = request_body.into();
+    entity.owner = user.as_ref().map(|user| user.subject.clone());
+    let history = repository.clone();
+    let entity = match batcher {
+        Some(batcher) => batcher.insert(entity).await.map_err(|error| {
+            error!(
+                "Unable to insert new todo item (request {}): {}",
+                request_id, error
+            );
+            match error {
+                BatchError::Repository(error) => Error::from(error),
+                BatchError::Stopped => {
+                    actix_web::error::ErrorInternalServerError("Unable to insert new todo item")
+                }
+            }
+        })?,
+        None => repository.insert(entity).await.map_err(|error| {
+            error!(
+                "Unable to insert new todo item (request {}): {}",
+                request_id, error
+            );
+            Error::from(error)
+        })?,
+    };
+
+    record_changes(history, &user, "create", [entity.id]).await;
+    publish_changes(&events, TodoEventKind::Created, [entity.id]);
+
+    // Respond with the persisted entity rather than the request, so values the data store
+    // adjusted (e.g. timestamp precision) match what a subsequent GET returns.
+    let result: TodoItem = entity.into();
+    if let Some(pending) = pending {
+        pending.complete(&result);
+    }
+    Ok(HttpResponse::Created()
+        .insert_header((LOCATION, format!("/todo/{}", result.id)))
+        .json(result))
+}
+
+/// Create multiple Todos at once.
+///
+/// Post a list of `CreateTodoItemRequest` items to import them within a single transaction. Either
+/// all todo items are created and returned with status 201, or none of them are persisted when one
+/// of them is invalid or can't be inserted. The authenticated caller, if any, becomes the owner.
+#[utoipa::path(
+    request_body = [CreateTodoItemRequest],
+    responses(
+        (status = 201, description = "All todo items were created", body = [TodoItem]),
+        (status = 400, description = "The list is empty, or a todo item has a negative effort or reminder lead time"),
+        (status = 413, description = "The request body is larger than MAX_JSON_BODY_BYTES", body = ErrorResponse),
+        (status = 409, description = "A todo item has the title of another todo item, while unique titles are enabled, none of them were persisted"),
+        (status = 422, description = "The batch is too large, or the request body contains an unknown field while strict mode is enabled"),
+        (status = 500, description = "Unable to insert the todo items, none of them were persisted", body = ErrorResponse)
+    )
+)]
+#[post("/todo/batch")]
+async fn create_todos(
+    todos: JsonBody<Vec<CreateTodoItemRequest>>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+    settings: Data<Settings>,
+    locale: Locale, // The language of the error messages
+) -> Result<HttpResponse, Error> {
+    let requests = todos.into_inner();
+    if requests.is_empty() {
+        return Err(actix_web::error::ErrorBadRequest(
+            Message::EmptyBatch.localize(locale),
+        ));
+    }
+    check_batch_size(requests.len(), &settings)?;
+
+    // Validate every todo item before touching the data store.
+    for (index, request) in requests.iter().enumerate() {
+        request.validate().map_err(|errors| {
+            actix_web::error::ErrorBadRequest(format!("Todo item {} is invalid: {}", index, errors))
+        })?;
+        validate_minutes(
+            request.estimated_minutes,
+            request.actual_minutes,
+            request.remind_before_minutes,
+            locale,
+        )
+        .map_err(|reason| {
+            actix_web::error::ErrorBadRequest(format!("Todo item {} is invalid: {}", index, reason))
+        })?;
+    }
+
+    let owner = user.as_ref().map(|user| user.subject.clone());
+    let entities: Vec<TodoEntity> = requests
+        .into_iter()
+        .map(|request| TodoEntity {
+            owner: owner.clone(),
+            ..request.into()
+        })
+        .collect();
+    let inserted = repository.insert_many(entities).await?;
+    let ids: Vec<Uuid> = inserted.iter().map(|entity| entity.id).collect();
+    record_changes(repository, &user, "create", ids.iter().copied()).await;
+    publish_changes(&events, TodoEventKind::Created, ids);
+
+    let response: Vec<TodoItem> = inserted.into_iter().map(|entity| entity.into()).collect();
+    Ok(HttpResponse::Created().json(response))
+}
+
+/// Import Todos from a JSON array.
+///
+/// Accepts a JSON array of `CreateTodoItemRequest` items of at most `MAX_IMPORT_BYTES` as the
+/// request body, `import_todos_upload` handles the same array uploaded as a file. The todo items
+/// are inserted within a single transaction, like a batch. An invalid todo item rejects the whole
+/// import, unless `?continue_on_error=true` is given, in which case the valid todo items are
+/// imported and the invalid ones are reported in the summary.
+#[utoipa::path(
+    request_body = [CreateTodoItemRequest],
+    responses(
+        (status = 200, description = "The todo items were imported", body = ImportSummary),
+        (status = 400, description = "The import is not a JSON array, or contains an invalid todo item while continue_on_error is not set"),
+        (status = 409, description = "A todo item has the title of another todo item, while unique titles are enabled, none of them were imported"),
+        (status = 413, description = "The import is larger than the configured maximum", body = ErrorResponse),
+        (status = 422, description = "The import contains more todo items than the maximum batch size"),
+        (status = 500, description = "Unable to insert the todo items, none of them were persisted", body = ErrorResponse)
+    ),
+    params(ImportQuery),
+    security(("bearer_auth" = []))
+)]
+#[post("/todo/import")]
+async fn import_todos(
+    body: web::Payload,
+    query: web::Query<ImportQuery>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+    settings: Data<Settings>,
+    locale: Locale, // The language of the error messages
+) -> Result<HttpResponse, Error> {
+    let body = body
+        .to_bytes_limited(settings.max_import_bytes)
+        .await
+        .map_err(|_| payload_too_large(settings.max_import_bytes))??;
+    import(
+        &body,
+        query.continue_on_error,
+        user,
+        repository,
+        events,
+        &settings,
+        locale,
+    )
+    .await
+}
+
+/// A JSON array of todo items uploaded as a file.
+#[derive(MultipartForm)]
+struct ImportUpload {
+    file: actix_multipart::form::bytes::Bytes,
+}
+
+/// Import Todos from an uploaded JSON file.
+///
+/// Registered before `import_todos`, it handles the `multipart/form-data` uploads with the JSON
+/// array as the `file` field, which is imported the same way.
+#[utoipa::path(
+    request_body(content = String, content_type = "multipart/form-data", description = "The JSON array of `CreateTodoItemRequest` items as the `file` field"),
+    responses(
+        (status = 200, description = "The todo items were imported", body = ImportSummary),
+        (status = 400, description = "The upload has no file, or the file is not a JSON array or contains an invalid todo item while continue_on_error is not set"),
+        (status = 409, description = "A todo item has the title of another todo item, while unique titles are enabled, none of them were imported"),
+        (status = 413, description = "The upload is larger than the configured maximum"),
+        (status = 422, description = "The file contains more todo items than the maximum batch size"),
+        (status = 500, description = "Unable to insert the todo items, none of them were persisted", body = ErrorResponse)
+    ),
+    params(ImportQuery),
+    security(("bearer_auth" = []))
+)]
+#[post("/todo/import", guard = "is_multipart")]
+async fn import_todos_upload(
+    upload: MultipartForm<ImportUpload>,
+    query: web::Query<ImportQuery>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+    settings: Data<Settings>,
+    locale: Locale, // The language of the error messages
+) -> Result<HttpResponse, Error> {
+    let body = &upload.file.data;
+    import(
+        body,
+        query.continue_on_error,
+        user,
+        repository,
+        events,
+        &settings,
+        locale,
+    )
+    .await
+}
+
+// Matches the requests uploading a file.
+fn is_multipart(context: &GuardContext) -> bool {
+    context
+        .header::<ContentType>()
+        .is_some_and(|content_type| content_type.essence_str() == "multipart/form-data")
+}
+
+/// Imports the todo items of a JSON array on behalf of the import endpoints.
+async fn import(
+    body: &[u8],
+    continue_on_error: bool,
+    user: Option<AuthenticatedUser>,
+    repository: Data<dyn TodoRepository>,
+    events: Option<Data<TodoEvents>>,
+    settings: &Settings,
+    locale: Locale,
+) -> Result<HttpResponse, Error> {
+    let records: Vec<serde_json::Value> = serde_json::from_slice(body).map_err(|error| {
+        actix_web::error::ErrorBadRequest(format!("The import is not a JSON array: {}", error))
+    })?;
+    if records.is_empty() {
+        return Err(actix_web::error::ErrorBadRequest(
+            Message::EmptyBatch.localize(locale),
+        ));
+    }
+    check_batch_size(records.len(), settings)?;
+
+    let owner = user.as_ref().map(|user| user.subject.clone());
+    let mut entities = Vec::new();
+    let mut errors = Vec::new();
+    for (index, record) in records.into_iter().enumerate() {
+        match parse_import_record(record, locale) {
+            Ok(request) => entities.push(TodoEntity {
+                owner: owner.clone(),
+                ..request.into()
+            }),
+            Err(message) if continue_on_error => errors.push(ImportError { index, message }),
+            Err(message) => {
+                return Err(actix_web::error::ErrorBadRequest(format!(
+                    "Todo item {} is invalid: {}",
+                    index, message
+                )))
+            }
+        }
+    }
+
+    let imported = match entities.is_empty() {
+        true => 0,
+        false => {
+            let inserted = repository.insert_many(entities).await?;
+            let ids: Vec<Uuid> = inserted.iter().map(|entity| entity.id).collect();
+            record_changes(repository, &user, "create", ids.iter().copied()).await;
+            publish_changes(&events, TodoEventKind::Created, ids);
+            inserted.len()
+        }
+    };
+    Ok(HttpResponse::Ok().json(ImportSummary {
+        imported,
+        failed: errors.len(),
+        errors,
+    }))
+}
+
+// Deserializes and validates a single todo item of an import.
+fn parse_import_record(
+    record: serde_json::Value,
+    locale: Locale,
+) -> Result<CreateTodoItemRequest, String> {
+    let request: CreateTodoItemRequest =
+        serde_json::from_value(record).map_err(|error| error.to_string())?;
+    request.validate().map_err(|errors| errors.to_string())?;
+    validate_minutes(
+        request.estimated_minutes,
+        request.actual_minutes,
+        request.remind_before_minutes,
+        locale,
+    )
+    .map_err(|reason| reason.to_string())?;
+    Ok(request)
+}
+
+/// Reassign all todos of one owner to another owner.
+///
+/// Only allowed for admins, e.g. when offboarding a user. All todo items are reassigned in a
+/// single update and every reassignment is recorded in the history.
+#[utoipa::path(
+    request_body = ReassignOwnerRequest,
+    responses(
+        (status = 200, description = "The todo items were reassigned", body = ReassignOwnerResponse),
+        (status = 401, description = "The caller is not authenticated"),
+        (status = 403, description = "The caller is not an admin"),
+        (status = 500, description = "Unable to reassign the todo items", body = ErrorResponse)
+    )
+)]
+#[post("/admin/todo/reassign-by-owner")]
+async fn reassign_by_owner(
+    request: JsonBody<ReassignOwnerRequest>,
+    user: AuthenticatedUser,              // The authenticated caller
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+) -> Result<HttpResponse, Error> {
+    if !user.is_admin() {
+        return Err(actix_web::error::ErrorForbidden(
+            "Only admins are allowed to reassign todo items",
+        ));
+    }
+
+    let request = request.into_inner();
+    let count = telemetry::block(move || {
+        repository.reassign_owner(&request.from, &request.to, &user.subject)
+    })
+    .await??;
+
+    Ok(HttpResponse::Ok().json(ReassignOwnerResponse { count }))
+}
+
+/// Apply a list of operations within a single transaction.
+///
+/// Post a list of `SyncOperation` items (`create`, `update` or `delete`) which are applied in order.
+/// Either all operations are persisted and the resulting state of each item is returned, or none
+/// of them are persisted and an error naming the failing operation is returned.
+#[utoipa::path(
+    request_body = [SyncOperation],
+    responses(
+        (status = 200, description = "All operations were applied", body = [SyncOperationResult]),
+        (status = 400, description = "An operation is missing a required field or has a negative effort"),
+        (status = 422, description = "An operation could not be applied or the batch is too large, none of the operations were persisted"),
+    )
+)]
+#[post("/todo/sync")]
+async fn sync_todos(
+    operations: JsonBody<Vec<SyncOperation>>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+    settings: Data<Settings>,
+) -> Result<HttpResponse, Error> {
+    let operations = operations.into_inner();
+    check_batch_size(operations.len(), &settings)?;
+    let kinds: Vec<_> = operations.iter().map(|operation| operation.op).collect();
+
+    // Validate every operation before touching the data store.
+    let changes = operations
+        .into_iter()
+        .enumerate()
+        .map(|(index, operation)| {
+            TodoChange::try_from(operation)
+                .map_err(|reason| format!("Operation {} is invalid: {}", index, reason))
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(actix_web::error::ErrorBadRequest)?;
+    let ids: Vec<_> = changes.iter().map(TodoChange::id).collect();
+
+    let history = repository.clone();
+    let result = telemetry::block(move || repository.apply_changes(changes)).await?;
+
+    match result {
+        Ok(applied) => {
+            for (op, id) in kinds.iter().zip(&ids) {
+                let (kind, action) = match op {
+                    SyncOperationKind::Create => (TodoEventKind::Created, "create"),
+                    SyncOperationKind::Update => (TodoEventKind::Updated, "update"),
+                    SyncOperationKind::Delete => (TodoEventKind::Deleted, "delete"),
+                };
+                record_changes(history.clone(), &user, action, [*id]).await;
+                publish_changes(&events, kind, [*id]);
+            }
+            record_completed_subtasks(history, &user, &events, applied.completed_subtasks).await;
+            let response: Vec<SyncOperationResult> = kinds
+                .into_iter()
+                .zip(ids)
+                .zip(applied.items)
+                .map(|((op, id), item)| SyncOperationResult {
+                    op,
+                    id,
+                    item: item.map(|entity| entity.into()),
+                })
+                .collect();
+            Ok(HttpResponse::Ok().json(response))
+        }
+        Err(ChangeError {
+            error: RepositoryError::PoolTimeout,
+            ..
+        }) => Err(RepositoryError::PoolTimeout.into()),
+        Err(reason) => {
+            error!("Unable to sync todo items: {}", reason);
+            Ok(HttpResponse::UnprocessableEntity().body(reason.to_string()))
+        }
+    }
+}
+
+/// Delete Todo by given path variable id.
+///
+/// Api will delete todo from datasource by the provided id and return success 200.
+/// If storage does not contain `Todo` with given id 404 not found will be returned.
+///
+/// With `?only_if_completed=true` the todo item is only deleted when it is completed, otherwise 409
+/// conflict is returned, which guards against accidentally deleting unfinished work.
+///
+/// The todo item is kept as a tombstone, which `POST /todo/{id}/restore` brings back. When a
+/// soft-delete retention period is configured, it is purged after that period, which the
+/// `X-Retention-Until` header of the response tells.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Todo deleted successfully, with an `X-Retention-Until` header when it is kept as a tombstone"),
+        (status = 400, description = "The given identifier was not a correct uuid"),
+        (status = 404, description = "Todo item was not found with the given identifier"),
+        (status = 409, description = "Todo item is not completed, while only_if_completed was given"),
+        (status = 500, description = "Unable to delete todo item", body = ErrorResponse)
+    ),
+    params(
+        ("id", description = "Unique storage id of Todo"),
+        DeleteQuery
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/todo/{id}")]
+async fn delete_todo(
+    id: web::Path<Uuid>,
+    query: web::Query<DeleteQuery>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+    settings: Option<Data<Settings>>, // The soft-delete retention, kept until restored when absent
+) -> Result<HttpResponse, Error> {
+    let uuid = id.into_inner();
+    let only_if_completed = query.only_if_completed;
+    let retention = settings.and_then(|settings| settings.soft_delete_retention());
+    let now = Utc::now();
+    let history = repository.clone();
+    let deleted = match (retention, only_if_completed) {
+        (None, false) => repository.delete(uuid).await,
+        _ => repository.soft_delete(uuid, only_if_completed, now).await,
+    }?;
+    match deleted {
+        true => {
+            record_changes(history, &user, "delete", [uuid]).await;
+            publish_changes(&events, TodoEventKind::Deleted, [uuid]);
+            let mut response = HttpResponse::Ok();
+            if let Some(retention) = retention {
+                let until = HttpDate::from(SystemTime::from(now + retention));
+                response.insert_header((RETENTION_UNTIL_HEADER, until.to_string()));
+            }
+            Ok(response.finish())
+        }
+        false if only_if_completed => {
+            Ok(HttpResponse::Conflict().body("Only completed todo items can be deleted"))
+        }
+        false => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// Delete multiple Todos at once.
+///
+/// Deletes all todo items with one of the ids in the request body in a single query, and returns
+/// the number of deleted todo items. The count is lower than the number of ids when some of them
+/// don't exist.
+#[utoipa::path(
+    request_body = DeleteTodoItemsRequest,
+    responses(
+        (status = 200, description = "The todo items were deleted", body = DeleteTodoItemsResponse),
+        (status = 400, description = "The list of ids is empty"),
+        (status = 422, description = "The list contains more ids than the maximum batch size"),
+        (status = 500, description = "Unable to delete the todo items", body = ErrorResponse)
+    )
+)]
+#[delete("/todo")]
+async fn delete_todos(
+    request: JsonBody<DeleteTodoItemsRequest>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+    settings: Data<Settings>,
+) -> Result<HttpResponse, Error> {
+    let ids = request.into_inner().ids;
+    if ids.is_empty() {
+        return Err(actix_web::error::ErrorBadRequest(
+            "At least one id is required",
+        ));
+    }
+    check_batch_size(ids.len(), &settings)?;
+
+    // Ids that don't exist or were deleted before aren't published as changes
+    let deleted = repository.delete_many(ids).await?;
+    let count = deleted.len();
+    record_changes(repository, &user, "delete", deleted.iter().copied()).await;
+    publish_changes(&events, TodoEventKind::Deleted, deleted);
+
+    Ok(HttpResponse::Ok().json(DeleteTodoItemsResponse { count }))
+}
+
+/// Permanently remove soft-deleted Todos.
+///
+/// Only allowed for admins, to reclaim space. Todo items deleted more than `older_than_days` ago
+/// (30 by default) are removed and can no longer be restored, recently deleted todo items are kept.
+/// The number of purged todo items is returned.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The soft-deleted todo items were purged", body = PurgeDeletedResponse),
+        (status = 401, description = "The caller is not authenticated"),
+        (status = 403, description = "The caller is not an admin"),
+        (status = 500, description = "Unable to purge the todo items", body = ErrorResponse)
+    ),
+    params(PurgeQuery),
+    security(("bearer_auth" = []))
+)]
+#[delete("/todo/purge")]
+async fn purge_deleted_todos(
+    query: web::Query<PurgeQuery>,
+    user: AuthenticatedUser,              // The authenticated caller
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+) -> Result<HttpResponse, Error> {
+    if !user.is_admin() {
+        return Err(actix_web::error::ErrorForbidden(
+            "Only admins are allowed to purge todo items",
+        ));
+    }
+
+    let days = query.older_than_days.unwrap_or(DEFAULT_PURGE_AGE_DAYS);
+    let before = Utc::now()
+        .checked_sub_days(Days::new(days))
+        .unwrap_or(DateTime::UNIX_EPOCH);
+    let history = repository.clone();
+    let purged = telemetry::block(move || repository.purge_deleted(before)).await??;
+    let count = purged.len();
+    activity::record(history.into_inner(), &user.subject, "purge", purged).await;
+
+    Ok(HttpResponse::Ok().json(PurgeDeletedResponse { count }))
+}
+
+/// Update Todo with given id.
+///
+/// Tries to update `Todo` by given id as path variable. If todo is found by id values are
+/// updated according `UpdateTodoItemRequest` and updated `Todo` is returned with status 200.
+/// If todo is not found then 404 not found is returned.
+///
+/// The `expected_version` of the request has to match the current version of the todo item,
+/// otherwise it was changed by someone else in the meantime and 409 conflict is returned.
+///
+/// With `?return=diff` the changed fields are returned instead, as `{ field: { from, to } }`, and
+/// with `?return=changed` only the id and the new values of the changed fields.
+///
+/// Deprecated in favor of the partial update of `PATCH /todo/{id}`, responses carry the
+/// `Deprecation` and `Sunset` headers.
+#[utoipa::path(
+    request_body = UpdateTodoItemRequest,
+    responses(
+        (status = 200, description = "Todo updated successfully, or the changed fields when `return=diff` or `return=changed`", body = TodoItem),
+        (status = 400, description = "The given identifier was not a correct uuid, or the effort is negative"),
+        (status = 404, description = "Todo item was not found with the given identifier"),
+        (status = 409, description = "The todo item was changed since the expected version, another todo item has the same title while unique titles are enabled, or its subtasks are incomplete while they are required to be done"),
+        (status = 422, description = "The request body contains an unknown field while strict mode is enabled"),
+        (status = 500, description = "Unable to update todo item", body = ErrorResponse)
+    ),
+    params(
+        ("id", description = "Unique storage id of Todo"),
+        ("return" = Option<String>, Query, description = "Set to `diff` or `changed` to return the changed fields instead of the todo item")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/todo/{id}")]
+async fn update_todo(
+    id: web::Path<Uuid>,
+    todo: JsonBody<UpdateTodoItemRequest>,
+    query: web::Query<ReturnQuery>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+    locale: Locale,                  // The language of the error messages
+) -> Result<HttpResponse, Error> {
+    let request_body = todo.into_inner();
+    validate_request(&request_body)?;
+    validate_minutes(
+        request_body.estimated_minutes,
+        request_body.actual_minutes,
+        request_body.remind_before_minutes,
+        locale,
+    )?;
+    let uuid = id.into_inner();
+    let history = repository.clone();
+    let changed =
+        telemetry::block(move || repository.update_with_previous(uuid, request_body.into()))
+            .await??;
+
+    Ok(changed_response(uuid, changed, query.representation, user, history, events).await)
+}
+
+/// Partially update Todo with given id.
+///
+/// Only the fields present in the `PatchTodoItemRequest` are changed, absent fields leave the todo
+/// item untouched. The updated `Todo` is returned with status 200, or 404 not found if the todo
+/// item doesn't exist. When an `expected_version` is given, 409 conflict is returned if the todo
+/// item has a different version.
+///
+/// Like the full update, `?return=diff` or `?return=changed` return the changed fields instead.
+///
+/// Bodies sent with `Content-Type: application/merge-patch+json` are applied as a JSON merge patch
+/// (RFC 7396) instead, where an explicit `null` clears the `due_date`, `estimated_minutes`,
+/// `actual_minutes` or `remind_before_minutes` of the todo item.
+#[utoipa::path(
+    request_body = PatchTodoItemRequest,
+    responses(
+        (status = 200, description = "Todo updated successfully, or the changed fields when `return=diff` or `return=changed`", body = TodoItem),
+        (status = 400, description = "The given identifier was not a correct uuid, or the merge patch is invalid"),
+        (status = 404, description = "Todo item was not found with the given identifier"),
+        (status = 409, description = "The todo item was changed since the expected version, another todo item has the same title while unique titles are enabled, or its subtasks are incomplete while they are required to be done"),
+        (status = 422, description = "The request body contains an unknown field while strict mode is enabled"),
+        (status = 500, description = "Unable to update todo item", body = ErrorResponse)
+    ),
+    params(
+        ("id", description = "Unique storage id of Todo"),
+        ("return" = Option<String>, Query, description = "Set to `diff` or `changed` to return the changed fields instead of the todo item")
+    ),
+)]
+#[patch("/todo/{id}")]
+async fn patch_todo(
+    id: web::Path<Uuid>,
+    todo: JsonBody<PatchTodoItemRequest>,
+    query: web::Query<ReturnQuery>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+) -> Result<HttpResponse, Error> {
+    let request = todo.into_inner();
+    validate_request(&request)?;
+    let expected_version = request.expected_version;
+    let patch: TodoPatch = request.into();
+    apply_patch(
+        id.into_inner(),
+        patch,
+        expected_version,
+        query.representation,
+        user,
+        repository,
+        events,
+    )
+    .await
+}
+
+/// Partially update Todo with given id from a JSON merge patch (RFC 7396).
+///
+/// Registered before `patch_todo`, it handles the requests sent with
+/// `Content-Type: application/merge-patch+json`. Members of the document update the field, `null`
+/// clears it and absent members leave it untouched. An invalid document is rejected with 400.
+#[utoipa::path(
+    request_body(content = PatchTodoItemRequest, content_type = "application/merge-patch+json", description = "A JSON merge patch of the todo item, `null` clears a field"),
+    responses(
+        (status = 200, description = "Todo updated successfully, or the changed fields when `return=diff` or `return=changed`", body = TodoItem),
+        (status = 400, description = "The given identifier was not a correct uuid, or the merge patch is invalid"),
+        (status = 404, description = "Todo item was not found with the given identifier"),
+        (status = 409, description = "Another todo item has the same title while unique titles are enabled, or its subtasks are incomplete while they are required to be done"),
+        (status = 500, description = "Unable to update todo item", body = ErrorResponse)
+    ),
+    params(
+        ("id", description = "Unique storage id of Todo"),
+        ("return" = Option<String>, Query, description = "Set to `diff` or `changed` to return the changed fields instead of the todo item")
+    ),
+)]
+#[patch("/todo/{id}", guard = "is_merge_patch")]
+async fn merge_patch_todo(
+    id: web::Path<Uuid>,
+    document: JsonBody<serde_json::Value>,
+    query: web::Query<ReturnQuery>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+    locale: Locale,                  // The language of the error messages
+) -> Result<HttpResponse, Error> {
+    let patch = todo_service::merge_patch(document.into_inner())
+        .map_err(actix_web::error::ErrorBadRequest)?;
+    // The merged title and description are held to the same limits as a regular patch
+    validate_request(&PatchTodoItemRequest {
+        title: patch.title.clone(),
+        description: patch.description.clone(),
+        ..Default::default()
+    })?;
+    validate_minutes(
+        patch.estimated_minutes.flatten(),
+        patch.actual_minutes.flatten(),
+        patch.remind_before_minutes.flatten(),
+        locale,
+    )?;
+    apply_patch(
+        id.into_inner(),
+        patch,
+        None,
+        query.representation,
+        user,
+        repository,
+        events,
+    )
+    .await
+}
+
+// Matches the requests sending a JSON merge patch document.
+fn is_merge_patch(context: &GuardContext) -> bool {
+    context
+        .header::<ContentType>()
+        .is_some_and(|content_type| content_type.essence_str() == MERGE_PATCH)
+}
+
+/// Applies the changes of a patch request to a todo item and responds with the requested
+/// representation of the result.
+async fn apply_patch(
+    uuid: Uuid,
+    patch: TodoPatch,
+    expected_version: Option<i32>,
+    representation: ReturnRepresentation,
+    user: Option<AuthenticatedUser>,
+    repository: Data<dyn TodoRepository>,
+    events: Option<Data<TodoEvents>>,
+) -> Result<HttpResponse, Error> {
+    let history = repository.clone();
+    let changed =
+        telemetry::block(move || repository.patch_with_previous(uuid, patch, expected_version))
+            .await??;
+
+    Ok(changed_response(uuid, changed, representation, user, history, events).await)
+}
+
+/// Records and publishes an updated todo item, along with the subtasks completed with it, and
+/// responds with the requested representation of the result.
+async fn changed_response(
+    uuid: Uuid,
+    changed: Option<Changed>,
+    representation: ReturnRepresentation,
+    user: Option<AuthenticatedUser>,
+    repository: Data<dyn TodoRepository>,
+    events: Option<Data<TodoEvents>>,
+) -> HttpResponse {
+    let Some(changed) = changed else {
+        warn!("Todo item with id {} was not found in the data store", uuid);
+        return HttpResponse::NotFound().finish();
+    };
+
+    record_changes(repository.clone(), &user, "update", [uuid]).await;
+    publish_changes(&events, TodoEventKind::Updated, [uuid]);
+    record_completed_subtasks(repository, &user, &events, changed.completed_subtasks).await;
+    changes_response(representation, changed.previous, changed.updated)
+}
+
+/// Records and publishes the subtasks completed along with a todo item, see `SubtaskRule`.
+async fn record_completed_subtasks(
+    repository: Data<dyn TodoRepository>,
+    user: &Option<AuthenticatedUser>,
+    events: &Option<Data<TodoEvents>>,
+    ids: Vec<Uuid>,
+) {
+    publish_changes(events, TodoEventKind::Updated, ids.iter().copied());
+    record_changes(repository, user, "complete", ids).await;
+}
+
+/// Restore a deleted Todo with given id.
+///
+/// Brings a soft-deleted todo item back and returns it with status 200, restoring a todo item that
+/// isn't deleted returns it unchanged. 404 not found is returned when the todo item doesn't exist,
+/// or has already been purged.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Todo restored successfully", body = TodoItem),
+        (status = 400, description = "The given identifier was not a correct uuid"),
+        (status = 404, description = "Todo item was not found with the given identifier"),
+        (status = 409, description = "Another todo item has the same title, while unique titles are enabled"),
+        (status = 500, description = "Unable to restore todo item", body = ErrorResponse)
+    ),
+    params(
+        ("id", description = "Unique storage id of Todo")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/todo/{id}/restore")]
+async fn restore_todo(
+    id: web::Path<Uuid>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+) -> Result<HttpResponse, Error> {
+    let uuid = id.into_inner();
+    let history = repository.clone();
+    let entity = telemetry::block(move || repository.restore(uuid)).await??;
+
+    match entity {
+        Some(entity) => {
+            record_changes(history, &user, "restore", [uuid]).await;
+            publish_changes(&events, TodoEventKind::Created, [uuid]);
+            let result: TodoItem = entity.into();
+            Ok(HttpResponse::Ok().json(result))
+        }
+        None => {
+            warn!("Todo item with id {} was not found in the data store", uuid);
+            Ok(HttpResponse::NotFound().finish())
+        }
+    }
+}
+
+/// Responds with an updated todo item, or its changes as the full diff or as only the new values
+/// of the changed fields.
+fn changes_response(
+    representation: ReturnRepresentation,
+    previous: TodoEntity,
+    updated: TodoEntity,
+) -> HttpResponse {
+    if representation == ReturnRepresentation::Item {
+        let result: TodoItem = updated.into();
+        return HttpResponse::Ok().json(result);
+    }
+
+    let id = updated.id;
+    let diff = todo_service::diff(&previous.into(), &updated.into());
+    match representation {
+        ReturnRepresentation::Changed => {
+            HttpResponse::Ok().json(todo_service::changed_fields(id, diff))
+        }
+        _ => HttpResponse::Ok().json(diff),
+    }
+}
+
+/// Mark Todo with given id as completed.
+///
+/// Sets the completion time to now and returns the updated `Todo` with status 200, or 404 not found
+/// if the todo item doesn't exist.
+///
+/// Like every write completing a todo item, with `CASCADE_COMPLETE_SUBTASKS` set the incomplete
+/// subtasks, and their subtasks, are completed along with it. Otherwise, with
+/// `REQUIRE_SUBTASKS_DONE` set, todo items with incomplete subtasks are left as they are and 409
+/// conflict is returned.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Todo marked as completed", body = TodoItem),
+        (status = 400, description = "The given identifier was not a correct uuid"),
+        (status = 404, description = "Todo item was not found with the given identifier"),
+        (status = 409, description = "Subtasks of the todo item are incomplete, while they are required to be done"),
+    ),
+    params(
+        ("id", description = "Unique storage id of Todo")
+    ),
+)]
+#[post("/todo/{id}/complete")]
+async fn complete_todo(
+    id: web::Path<Uuid>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+) -> Result<HttpResponse, Error> {
+    set_completed(id.into_inner(), true, user, repository, events).await
+}
+
+/// Mark Todo with given id as not completed.
+///
+/// Clears the completion time and returns the updated `Todo` with status 200, or 404 not found if
+/// the todo item doesn't exist.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Todo marked as not completed", body = TodoItem),
+        (status = 400, description = "The given identifier was not a correct uuid"),
+        (status = 404, description = "Todo item was not found with the given identifier"),
+    ),
+    params(
+        ("id", description = "Unique storage id of Todo")
+    ),
+)]
+#[post("/todo/{id}/incomplete")]
+async fn incomplete_todo(
+    id: web::Path<Uuid>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+) -> Result<HttpResponse, Error> {
+    set_completed(id.into_inner(), false, user, repository, events).await
+}
+
+/// Marks a todo item as (not) completed on behalf of the complete and incomplete endpoints.
+async fn set_completed(
+    uuid: Uuid,
+    completed: bool,
+    user: Option<AuthenticatedUser>,
+    repository: Data<dyn TodoRepository>,
+    events: Option<Data<TodoEvents>>,
+) -> Result<HttpResponse, Error> {
+    let history = repository.clone();
+    let result = telemetry::block(move || match completed {
+        true => Ok(repository
+            .complete(uuid)?
+            .map(|changed| (changed.updated, changed.completed_subtasks))),
+        false => Ok::<_, RepositoryError>(
+            repository
+                .set_completed(uuid, false)?
+                .map(|entity| (entity, Vec::new())),
+        ),
+    })
+    .await??;
+
+    match result {
+        Some((entity, completed_subtasks)) => {
+            let action = match completed {
+                true => "complete",
+                false => "incomplete",
+            };
+            record_changes(history.clone(), &user, action, [uuid]).await;
+            publish_changes(&events, TodoEventKind::Updated, [uuid]);
+            record_completed_subtasks(history, &user, &events, completed_subtasks).await;
+            let result: TodoItem = entity.into();
+            Ok(HttpResponse::Ok().json(result))
+        }
+        None => {
+            warn!("Todo item with id {} was not found in the data store", uuid);
+            Ok(HttpResponse::NotFound().finish())
+        }
+    }
+}
+
+/// Add an item to the checklist of the Todo with given id.
+///
+/// The item starts out not done. Returns the updated `Todo` with status 201, or 404 not found if the
+/// todo item doesn't exist.
+#[utoipa::path(
+    request_body = AddChecklistItemRequest,
+    responses(
+        (status = 201, description = "Checklist item added", body = TodoItem),
+        (status = 400, description = "The text is empty or too long"),
+        (status = 404, description = "Todo item was not found with the given identifier"),
+    ),
+    params(
+        ("id", description = "Unique storage id of Todo")
+    ),
+)]
+#[post("/todo/{id}/checklist")]
+async fn add_checklist_item(
+    id: web::Path<Uuid>,
+    item: JsonBody<AddChecklistItemRequest>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+) -> Result<HttpResponse, Error> {
+    let item = item.into_inner();
+    validate_request(&item)?;
+
+    let uuid = id.into_inner();
+    let history = repository.clone();
+    let entity = telemetry::block(move || repository.add_checklist_item(uuid, item.text)).await??;
+
+    record_changes(history, &user, "update", [uuid]).await;
+    publish_changes(&events, TodoEventKind::Updated, [uuid]);
+    let result: TodoItem = entity.into();
+    Ok(HttpResponse::Created().json(result))
+}
+
+/// Toggle a checklist item of the Todo with given id.
+///
+/// Marks the checklist item at the given (0-based) index as done, or as not done when it already
+/// was. Returns the updated `Todo` with its checklist progress, or 404 not found if the todo item or
+/// checklist item doesn't exist.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Checklist item toggled", body = TodoItem),
+        (status = 404, description = "Todo item or checklist item was not found"),
+    ),
+    params(
+        ("id", description = "Unique storage id of Todo"),
+        ("index", description = "Position of the item in the checklist")
+    ),
+)]
+#[patch("/todo/{id}/checklist/{index}")]
+async fn toggle_checklist_item(
+    path: web::Path<(Uuid, usize)>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+) -> Result<HttpResponse, Error> {
+    let (uuid, index) = path.into_inner();
+    let history = repository.clone();
+    let entity = telemetry::block(move || repository.toggle_checklist_item(uuid, index)).await??;
+
+    record_changes(history, &user, "update", [uuid]).await;
+    publish_changes(&events, TodoEventKind::Updated, [uuid]);
+    let result: TodoItem = entity.into();
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// Add a tag to the Todo with given id.
+///
+/// Tags are free-form labels, optionally namespaced like `project/alpha`. Adding a tag the todo
+/// item already has leaves it unchanged. Returns the updated `Todo` with status 200, or 404 not found
+/// if the todo item doesn't exist.
+#[utoipa::path(
+    request_body = AddTagRequest,
+    responses(
+        (status = 200, description = "Tag added", body = TodoItem),
+        (status = 400, description = "The tag is not formatted correctly"),
+        (status = 404, description = "Todo item was not found with the given identifier"),
+    ),
+    params(
+        ("id", description = "Unique storage id of Todo")
+    ),
+)]
+#[post("/todo/{id}/tags")]
+async fn add_tag(
+    id: web::Path<Uuid>,
+    request: JsonBody<AddTagRequest>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+) -> Result<HttpResponse, Error> {
+    let request = request.into_inner();
+    validate_request(&request)?;
+
+    let uuid = id.into_inner();
+    let history = repository.clone();
+    let entity = telemetry::block(move || repository.add_tag(uuid, request.tag)).await??;
+
+    record_changes(history, &user, "update", [uuid]).await;
+    publish_changes(&events, TodoEventKind::Updated, [uuid]);
+    let result: TodoItem = entity.into();
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// Remove a tag from the Todo with given id.
+///
+/// Namespaced tags are given as is, e.g. `DELETE /todo/{id}/tags/project/alpha`. Returns the
+/// updated `Todo` with status 200, or 404 not found if the todo item doesn't exist or doesn't have
+/// the tag.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Tag removed", body = TodoItem),
+        (status = 404, description = "Todo item was not found, or doesn't have the tag"),
+    ),
+    params(
+        ("id", description = "Unique storage id of Todo"),
+        ("tag", description = "The tag to remove")
+    ),
+)]
+#[delete("/todo/{id}/tags/{tag:.+}")]
+async fn remove_tag(
+    path: web::Path<(Uuid, String)>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+) -> Result<HttpResponse, Error> {
+    let (uuid, tag) = path.into_inner();
+    let history = repository.clone();
+    let entity = telemetry::block(move || repository.remove_tag(uuid, &tag)).await??;
+
+    record_changes(history, &user, "update", [uuid]).await;
+    publish_changes(&events, TodoEventKind::Updated, [uuid]);
+    let result: TodoItem = entity.into();
+    Ok(HttpResponse::Ok().json(result))
+}
+
+/// Get the dependency graph of the todos.
+///
+/// Returns the live todo items as nodes and their dependencies as edges, pointing from the todo
+/// item that has to be done first to the todo item it blocks. The edges never form a cycle, so
+/// planning tools can lay the graph out as a DAG.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The todo items and their dependencies", body = TodoGraph)
+    )
+)]
+#[get("/todo/graph")]
+async fn get_todo_graph(
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+) -> Result<HttpResponse, Error> {
+    let entities = repository.get_all().await?;
+    let dependencies = repository.get_dependencies().await?;
+
+    Ok(HttpResponse::Ok().json(dependency_graph::build_graph(entities, dependencies)))
+}
+
+/// Add a dependency to the Todo with given id.
+///
+/// The todo item becomes blocked by the todo item in the request body. Dependencies that would
+/// create a cycle, including a todo item blocking itself, are rejected with 409 conflict.
+#[utoipa::path(
+    request_body = AddDependencyRequest,
+    responses(
+        (status = 201, description = "Dependency added", body = TodoGraphEdge),
+        (status = 404, description = "Either todo item was not found with the given identifier"),
+        (status = 409, description = "The dependency would create a cycle"),
+    ),
+    params(
+        ("id", description = "Unique storage id of the blocked Todo")
+    ),
+)]
+#[post("/todo/{id}/dependencies")]
+async fn add_dependency(
+    id: web::Path<Uuid>,
+    dependency: JsonBody<AddDependencyRequest>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+) -> Result<HttpResponse, Error> {
+    let uuid = id.into_inner();
+    let blocked_by = dependency.into_inner().blocked_by;
+    let history = repository.clone();
+    let added = telemetry::block(move || repository.add_dependency(uuid, blocked_by)).await??;
+
+    if !added {
+        return Ok(HttpResponse::Conflict().body("The dependency would create a cycle"));
+    }
+    record_changes(history, &user, "update", [uuid]).await;
+    publish_changes(&events, TodoEventKind::Updated, [uuid]);
+    Ok(HttpResponse::Created().json(TodoGraphEdge {
+        from: blocked_by,
+        to: uuid,
+    }))
+}
+
+/// Remove a dependency from the Todo with given id.
+///
+/// Returns 200 when the todo item is no longer blocked by the other todo item, or 404 not found if
+/// there was no such dependency.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Dependency removed"),
+        (status = 404, description = "Dependency was not found"),
+    ),
+    params(
+        ("id", description = "Unique storage id of the blocked Todo"),
+        ("blocked_by", description = "Unique storage id of the Todo blocking it")
+    ),
+)]
+#[delete("/todo/{id}/dependencies/{blocked_by}")]
+async fn remove_dependency(
+    path: web::Path<(Uuid, Uuid)>,
+    user: Option<AuthenticatedUser>, // The authenticated caller, if any
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+    events: Option<Data<TodoEvents>>, // Publishes the change to the event stream, when registered
+) -> Result<HttpResponse, Error> {
+    let (uuid, blocked_by) = path.into_inner();
+    let history = repository.clone();
+    let removed =
+        telemetry::block(move || repository.remove_dependency(uuid, blocked_by)).await??;
+
+    if !removed {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    record_changes(history, &user, "update", [uuid]).await;
+    publish_changes(&events, TodoEventKind::Updated, [uuid]);
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Get the activity feed.
+///
+/// Lists the changes made to the todo items, newest first and paged by `offset` and `limit`, with
+/// the total number of changes in the `X-Total-Count` header. Admins see the changes of all users,
+/// including the purges of the service itself, other users only their own changes. Every write of
+/// an authenticated caller is listed, including the batch, import, sync and bulk delete endpoints.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "A page of the activity feed", body = [ActivityEntry]),
+        (status = 400, description = "The page is out of range"),
+        (status = 401, description = "The caller is not authenticated"),
+    ),
+    params(PageQuery)
+)]
+#[get("/activity")]
+async fn get_activity(
+    page: web::Query<PageQuery>,
+    user: AuthenticatedUser,              // The authenticated caller
+    repository: Data<dyn TodoRepository>, // The todo item repository, injected from app_data
+) -> Result<HttpResponse, Error> {
+    let (offset, limit) = page_bounds(&page)?;
+    let actor = match user.is_admin() {
+        true => None,
+        false => Some(user.subject),
+    };
+
+    let (entries, total) = telemetry::block(move || {
+        let actor = actor.as_deref();
+        Ok::<_, RepositoryError>((
+            repository.activity(actor, offset, limit)?,
+            repository.activity_count(actor)?,
+        ))
+    })
+    .await??;
+
+    let response: Vec<ActivityEntry> = entries.into_iter().map(|entry| entry.into()).collect();
+    Ok(HttpResponse::Ok()
+        .insert_header((TOTAL_COUNT_HEADER, total))
+        .json(response))
+}
+
+pub fn configure(metrics: Data<Metrics>) -> impl FnOnce(&mut ServiceConfig) {
+    move |config: &mut ServiceConfig| {
+        let settings = Settings::from_env();
+
+        // Create our repository
+        let repository = TodoEntityRepository::new(&settings);
+
+        // Todo entity repository is unsized, so we need to wrap this in a Atomic Reference Counter
+        // "For types that are unsized, most commonly dyn T, Data can wrap these types by first constructing an Arc<dyn T> and using the From implementation to convert it."
+        // https://docs.rs/actix-web/latest/actix_web/web/struct.Data.html
+        let probe_arc: Arc<dyn DatabaseProbe> = Arc::new(repository.pool());
+        metrics.watch_pool(repository.pool());
+        let repository_arc: Arc<dyn TodoRepository> = Arc::new(repository);
+        let clock_arc: Arc<dyn Clock> = Arc::new(SystemClock);
+
+        // Reject JSON bodies beyond the configured size with a clear 413
+        config.app_data(json_config(settings.max_json_body_bytes));
+
+        // Bound the uploaded import files like the imported JSON arrays
+        config.app_data(
+            MultipartFormConfig::default()
+                .total_limit(settings.max_import_bytes)
+                .memory_limit(settings.max_import_bytes),
+        );
+
+        // Buffer creates into multi-row inserts when a batch window is configured
+        if settings.create_batch_window_ms > 0 {
+            let window = Duration::from_millis(settings.create_batch_window_ms);
+            config.app_data(Data::new(CreateBatcher::new(
+                repository_arc.clone(),
+                window,
+            )));
+        }
+
+        config
+            // Register our repository for data injection;
+            .app_data(Data::from(repository_arc))
+            // Register the clock used to derive time dependent values
+            .app_data(Data::from(clock_arc))
+            // Register the probe checking whether the data store is reachable
+            .app_data(Data::from(probe_arc))
+            // Register the deployment specific settings
+            .app_data(Data::new(settings))
+            // Register how request bodies should be deserialized
+            .app_data(Data::new(JsonOptions::from_env()))
+            // register our endpoints
+            // Before get_todos, which would answer the cursor pages otherwise
+            .service(get_todos_after)
+            .service(get_todos)
+            .service(create_todo)
+            .service(create_todos)
+            .service(sync_todos)
+            .service(reassign_by_owner)
+            .service(import_todos_upload)
+            .service(import_todos)
+            .service(purge_deleted_todos)
+            .service(delete_todo)
+            .service(delete_todos)
+            // before get_todo_by_id, so `export`, `search`, `count`, `stats`, `graph`, `events` and
+            // `ws` are not mistaken for an id
+            .service(export_todos)
+            .service(export_todos_csv)
+            .service(search_todos)
+            .service(count_todos)
+            .service(get_todo_stats)
+            .service(get_todo_graph)
+            .service(get_todo_events)
+            .service(get_todo_events_socket)
+            .service(get_todo_by_id)
+            .service(head_todo)
+            .service(get_todo_status)
+            .service(get_occurrences)
+            .service(get_effort_summary)
+            .service(get_tag_stats)
+            .service(update_todo)
+            .service(merge_patch_todo)
+            .service(patch_todo)
+            .service(complete_todo)
+            .service(incomplete_todo)
+            .service(restore_todo)
+            .service(add_checklist_item)
+            .service(toggle_checklist_item)
+            .service(add_tag)
+            .service(remove_tag)
+            .service(add_dependency)
+            .service(remove_dependency)
+            .service(get_activity);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use chrono::SubsecRound;
+    use std::time::Duration;
+
+    use actix_web::http::header::{
+        AUTHORIZATION, CONNECTION, ETAG, IF_NONE_MATCH, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY,
+        SEC_WEBSOCKET_VERSION, UPGRADE,
+    };
+    use actix_web::{test, App};
+    use uuid::Uuid;
+
+    use diesel::result::{DatabaseErrorKind, Error as DieselError};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use crate::data::repository::AsyncRepository;
+    use crate::data::todo_repository::{
+        AppliedChanges, AsyncTodoRepository, StatusFields, SubtaskRule,
+    };
+    use crate::entities::checklist::Checklist;
+    use crate::entities::todo_dependency_entity::TodoDependencyEntity;
+    use crate::entities::todo_entity::TodoEntity;
+    use crate::entities::todo_history_entity::TodoHistoryEntity;
+    use crate::services::clock::FixedClock;
+    use crate::services::reminders;
+    use actix_web::body::MessageBody;
+    use actix_web::HttpMessage;
+    use todo_shared::{ChecklistItem, ChecklistProgress};
+    use todo_shared::{SortOrder, TimestampPrecision, TodoGraph, TodoSortField};
+
+    use super::*;
+
+    // Define a mock for TodoRepository
+    #[derive(Default)]
+    pub struct TodoEntityRepositoryMock {
+        db: Arc<Mutex<HashMap<Uuid, TodoEntity>>>,
+
+        // The size of every multi-row insert
+        insert_batches: Arc<Mutex<Vec<usize>>>,
+
+        history: Arc<Mutex<Vec<TodoHistoryEntity>>>,
+
+        dependencies: Arc<Mutex<Vec<TodoDependencyEntity>>>,
+
+        // Reject writes leaving several live todo items with the same title, ignoring case
+        unique_titles: bool,
+
+        // How the writes completing a todo item treat its incomplete subtasks
+        subtask_rule: SubtaskRule,
+    }
+
+    // Implement our repository pattern for the mock.
+    // The mock answers right away, as it keeps the todo items in memory.
+    #[async_trait::async_trait]
+    impl AsyncRepository<TodoEntity> for TodoEntityRepositoryMock {
+        type Key = Uuid;
+
+        async fn get_all(&self) -> Result<Vec<TodoEntity>, RepositoryError> {
+            Ok(self.live())
+        }
+
+        async fn get_by_id(&self, todo_id: Uuid) -> Result<Option<TodoEntity>, RepositoryError> {
+            Ok(self
+                .db
+                .lock()
+                .unwrap()
+                .get(&todo_id)
+                .filter(|f| f.deleted_at.is_none())
+                .cloned())
+        }
+
+        async fn exists(&self, todo_id: Uuid) -> Result<bool, RepositoryError> {
+            Ok(self.get_by_id(todo_id).await?.is_some())
+        }
+
+        async fn insert(&self, entity: TodoEntity) -> Result<TodoEntity, RepositoryError> {
+            let mut db = self.db.lock().unwrap();
+            self.check_title(&db, entity.id, &entity.title)?;
+            insert_new(&mut db, entity)
+        }
+
+        async fn insert_many(
+            &self,
+            entities: Vec<TodoEntity>,
+        ) -> Result<Vec<TodoEntity>, RepositoryError> {
+            self.insert_batches.lock().unwrap().push(entities.len());
+            self.transaction(|db| {
+                entities
+                    .into_iter()
+                    .map(|entity| {
+                        self.check_title(db, entity.id, &entity.title)?;
+                        insert_new(db, entity)
+                    })
+                    .collect()
+            })
+        }
+
+        async fn delete(&self, todo_id: Uuid) -> Result<bool, RepositoryError> {
+            self.delete_many(vec![todo_id])
+                .await
+                .map(|deleted| !deleted.is_empty())
+        }
+
+        async fn delete_many(&self, ids: Vec<Uuid>) -> Result<Vec<Uuid>, RepositoryError> {
+            let mut db = self.db.lock().unwrap();
+            let now = truncate_to_micros(Utc::now());
+            let mut deleted = Vec::new();
+            for id in ids {
+                if let Some(existing) = db.get_mut(&id).filter(|f| f.deleted_at.is_none()) {
+                    existing.deleted_at = Some(now);
+                    existing.updated_at = now;
+                    existing.version += 1;
+                    deleted.push(id);
+                }
+            }
+            Ok(deleted)
+        }
+    }
+
+    // Clears the sent reminder when the due date or lead time changes, like the data store does.
+    fn reschedule_reminder(
+        existing: &mut TodoEntity,
+        due_date: Option<DateTime<Utc>>,
+        remind_before_minutes: Option<i32>,
+    ) {
+        if existing.due_date != due_date || existing.remind_before_minutes != remind_before_minutes
+        {
+            existing.reminded_at = None;
+        }
+    }
+
+    // Postgres stores timestamps with microsecond precision, mimic that so tests notice
+    // responses that were not built from the persisted values.
+    fn truncate_to_micros(time: DateTime<Utc>) -> DateTime<Utc> {
+        time.trunc_subsecs(6)
+    }
+
+    // Inserts or replaces a todo item, with the timestamp precision of Postgres.
+    fn store(db: &mut HashMap<Uuid, TodoEntity>, mut entity: TodoEntity) -> TodoEntity {
+        entity.created_at = truncate_to_micros(entity.created_at);
+        entity.updated_at = truncate_to_micros(entity.updated_at);
+        db.insert(entity.id, entity.clone());
+        entity
+    }
+
+    // Inserts a new todo item, failing like the primary key constraint for an existing id.
+    fn insert_new(
+        db: &mut HashMap<Uuid, TodoEntity>,
+        entity: TodoEntity,
+    ) -> Result<TodoEntity, RepositoryError> {
+        if db.contains_key(&entity.id) {
+            return Err(RepositoryError::Database(DieselError::DatabaseError(
+                DatabaseErrorKind::UniqueViolation,
+                Box::new("duplicate key value".to_string()),
+            )));
+        }
+        Ok(store(db, entity))
+    }
+
+    impl TodoEntityRepositoryMock {
+        // Fails like the data store when titles have to be unique and another live todo item
+        // than the written one has the given title, ignoring case.
+        fn check_title(
+            &self,
+            db: &HashMap<Uuid, TodoEntity>,
+            todo_id: Uuid,
+            title: &str,
+        ) -> Result<(), RepositoryError> {
+            let taken = db.values().any(|f| {
+                f.id != todo_id
+                    && f.deleted_at.is_none()
+                    && f.title.to_lowercase() == title.to_lowercase()
+            });
+            match self.unique_titles && taken {
+                true => Err(RepositoryError::DuplicateTitle),
+                false => Ok(()),
+            }
+        }
+
+        // Treats the subtasks of a completed todo item according to the subtask rule, like the
+        // data store, returning the subtasks completed along with it.
+        fn complete_subtasks(
+            &self,
+            db: &mut HashMap<Uuid, TodoEntity>,
+            todo_id: Uuid,
+            completion_time: DateTime<Utc>,
+        ) -> Result<Vec<Uuid>, RepositoryError> {
+            // Collect the live subtasks, and their subtasks
+            let mut subtasks = Vec::new();
+            let mut parents = vec![todo_id];
+            while let Some(parent) = parents.pop() {
+                for f in db.values() {
+                    if f.parent_id == Some(parent) && f.deleted_at.is_none() {
+                        subtasks.push(f.id);
+                        parents.push(f.id);
+                    }
+                }
+            }
+            subtasks.retain(|subtask| !db[subtask].completed);
+
+            match self.subtask_rule {
+                SubtaskRule::Ignore => Ok(Vec::new()),
+                SubtaskRule::Cascade => {
+                    for subtask in &subtasks {
+                        let f = db.get_mut(subtask).unwrap();
+                        f.completed = true;
+                        f.completed_at = Some(completion_time);
+                        f.updated_at = completion_time;
+                        f.version += 1;
+                    }
+                    Ok(subtasks)
+                }
+                SubtaskRule::RequireDone => match subtasks.len() {
+                    0 => Ok(Vec::new()),
+                    count => Err(RepositoryError::SubtasksIncomplete(count as i64)),
+                },
+            }
+        }
+
+        // Completes the subtasks of a todo item the write completed, like the data store.
+        fn changed(
+            &self,
+            db: &mut HashMap<Uuid, TodoEntity>,
+            previous: TodoEntity,
+            updated: TodoEntity,
+        ) -> Result<Changed, RepositoryError> {
+            let completed_subtasks = match (previous.completed, updated.completed) {
+                (false, true) => self.complete_subtasks(
+                    db,
+                    updated.id,
+                    updated.completed_at.unwrap_or(updated.updated_at),
+                )?,
+                _ => Vec::new(),
+            };
+            Ok(Changed {
+                previous,
+                updated,
+                completed_subtasks,
+            })
+        }
+
+        // Inserts or replaces a todo item.
+        fn store(&self, entity: TodoEntity) -> TodoEntity {
+            store(&mut self.db.lock().unwrap(), entity)
+        }
+
+        // Runs the operations on the todo items like a database transaction, restoring the
+        // todo items as they were when the operations fail.
+        fn transaction<R, E>(
+            &self,
+            operations: impl FnOnce(&mut HashMap<Uuid, TodoEntity>) -> Result<R, E>,
+        ) -> Result<R, E> {
+            let mut db = self.db.lock().unwrap();
+            let snapshot = db.clone();
+            let result = operations(&mut db);
+            if result.is_err() {
+                *db = snapshot;
+            }
+            result
+        }
+
+        // The todo items that are not soft-deleted.
+        fn live(&self) -> Vec<TodoEntity> {
+            self.db
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|v| v.deleted_at.is_none())
+                .map(|v| v.clone())
+                .collect()
+        }
+
+        // The history entries, limited to those of the given actor when given.
+        fn history_of(&self, actor: Option<&str>) -> Vec<TodoHistoryEntity> {
+            self.history
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|entry| actor.is_none_or(|actor| entry.actor == actor))
+                .cloned()
+                .collect()
+        }
+
+        fn matching(&self, term: &str) -> Vec<TodoEntity> {
+            let term = term.to_lowercase();
+            self.live()
+                .into_iter()
+                .filter(|f| {
+                    f.title.to_lowercase().contains(&term)
+                        || f.description.to_lowercase().contains(&term)
+                })
+                .collect()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl AsyncTodoRepository for TodoEntityRepositoryMock {
+        async fn soft_delete(
+            &self,
+            todo_id: Uuid,
+            only_if_completed: bool,
+            at: DateTime<Utc>,
+        ) -> Result<bool, RepositoryError> {
+            let mut db = self.db.lock().unwrap();
+            match db.get_mut(&todo_id) {
+                Some(existing) if existing.deleted_at.is_none() => {
+                    if only_if_completed && !existing.completed {
+                        return Ok(false);
+                    }
+                    existing.deleted_at = Some(truncate_to_micros(at));
+                    Ok(true)
+                }
+                _ => Err(RepositoryError::NotFound),
+            }
+        }
+
+        async fn get_by_id_including_deleted(
+            &self,
+            todo_id: Uuid,
+        ) -> Result<Option<TodoEntity>, RepositoryError> {
+            Ok(self.db.lock().unwrap().get(&todo_id).cloned())
+        }
+
+        async fn get_dependencies(&self) -> Result<Vec<TodoDependencyEntity>, RepositoryError> {
+            Ok(self.dependencies.lock().unwrap().clone())
+        }
+    }
+
+    impl TodoRepository for TodoEntityRepositoryMock {
+        fn get_status_fields(
+            &self,
+            todo_id: Uuid,
+        ) -> Result<Option<StatusFields>, RepositoryError> {
+            Ok(self
+                .db
+                .lock()
+                .unwrap()
+                .get(&todo_id)
+                .map(|f| (f.completed, f.due_date)))
+        }
+
+        fn get_listed(
+            &self,
+            listing: &ListQuery,
+            page: Option<(i64, i64)>,
+        ) -> Result<Vec<TodoEntity>, RepositoryError> {
+            let mut items = match listing.include_deleted {
+                true => self.db.lock().unwrap().values().cloned().collect(),
+                false => self.live(),
+            };
+            items.retain(|f| {
+                listing.completed.is_none_or(|value| f.completed == value)
+                    && listing.tag.as_ref().is_none_or(|tag| tag.matches(&f.tags))
+            });
+            items.sort_by(|a, b| {
+                let ordering = match listing.sort.unwrap_or(TodoSortField::CreatedAt) {
+                    TodoSortField::CreatedAt => a.created_at.cmp(&b.created_at),
+                    TodoSortField::Title => a.title.cmp(&b.title),
+                    TodoSortField::CompletedAt => a.completed_at.cmp(&b.completed_at),
+                };
+                match listing.order {
+                    SortOrder::Asc => ordering,
+                    SortOrder::Desc => ordering.reverse(),
+                }
+                .then(a.id.cmp(&b.id))
+            });
+            let (offset, limit) = page.unwrap_or((0, i64::MAX));
+            Ok(items
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect())
+        }
+
+        fn count_listed(&self, listing: &ListQuery) -> Result<i64, RepositoryError> {
+            Ok(self.get_listed(listing, None)?.len() as i64)
+        }
+
+        fn get_after(
+            &self,
+            after: Option<Cursor>,
+            limit: i64,
+        ) -> Result<Vec<TodoEntity>, RepositoryError> {
+            let mut items = self.live();
+            items.sort_by_key(|f| (f.created_at, f.id));
+            Ok(items
+                .into_iter()
+                .filter(|f| {
+                    after.is_none_or(|cursor| (f.created_at, f.id) > (cursor.created_at, cursor.id))
+                })
+                .take(limit as usize)
+                .collect())
+        }
+
+        fn count(&self) -> Result<i64, RepositoryError> {
+            Ok(self.live().len() as i64)
+        }
+
+        fn estimated_count(&self) -> Result<i64, RepositoryError> {
+            // Like the table statistics, the estimate includes the soft-deleted todo items
+            Ok(self.db.lock().unwrap().len() as i64)
+        }
+
+        fn tag_stats(
+            &self,
+            requested: &[String],
+            overdue_before: DateTime<Utc>,
+        ) -> Result<Vec<(String, i64, i64, i64)>, RepositoryError> {
+            let items = self.live();
+            Ok(requested
+                .iter()
+                .map(|tag| {
+                    let tagged: Vec<_> = items.iter().filter(|f| f.tags.contains(tag)).collect();
+                    let completed = tagged.iter().filter(|f| f.completed).count();
+                    let overdue = tagged
+                        .iter()
+                        .filter(|f| !f.completed && f.due_date.is_some_and(|d| d < overdue_before))
+                        .count();
+                    (
+                        tag.clone(),
+                        tagged.len() as i64,
+                        completed as i64,
+                        overdue as i64,
+                    )
+                })
+                .filter(|(_, total, ..)| *total > 0)
+                .collect())
+        }
+
+        fn purge_deleted(&self, before: DateTime<Utc>) -> Result<Vec<Uuid>, RepositoryError> {
+            let mut db = self.db.lock().unwrap();
+            let purged: Vec<Uuid> = db
+                .values()
+                .filter(|f| f.deleted_at.is_some_and(|at| at < before))
+                .map(|f| f.id)
+                .collect();
+            db.retain(|key, _| !purged.contains(key));
+            Ok(purged)
+        }
+
+        fn restore(&self, todo_id: Uuid) -> Result<Option<TodoEntity>, RepositoryError> {
+            let mut db = self.db.lock().unwrap();
+            if let Some(existing) = db.get(&todo_id).filter(|f| f.deleted_at.is_some()) {
+                self.check_title(&db, todo_id, &existing.title)?;
+            }
+            Ok(db.get_mut(&todo_id).map(|existing| {
+                if existing.deleted_at.take().is_some() {
+                    existing.updated_at = truncate_to_micros(Utc::now());
+                    existing.version += 1;
+                }
+                existing.clone()
+            }))
+        }
+
+        fn search(&self, term: &str) -> Result<Vec<TodoEntity>, RepositoryError> {
+            let mut matches = self.matching(term);
+            matches.sort_by_key(|f| std::cmp::Reverse(f.created_at));
+            Ok(matches)
+        }
+
+        fn search_paged(
+            &self,
+            term: &str,
+            offset: i64,
+            limit: i64,
+        ) -> Result<Vec<TodoEntity>, RepositoryError> {
+            let mut matches = self.matching(term);
+            matches.sort_by_key(|f| f.created_at);
+            Ok(matches
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect())
+        }
+
+        fn search_count(&self, term: &str) -> Result<i64, RepositoryError> {
+            Ok(self.matching(term).len() as i64)
+        }
+
+        fn export(&self, shard: Option<Shard>) -> Result<Vec<TodoEntity>, RepositoryError> {
+            let mut items: Vec<_> = self
+                .live()
+                .into_iter()
+                .filter(|f| match shard {
+                    Some(shard) => {
+                        (f.id.as_u128() % shard.count as u128) as i32 == shard.remainder()
+                    }
+                    None => true,
+                })
+                .collect();
+            items.sort_by_key(|f| f.created_at);
+            Ok(items)
+        }
+
+        fn effort_summary(&self) -> Result<(i64, i64, i64), RepositoryError> {
+            let completed: Vec<_> = self.live().into_iter().filter(|f| f.completed).collect();
+            Ok((
+                completed.len() as i64,
+                completed
+                    .iter()
+                    .filter_map(|f| f.estimated_minutes)
+                    .map(i64::from)
+                    .sum(),
+                completed
+                    .iter()
+                    .filter_map(|f| f.actual_minutes)
+                    .map(i64::from)
+                    .sum(),
+            ))
+        }
+
+        fn stats(
+            &self,
+            completed_since: DateTime<Utc>,
+        ) -> Result<(i64, i64, i64), RepositoryError> {
+            let live = self.live();
+            let completed = live.iter().filter(|f| f.completed);
+            Ok((
+                live.len() as i64,
+                completed.clone().count() as i64,
+                completed
+                    .filter(|f| f.completed_at.is_some_and(|at| at >= completed_since))
+                    .count() as i64,
+            ))
+        }
+
+        fn due_for_reminder(&self, now: DateTime<Utc>) -> Result<Vec<TodoEntity>, RepositoryError> {
+            let db = self.db.lock().unwrap();
+            Ok(db
+                .values()
+                .filter(|f| reminders::is_reminder_due(f, now))
+                .cloned()
+                .collect())
+        }
+
+        fn mark_reminded(&self, todo_id: Uuid, at: DateTime<Utc>) -> Result<bool, RepositoryError> {
+            let mut db = self.db.lock().unwrap();
+            match db.get_mut(&todo_id) {
+                Some(existing) if existing.reminded_at.is_none() => {
+                    existing.reminded_at = Some(at);
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+
+        fn reassign_owner(
+            &self,
+            from: &str,
+            to: &str,
+            actor: &str,
+        ) -> Result<i64, RepositoryError> {
+            let mut db = self.db.lock().unwrap();
+            let mut count = 0;
+            for entity in db.values_mut() {
+                if entity.owner.as_deref() == Some(from) {
+                    entity.owner = Some(to.to_string());
+                    self.record_history(vec![TodoHistoryEntity::new(
+                        entity.id, actor, "reassign", None,
+                    )])?;
+                    count += 1;
+                }
+            }
+            Ok(count)
+        }
+
+        fn set_completed(
+            &self,
+            todo_id: Uuid,
+            completed: bool,
+        ) -> Result<Option<TodoEntity>, RepositoryError> {
+            let mut db = self.db.lock().unwrap();
+            Ok(match db.get_mut(&todo_id) {
+                Some(existing) if existing.deleted_at.is_none() => {
+                    let now = truncate_to_micros(Utc::now());
+                    existing.completed = completed;
+                    existing.completed_at = completed.then_some(now);
+                    existing.updated_at = now;
+                    existing.version += 1;
+                    Some(existing.clone())
+                }
+                _ => None,
+            })
+        }
+
+        fn complete(&self, todo_id: Uuid) -> Result<Option<Changed>, RepositoryError> {
+            self.transaction(|db| {
+                let previous = match db.get(&todo_id) {
+                    Some(existing) if existing.deleted_at.is_none() => existing.clone(),
+                    _ => return Ok(None),
+                };
+
+                let now = truncate_to_micros(Utc::now());
+                let existing = db.get_mut(&todo_id).unwrap();
+                existing.completed = true;
+                existing.completed_at = Some(now);
+                existing.updated_at = now;
+                existing.version += 1;
+                let updated = existing.clone();
+                let completed_subtasks = self.complete_subtasks(db, todo_id, now)?;
+                Ok(Some(Changed {
+                    previous,
+                    updated,
+                    completed_subtasks,
+                }))
+            })
+        }
+
+        fn add_checklist_item(
+            &self,
+            todo_id: Uuid,
+            text: String,
+        ) -> Result<TodoEntity, RepositoryError> {
+            let mut db = self.db.lock().unwrap();
+            let existing = db.get_mut(&todo_id).ok_or(RepositoryError::NotFound)?;
+            existing
+                .checklist
+                .0
+                .push(ChecklistItem { text, done: false });
+            Ok(existing.clone())
+        }
+
+        fn add_tag(&self, todo_id: Uuid, tag: String) -> Result<TodoEntity, RepositoryError> {
+            self.transaction(|db| {
+                let existing = db.get_mut(&todo_id).ok_or(RepositoryError::NotFound)?;
+                if !existing.tags.contains(&tag) {
+                    existing.tags.push(tag);
+                    existing.tag_namespaces = todo_shared::tag_namespaces(&existing.tags);
+                }
+                Ok(existing.clone())
+            })
+        }
+
+        fn remove_tag(&self, todo_id: Uuid, tag: &str) -> Result<TodoEntity, RepositoryError> {
+            self.transaction(|db| {
+                let existing = db.get_mut(&todo_id).ok_or(RepositoryError::NotFound)?;
+                if !existing.tags.iter().any(|existing_tag| existing_tag == tag) {
+                    return Err(RepositoryError::NotFound);
+                }
+                existing.tags.retain(|existing_tag| existing_tag != tag);
+                existing.tag_namespaces = todo_shared::tag_namespaces(&existing.tags);
+                Ok(existing.clone())
+            })
+        }
+
+        fn toggle_checklist_item(
+            &self,
+            todo_id: Uuid,
+            index: usize,
+        ) -> Result<TodoEntity, RepositoryError> {
+            let mut db = self.db.lock().unwrap();
+            let existing = db.get_mut(&todo_id).ok_or(RepositoryError::NotFound)?;
+            let item = existing
+                .checklist
+                .0
+                .get_mut(index)
+                .ok_or(RepositoryError::NotFound)?;
+            item.done = !item.done;
+            Ok(existing.clone())
+        }
+
+        fn add_dependency(&self, todo_id: Uuid, blocked_by: Uuid) -> Result<bool, RepositoryError> {
+            let live = self.live();
+            if ![todo_id, blocked_by]
+                .iter()
+                .all(|uuid| live.iter().any(|entity| entity.id == *uuid))
+            {
+                return Err(RepositoryError::NotFound);
+            }
+
+            let mut dependencies = self.dependencies.lock().unwrap();
+            if dependency_graph::creates_cycle(&dependencies, todo_id, blocked_by) {
+                return Ok(false);
+            }
+            let dependency = TodoDependencyEntity {
+                todo_id,
+                blocked_by,
+            };
+            if !dependencies.contains(&dependency) {
+                dependencies.push(dependency);
+            }
+            Ok(true)
+        }
+
+        fn remove_dependency(
+            &self,
+            todo_id: Uuid,
+            blocked_by: Uuid,
+        ) -> Result<bool, RepositoryError> {
+            let mut dependencies = self.dependencies.lock().unwrap();
+            let count = dependencies.len();
+            dependencies.retain(|dependency| {
+                dependency.todo_id != todo_id || dependency.blocked_by != blocked_by
+            });
+            Ok(dependencies.len() < count)
+        }
+
+        fn record_history(&self, entries: Vec<TodoHistoryEntity>) -> Result<(), RepositoryError> {
+            self.history.lock().unwrap().extend(entries);
+            Ok(())
+        }
+
+        fn activity(
+            &self,
+            actor: Option<&str>,
+            offset: i64,
+            limit: i64,
+        ) -> Result<Vec<TodoHistoryEntity>, RepositoryError> {
+            let mut entries = self.history_of(actor);
+            entries.sort_by_key(|entry| std::cmp::Reverse(entry.created_at));
+            Ok(entries
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect())
+        }
+
+        fn activity_count(&self, actor: Option<&str>) -> Result<i64, RepositoryError> {
+            Ok(self.history_of(actor).len() as i64)
+        }
+
+        fn update_with_previous(
+            &self,
+            todo_id: Uuid,
+            entity: TodoEntity,
+        ) -> Result<Option<Changed>, RepositoryError> {
+            self.transaction(|db| {
+                self.check_title(db, todo_id, &entity.title)?;
+                let existing = match db.get_mut(&todo_id) {
+                    Some(existing) if existing.deleted_at.is_none() => existing,
+                    _ => return Ok(None),
+                };
+                if existing.version != entity.version {
+                    return Err(RepositoryError::Conflict);
+                }
+
+                // Only the updatable columns change, like in the data store.
+                let previous = existing.clone();
+                reschedule_reminder(existing, entity.due_date, entity.remind_before_minutes);
+                existing.title = entity.title;
+                existing.description = entity.description;
+                existing.completed = entity.completed;
+                existing.completed_at = entity.completed_at;
+                existing.due_date = entity.due_date;
+                existing.estimated_minutes = entity.estimated_minutes;
+                existing.actual_minutes = entity.actual_minutes;
+                existing.remind_before_minutes = entity.remind_before_minutes;
+                existing.updated_at = truncate_to_micros(Utc::now());
+                existing.version += 1;
+                let updated = existing.clone();
+                self.changed(db, previous, updated).map(Some)
+            })
+        }
+
+        fn patch_with_previous(
+            &self,
+            todo_id: Uuid,
+            patch: TodoPatch,
+            expected_version: Option<i32>,
+        ) -> Result<Option<Changed>, RepositoryError> {
+            self.transaction(|db| {
+                if let Some(title) = &patch.title {
+                    self.check_title(db, todo_id, title)?;
+                }
+                let existing = match db.get_mut(&todo_id) {
+                    Some(existing) if existing.deleted_at.is_none() => existing,
+                    _ => return Ok(None),
+                };
+                if expected_version.is_some_and(|expected| expected != existing.version) {
+                    return Err(RepositoryError::Conflict);
+                }
+                let previous = existing.clone();
+
+                if !patch.is_empty() {
+                    existing.updated_at = truncate_to_micros(Utc::now());
+                    existing.version += 1;
+                }
+                if let Some(title) = patch.title {
+                    existing.title = title;
+                }
+                if let Some(description) = patch.description {
+                    existing.description = description;
+                }
+                if let Some(completed) = patch.completed {
+                    existing.completed = completed;
+                }
+                if let Some(completed_at) = patch.completed_at {
+                    existing.completed_at = completed_at;
+                }
+                if let Some(due_date) = patch.due_date {
+                    let due_date = due_date.map(truncate_to_micros);
+                    reschedule_reminder(existing, due_date, existing.remind_before_minutes);
+                    existing.due_date = due_date;
+                }
+                if let Some(estimated_minutes) = patch.estimated_minutes {
+                    existing.estimated_minutes = estimated_minutes;
+                }
+                if let Some(actual_minutes) = patch.actual_minutes {
+                    existing.actual_minutes = actual_minutes;
+                }
+                if let Some(remind_before_minutes) = patch.remind_before_minutes {
+                    reschedule_reminder(existing, existing.due_date, remind_before_minutes);
+                    existing.remind_before_minutes = remind_before_minutes;
+                }
+                let patched = existing.clone();
+                self.changed(db, previous, patched).map(Some)
+            })
+        }
+
+        fn apply_changes(&self, changes: Vec<TodoChange>) -> Result<AppliedChanges, ChangeError> {
+            // Nothing is persisted when one of the changes fails
+            self.transaction(|db| {
+                let mut results = Vec::new();
+                let mut completed_subtasks = Vec::new();
+                for (index, change) in changes.into_iter().enumerate() {
+                    let not_found = || ChangeError::new(index, RepositoryError::NotFound);
+                    match change {
+                        TodoChange::Create(entity) => {
+                            self.check_title(db, entity.id, &entity.title)
+                                .map_err(|error| ChangeError::new(index, error))?;
+                            db.insert(entity.id, entity.clone());
+                            results.push(Some(entity));
+                        }
+                        TodoChange::Update(todo_id, entity) => {
+                            self.check_title(db, todo_id, &entity.title)
+                                .map_err(|error| ChangeError::new(index, error))?;
+                            let existing = db.get_mut(&todo_id).ok_or_else(not_found)?;
+                            let previous = existing.clone();
+                            reschedule_reminder(
+                                existing,
+                                entity.due_date,
+                                entity.remind_before_minutes,
+                            );
+                            existing.title = entity.title;
+                            existing.description = entity.description;
+                            existing.completed = entity.completed;
+                            existing.completed_at = entity.completed_at;
+                            existing.due_date = entity.due_date;
+                            existing.estimated_minutes = entity.estimated_minutes;
+                            existing.actual_minutes = entity.actual_minutes;
+                            existing.remind_before_minutes = entity.remind_before_minutes;
+                            existing.version += 1;
+                            let updated = existing.clone();
+                            let changed = self
+                                .changed(db, previous, updated)
+                                .map_err(|error| ChangeError::new(index, error))?;
+                            completed_subtasks.extend(changed.completed_subtasks);
+                            results.push(Some(changed.updated));
+                        }
+                        TodoChange::Delete(todo_id) => {
+                            let existing = db
+                                .get_mut(&todo_id)
+                                .filter(|f| f.deleted_at.is_none())
+                                .ok_or_else(not_found)?;
+                            let now = truncate_to_micros(Utc::now());
+                            existing.deleted_at = Some(now);
+                            existing.updated_at = now;
+                            existing.version += 1;
+                            results.push(None);
+                        }
+                    }
+                }
+                Ok(AppliedChanges {
+                    items: results,
+                    completed_subtasks,
+                })
+            })
+        }
+    }
+
+    pub(crate) fn get_repository_mock_with_data() -> Arc<dyn TodoRepository> {
+        with_mock_data(TodoEntityRepositoryMock::default())
+    }
+
+    // Like the data store with `UNIQUE_TITLES` set, the titles of the todo items have to be unique.
+    pub(crate) fn get_repository_mock_with_unique_titles() -> Arc<dyn TodoRepository> {
+        with_mock_data(TodoEntityRepositoryMock {
+            unique_titles: true,
+            ..Default::default()
+        })
+    }
+
+    fn with_mock_data(repository: TodoEntityRepositoryMock) -> Arc<dyn TodoRepository> {
+        // insert some mock data
+        repository.store(TodoEntity {
+            id: Uuid::parse_str("cdce7fda-909e-41cb-8507-abceb316a5b4").unwrap(),
             title: "Test the microservice".to_string(),
             description: "We should test the get all method".to_string(),
             completed: true,
-            completed_at: Some(SystemTime::now()),
-            created_at: SystemTime::now(),
+            completed_at: Some(Utc::now()),
+            created_at: Utc::now(),
+            due_date: None,
+            deleted_at: None,
+            estimated_minutes: None,
+            actual_minutes: None,
+            owner: None,
+            remind_before_minutes: None,
+            reminded_at: None,
+            checklist: Checklist::default(),
+            tags: Vec::new(),
+            tag_namespaces: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+            updated_at: Utc::now(),
+            version: 1,
+        });
+        repository.store(TodoEntity {
+            id: Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap(),
+            title: "Use a mock repository".to_string(),
+            description: "We should test that we can also use a mock for the same handler"
+                .to_string(),
+            completed: true,
+            completed_at: Some(Utc::now()),
+            created_at: Utc::now(),
+            due_date: None,
+            deleted_at: None,
+            estimated_minutes: None,
+            actual_minutes: None,
+            owner: None,
+            remind_before_minutes: None,
+            reminded_at: None,
+            checklist: Checklist::default(),
+            tags: Vec::new(),
+            tag_namespaces: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+            updated_at: Utc::now(),
+            version: 1,
+        });
+        repository.store(TodoEntity {
+            id: Uuid::parse_str("5b2a9c0e-1d4f-4a8e-9c3b-7f6e2d1a0b9c").unwrap(),
+            title: "Hide deleted items".to_string(),
+            description: "Deleted items should only be visible to admins".to_string(),
+            completed: false,
+            completed_at: None,
+            created_at: Utc::now(),
+            due_date: None,
+            deleted_at: Some(Utc::now()),
+            estimated_minutes: None,
+            actual_minutes: None,
+            owner: None,
+            remind_before_minutes: None,
+            reminded_at: None,
+            checklist: Checklist::default(),
+            tags: Vec::new(),
+            tag_namespaces: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+            updated_at: Utc::now(),
+            version: 1,
+        });
+
+        let repository_arc: Arc<dyn TodoRepository> = Arc::new(repository);
+        repository_arc
+    }
+
+    #[actix_web::test]
+    async fn test_get_all() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todos),
+        )
+        .await;
+        let req = test::TestRequest::default().uri("/todo").to_request();
+
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_delete_completed_todo_only_if_completed() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .service(delete_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::delete()
+            .uri("/todo/cdce7fda-909e-41cb-8507-abceb316a5b4?only_if_completed=true")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(repository.get_all().await.unwrap().len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_delete_incomplete_todo_only_if_completed() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .service(delete_todo),
+        )
+        .await;
+        let uuid = Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap();
+        repository.set_completed(uuid, false).unwrap();
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/todo/{}?only_if_completed=true", uuid))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+        assert!(repository.get_by_id(uuid).await.unwrap().is_some());
+    }
+
+    #[actix_web::test]
+    async fn test_count_todos() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .service(count_todos),
+        )
+        .await;
+        repository
+            .set_completed(
+                Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap(),
+                false,
+            )
+            .unwrap();
+
+        // Like the list, a search term takes precedence over the other filters
+        for (query, expected) in [
+            ("", 2),
+            ("?completed=false", 1),
+            ("?q=mock&completed=true", 1),
+        ] {
+            let req = test::TestRequest::default()
+                .uri(&format!("/todo/count{}", query))
+                .to_request();
+            let resp: TodoCount = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(resp.count, expected, "{}", query);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_count_todos_filtered_by_tag() {
+        let repository = get_repository_mock_with_data();
+        for (title, tags) in [
+            ("Design the dashboard", vec!["project/alpha"]),
+            ("Plan the beta", vec!["project/beta"]),
+            ("Water the plants", vec!["context/home"]),
+        ] {
+            let request = CreateTodoItemRequest {
+                title: title.to_string(),
+                description: String::new(),
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: tags.into_iter().map(String::from).collect(),
+                parent_id: None,
+                recurrence: None,
+            };
+            repository.insert(request.into()).await.unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(count_todos)
+                .service(get_todos),
+        )
+        .await;
+
+        // The count agrees with the total of the list with the same filters
+        for (filter, expected) in [
+            ("tag=project/*", 2),
+            ("tag=project/beta", 1),
+            ("tag=project/*&completed=true", 0),
+        ] {
+            let req = test::TestRequest::default()
+                .uri(&format!("/todo/count?{}", filter))
+                .to_request();
+            let resp: TodoCount = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(resp.count, expected, "{}", filter);
+
+            let req = test::TestRequest::default()
+                .uri(&format!("/todo?{}&limit=1", filter))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(
+                resp.headers().get(TOTAL_COUNT_HEADER).unwrap(),
+                &expected.to_string(),
+                "{}",
+                filter
+            );
+        }
+
+        let req = test::TestRequest::default()
+            .uri("/todo/count?tag=project//alpha")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_search_todos() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(search_todos)
+                .service(get_todo_by_id),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .uri("/todo/search?q=MOCK")
+            .to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 1);
+        assert_eq!(resp[0].title, "Use a mock repository");
+
+        let req = test::TestRequest::default()
+            .uri("/todo/search?q=%20")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_get_all_with_selected_fields() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todos),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .uri("/todo?fields=id,%20title")
+            .to_request();
+        let resp: Vec<serde_json::Map<String, serde_json::Value>> =
+            test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 2);
+        for item in resp {
+            let keys: Vec<_> = item.keys().map(String::as_str).collect();
+            assert_eq!(keys, ["id", "title"]);
+        }
+
+        // Every serialized field can be selected
+        let req = test::TestRequest::default().uri("/todo").to_request();
+        let resp: Vec<serde_json::Map<String, serde_json::Value>> =
+            test::call_and_read_body_json(&app, req).await;
+        let mut keys: Vec<_> = resp[0].keys().map(String::as_str).collect();
+        let mut fields = TodoItem::FIELDS.to_vec();
+        keys.sort();
+        fields.sort();
+        assert_eq!(keys, fields);
+
+        let req = test::TestRequest::default()
+            .uri("/todo?fields=id,secret")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body = test::read_body(resp).await;
+        assert!(String::from_utf8_lossy(&body).contains("Unknown field `secret`"));
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_after_cursor() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todos_after)
+                .service(get_todos),
+        )
+        .await;
+
+        // Follow the cursors until the last page
+        let mut ids = Vec::new();
+        let mut after = String::new();
+        loop {
+            let req = test::TestRequest::default()
+                .uri(&format!("/todo?after={}&limit=1", after))
+                .to_request();
+            let page: TodoPage = test::call_and_read_body_json(&app, req).await;
+            assert!(page.items.len() <= 1);
+            ids.extend(page.items.into_iter().map(|item| item.id));
+            match page.next_cursor {
+                Some(cursor) => after = cursor,
+                None => break,
+            }
+        }
+        let req = test::TestRequest::default().uri("/todo").to_request();
+        let all: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        let mut expected: Vec<_> = all.into_iter().map(|item| item.id).collect();
+        ids.sort();
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        let req = test::TestRequest::default()
+            .uri("/todo?after=invalid")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_get_all_beyond_max_unpaged_items() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .app_data(Data::new(Settings {
+                    max_unpaged_items: 1,
+                    ..Settings::default()
+                }))
+                .service(get_todos),
+        )
+        .await;
+
+        let req = test::TestRequest::default().uri("/todo").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::PAYLOAD_TOO_LARGE
+        );
+        let error: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(error.status, 413);
+        assert_eq!(error.error_code, "TOO_MANY_ITEMS");
+
+        let req = test::TestRequest::default()
+            .uri("/todo?limit=1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "2");
+        let items: Vec<TodoItem> = test::read_body_json(resp).await;
+        assert_eq!(items.len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_get_todos_rejects_pages_out_of_range() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todos_after)
+                .service(get_todos),
+        )
+        .await;
+
+        for uri in [
+            format!("/todo?limit={}", MAX_PAGE_SIZE + 1),
+            "/todo?limit=0".to_string(),
+            "/todo?offset=-1".to_string(),
+            format!("/todo?after=&limit={}", MAX_PAGE_SIZE + 1),
+        ] {
+            let req = test::TestRequest::default().uri(&uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(
+                resp.status(),
+                actix_web::http::StatusCode::BAD_REQUEST,
+                "{}",
+                uri
+            );
+        }
+
+        let req = test::TestRequest::default()
+            .uri(&format!("/todo?limit={}", MAX_PAGE_SIZE))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn test_get_paged_with_estimated_total() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todos),
+        )
+        .await;
+
+        // The statistics of the mock include the soft-deleted todo item
+        let req = test::TestRequest::default()
+            .uri("/todo?limit=1&count=estimate")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "3");
+        assert_eq!(
+            resp.headers().get(TOTAL_IS_ESTIMATE_HEADER).unwrap(),
+            "true"
+        );
+
+        // Searches are filtered, so they are counted exactly
+        let req = test::TestRequest::default()
+            .uri("/todo?q=mock&limit=1&count=estimate")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "1");
+        assert!(resp.headers().get(TOTAL_IS_ESTIMATE_HEADER).is_none());
+
+        let req = test::TestRequest::default()
+            .uri("/todo?limit=1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "2");
+        assert!(resp.headers().get(TOTAL_IS_ESTIMATE_HEADER).is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_get_all_sorted() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todos),
+        )
+        .await;
+
+        for (order, first) in [
+            ("asc", "Test the microservice"),
+            ("desc", "Use a mock repository"),
+        ] {
+            let req = test::TestRequest::default()
+                .uri(&format!("/todo?sort=title&order={}", order))
+                .to_request();
+            let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(resp.len(), 2);
+            assert_eq!(resp[0].title, first);
+        }
+
+        // The order applies along with the filters, before paging
+        for uri in [
+            "/todo?sort=title&order=desc&completed=true",
+            "/todo?sort=title&order=desc&limit=1",
+        ] {
+            let req = test::TestRequest::default().uri(uri).to_request();
+            let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(resp[0].title, "Use a mock repository", "{}", uri);
+        }
+        let req = test::TestRequest::default()
+            .uri("/todo?sort=title&order=desc")
+            .insert_header((RANGE, "items=1-1"))
+            .to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 1);
+        assert_eq!(resp[0].title, "Test the microservice");
+
+        let req = test::TestRequest::default()
+            .uri("/todo?q=mock&sort=title")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::default()
+            .uri("/todo?sort=priority")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_get_all_filtered_by_completed() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .service(get_todos),
+        )
+        .await;
+        repository
+            .set_completed(
+                Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap(),
+                false,
+            )
+            .unwrap();
+
+        let req = test::TestRequest::default()
+            .uri("/todo?completed=true")
+            .to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 1);
+        assert_eq!(resp[0].title, "Test the microservice");
+
+        let req = test::TestRequest::default()
+            .uri("/todo?completed=false")
+            .to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 1);
+        assert_eq!(resp[0].title, "Use a mock repository");
+    }
+
+    #[actix_web::test]
+    async fn test_get_filtered_paged() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todos),
+        )
+        .await;
+        let list = |uri: &str| {
+            let req = test::TestRequest::default().uri(uri).to_request();
+            req.extensions_mut().insert(AuthenticatedUser {
+                subject: "admin-1".to_string(),
+                roles: vec!["admin".to_string()],
+            });
+            req
+        };
+
+        // The filters apply before paging, the total counts the filtered todo items
+        for (uri, total, title) in [
+            ("/todo?completed=true&limit=1", "2", "Test the microservice"),
+            (
+                "/todo?completed=true&offset=1&limit=1",
+                "2",
+                "Use a mock repository",
+            ),
+            (
+                "/todo?include_deleted=true&completed=false&limit=1",
+                "1",
+                "Hide deleted items",
+            ),
+            (
+                "/todo?include_deleted=true&offset=2&limit=1",
+                "3",
+                "Hide deleted items",
+            ),
+        ] {
+            let resp = test::call_service(&app, list(uri)).await;
+            assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), total);
+            let items: Vec<TodoItem> = test::read_body_json(resp).await;
+            assert_eq!(items.len(), 1, "{}", uri);
+            assert_eq!(items[0].title, title);
+        }
+
+        let req = test::TestRequest::default()
+            .uri("/todo?include_deleted=true")
+            .insert_header((RANGE, "items=1-2"))
+            .to_request();
+        req.extensions_mut().insert(AuthenticatedUser {
+            subject: "admin-1".to_string(),
+            roles: vec!["admin".to_string()],
+        });
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::PARTIAL_CONTENT);
+        assert_eq!(resp.headers().get(CONTENT_RANGE).unwrap(), "items 1-2/3");
+    }
+
+    #[actix_web::test]
+    async fn test_get_all_filtered_by_tag_namespace() {
+        let repository = get_repository_mock_with_data();
+        for (title, tags) in [
+            (
+                "Design the dashboard",
+                vec!["project/alpha/design", "context/office"],
+            ),
+            ("Plan the beta", vec!["project/beta"]),
+            ("Water the plants", vec!["context/home"]),
+            ("Review the projection", vec!["projects"]),
+        ] {
+            let request = CreateTodoItemRequest {
+                title: title.to_string(),
+                description: String::new(),
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: tags.into_iter().map(String::from).collect(),
+                parent_id: None,
+                recurrence: None,
+            };
+            repository.insert(request.into()).await.unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(get_todos),
+        )
+        .await;
+
+        let titles = |resp: Vec<TodoItem>| {
+            let mut titles: Vec<String> = resp.into_iter().map(|item| item.title).collect();
+            titles.sort();
+            titles
+        };
+
+        let req = test::TestRequest::default()
+            .uri("/todo?tag=project/*")
+            .to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(titles(resp), ["Design the dashboard", "Plan the beta"]);
+
+        let req = test::TestRequest::default()
+            .uri("/todo?tag=project/alpha/*")
+            .to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(titles(resp), ["Design the dashboard"]);
+
+        let req = test::TestRequest::default()
+            .uri("/todo?tag=context/home")
+            .to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(titles(resp), ["Water the plants"]);
+
+        for tag in ["Project/*", "project//alpha", "project/*/alpha"] {
+            let req = test::TestRequest::default()
+                .uri(&format!("/todo?tag={}", tag))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_get_tag_stats() {
+        let repository = get_repository_mock_with_data();
+        let now = DateTime::from_timestamp(1_664_452_800, 0).unwrap();
+        let yesterday = now - Duration::from_secs(24 * 60 * 60);
+        for (tags, completed, due_date) in [
+            (vec!["project/alpha", "urgent"], false, Some(yesterday)),
+            (vec!["project/alpha"], true, Some(yesterday)),
+            (vec!["project/alpha"], false, Some(now)),
+            (vec!["project/beta"], false, Some(yesterday)),
+        ] {
+            let request = CreateTodoItemRequest {
+                title: "Count by tag".to_string(),
+                description: String::new(),
+                due_date,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: tags.into_iter().map(String::from).collect(),
+                parent_id: None,
+                recurrence: None,
+            };
+            let mut entity: TodoEntity = request.into();
+            entity.completed = completed;
+            repository.insert(entity).await.unwrap();
+        }
+        let clock: Arc<dyn Clock> = Arc::new(FixedClock(now));
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::from(clock))
+                .service(get_tag_stats),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo/stats/tags")
+            .set_json(&TagStatsRequest {
+                tags: vec!["project/alpha".to_string(), "urgent".to_string()],
+            })
+            .to_request();
+        let resp: Vec<TagStats> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            resp,
+            [
+                TagStats {
+                    tag: "project/alpha".to_string(),
+                    total: 3,
+                    completed: 1,
+                    overdue: 1,
+                },
+                TagStats {
+                    tag: "urgent".to_string(),
+                    total: 1,
+                    completed: 0,
+                    overdue: 1,
+                },
+            ]
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/todo/stats/tags")
+            .set_json(&TagStatsRequest { tags: Vec::new() })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_get_all_including_deleted_as_admin() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todos),
+        )
+        .await;
+        let req = test::TestRequest::default()
+            .uri("/todo?include_deleted=true")
+            .to_request();
+        req.extensions_mut().insert(AuthenticatedUser {
+            subject: "admin-1".to_string(),
+            roles: vec!["admin".to_string()],
+        });
+
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 3);
+        assert!(resp.iter().any(|item| item.deleted_at.is_some()));
+    }
+
+    #[actix_web::test]
+    async fn test_get_all_including_deleted_forbidden_for_users() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todos)
+                .service(get_todo_by_id),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .uri("/todo?include_deleted=true")
+            .to_request();
+        req.extensions_mut().insert(AuthenticatedUser {
+            subject: "user-1".to_string(),
+            roles: vec![],
+        });
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::default()
+            .uri("/todo/5b2a9c0e-1d4f-4a8e-9c3b-7f6e2d1a0b9c?include_deleted=true")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::default()
+            .uri("/todo/5b2a9c0e-1d4f-4a8e-9c3b-7f6e2d1a0b9c")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_get_by_id_including_deleted_as_admin() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todo_by_id),
+        )
+        .await;
+        let req = test::TestRequest::default()
+            .uri("/todo/5b2a9c0e-1d4f-4a8e-9c3b-7f6e2d1a0b9c?include_deleted=true")
+            .to_request();
+        req.extensions_mut().insert(AuthenticatedUser {
+            subject: "admin-1".to_string(),
+            roles: vec!["admin".to_string()],
+        });
+
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.title, "Hide deleted items");
+        assert!(resp.deleted_at.is_some());
+    }
+
+    #[actix_web::test]
+    async fn test_search_paged() {
+        let repository = get_repository_mock_with_data();
+        for index in 0..5 {
+            let _ = repository
+                .insert(
+                    CreateTodoItemRequest {
+                        title: format!("Paged search {}", index),
+                        description: "We should page through search results".to_string(),
+                        due_date: None,
+                        estimated_minutes: None,
+                        actual_minutes: None,
+                        remind_before_minutes: None,
+                        tags: Vec::new(),
+                        parent_id: None,
+                        recurrence: None,
+                    }
+                    .into(),
+                )
+                .await;
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(get_todos),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .uri("/todo?q=PAGED&offset=2&limit=2")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "5");
+        let items: Vec<TodoItem> = test::read_body_json(resp).await;
+        assert_eq!(items.len(), 2);
+        assert!(items
+            .iter()
+            .all(|item| item.title.starts_with("Paged search")));
+    }
+
+    #[actix_web::test]
+    async fn test_get_by_id_not_modified() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .service(get_todo_by_id),
+        )
+        .await;
+        let uri = "/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874";
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let tag = resp.headers().get(ETAG).unwrap().clone();
+
+        // The current entity tag skips the body
+        let req = test::TestRequest::get()
+            .uri(uri)
+            .insert_header((IF_NONE_MATCH, tag.clone()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_MODIFIED);
+        assert_eq!(resp.headers().get(ETAG), Some(&tag));
+        assert!(test::read_body(resp).await.is_empty());
+
+        // Updating the todo item changes its entity tag
+        let uuid = Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap();
+        let entity = repository.get_by_id(uuid).await.unwrap().unwrap();
+        repository
+            .update_with_previous(
+                uuid,
+                TodoEntity {
+                    title: "Changed".to_string(),
+                    ..entity
+                },
+            )
+            .unwrap();
+        let req = test::TestRequest::get()
+            .uri(uri)
+            .insert_header((IF_NONE_MATCH, tag.clone()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_ne!(resp.headers().get(ETAG), Some(&tag));
+    }
+
+    #[actix_web::test]
+    async fn test_get_by_id() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(get_todo_by_id),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874")
+            .to_request();
+
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.title, "Use a mock repository");
+        assert_eq!(
+            resp.description,
+            "We should test that we can also use a mock for the same handler"
+        );
+        assert_eq!(resp.completed, true);
+    }
+
+    #[actix_web::test]
+    async fn test_head_todo() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(head_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .method(actix_web::http::Method::HEAD)
+            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert!(test::read_body(resp).await.is_empty());
+
+        let req = test::TestRequest::default()
+            .method(actix_web::http::Method::HEAD)
+            .uri("/todo/00000000-0000-0000-0000-000000000000")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_get_status() {
+        let repository = get_repository_mock_with_data();
+        let clock: Arc<dyn Clock> = Arc::new(FixedClock(Utc::now()));
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::from(clock))
+                .service(get_todo_status),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874/status")
+            .to_request();
+
+        let resp: TodoStatusResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.status, todo_shared::TodoStatus::Completed);
+    }
+
+    #[actix_web::test]
+    async fn test_compress_large_list() {
+        let repository = get_repository_mock_with_data();
+        for index in 0..200 {
+            let request = CreateTodoItemRequest {
+                title: format!("Compress the list {}", index),
+                description: "Hundreds of todo items shouldn't be sent uncompressed".to_string(),
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: Vec::new(),
+                parent_id: None,
+                recurrence: None,
+            };
+            repository.insert(request.into()).await.unwrap();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::new(Settings::default()))
+                .app_data(Data::new(TodoEvents::default()))
+                .wrap(actix_web::middleware::Compress::default())
+                .service(get_todos)
+                .service(get_todo_events),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .uri("/todo")
+            .insert_header((actix_web::http::header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(
+            resp.headers()
+                .get(actix_web::http::header::CONTENT_ENCODING)
+                .unwrap(),
+            "gzip"
+        );
+
+        // The events would be held back by the compression
+        let req = test::TestRequest::default()
+            .uri("/todo/events")
+            .insert_header((actix_web::http::header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers()
+                .get(actix_web::http::header::CONTENT_ENCODING)
+                .unwrap(),
+            "identity"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_todo_events_stream_changes() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .app_data(Data::new(Settings::default()))
+                .app_data(Data::new(TodoEvents::default()))
+                .service(create_todo)
+                .service(delete_todo)
+                .service(get_todo_events),
+        )
+        .await;
+
+        let resp = test::call_service(
+            &app,
+            test::TestRequest::get().uri("/todo/events").to_request(),
+        )
+        .await;
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+        let body = resp.into_body();
+        futures::pin_mut!(body);
+
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .set_json(&CreateTodoItemRequest {
+                title: "Stream changes".to_string(),
+                description: "We should push changes to the front-end".to_string(),
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: Vec::new(),
+                parent_id: None,
+                recurrence: None,
+            })
+            .to_request();
+        let created: TodoItem = test::call_and_read_body_json(&app, req).await;
+        let frame = futures::future::poll_fn(|cx| body.as_mut().poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            frame,
+            format!(
+                "data: {{\"kind\":\"created\",\"id\":\"{}\"}}\n\n",
+                created.id
+            )
+        );
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/todo/{}", created.id))
+            .to_request();
+        test::call_service(&app, req).await;
+        let frame = futures::future::poll_fn(|cx| body.as_mut().poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            frame,
+            format!(
+                "data: {{\"kind\":\"deleted\",\"id\":\"{}\"}}\n\n",
+                created.id
+            )
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_todo_events_socket_handshake() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(Settings::default()))
+                .app_data(Data::new(TodoEvents::default()))
+                .service(get_todo_events_socket),
+        )
+        .await;
+
+        // Plain requests aren't upgraded
+        let req = test::TestRequest::get().uri("/todo/ws").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::get()
+            .uri("/todo/ws")
+            .insert_header((UPGRADE, "websocket"))
+            .insert_header((CONNECTION, "Upgrade"))
+            .insert_header((SEC_WEBSOCKET_VERSION, "13"))
+            .insert_header((SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ=="))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::SWITCHING_PROTOCOLS
+        );
+        assert_eq!(
+            resp.headers().get(SEC_WEBSOCKET_ACCEPT).unwrap(),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_effort_round_trip_and_summary() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todo)
+                .service(update_todo)
+                .service(get_effort_summary),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .set_json(&CreateTodoItemRequest {
+                title: "Track effort".to_string(),
+                description: "We should track the effort".to_string(),
+                due_date: None,
+                estimated_minutes: Some(30),
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: Vec::new(),
+                parent_id: None,
+                recurrence: None,
+            })
+            .to_request();
+        let created: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(created.estimated_minutes, Some(30));
+        assert_eq!(created.actual_minutes, None);
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/todo/{}", created.id))
+            .set_json(&UpdateTodoItemRequest {
+                new_title: "Track effort".to_string(),
+                new_description: "We should track the effort".to_string(),
+                completed: true,
+                due_date: None,
+                estimated_minutes: Some(30),
+                actual_minutes: Some(45),
+                remind_before_minutes: None,
+                expected_version: created.version,
+            })
+            .to_request();
+        let updated: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(updated.estimated_minutes, Some(30));
+        assert_eq!(updated.actual_minutes, Some(45));
+
+        let req = test::TestRequest::default()
+            .uri("/todo/stats/effort")
+            .to_request();
+        let summary: EffortSummary = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(summary.completed_items, 3);
+        assert_eq!(summary.total_estimated_minutes, 30);
+        assert_eq!(summary.total_actual_minutes, 45);
+    }
+
+    #[actix_web::test]
+    async fn test_get_todo_stats() {
+        let repository = get_repository_mock_with_data();
+        let request = CreateTodoItemRequest {
+            title: "Count the stats".to_string(),
+            description: "Dashboards need the numbers in one call".to_string(),
+            due_date: None,
+            estimated_minutes: None,
+            actual_minutes: None,
+            remind_before_minutes: None,
+            tags: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+        };
+        repository.insert(request.into()).await.unwrap();
+
+        for (days, completed_last_7_days) in [(0, 2), (8, 0)] {
+            let clock: Arc<dyn Clock> =
+                Arc::new(FixedClock(Utc::now() + chrono::Duration::days(days)));
+            let app = test::init_service(
+                App::new()
+                    .app_data(Data::from(repository.clone()))
+                    .app_data(Data::from(clock))
+                    .service(get_todo_stats)
+                    .service(get_todo_by_id),
+            )
+            .await;
+
+            let req = test::TestRequest::default().uri("/todo/stats").to_request();
+            let stats: TodoStats = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(stats.total, 3);
+            assert_eq!(stats.completed, 2);
+            assert_eq!(stats.pending, 1);
+            assert_eq!(stats.completed_last_7_days, completed_last_7_days);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_rejects_negative_effort() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .set_json(&CreateTodoItemRequest {
+                title: "Track effort".to_string(),
+                description: "We should not accept negative effort".to_string(),
+                due_date: None,
+                estimated_minutes: Some(-5),
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: Vec::new(),
+                parent_id: None,
+                recurrence: None,
+            })
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_validation_error_in_requested_language() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todo),
+        )
+        .await;
+        let request = CreateTodoItemRequest {
+            title: "Track effort".to_string(),
+            description: "We should explain the error in Dutch".to_string(),
+            due_date: None,
+            estimated_minutes: Some(-5),
+            actual_minutes: None,
+            remind_before_minutes: None,
+            tags: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .insert_header((actix_web::http::header::ACCEPT_LANGUAGE, "nl-NL,en;q=0.5"))
+            .set_json(&request)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body = test::read_body(resp).await;
+        assert_eq!(body, "estimated_minutes mag niet negatief zijn");
+
+        // Unsupported languages fall back to English
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .insert_header((actix_web::http::header::ACCEPT_LANGUAGE, "fr-FR"))
+            .set_json(&request)
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(body, "estimated_minutes must not be negative");
+    }
+
+    #[actix_web::test]
+    async fn test_export_shards_cover_all_items() {
+        let repository = get_repository_mock_with_data();
+        for index in 0..20 {
+            let _ = repository
+                .insert(
+                    CreateTodoItemRequest {
+                        title: format!("Export item {}", index),
+                        description: "We should export every item exactly once".to_string(),
+                        due_date: None,
+                        estimated_minutes: None,
+                        actual_minutes: None,
+                        remind_before_minutes: None,
+                        tags: Vec::new(),
+                        parent_id: None,
+                        recurrence: None,
+                    }
+                    .into(),
+                )
+                .await;
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .service(export_todos),
+        )
+        .await;
+
+        let mut exported = Vec::new();
+        for index in 1..=5 {
+            let req = test::TestRequest::default()
+                .uri(&format!("/todo/export?shard={}/5", index))
+                .to_request();
+            let shard: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+            exported.extend(shard.into_iter().map(|item| item.id));
+        }
+
+        // Every live item is exported exactly once.
+        let mut expected: Vec<_> = repository
+            .get_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|f| f.id)
+            .collect();
+        expected.sort();
+        exported.sort();
+        assert_eq!(exported, expected);
+    }
+
+    #[actix_web::test]
+    async fn test_export_todos_csv() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(export_todos_csv)
+                .service(get_todo_by_id),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/todo/export.csv")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(resp.headers().get("content-type").unwrap(), "text/csv");
+        assert_eq!(
+            resp.headers().get("content-disposition").unwrap(),
+            "attachment; filename=\"todos.csv\""
+        );
+        let body = test::read_body(resp).await;
+        let lines: Vec<&str> = std::str::from_utf8(&body).unwrap().lines().collect();
+        assert_eq!(
+            lines[0],
+            "id,title,description,completed,completed_at,created_at"
+        );
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[actix_web::test]
+    async fn test_csv_body_pages() {
+        use futures::TryStreamExt;
+
+        let repository = get_repository_mock_with_data();
+        let expected = repository.export(None).unwrap();
+
+        // Pages of a single todo item, with an empty page after the last one
+        let body: Vec<actix_web::web::Bytes> = csv_body(repository, 1).try_collect().await.unwrap();
+        let body = body.concat();
+        let lines: Vec<&str> = std::str::from_utf8(&body).unwrap().lines().collect();
+        assert_eq!(lines.len(), expected.len() + 1);
+        for (line, entity) in lines[1..].iter().zip(&expected) {
+            assert!(line.starts_with(&entity.id.to_string()), "{}", line);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_export_invalid_shard() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(export_todos),
+        )
+        .await;
+
+        for shard in ["6/5", "0/5", "two/five"] {
+            let req = test::TestRequest::default()
+                .uri(&format!("/todo/export?shard={}", shard))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_reassign_by_owner() {
+        let repository = get_repository_mock_with_data();
+        for (index, owner) in ["owner-a", "owner-a", "owner-c"].iter().enumerate() {
+            let mut entity: TodoEntity = CreateTodoItemRequest {
+                title: format!("Owned item {}", index),
+                description: "We should reassign the items of an owner".to_string(),
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: Vec::new(),
+                parent_id: None,
+                recurrence: None,
+            }
+            .into();
+            entity.owner = Some(owner.to_string());
+            let _ = repository.insert(entity).await;
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .service(reassign_by_owner),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/admin/todo/reassign-by-owner")
+            .set_json(&ReassignOwnerRequest {
+                from: "owner-a".to_string(),
+                to: "owner-b".to_string(),
+            })
+            .to_request();
+        req.extensions_mut().insert(AuthenticatedUser {
+            subject: "admin-1".to_string(),
+            roles: vec!["admin".to_string()],
+        });
+        let resp: ReassignOwnerResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.count, 2);
+
+        let entities = repository.get_all().await.unwrap();
+        let owned_by = |owner: &str| {
+            entities
+                .iter()
+                .filter(|f| f.owner.as_deref() == Some(owner))
+                .count()
+        };
+        assert_eq!(owned_by("owner-a"), 0);
+        assert_eq!(owned_by("owner-b"), 2);
+        assert_eq!(owned_by("owner-c"), 1);
+    }
+
+    #[actix_web::test]
+    async fn test_reassign_by_owner_requires_admin() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(reassign_by_owner),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/admin/todo/reassign-by-owner")
+            .set_json(&ReassignOwnerRequest {
+                from: "owner-a".to_string(),
+                to: "owner-b".to_string(),
+            })
+            .to_request();
+        req.extensions_mut().insert(AuthenticatedUser {
+            subject: "user-1".to_string(),
+            roles: vec![],
+        });
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_routes_with_bearer_token() {
+        use crate::api::auth::JwtAuth;
+        use jsonwebtoken::{encode, EncodingKey, Header};
+
+        let app = test::init_service(
+            App::new()
+                .wrap(JwtAuth::new("test-secret"))
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .service(get_todos)
+                .service(get_activity)
+                .service(reassign_by_owner),
+        )
+        .await;
+        let bearer = |roles: serde_json::Value| {
+            let claims =
+                serde_json::json!({ "sub": "admin-1", "roles": roles, "exp": 4_102_444_800u64 });
+            let key = EncodingKey::from_secret("test-secret".as_bytes());
+            format!(
+                "Bearer {}",
+                encode(&Header::default(), &claims, &key).unwrap()
+            )
+        };
+        let admin = bearer(serde_json::json!(["admin"]));
+
+        let req = test::TestRequest::default()
+            .uri("/todo?include_deleted=true")
+            .insert_header((AUTHORIZATION, admin.clone()))
+            .to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 3);
+
+        let req = test::TestRequest::default()
+            .uri("/todo?include_deleted=true")
+            .insert_header((AUTHORIZATION, bearer(serde_json::json!([]))))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::default()
+            .uri("/activity")
+            .insert_header((AUTHORIZATION, admin.clone()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let req = test::TestRequest::default().uri("/activity").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post()
+            .uri("/admin/todo/reassign-by-owner")
+            .insert_header((AUTHORIZATION, admin))
+            .set_json(&ReassignOwnerRequest {
+                from: "owner-a".to_string(),
+                to: "owner-b".to_string(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn test_purge_deleted_todos() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .service(purge_deleted_todos)
+                .service(delete_todo),
+        )
+        .await;
+        let purge = |uri: &str, roles: Vec<String>| {
+            let req = test::TestRequest::delete().uri(uri).to_request();
+            req.extensions_mut().insert(AuthenticatedUser {
+                subject: "admin-1".to_string(),
+                roles,
+            });
+            req
+        };
+        let deleted = Uuid::parse_str("5b2a9c0e-1d4f-4a8e-9c3b-7f6e2d1a0b9c").unwrap();
+
+        let req = purge("/todo/purge?older_than_days=0", vec![]);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+
+        // The todo item was deleted just now, so it is kept by default
+        let req = purge("/todo/purge", vec!["admin".to_string()]);
+        let resp: PurgeDeletedResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.count, 0);
+        assert!(repository
+            .get_by_id_including_deleted(deleted)
+            .await
+            .unwrap()
+            .is_some());
+
+        let req = purge("/todo/purge?older_than_days=0", vec!["admin".to_string()]);
+        let resp: PurgeDeletedResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.count, 1);
+        assert!(repository
+            .get_by_id_including_deleted(deleted)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(repository.get_all().await.unwrap().len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_get_range() {
+        let repository = get_repository_mock_with_data();
+        for index in 0..5 {
+            let _ = repository
+                .insert(
+                    CreateTodoItemRequest {
+                        title: format!("Ranged item {}", index),
+                        description: "We should return a range of the items".to_string(),
+                        due_date: None,
+                        estimated_minutes: None,
+                        actual_minutes: None,
+                        remind_before_minutes: None,
+                        tags: Vec::new(),
+                        parent_id: None,
+                        recurrence: None,
+                    }
+                    .into(),
+                )
+                .await;
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(get_todos),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .uri("/todo")
+            .insert_header((RANGE, "items=2-4"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::PARTIAL_CONTENT);
+        assert_eq!(resp.headers().get(CONTENT_RANGE).unwrap(), "items 2-4/7");
+        let items: Vec<TodoItem> = test::read_body_json(resp).await;
+        assert_eq!(items.len(), 3);
+
+        // The last item of the range is bound to the available items
+        let req = test::TestRequest::default()
+            .uri("/todo")
+            .insert_header((RANGE, "items=5-49"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(CONTENT_RANGE).unwrap(), "items 5-6/7");
+
+        // A range up to the largest index is bound to the maximum page size instead of overflowing
+        let req = test::TestRequest::default()
+            .uri("/todo")
+            .insert_header((RANGE, "items=0-9223372036854775807"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::PARTIAL_CONTENT);
+        assert_eq!(resp.headers().get(CONTENT_RANGE).unwrap(), "items 0-6/7");
+
+        let req = test::TestRequest::default()
+            .uri("/todo")
+            .insert_header((RANGE, "items=10-19"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::RANGE_NOT_SATISFIABLE
+        );
+        assert_eq!(resp.headers().get(CONTENT_RANGE).unwrap(), "items */7");
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_batched() {
+        let repository = TodoEntityRepositoryMock::default();
+        let insert_batches = repository.insert_batches.clone();
+        let repository: Arc<dyn TodoRepository> = Arc::new(repository);
+        let batcher = CreateBatcher::new(repository.clone(), Duration::from_millis(50));
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .app_data(Data::new(batcher))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todo),
+        )
+        .await;
+
+        let requests = (0..10).map(|index| {
+            let req = test::TestRequest::post()
+                .uri("/todo")
+                .set_json(&CreateTodoItemRequest {
+                    title: format!("Batched item {}", index),
+                    description: "We should insert concurrent creates together".to_string(),
+                    due_date: None,
+                    estimated_minutes: None,
+                    actual_minutes: None,
+                    remind_before_minutes: None,
+                    tags: Vec::new(),
+                    parent_id: None,
+                    recurrence: None,
+                })
+                .to_request();
+            test::call_service(&app, req)
+        });
+        let responses = futures::future::join_all(requests).await;
+
+        assert!(responses
+            .iter()
+            .all(|resp| resp.status() == actix_web::http::StatusCode::CREATED));
+        assert_eq!(repository.get_all().await.unwrap().len(), 10);
+
+        // All creates arrived within the window, so fewer inserts than creates were needed
+        let insert_batches = insert_batches.lock().unwrap();
+        assert!(insert_batches.len() < 10);
+        assert_eq!(insert_batches.iter().sum::<usize>(), 10);
+    }
+
+    #[actix_web::test]
+    async fn test_create_batcher_only_fails_the_invalid_item() {
+        let repository = get_repository_mock_with_data();
+        let batcher = CreateBatcher::new(repository.clone(), Duration::from_millis(50));
+        let entity = |title: &str| -> TodoEntity {
+            CreateTodoItemRequest {
+                title: title.to_string(),
+                description: "We should only fail the invalid item".to_string(),
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: Vec::new(),
+                parent_id: None,
+                recurrence: None,
+            }
+            .into()
+        };
+        // Reuses the id of an existing todo item, which fails the multi-row insert
+        let mut duplicate = entity("Duplicate the primary key");
+        duplicate.id = Uuid::parse_str("cdce7fda-909e-41cb-8507-abceb316a5b4").unwrap();
+
+        let (first, second, third) = futures::join!(
+            batcher.insert(entity("Insert the first item")),
+            batcher.insert(duplicate),
+            batcher.insert(entity("Insert the third item")),
+        );
+        assert_eq!(first.unwrap().title, "Insert the first item");
+        assert!(matches!(second, Err(BatchError::Repository(_))));
+        assert_eq!(third.unwrap().title, "Insert the third item");
+        assert_eq!(repository.get_all().await.unwrap().len(), 4);
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .set_json(&CreateTodoItemRequest {
+                title: "Test create".to_string(),
+                description: "We should test the create method".to_string(),
+                due_date: Some(DateTime::from_timestamp(1_700_000_000, 0).unwrap()),
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: Vec::new(),
+                parent_id: None,
+                recurrence: None,
+            })
+            .to_request();
+
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.title, "Test create");
+        assert_eq!(resp.description, "We should test the create method");
+        assert_eq!(resp.completed, false);
+        assert_eq!(
+            resp.due_date,
+            Some(DateTime::from_timestamp(1_700_000_000, 0).unwrap())
+        );
+        assert_eq!(resp.updated_at, resp.created_at);
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_with_idempotency_key() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .app_data(Data::new(Settings::default()))
+                .app_data(Data::new(IdempotencyKeys::new(
+                    Duration::from_secs(60),
+                    100,
+                )))
+                .service(create_todo),
+        )
+        .await;
+        let create = |key: &str| {
+            test::TestRequest::post()
+                .uri("/todo")
+                .insert_header((IDEMPOTENCY_KEY_HEADER, key.to_string()))
+                .set_json(&CreateTodoItemRequest {
+                    title: "Create once".to_string(),
+                    description: "Retried after a timeout".to_string(),
+                    due_date: None,
+                    estimated_minutes: None,
+                    actual_minutes: None,
+                    remind_before_minutes: None,
+                    tags: Vec::new(),
+                    parent_id: None,
+                    recurrence: None,
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, create("retry-1")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CREATED);
+        assert!(resp.headers().get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+        let created: TodoItem = test::read_body_json(resp).await;
+
+        // The retry returns the same todo item, without inserting it again
+        let count = repository.get_all().await.unwrap().len();
+        let resp = test::call_service(&app, create("retry-1")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CREATED);
+        assert_eq!(
+            resp.headers().get(IDEMPOTENT_REPLAYED_HEADER).unwrap(),
+            "true"
+        );
+        let replayed: TodoItem = test::read_body_json(resp).await;
+        assert_eq!(replayed.id, created.id);
+        assert_eq!(repository.get_all().await.unwrap().len(), count);
+
+        // Another key creates another todo item
+        let other: TodoItem = test::call_and_read_body_json(&app, create("retry-2")).await;
+        assert_ne!(other.id, created.id);
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_rejects_invalid_fields() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .set_json(&CreateTodoItemRequest {
+                title: "".to_string(),
+                description: "x".repeat(4097),
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: Vec::new(),
+                parent_id: None,
+                recurrence: None,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        let errors: serde_json::Value = test::read_body_json(resp).await;
+        assert!(errors.get("title").is_some());
+        assert!(errors.get("description").is_some());
+        assert_eq!(repository.get_all().await.unwrap().len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_create_todos() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todos),
+        )
+        .await;
+        let request = |title: &str, estimated_minutes: Option<i32>| CreateTodoItemRequest {
+            title: title.to_string(),
+            description: "We should import this todo item".to_string(),
+            due_date: None,
+            estimated_minutes,
+            actual_minutes: None,
+            remind_before_minutes: None,
+            tags: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/todo/batch")
+            .set_json(vec![
+                request("Import one", None),
+                request("Import two", Some(15)),
+                request("Import three", None),
+            ])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CREATED);
+        let created: Vec<TodoItem> = test::read_body_json(resp).await;
+        let titles: Vec<_> = created.iter().map(|item| item.title.as_str()).collect();
+        assert_eq!(titles, vec!["Import one", "Import two", "Import three"]);
+        assert_eq!(created[1].estimated_minutes, Some(15));
+        assert_eq!(repository.get_all().await.unwrap().len(), 5);
+
+        // A single invalid todo item rejects the whole batch
+        let req = test::TestRequest::post()
+            .uri("/todo/batch")
+            .set_json(vec![request("Valid", None), request("Invalid", Some(-1))])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(repository.get_all().await.unwrap().len(), 5);
+    }
+
+    #[actix_web::test]
+    async fn test_import_todos() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .app_data(Data::new(Settings {
+                    max_import_bytes: 512,
+                    ..Settings::default()
+                }))
+                .service(import_todos_upload)
+                .service(import_todos),
+        )
+        .await;
+        let records = serde_json::json!([
+            { "title": "Imported", "description": "From a file" },
+            { "title": "", "description": "Without a title" },
+            { "title": "Negative", "description": "Effort", "estimated_minutes": -5 }
+        ]);
+
+        // Invalid todo items reject the whole import by default
+        let req = test::TestRequest::post()
+            .uri("/todo/import")
+            .set_json(&records)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(repository.get_all().await.unwrap().len(), 2);
+
+        // Or are reported, while the valid ones are imported
+        let req = test::TestRequest::post()
+            .uri("/todo/import?continue_on_error=true")
+            .set_json(&records)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let summary: ImportSummary = test::read_body_json(resp).await;
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.failed, 2);
+        let indices: Vec<_> = summary.errors.iter().map(|error| error.index).collect();
+        assert_eq!(indices, vec![1, 2]);
+        assert_eq!(repository.get_all().await.unwrap().len(), 3);
+
+        // The same array uploaded as a file
+        let body = format!(
+            "--boundary\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"todos.json\"\r\n\
+             Content-Type: application/json\r\n\r\n\
+             {}\r\n\
+             --boundary--\r\n",
+            serde_json::json!([{ "title": "Uploaded", "description": "As a file" }])
+        );
+        let req = test::TestRequest::post()
+            .uri("/todo/import")
+            .insert_header((
+                actix_web::http::header::CONTENT_TYPE,
+                "multipart/form-data; boundary=boundary",
+            ))
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let summary: ImportSummary = test::read_body_json(resp).await;
+        assert_eq!(summary.imported, 1);
+        assert_eq!(repository.get_all().await.unwrap().len(), 4);
+
+        // Imports larger than the maximum are rejected
+        let req = test::TestRequest::post()
+            .uri("/todo/import")
+            .set_payload(format!("[{}]", "1,".repeat(300)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::PAYLOAD_TOO_LARGE
+        );
+        let error: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(error.error_code, "PAYLOAD_TOO_LARGE");
+    }
+
+    #[actix_web::test]
+    async fn test_create_todos_beyond_max_json_body_bytes() {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(get_repository_mock_with_data()))
+                .app_data(Data::new(Settings::default()))
+                .app_data(json_config(256))
+                .service(create_todos),
+        )
+        .await;
+
+        let requests: Vec<_> = (0..10)
+            .map(|index| serde_json::json!({ "title": format!("Batch {}", index), "description": "" }))
+            .collect();
+        let req = test::TestRequest::post()
+            .uri("/todo/batch")
+            .set_json(&requests)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::PAYLOAD_TOO_LARGE
+        );
+        let error: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(error.status, 413);
+        assert_eq!(error.error_code, "PAYLOAD_TOO_LARGE");
+        assert!(error.message.contains("256 bytes"));
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_returns_persisted_item() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todo)
+                .service(get_todo_by_id),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .set_json(&CreateTodoItemRequest {
+                title: "Test create".to_string(),
+                description: "We should return the persisted item".to_string(),
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: Vec::new(),
+                parent_id: None,
+                recurrence: None,
+            })
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CREATED);
+        let location = resp
+            .headers()
+            .get(LOCATION)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        let created: TodoItem = test::read_body_json(resp).await;
+        assert_eq!(location, format!("/todo/{}", created.id));
+
+        let req = test::TestRequest::default().uri(&location).to_request();
+        let fetched: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(created.created_at, fetched.created_at);
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_rejects_duplicate_title_ignoring_case() {
+        let repository = get_repository_mock_with_unique_titles();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(create_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .set_json(&CreateTodoItemRequest {
+                title: "use A MOCK repository".to_string(),
+                description: "We should not be able to create duplicates".to_string(),
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: Vec::new(),
+                parent_id: None,
+                recurrence: None,
+            })
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+    }
+
+    #[actix_web::test]
+    async fn test_every_write_rejects_duplicate_titles() {
+        let repository = get_repository_mock_with_unique_titles();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todos)
+                .service(import_todos)
+                .service(sync_todos)
+                .service(update_todo)
+                .service(merge_patch_todo)
+                .service(patch_todo)
+                .service(restore_todo),
+        )
+        .await;
+        let taken = "USE A MOCK REPOSITORY";
+        let other_id = "cdce7fda-909e-41cb-8507-abceb316a5b4";
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/todo/{}", other_id))
+            .set_json(&UpdateTodoItemRequest {
+                new_title: taken.to_string(),
+                new_description: "Renamed to a taken title".to_string(),
+                completed: true,
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                expected_version: 1,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+
+        let req = test::TestRequest::patch()
+            .uri(&format!("/todo/{}", other_id))
+            .set_json(serde_json::json!({ "title": taken }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+
+        let req = test::TestRequest::patch()
+            .uri(&format!("/todo/{}", other_id))
+            .insert_header((actix_web::http::header::CONTENT_TYPE, MERGE_PATCH))
+            .set_payload(serde_json::json!({ "title": taken }).to_string())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+
+        // Duplicates within a batch or an import are rejected as well, none of them are persisted
+        let records = serde_json::json!([
+            { "title": "Only once", "description": "First" },
+            { "title": "only ONCE", "description": "Second" }
+        ]);
+        for uri in ["/todo/batch", "/todo/import"] {
+            let req = test::TestRequest::post()
+                .uri(uri)
+                .set_json(&records)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/todo/sync")
+            .set_json(serde_json::json!([
+                { "op": "create", "title": "Test sync", "description": "Created by sync" },
+                { "op": "create", "title": taken, "description": "Created by sync" }
+            ]))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+        let body = test::read_body(resp).await;
+        assert!(String::from_utf8_lossy(&body).contains("Operation 1"));
+
+        // A deleted todo item can't be restored once its title is taken
+        let req = test::TestRequest::post()
+            .uri("/todo/batch")
+            .set_json(serde_json::json!([
+                { "title": "Hide DELETED items", "description": "Takes the title" }
+            ]))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CREATED);
+        let req = test::TestRequest::post()
+            .uri("/todo/5b2a9c0e-1d4f-4a8e-9c3b-7f6e2d1a0b9c/restore")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+
+        let titles: Vec<String> = repository
+            .get_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entity| entity.title)
+            .collect();
+        assert_eq!(titles.len(), 3);
+        assert!(!titles
+            .iter()
+            .any(|title| title.to_lowercase() == "only once"));
+        assert!(!titles.contains(&"Test sync".to_string()));
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_ignores_unknown_fields_by_default() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .set_json(serde_json::json!({
+                "title": "Test create",
+                "description": "We should ignore unknown fields",
+                "titel": "Typo"
+            }))
+            .to_request();
+
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.title, "Test create");
+    }
+
+    #[actix_web::test]
+    async fn test_create_todo_strict_rejects_unknown_fields() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::new(JsonOptions { strict: true }))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .set_json(serde_json::json!({
+                "title": "Test create",
+                "description": "We should reject unknown fields",
+                "titel": "Typo"
+            }))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        let body = test::read_body(resp).await;
+        assert!(String::from_utf8_lossy(&body).contains("titel"));
+    }
+
+    #[actix_web::test]
+    async fn test_sync_todos() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::new(Settings::default()))
+                .service(sync_todos)
+                .service(get_todos),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo/sync")
+            .set_json(serde_json::json!([
+                { "op": "create", "title": "Test sync", "description": "Created by sync" },
+                {
+                    "op": "update",
+                    "id": "120400b8-eee8-47cc-9e96-5bc0a3e2e874",
+                    "title": "Updated by sync",
+                    "description": "We should test the sync method",
+                    "completed": false
+                },
+                { "op": "delete", "id": "cdce7fda-909e-41cb-8507-abceb316a5b4" }
+            ]))
+            .to_request();
+
+        let resp: Vec<SyncOperationResult> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.len(), 3);
+        assert_eq!(resp[0].item.as_ref().unwrap().title, "Test sync");
+        assert_eq!(resp[1].item.as_ref().unwrap().title, "Updated by sync");
+        assert!(resp[2].item.is_none());
+
+        let validation_req = test::TestRequest::default().uri("/todo").to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, validation_req).await;
+        assert_eq!(resp.len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_sync_todos_is_atomic() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::new(Settings::default()))
+                .service(sync_todos)
+                .service(get_todos),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo/sync")
+            .set_json(serde_json::json!([
+                { "op": "create", "title": "Test sync", "description": "Created by sync" },
+                { "op": "delete", "id": "cdce7fda-909e-41cb-8507-abceb316a5b4" },
+                { "op": "delete", "id": "00000000-0000-0000-0000-000000000000" }
+            ]))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        // None of the operations should have been persisted.
+        let validation_req = test::TestRequest::default().uri("/todo").to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, validation_req).await;
+        assert_eq!(resp.len(), 2);
+        assert!(resp.iter().all(|item| item.title != "Test sync"));
+    }
+
+    #[actix_web::test]
+    async fn test_batch_endpoints_honor_max_batch_size() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::new(Settings {
+                    max_batch_size: 2,
+                    ..Settings::default()
+                }))
+                .service(sync_todos)
+                .service(get_todos),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo/sync")
+            .set_json(serde_json::json!([
+                { "op": "create", "title": "One", "description": "First" },
+                { "op": "create", "title": "Two", "description": "Second" },
+                { "op": "create", "title": "Three", "description": "Third" }
+            ]))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+        let body = test::read_body(resp).await;
+        assert!(String::from_utf8_lossy(&body).contains("the maximum is 2"));
+
+        let validation_req = test::TestRequest::default().uri("/todo").to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, validation_req).await;
+        assert_eq!(resp.len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_update_todo() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(update_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874")
+            .set_json(&UpdateTodoItemRequest {
+                new_title: "Test update".to_string(),
+                new_description: "We should test the update method".to_string(),
+                completed: true,
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                expected_version: 1,
+            })
+            .to_request();
+
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.title, "Test update");
+        assert_eq!(resp.description, "We should test the update method");
+        assert_eq!(resp.completed, true);
+        assert_eq!(resp.version, 2);
+    }
+
+    #[actix_web::test]
+    async fn test_update_todo_rejects_stale_version() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(update_todo)
+                .service(patch_todo),
+        )
+        .await;
+
+        let update = |expected_version| {
+            test::TestRequest::put()
+                .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874")
+                .set_json(&UpdateTodoItemRequest {
+                    new_title: "Test update".to_string(),
+                    new_description: "Only one of the clients should win".to_string(),
+                    completed: false,
+                    due_date: None,
+                    estimated_minutes: None,
+                    actual_minutes: None,
+                    remind_before_minutes: None,
+                    expected_version,
+                })
+                .to_request()
+        };
+        let resp = test::call_service(&app, update(1)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        // The second client still has the first version
+        let resp = test::call_service(&app, update(1)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+
+        let patch = |expected_version| {
+            test::TestRequest::patch()
+                .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874")
+                .set_json(&PatchTodoItemRequest {
+                    completed: Some(true),
+                    expected_version,
+                    ..Default::default()
+                })
+                .to_request()
+        };
+        let resp = test::call_service(&app, patch(Some(1))).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+        let resp: TodoItem = test::call_and_read_body_json(&app, patch(Some(2))).await;
+        assert_eq!(resp.version, 3);
+        let resp: TodoItem = test::call_and_read_body_json(&app, patch(None)).await;
+        assert_eq!(resp.version, 4);
+    }
+
+    #[actix_web::test]
+    async fn test_patch_todo() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(patch_todo),
+        )
+        .await;
+
+        // Only flip the completion, the title and description must be left untouched
+        let req = test::TestRequest::patch()
+            .uri("/todo/cdce7fda-909e-41cb-8507-abceb316a5b4")
+            .set_json(&PatchTodoItemRequest {
+                completed: Some(false),
+                ..Default::default()
+            })
+            .to_request();
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.title, "Test the microservice");
+        assert_eq!(resp.description, "We should test the get all method");
+        assert!(!resp.completed);
+        assert_eq!(resp.completed_at, None);
+
+        let req = test::TestRequest::patch()
+            .uri("/todo/cdce7fda-909e-41cb-8507-abceb316a5b4")
+            .set_json(&PatchTodoItemRequest {
+                title: Some("Test the patch method".to_string()),
+                ..Default::default()
+            })
+            .to_request();
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.title, "Test the patch method");
+        assert_eq!(resp.description, "We should test the get all method");
+        assert!(!resp.completed);
+
+        // The patched fields are validated like a created todo item
+        for request in [
+            PatchTodoItemRequest {
+                title: Some(String::new()),
+                ..Default::default()
+            },
+            PatchTodoItemRequest {
+                description: Some("x".repeat(4097)),
+                ..Default::default()
+            },
+        ] {
+            let req = test::TestRequest::patch()
+                .uri("/todo/cdce7fda-909e-41cb-8507-abceb316a5b4")
+                .set_json(&request)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_merge_patch_todo() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(merge_patch_todo)
+                .service(patch_todo),
+        )
+        .await;
+        let merge_patch = |document: serde_json::Value| {
+            test::TestRequest::patch()
+                .uri("/todo/cdce7fda-909e-41cb-8507-abceb316a5b4")
+                .insert_header((actix_web::http::header::CONTENT_TYPE, MERGE_PATCH))
+                .set_payload(document.to_string())
+                .to_request()
+        };
+
+        // Provided members update the field
+        let due_date = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let req = merge_patch(serde_json::json!({
+            "title": "Merge the patch",
+            "due_date": due_date,
+            "estimated_minutes": 30,
+        }));
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.title, "Merge the patch");
+        assert_eq!(resp.due_date, Some(due_date));
+        assert_eq!(resp.estimated_minutes, Some(30));
+
+        // An explicit null clears the field, omitted members are left untouched
+        let req = merge_patch(serde_json::json!({ "due_date": null }));
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.due_date, None);
+        assert_eq!(resp.title, "Merge the patch");
+        assert_eq!(resp.description, "We should test the get all method");
+        assert_eq!(resp.estimated_minutes, Some(30));
+
+        // The title can't be cleared
+        let req = merge_patch(serde_json::json!({ "title": null }));
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        // Nor emptied or made longer than a created title
+        for title in [String::new(), "x".repeat(256)] {
+            let req = merge_patch(serde_json::json!({ "title": title }));
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_patch_todo_not_found() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(patch_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::patch()
+            .uri(&format!("/todo/{}", Uuid::new_v4()))
+            .set_json(&PatchTodoItemRequest {
+                completed: Some(true),
+                ..Default::default()
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_update_todo_not_found() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(update_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/todo/{}", Uuid::new_v4()))
+            .set_json(&UpdateTodoItemRequest {
+                new_title: "Test update".to_string(),
+                new_description: "We should not find this todo item".to_string(),
+                completed: true,
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                expected_version: 1,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_complete_and_incomplete_todo() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(complete_todo)
+                .service(incomplete_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874/incomplete")
+            .to_request();
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.completed);
+        assert_eq!(resp.completed_at, None);
+        assert_eq!(resp.title, "Use a mock repository");
+
+        let req = test::TestRequest::post()
+            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874/complete")
+            .to_request();
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.completed);
+        assert!(resp.completed_at.is_some());
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/todo/{}/complete", Uuid::new_v4()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    // Inserts an incomplete subtask of the given todo item, returning its identifier.
+    async fn insert_subtask(repository: &Arc<dyn TodoRepository>, parent: Uuid) -> Uuid {
+        let request = CreateTodoItemRequest {
+            title: "Subtask".to_string(),
+            description: String::new(),
+            due_date: None,
+            estimated_minutes: None,
+            actual_minutes: None,
+            remind_before_minutes: None,
+            tags: Vec::new(),
+            parent_id: Some(parent),
+            recurrence: None,
+        };
+        repository.insert(request.into()).await.unwrap().id
+    }
+
+    // Like the data store configured with the given subtask rule.
+    fn get_repository_mock_with_subtask_rule(subtask_rule: SubtaskRule) -> Arc<dyn TodoRepository> {
+        with_mock_data(TodoEntityRepositoryMock {
+            subtask_rule,
+            ..Default::default()
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_complete_cascades_to_subtasks() {
+        let repository = get_repository_mock_with_subtask_rule(SubtaskRule::Cascade);
+        let parent = Uuid::parse_str("cdce7fda-909e-41cb-8507-abceb316a5b4").unwrap();
+        let subtask = insert_subtask(&repository, parent).await;
+        let nested = insert_subtask(&repository, subtask).await;
+        let events = Data::new(TodoEvents::default());
+        let mut receiver = events.subscribe();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .app_data(events)
+                .service(complete_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/todo/{}/complete", parent))
+            .to_request();
+        req.extensions_mut().insert(AuthenticatedUser {
+            subject: "alice".to_string(),
+            roles: Vec::new(),
         });
-        let _ = repository
-            .insert(TodoEntity {
-                id: Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap(),
-                title: "Use a mock repository".to_string(),
-                description: "We should test that we can also use a mock for the same handler"
-                    .to_string(),
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.completed);
+        for id in [subtask, nested] {
+            let entity = repository.get_by_id(id).await.unwrap().unwrap();
+            assert!(entity.completed);
+            assert!(entity.completed_at.is_some());
+        }
+
+        // The cascaded subtasks are published and recorded like the todo item itself
+        let mut published = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            assert_eq!(event.kind, TodoEventKind::Updated);
+            published.push(event.id);
+        }
+        published.sort();
+        let mut expected = vec![parent, subtask, nested];
+        expected.sort();
+        assert_eq!(published, expected);
+        let history = repository.activity(Some("alice"), 0, 10).unwrap();
+        assert_eq!(history.len(), 3);
+        assert!(history.iter().all(|entry| entry.action == "complete"));
+    }
+
+    #[actix_web::test]
+    async fn test_every_completion_cascades_to_subtasks() {
+        let repository = get_repository_mock_with_subtask_rule(SubtaskRule::Cascade);
+        let parent = Uuid::parse_str("cdce7fda-909e-41cb-8507-abceb316a5b4").unwrap();
+        let subtask = insert_subtask(&repository, parent).await;
+        let nested = insert_subtask(&repository, subtask).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .app_data(Data::new(Settings::default()))
+                .service(sync_todos)
+                .service(update_todo)
+                .service(patch_todo),
+        )
+        .await;
+        let reopen = || {
+            for id in [parent, subtask, nested] {
+                repository.set_completed(id, false).unwrap();
+            }
+        };
+        let completed = || async {
+            let mut completed = Vec::new();
+            for id in [subtask, nested] {
+                completed.push(repository.get_by_id(id).await.unwrap().unwrap().completed);
+            }
+            completed
+        };
+
+        reopen();
+        let req = test::TestRequest::patch()
+            .uri(&format!("/todo/{}", parent))
+            .set_json(&PatchTodoItemRequest {
+                completed: Some(true),
+                ..Default::default()
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(completed().await, [true, true]);
+
+        // Changing a completed todo item leaves its subtasks alone
+        repository.set_completed(nested, false).unwrap();
+        let req = test::TestRequest::patch()
+            .uri(&format!("/todo/{}", parent))
+            .set_json(&PatchTodoItemRequest {
+                description: Some("Still completed".to_string()),
+                ..Default::default()
+            })
+            .to_request();
+        test::call_service(&app, req).await;
+        assert_eq!(completed().await, [true, false]);
+
+        reopen();
+        let version = repository.get_by_id(parent).await.unwrap().unwrap().version;
+        let req = test::TestRequest::put()
+            .uri(&format!("/todo/{}", parent))
+            .set_json(&UpdateTodoItemRequest {
+                new_title: "Test the microservice".to_string(),
+                new_description: "Completed by a full update".to_string(),
+                completed: true,
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                expected_version: version,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(completed().await, [true, true]);
+
+        reopen();
+        let req = test::TestRequest::post()
+            .uri("/todo/sync")
+            .set_json(serde_json::json!([{
+                "op": "update",
+                "id": parent,
+                "title": "Test the microservice",
+                "description": "Completed by a sync",
+                "completed": true
+            }]))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(completed().await, [true, true]);
+    }
+
+    #[actix_web::test]
+    async fn test_complete_requires_subtasks_done() {
+        let repository = get_repository_mock_with_subtask_rule(SubtaskRule::RequireDone);
+        let parent = Uuid::parse_str("cdce7fda-909e-41cb-8507-abceb316a5b4").unwrap();
+        let subtask = insert_subtask(&repository, parent).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .service(complete_todo),
+        )
+        .await;
+        let complete = |id: Uuid| {
+            test::TestRequest::post()
+                .uri(&format!("/todo/{}/complete", id))
+                .to_request()
+        };
+
+        let before = repository.get_by_id(parent).await.unwrap().unwrap();
+        let resp = test::call_service(&app, complete(parent)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+        let after = repository.get_by_id(parent).await.unwrap().unwrap();
+        assert_eq!(after.completed_at, before.completed_at);
+
+        let resp = test::call_service(&app, complete(subtask)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let resp = test::call_service(&app, complete(parent)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn test_every_completion_requires_nested_subtasks_done() {
+        let repository = get_repository_mock_with_subtask_rule(SubtaskRule::RequireDone);
+        let parent = Uuid::parse_str("cdce7fda-909e-41cb-8507-abceb316a5b4").unwrap();
+        let subtask = insert_subtask(&repository, parent).await;
+        let nested = insert_subtask(&repository, subtask).await;
+        repository.set_completed(parent, false).unwrap();
+        repository.set_completed(subtask, true).unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .app_data(Data::new(Settings::default()))
+                .service(sync_todos)
+                .service(update_todo)
+                .service(patch_todo)
+                .service(complete_todo),
+        )
+        .await;
+
+        // Only a subtask of the subtask is incomplete
+        let req = test::TestRequest::post()
+            .uri(&format!("/todo/{}/complete", parent))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+
+        let req = test::TestRequest::patch()
+            .uri(&format!("/todo/{}", parent))
+            .set_json(&PatchTodoItemRequest {
+                completed: Some(true),
+                ..Default::default()
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+        let body: ErrorResponse = test::read_body_json(resp).await;
+        assert_eq!(body.error_code, "SUBTASKS_INCOMPLETE");
+
+        let version = repository.get_by_id(parent).await.unwrap().unwrap().version;
+        let req = test::TestRequest::put()
+            .uri(&format!("/todo/{}", parent))
+            .set_json(&UpdateTodoItemRequest {
+                new_title: "Test the microservice".to_string(),
+                new_description: "Completed by a full update".to_string(),
                 completed: true,
-                completed_at: Some(SystemTime::now()),
-                created_at: SystemTime::now(),
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                expected_version: version,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+
+        let req = test::TestRequest::post()
+            .uri("/todo/sync")
+            .set_json(serde_json::json!([{
+                "op": "update",
+                "id": parent,
+                "title": "Test the microservice",
+                "description": "Completed by a sync",
+                "completed": true
+            }]))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert!(
+            !repository
+                .get_by_id(parent)
+                .await
+                .unwrap()
+                .unwrap()
+                .completed
+        );
+
+        repository.set_completed(nested, true).unwrap();
+        let req = test::TestRequest::patch()
+            .uri(&format!("/todo/{}", parent))
+            .set_json(&PatchTodoItemRequest {
+                completed: Some(true),
+                ..Default::default()
             })
-            .unwrap();
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+    }
 
-        let repository_arc: Arc<dyn Repository<TodoEntity>> = Arc::new(repository);
-        repository_arc
+    #[actix_web::test]
+    async fn test_get_weekly_occurrences() {
+        let repository = get_repository_mock_with_data();
+        let due_date = DateTime::from_timestamp(1_706_693_400, 0).unwrap();
+        let request = CreateTodoItemRequest {
+            title: "Weekly review".to_string(),
+            description: String::new(),
+            due_date: Some(due_date),
+            estimated_minutes: None,
+            actual_minutes: None,
+            remind_before_minutes: None,
+            tags: Vec::new(),
+            parent_id: None,
+            recurrence: Some("FREQ=WEEKLY;INTERVAL=1".to_string()),
+        };
+        let recurring = repository.insert(request.into()).await.unwrap().id;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(get_occurrences),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/todo/{}/occurrences?count=3", recurring))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let week = Duration::from_secs(7 * 24 * 60 * 60);
+        let expected: Vec<_> = (1..=3)
+            .map(|weeks| {
+                serde_json::Value::String(
+                    TimestampPrecision::Micros.format(&(due_date + week * weeks)),
+                )
+            })
+            .collect();
+        assert_eq!(resp["recurrence"], "FREQ=WEEKLY;INTERVAL=1");
+        assert_eq!(resp["occurrences"], serde_json::Value::Array(expected));
+
+        // Todo items without a recurrence rule have no occurrences
+        let req = test::TestRequest::get()
+            .uri("/todo/cdce7fda-909e-41cb-8507-abceb316a5b4/occurrences")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
     }
 
     #[actix_web::test]
-    async fn test_get_all() {
+    async fn test_add_checklist_items() {
         let app = test::init_service(
             App::new()
                 .app_data(Data::from(get_repository_mock_with_data()))
-                .service(get_todos),
+                .service(add_checklist_item),
         )
         .await;
-        let req = test::TestRequest::default().uri("/todo").to_request();
+        let add = |text: &str| {
+            test::TestRequest::post()
+                .uri("/todo/cdce7fda-909e-41cb-8507-abceb316a5b4/checklist")
+                .set_json(AddChecklistItemRequest {
+                    text: text.to_string(),
+                })
+                .to_request()
+        };
 
-        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, req).await;
-        assert_eq!(resp.len(), 2);
+        test::call_service(&app, add("Write the tests")).await;
+        let resp: TodoItem = test::call_and_read_body_json(&app, add("Run the tests")).await;
+        assert_eq!(resp.checklist.len(), 2);
+        assert_eq!(resp.checklist[1].text, "Run the tests");
+        assert!(!resp.checklist[1].done);
+        assert_eq!(
+            resp.checklist_progress,
+            ChecklistProgress { done: 0, total: 2 }
+        );
+
+        let resp = test::call_service(&app, add("")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
     }
 
     #[actix_web::test]
-    async fn test_get_by_id() {
+    async fn test_toggle_checklist_item() {
         let repository = get_repository_mock_with_data();
         let app = test::init_service(
             App::new()
-                .app_data(Data::from(repository))
-                .service(get_todo_by_id),
+                .app_data(Data::from(repository.clone()))
+                .service(toggle_checklist_item),
         )
         .await;
+        let uuid = Uuid::parse_str("cdce7fda-909e-41cb-8507-abceb316a5b4").unwrap();
+        for text in ["Write the tests", "Run the tests"] {
+            repository
+                .add_checklist_item(uuid, text.to_string())
+                .unwrap();
+        }
+        let toggle = |index: usize| {
+            test::TestRequest::patch()
+                .uri(&format!("/todo/{}/checklist/{}", uuid, index))
+                .to_request()
+        };
 
-        let req = test::TestRequest::default()
-            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874")
-            .to_request();
+        let resp: TodoItem = test::call_and_read_body_json(&app, toggle(1)).await;
+        assert!(resp.checklist[1].done);
+        assert_eq!(
+            resp.checklist_progress,
+            ChecklistProgress { done: 1, total: 2 }
+        );
 
-        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
-        assert_eq!(resp.title, "Use a mock repository");
+        let resp: TodoItem = test::call_and_read_body_json(&app, toggle(1)).await;
+        assert!(!resp.checklist[1].done);
         assert_eq!(
-            resp.description,
-            "We should test that we can also use a mock for the same handler"
+            resp.checklist_progress,
+            ChecklistProgress { done: 0, total: 2 }
         );
-        assert_eq!(resp.completed, true);
+
+        let resp = test::call_service(&app, toggle(2)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
     }
 
     #[actix_web::test]
-    async fn test_create_todo() {
+    async fn test_add_and_remove_tags() {
         let repository = get_repository_mock_with_data();
         let app = test::init_service(
             App::new()
                 .app_data(Data::from(repository))
-                .service(create_todo),
+                .service(add_tag)
+                .service(remove_tag),
         )
         .await;
+        let uri = "/todo/cdce7fda-909e-41cb-8507-abceb316a5b4/tags";
+        let add = |tag: &str| {
+            test::TestRequest::post()
+                .uri(uri)
+                .set_json(&AddTagRequest {
+                    tag: tag.to_string(),
+                })
+                .to_request()
+        };
 
-        let req = test::TestRequest::post()
-            .uri("/todo")
-            .set_json(&CreateTodoItemRequest {
-                title: "Test create".to_string(),
-                description: "We should test the create method".to_string(),
-            })
-            .to_request();
+        let resp: TodoItem = test::call_and_read_body_json(&app, add("work")).await;
+        assert_eq!(resp.tags, vec!["work"]);
+        let resp: TodoItem = test::call_and_read_body_json(&app, add("project/alpha")).await;
+        assert_eq!(resp.tags, vec!["work", "project/alpha"]);
+
+        // Adding a tag twice doesn't duplicate it
+        let resp: TodoItem = test::call_and_read_body_json(&app, add("work")).await;
+        assert_eq!(resp.tags, vec!["work", "project/alpha"]);
 
+        let resp = test::call_service(&app, add("Not A Tag")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        // Namespaced tags are removed by their full path
+        let req = test::TestRequest::delete()
+            .uri(&format!("{}/project/alpha", uri))
+            .to_request();
         let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
-        assert_eq!(resp.title, "Test create");
-        assert_eq!(resp.description, "We should test the create method");
-        assert_eq!(resp.completed, false);
+        assert_eq!(resp.tags, vec!["work"]);
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("{}/project/alpha", uri))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
     }
 
     #[actix_web::test]
-    async fn test_update_todo() {
+    async fn test_update_todo_keeps_created_at() {
         let repository = get_repository_mock_with_data();
+        let todo_id = Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap();
+        let created_at = repository
+            .get_by_id(todo_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .created_at;
         let app = test::init_service(
             App::new()
-                .app_data(Data::from(repository))
+                .app_data(Data::from(repository.clone()))
                 .service(update_todo),
         )
         .await;
@@ -349,15 +6254,153 @@ mod tests {
             .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874")
             .set_json(&UpdateTodoItemRequest {
                 new_title: "Test update".to_string(),
-                new_description: "We should test the update method".to_string(),
-                completed: true,
+                new_description: "We should keep the creation time".to_string(),
+                completed: false,
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                expected_version: 1,
             })
             .to_request();
 
         let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
-        assert_eq!(resp.title, "Test update");
-        assert_eq!(resp.description, "We should test the update method");
-        assert_eq!(resp.completed, true);
+        assert_eq!(resp.id, todo_id);
+        assert_eq!(resp.created_at, created_at);
+        assert_eq!(
+            repository
+                .get_by_id(todo_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .created_at,
+            created_at
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_update_todo_reschedules_reminder() {
+        let repository = get_repository_mock_with_data();
+        let todo_id = Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .service(update_todo)
+                .service(merge_patch_todo),
+        )
+        .await;
+        let update = |remind_before_minutes, expected_version| {
+            test::TestRequest::put()
+                .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874")
+                .set_json(&UpdateTodoItemRequest {
+                    new_title: "Test update".to_string(),
+                    new_description: "We should remind again".to_string(),
+                    completed: false,
+                    due_date: None,
+                    estimated_minutes: None,
+                    actual_minutes: None,
+                    remind_before_minutes,
+                    expected_version,
+                })
+                .to_request()
+        };
+        let reminded_at = || async {
+            repository
+                .get_by_id(todo_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .reminded_at
+        };
+
+        // Unrelated changes keep the sent reminder
+        repository.mark_reminded(todo_id, Utc::now()).unwrap();
+        let resp = test::call_service(&app, update(None, 1)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert!(reminded_at().await.is_some());
+
+        // A new lead time sends the reminder again
+        let resp = test::call_service(&app, update(Some(30), 2)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert!(reminded_at().await.is_none());
+
+        // So does a new due date
+        repository.mark_reminded(todo_id, Utc::now()).unwrap();
+        let req = test::TestRequest::patch()
+            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874")
+            .insert_header((actix_web::http::header::CONTENT_TYPE, MERGE_PATCH))
+            .set_payload(serde_json::json!({ "due_date": Utc::now() }).to_string())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert!(reminded_at().await.is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_update_todo_returns_diff() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(update_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::put()
+            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874?return=diff")
+            .set_json(&UpdateTodoItemRequest {
+                new_title: "Test diff".to_string(),
+                new_description: "We should test that we can also use a mock for the same handler"
+                    .to_string(),
+                completed: false,
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                expected_version: 1,
+            })
+            .to_request();
+
+        let resp: todo_shared::TodoDiff = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            resp.keys().collect::<Vec<_>>(),
+            vec!["completed", "completed_at", "title"]
+        );
+        assert_eq!(resp["title"].from, "Use a mock repository");
+        assert_eq!(resp["title"].to, "Test diff");
+        assert_eq!(resp["completed"].from, true);
+        assert_eq!(resp["completed"].to, false);
+        assert_eq!(resp["completed_at"].to, serde_json::Value::Null);
+    }
+
+    #[actix_web::test]
+    async fn test_patch_todo_returns_changed_fields() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(patch_todo),
+        )
+        .await;
+
+        let req = test::TestRequest::patch()
+            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874?return=changed")
+            .set_json(serde_json::json!({ "title": "Return the changes" }))
+            .to_request();
+        let resp: serde_json::Map<String, serde_json::Value> =
+            test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.keys().collect::<Vec<_>>(), vec!["id", "title"]);
+        assert_eq!(resp["id"], "120400b8-eee8-47cc-9e96-5bc0a3e2e874");
+        assert_eq!(resp["title"], "Return the changes");
+
+        // Updating to the same values changes nothing, apart from the id
+        let req = test::TestRequest::patch()
+            .uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874?return=changed")
+            .set_json(serde_json::json!({ "title": "Return the changes" }))
+            .to_request();
+        let resp: serde_json::Map<String, serde_json::Value> =
+            test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.keys().collect::<Vec<_>>(), vec!["id"]);
     }
 
     #[actix_web::test]
@@ -386,4 +6429,397 @@ mod tests {
         let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, validation_req).await;
         assert_eq!(resp.len(), 1);
     }
+
+    #[actix_web::test]
+    async fn test_soft_delete_reports_retention() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .app_data(Data::new(Settings {
+                    soft_delete_retention_days: 7,
+                    ..Settings::default()
+                }))
+                .service(delete_todo),
+        )
+        .await;
+        let id = Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap();
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/todo/{}", id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        // The tombstone is kept for the configured retention
+        let deleted_at = repository
+            .get_by_id_including_deleted(id)
+            .await
+            .unwrap()
+            .unwrap()
+            .deleted_at
+            .unwrap();
+        let retention = Duration::from_secs(7 * 24 * 60 * 60);
+        assert_eq!(
+            resp.headers().get(RETENTION_UNTIL_HEADER).unwrap(),
+            HttpDate::from(SystemTime::from(deleted_at + retention))
+                .to_string()
+                .as_str()
+        );
+        assert!(repository.get_by_id(id).await.unwrap().is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_restore_todo() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(delete_todo)
+                .service(restore_todo)
+                .service(get_todo_by_id),
+        )
+        .await;
+        let uri = "/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874";
+
+        let req = test::TestRequest::delete().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        // The deleted todo item is brought back as it was
+        let req = test::TestRequest::post()
+            .uri(&format!("{}/restore", uri))
+            .to_request();
+        let resp: TodoItem = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.title, "Use a mock repository");
+        assert_eq!(resp.deleted_at, None);
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/todo/{}/restore", Uuid::new_v4()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_get_activity() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todo)
+                .service(patch_todo)
+                .service(delete_todo)
+                .service(get_activity),
+        )
+        .await;
+        let authenticate = |req: test::TestRequest, subject: &str, roles: &[&str]| {
+            let req = req.to_request();
+            req.extensions_mut().insert(AuthenticatedUser {
+                subject: subject.to_string(),
+                roles: roles.iter().map(|role| role.to_string()).collect(),
+            });
+            req
+        };
+
+        // Alice creates, completes and deletes todo items, then Bob completes one
+        let req = test::TestRequest::post()
+            .uri("/todo")
+            .set_json(&CreateTodoItemRequest {
+                title: "Test activity".to_string(),
+                description: "We should see this in the activity feed".to_string(),
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: Vec::new(),
+                parent_id: None,
+                recurrence: None,
+            });
+        let created: TodoItem =
+            test::call_and_read_body_json(&app, authenticate(req, "alice", &[])).await;
+        let req = test::TestRequest::patch()
+            .uri(&format!("/todo/{}", created.id))
+            .set_json(&PatchTodoItemRequest {
+                completed: Some(true),
+                ..Default::default()
+            });
+        test::call_service(&app, authenticate(req, "alice", &[])).await;
+        let req = test::TestRequest::delete().uri("/todo/120400b8-eee8-47cc-9e96-5bc0a3e2e874");
+        test::call_service(&app, authenticate(req, "alice", &[])).await;
+        let req = test::TestRequest::patch()
+            .uri("/todo/cdce7fda-909e-41cb-8507-abceb316a5b4")
+            .set_json(&PatchTodoItemRequest {
+                completed: Some(true),
+                ..Default::default()
+            });
+        test::call_service(&app, authenticate(req, "bob", &[])).await;
+
+        // Users only see their own changes, newest first
+        let req = test::TestRequest::default().uri("/activity?limit=2");
+        let resp = test::call_service(&app, authenticate(req, "alice", &[])).await;
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "3");
+        let page: Vec<ActivityEntry> = test::read_body_json(resp).await;
+        let actions: Vec<_> = page.iter().map(|entry| entry.action.as_str()).collect();
+        assert_eq!(actions, vec!["delete", "update"]);
+        assert!(page.iter().all(|entry| entry.actor == "alice"));
+
+        let req = test::TestRequest::default().uri("/activity?offset=2&limit=2");
+        let page: Vec<ActivityEntry> =
+            test::call_and_read_body_json(&app, authenticate(req, "alice", &[])).await;
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].action, "create");
+        assert_eq!(page[0].todo_id, created.id);
+
+        // Admins see the changes of everyone
+        let req = test::TestRequest::default().uri("/activity");
+        let resp = test::call_service(&app, authenticate(req, "admin-1", &["admin"])).await;
+        assert_eq!(resp.headers().get(TOTAL_COUNT_HEADER).unwrap(), "4");
+        let page: Vec<ActivityEntry> = test::read_body_json(resp).await;
+        assert_eq!(page[0].actor, "bob");
+    }
+
+    #[actix_web::test]
+    async fn test_activity_of_batch_writes() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository.clone()))
+                .app_data(Data::new(Settings::default()))
+                .service(create_todos)
+                .service(delete_todos)
+                .service(sync_todos)
+                .service(purge_deleted_todos),
+        )
+        .await;
+        let authenticate = |req: test::TestRequest, subject: &str, roles: &[&str]| {
+            let req = req.to_request();
+            req.extensions_mut().insert(AuthenticatedUser {
+                subject: subject.to_string(),
+                roles: roles.iter().map(|role| role.to_string()).collect(),
+            });
+            req
+        };
+        let actions = |actor: &str| -> Vec<(Uuid, String)> {
+            let mut entries = repository.activity(Some(actor), 0, 10).unwrap();
+            entries.reverse();
+            entries
+                .into_iter()
+                .map(|entry| (entry.todo_id, entry.action))
+                .collect()
+        };
+
+        // Every todo item of a batch is recorded
+        let req = test::TestRequest::post().uri("/todo/batch").set_json(
+            ["Batch one", "Batch two"]
+                .map(|title| serde_json::json!({ "title": title, "description": "" })),
+        );
+        let created: Vec<TodoItem> =
+            test::call_and_read_body_json(&app, authenticate(req, "alice", &[])).await;
+        let ids: Vec<Uuid> = created.iter().map(|item| item.id).collect();
+        assert_eq!(
+            actions("alice"),
+            ids.iter()
+                .map(|id| (*id, "create".to_string()))
+                .collect::<Vec<_>>()
+        );
+
+        // Only the todo items a bulk delete actually deleted are recorded
+        let req = test::TestRequest::delete()
+            .uri("/todo")
+            .set_json(&DeleteTodoItemsRequest {
+                ids: vec![ids[0], ids[1], Uuid::new_v4()],
+            });
+        test::call_service(&app, authenticate(req, "bob", &[])).await;
+        assert_eq!(
+            actions("bob"),
+            ids.iter()
+                .map(|id| (*id, "delete".to_string()))
+                .collect::<Vec<_>>()
+        );
+
+        // Every operation of a sync is recorded
+        let existing = Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap();
+        let req = test::TestRequest::post()
+            .uri("/todo/sync")
+            .set_json(serde_json::json!([{ "op": "delete", "id": existing }]));
+        test::call_service(&app, authenticate(req, "carol", &[])).await;
+        assert_eq!(actions("carol"), vec![(existing, "delete".to_string())]);
+
+        // Purged todo items are recorded for the admin purging them
+        let req = test::TestRequest::delete().uri("/todo/purge?older_than_days=0");
+        test::call_service(&app, authenticate(req, "admin-1", &["admin"])).await;
+        let purged = actions("admin-1");
+        assert_eq!(purged.len(), 4);
+        assert!(purged.iter().all(|(_, action)| action == "purge"));
+        assert!(purged.contains(&(existing, "purge".to_string())));
+    }
+
+    #[actix_web::test]
+    async fn test_delete_todos() {
+        let repository = get_repository_mock_with_data();
+        let events = Data::new(TodoEvents::default());
+        let mut receiver = events.subscribe();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .app_data(events.clone())
+                .app_data(Data::new(Settings {
+                    max_batch_size: 4,
+                    ..Settings::default()
+                }))
+                .service(delete_todos)
+                .service(get_todos),
+        )
+        .await;
+
+        let deleted = [
+            Uuid::parse_str("cdce7fda-909e-41cb-8507-abceb316a5b4").unwrap(),
+            Uuid::parse_str("120400b8-eee8-47cc-9e96-5bc0a3e2e874").unwrap(),
+        ];
+        let req = test::TestRequest::delete()
+            .uri("/todo")
+            .set_json(&DeleteTodoItemsRequest {
+                ids: vec![
+                    deleted[0],
+                    deleted[1],
+                    // Already deleted
+                    Uuid::parse_str("5b2a9c0e-1d4f-4a8e-9c3b-7f6e2d1a0b9c").unwrap(),
+                    Uuid::new_v4(),
+                ],
+            })
+            .to_request();
+        let resp: DeleteTodoItemsResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.count, 2);
+
+        // Only the deleted todo items are published
+        let mut published = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            assert_eq!(event.kind, TodoEventKind::Deleted);
+            published.push(event.id);
+        }
+        assert_eq!(published, deleted);
+
+        let too_many_req = test::TestRequest::delete()
+            .uri("/todo")
+            .set_json(&DeleteTodoItemsRequest {
+                ids: (0..5).map(|_| Uuid::new_v4()).collect(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, too_many_req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        let validation_req = test::TestRequest::default().uri("/todo").to_request();
+        let resp: Vec<TodoItem> = test::call_and_read_body_json(&app, validation_req).await;
+        assert!(resp.is_empty());
+
+        let empty_req = test::TestRequest::delete()
+            .uri("/todo")
+            .set_json(&DeleteTodoItemsRequest { ids: vec![] })
+            .to_request();
+        let resp = test::call_service(&app, empty_req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_dependency_graph_rejects_cycles() {
+        let repository = get_repository_mock_with_data();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::from(repository))
+                .service(get_todo_graph)
+                .service(add_dependency)
+                .service(remove_dependency),
+        )
+        .await;
+        let test = "cdce7fda-909e-41cb-8507-abceb316a5b4";
+        let mock = "120400b8-eee8-47cc-9e96-5bc0a3e2e874";
+        let add = |todo_id: &str, blocked_by: &str| {
+            test::TestRequest::post()
+                .uri(&format!("/todo/{}/dependencies", todo_id))
+                .set_json(serde_json::json!({ "blocked_by": blocked_by }))
+                .to_request()
+        };
+
+        // Testing the microservice is blocked by using a mock repository
+        let resp = test::call_service(&app, add(test, mock)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CREATED);
+
+        for (todo_id, blocked_by) in [(mock, test), (test, test)] {
+            let resp = test::call_service(&app, add(todo_id, blocked_by)).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+        }
+
+        // The soft-deleted todo item can't take part in the graph
+        let deleted = "5b2a9c0e-1d4f-4a8e-9c3b-7f6e2d1a0b9c";
+        let resp = test::call_service(&app, add(deleted, test)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get().uri("/todo/graph").to_request();
+        let graph: TodoGraph = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(
+            graph.edges,
+            [TodoGraphEdge {
+                from: Uuid::parse_str(mock).unwrap(),
+                to: Uuid::parse_str(test).unwrap(),
+            }]
+        );
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/todo/{}/dependencies/{}", test, mock))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let req = test::TestRequest::delete()
+            .uri(&format!("/todo/{}/dependencies/{}", test, mock))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_insert_many_rolls_back() {
+        let repository = TodoEntityRepositoryMock::default();
+        let entity = |title: &str| TodoEntity {
+            title: title.to_string(),
+            ..CreateTodoItemRequest {
+                title: String::new(),
+                description: String::new(),
+                due_date: None,
+                estimated_minutes: None,
+                actual_minutes: None,
+                remind_before_minutes: None,
+                tags: Vec::new(),
+                parent_id: None,
+                recurrence: None,
+            }
+            .into()
+        };
+        let existing = repository.insert(entity("Existing")).await.unwrap();
+
+        // The second todo item conflicts with the existing one, so the first isn't kept either
+        let result = repository
+            .insert_many(vec![entity("New"), existing.clone()])
+            .await;
+        assert!(matches!(result, Err(RepositoryError::Database(_))));
+        let ids: Vec<Uuid> = repository
+            .get_all()
+            .await
+            .unwrap()
+            .iter()
+            .map(|entity| entity.id)
+            .collect();
+        assert_eq!(ids, [existing.id]);
+    }
 }
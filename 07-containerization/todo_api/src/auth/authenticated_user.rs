@@ -0,0 +1,75 @@
+use actix_web::dev::Payload;
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+
+/// The principal of an authenticated request.
+///
+/// The authentication middleware stores it in the request extensions once the caller has been
+/// authenticated. Handlers that need the current user can take it as a parameter, requests
+/// without an authenticated principal are rejected with 401.
+#[derive(Clone, Debug)]
+pub struct AuthenticatedUser {
+    /// The subject (user identifier) of the authenticated caller
+    pub subject: String,
+
+    /// The roles granted to the authenticated caller
+    pub roles: Vec<String>,
+}
+
+impl AuthenticatedUser {
+    /// Indicates whether the caller has been granted the `admin` role.
+    pub fn is_admin(&self) -> bool {
+        self.roles.iter().any(|role| role == "admin")
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<AuthenticatedUser>()
+                .cloned()
+                .ok_or_else(|| actix_web::error::ErrorUnauthorized("Authentication required")),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+    use actix_web::{get, test, App, HttpResponse};
+
+    use super::*;
+
+    #[get("/me")]
+    async fn me(user: AuthenticatedUser) -> HttpResponse {
+        HttpResponse::Ok().body(user.subject)
+    }
+
+    #[actix_web::test]
+    async fn test_rejects_unauthenticated_request() {
+        let app = test::init_service(App::new().service(me)).await;
+
+        let req = test::TestRequest::default().uri("/me").to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn test_extracts_authenticated_user() {
+        let app = test::init_service(App::new().service(me)).await;
+
+        let req = test::TestRequest::default().uri("/me").to_request();
+        req.extensions_mut().insert(AuthenticatedUser {
+            subject: "user-1".to_string(),
+            roles: vec![],
+        });
+
+        let resp = test::call_and_read_body(&app, req).await;
+        assert_eq!(resp, "user-1");
+    }
+}
@@ -0,0 +1,195 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::data::repository::{AsyncRepository, RepositoryError};
+use crate::data::todo_repository::{
+    duplicate_titles, lock_titles, AsyncTodoRepository, DuplicateTitles, TodoEntityRepository,
+};
+use crate::entities::todo_dependency_entity::TodoDependencyEntity;
+use crate::entities::todo_entity::TodoEntity;
+use crate::schema::todo_dependencies;
+use crate::schema::todos;
+use crate::schema::todos::dsl::*;
+
+// The generic operations of `TodoEntityRepository`, on its non-blocking connections. Kept apart
+// from the blocking queries, as diesel and diesel-async both provide the query methods through a
+// `RunQueryDsl` trait. The same rows are queried, so soft-deleted todo items are left out and
+// deletes keep a tombstone.
+#[async_trait]
+impl AsyncRepository<TodoEntity> for TodoEntityRepository {
+    type Key = Uuid;
+
+    async fn get_all(&self) -> Result<Vec<TodoEntity>, RepositoryError> {
+        let mut connection = self.async_db_context.get_conn().await?;
+        todos
+            .filter(deleted_at.is_null())
+            .load::<TodoEntity>(&mut connection)
+            .await
+            .map_err(RepositoryError::from)
+    }
+
+    async fn get_by_id(&self, todo_id: Uuid) -> Result<Option<TodoEntity>, RepositoryError> {
+        let mut connection = self.async_db_context.get_conn().await?;
+        todos
+            .find(todo_id)
+            .filter(deleted_at.is_null())
+            .first(&mut connection)
+            .await
+            .optional()
+            .map_err(RepositoryError::from)
+    }
+
+    async fn exists(&self, todo_id: Uuid) -> Result<bool, RepositoryError> {
+        let mut connection = self.async_db_context.get_conn().await?;
+        diesel::select(diesel::dsl::exists(
+            todos.find(todo_id).filter(deleted_at.is_null()),
+        ))
+        .get_result(&mut connection)
+        .await
+        .map_err(RepositoryError::from)
+    }
+
+    async fn insert(&self, entity: TodoEntity) -> Result<TodoEntity, RepositoryError> {
+        let mut inserted = self.insert_many(vec![entity]).await?;
+        inserted.pop().ok_or(RepositoryError::NotFound)
+    }
+
+    async fn insert_many(
+        &self,
+        entities: Vec<TodoEntity>,
+    ) -> Result<Vec<TodoEntity>, RepositoryError> {
+        let inserted_titles =
+            self.checked_titles(entities.iter().map(|entity| entity.title.clone()));
+        self.async_db_context
+            .transaction(move |connection| {
+                async move {
+                    lock_written_titles(connection, &inserted_titles).await?;
+                    let inserted = diesel::insert_into(todos::table)
+                        .values(&entities)
+                        .get_results::<TodoEntity>(connection)
+                        .await?;
+                    check_written_titles(connection, inserted_titles).await?;
+                    Ok::<_, RepositoryError>(inserted)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    async fn delete(&self, todo_id: Uuid) -> Result<bool, RepositoryError> {
+        let deleted = self.delete_many(vec![todo_id]).await?;
+        Ok(!deleted.is_empty())
+    }
+
+    async fn delete_many(&self, ids: Vec<Uuid>) -> Result<Vec<Uuid>, RepositoryError> {
+        let mut connection = self.async_db_context.get_conn().await?;
+        let now = Utc::now();
+        let deleted = diesel::update(todos.filter(id.eq_any(ids)).filter(deleted_at.is_null()))
+            .set((
+                deleted_at.eq(now),
+                updated_at.eq(now),
+                version.eq(version + 1),
+            ))
+            .returning(id)
+            .get_results(&mut connection)
+            .await?;
+        Ok(deleted)
+    }
+}
+
+// The todo specific queries moved to the non-blocking connections, so the handlers running them
+// don't need a blocking connection as well.
+#[async_trait]
+impl AsyncTodoRepository for TodoEntityRepository {
+    async fn soft_delete(
+        &self,
+        todo_id: Uuid,
+        only_if_completed: bool,
+        at: DateTime<Utc>,
+    ) -> Result<bool, RepositoryError> {
+        self.async_db_context
+            .transaction(move |connection| {
+                async move {
+                    // Lock the row, so it can't be reopened between the check and the delete
+                    let is_completed = todos
+                        .find(todo_id)
+                        .filter(deleted_at.is_null())
+                        .select(completed)
+                        .for_update()
+                        .first::<bool>(connection)
+                        .await?;
+                    if only_if_completed && !is_completed {
+                        return Ok(false);
+                    }
+                    diesel::update(todos.find(todo_id))
+                        .set((
+                            deleted_at.eq(at),
+                            updated_at.eq(at),
+                            version.eq(version + 1),
+                        ))
+                        .execute(connection)
+                        .await
+                        .map(|num_deleted| num_deleted > 0)
+                }
+                .scope_boxed()
+            })
+            .await
+    }
+
+    async fn get_by_id_including_deleted(
+        &self,
+        todo_id: Uuid,
+    ) -> Result<Option<TodoEntity>, RepositoryError> {
+        let mut connection = self.async_db_context.get_conn().await?;
+        todos
+            .find(todo_id)
+            .first(&mut connection)
+            .await
+            .optional()
+            .map_err(RepositoryError::from)
+    }
+
+    async fn get_dependencies(&self) -> Result<Vec<TodoDependencyEntity>, RepositoryError> {
+        let mut connection = self.async_db_context.get_conn().await?;
+        todo_dependencies::table
+            .load::<TodoDependencyEntity>(&mut connection)
+            .await
+            .map_err(RepositoryError::from)
+    }
+}
+
+// Locks the titles written within the current transaction, see `lock_titles`. Nothing is locked
+// when titles don't have to be unique, which leaves no titles to check.
+async fn lock_written_titles(
+    connection: &mut AsyncPgConnection,
+    written_titles: &[String],
+) -> Result<(), RepositoryError> {
+    if !written_titles.is_empty() {
+        lock_titles(written_titles.to_vec())
+            .execute(connection)
+            .await?;
+    }
+    Ok(())
+}
+
+// Fails with `DuplicateTitle` when the write within the current transaction left several live
+// todo items with one of its titles.
+async fn check_written_titles(
+    connection: &mut AsyncPgConnection,
+    written_titles: Vec<String>,
+) -> Result<(), RepositoryError> {
+    if written_titles.is_empty() {
+        return Ok(());
+    }
+    let check = duplicate_titles(written_titles)
+        .get_result::<DuplicateTitles>(connection)
+        .await?;
+    match check.duplicated {
+        true => Err(RepositoryError::DuplicateTitle),
+        false => Ok(()),
+    }
+}
@@ -1,18 +1,374 @@
+use crate::data::repository::RepositoryError;
 use diesel::pg::PgConnection;
-use diesel::r2d2::ConnectionManager;
+use diesel::r2d2::{ConnectionManager, PooledConnection};
+use diesel::{Connection, ConnectionResult, RunQueryDsl};
+use diesel_async::pooled_connection::deadpool::{Object, Pool as AsyncPool};
+use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+use diesel_async::scoped_futures::ScopedBoxFuture;
+use diesel_async::{AsyncConnection, AsyncPgConnection};
 use dotenv::dotenv;
-use r2d2::Pool;
+use log::{info, warn};
+use r2d2::{Builder, Pool};
 use std::env;
+use std::fmt::Display;
+use std::thread;
+use std::time::Duration;
 
 // The Postgres-specific connection pool managing all database connections.
 pub type PostgresPool = Pool<ConnectionManager<PgConnection>>;
 
+// The Postgres-specific pool of non-blocking connections, as used by the async repositories.
+pub type AsyncPostgresPool = AsyncPool<AsyncPgConnection>;
+
+// The maximum number of connections when `DB_POOL_MAX_SIZE` is not set.
+const DEFAULT_MAX_SIZE: u32 = 10;
+
+// The share of the connections given to the pool of non-blocking connections, e.g. 4 for a quarter.
+const ASYNC_POOL_SHARE: u32 = 4;
+
+// The time to wait for a connection when `DB_POOL_CONNECTION_TIMEOUT_SECS` is not set.
+const DEFAULT_CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+// The time a request waits for a connection while all of them are checked out, before giving up.
+const CHECKOUT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// The number of times to retry connecting at startup when `DB_STARTUP_RETRIES` is not set.
+const DEFAULT_STARTUP_RETRIES: u32 = 5;
+
+// The wait before the first retry when `DB_STARTUP_BACKOFF_MS` is not set.
+const DEFAULT_STARTUP_BACKOFF: Duration = Duration::from_millis(500);
+
+// The longest wait between two attempts to connect at startup.
+const MAX_STARTUP_BACKOFF: Duration = Duration::from_secs(30);
+
+/// The connection pool of the data store, as used by the repositories.
+#[derive(Clone)]
+pub struct DbContext {
+    pool: PostgresPool,
+}
+
+impl DbContext {
+    pub fn new(pool: PostgresPool) -> Self {
+        DbContext { pool }
+    }
+
+    /// Checks out a connection from the pool.
+    ///
+    /// Fails with `RepositoryError::PoolTimeout` when all connections remain checked out for a few
+    /// seconds, so an exhausted pool is reported to the caller rather than blocking the request.
+    pub fn get_conn(
+        &self,
+    ) -> Result<PooledConnection<ConnectionManager<PgConnection>>, RepositoryError> {
+        self.pool
+            .get_timeout(CHECKOUT_TIMEOUT)
+            .map_err(RepositoryError::from)
+    }
+
+    /// Runs the given operations on a single connection, within a transaction.
+    ///
+    /// The transaction is committed when the operations succeed and rolled back when they fail, so
+    /// either all the rows they touch are changed or none of them. The operations fail with either
+    /// a query error or a `RepositoryError`, e.g. to reject a conflicting change.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `operations` - The queries to run, on the connection they are given.
+    pub fn transaction<R, E, F>(&self, operations: F) -> Result<R, RepositoryError>
+    where
+        F: FnOnce(&mut PgConnection) -> Result<R, E>,
+        E: From<diesel::result::Error>,
+        RepositoryError: From<E>,
+    {
+        let mut connection = self.get_conn()?;
+        PgConnection::transaction(&mut connection, operations).map_err(RepositoryError::from)
+    }
+
+    /// Returns the underlying connection pool.
+    pub fn pool(&self) -> &PostgresPool {
+        &self.pool
+    }
+}
+
+/// The non-blocking connection pool of the data store, as used by the async repositories.
+#[derive(Clone)]
+pub struct AsyncDbContext {
+    pool: AsyncPostgresPool,
+}
+
+impl AsyncDbContext {
+    pub fn new(pool: AsyncPostgresPool) -> Self {
+        AsyncDbContext { pool }
+    }
+
+    /// Checks out a connection from the pool, connecting when none is idle.
+    ///
+    /// Fails with `RepositoryError::PoolTimeout` like `DbContext::get_conn`, when no connection
+    /// becomes available within a few seconds.
+    pub async fn get_conn(&self) -> Result<Object<AsyncPgConnection>, RepositoryError> {
+        tokio::time::timeout(CHECKOUT_TIMEOUT, self.pool.get())
+            .await
+            .map_err(|_| RepositoryError::PoolTimeout)?
+            .map_err(RepositoryError::from)
+    }
+
+    /// Runs the given operations on a single connection, within a transaction.
+    ///
+    /// Like `DbContext::transaction`, the transaction is rolled back when the operations fail.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `operations` - The queries to run, on the connection they are given.
+    pub async fn transaction<'a, R, E, F>(&self, operations: F) -> Result<R, RepositoryError>
+    where
+        F: for<'r> FnOnce(&'r mut AsyncPgConnection) -> ScopedBoxFuture<'a, 'r, Result<R, E>>
+            + Send
+            + 'a,
+        R: Send + 'a,
+        E: From<diesel::result::Error> + Send + 'a,
+        RepositoryError: From<E>,
+    {
+        let mut connection = self.get_conn().await?;
+        AsyncPgConnection::transaction(&mut connection, operations)
+            .await
+            .map_err(RepositoryError::from)
+    }
+}
+
+/// Creates the pool of non-blocking connections, with its share of `DB_POOL_MAX_SIZE`.
+///
+/// Connections are only established once they are checked out, so the pool can be created
+/// outside of an async runtime.
+pub fn get_async_pool() -> AsyncPostgresPool {
+    dotenv().ok();
+    let url = env::var("DATABASE_URL").expect("no DB URL");
+    let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new(url);
+    let (_, async_size) = split_max_size(max_size());
+    AsyncPool::builder(manager)
+        .max_size(async_size as usize)
+        .build()
+        .expect("could not build async connection pool")
+}
+
 pub fn get_pool() -> PostgresPool {
     // it from the environment within this function
     dotenv().ok();
     let url = env::var("DATABASE_URL").expect("no DB URL");
     let migr = ConnectionManager::<PgConnection>::new(url);
-    r2d2::Pool::builder()
+    let pool = pool_builder()
         .build(migr)
-        .expect("could not build connection pool")
+        .expect("could not build connection pool");
+    info!(
+        "Database pool: max size {}, min idle {:?}, connection timeout {:?}, idle timeout {:?}",
+        pool.max_size(),
+        pool.min_idle(),
+        pool.connection_timeout(),
+        pool.idle_timeout()
+    );
+    pool
+}
+
+/// Connects to the database at startup, retrying while it isn't accepting connections yet.
+///
+/// In Docker Compose the API may start before Postgres is ready. Failed attempts are retried
+/// `DB_STARTUP_RETRIES` times (5 by default), waiting `DB_STARTUP_BACKOFF_MS` milliseconds (500
+/// by default) before the first retry and twice as long before every next one.
+pub fn establish_with_retries() -> ConnectionResult<PgConnection> {
+    dotenv().ok();
+    let url = env::var("DATABASE_URL").expect("no DB URL");
+    let retries = env_number("DB_STARTUP_RETRIES").unwrap_or(DEFAULT_STARTUP_RETRIES);
+    let backoff = env_number("DB_STARTUP_BACKOFF_MS").map_or(DEFAULT_STARTUP_BACKOFF, |millis| {
+        Duration::from_millis(millis.into())
+    });
+    retry(retries, backoff, thread::sleep, || {
+        PgConnection::establish(&url)
+    })
+}
+
+// Runs the attempt until it succeeds or it failed `retries` times more, doubling the wait between
+// the attempts from `backoff` on.
+fn retry<T, E: Display>(
+    retries: u32,
+    backoff: Duration,
+    mut sleep: impl FnMut(Duration),
+    mut attempt: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let mut delay = backoff;
+    let mut retry = 0;
+    loop {
+        match attempt() {
+            Ok(result) => {
+                if retry > 0 {
+                    info!("Connected to the database after {} retries", retry);
+                }
+                return Ok(result);
+            }
+            Err(error) if retry < retries => {
+                warn!(
+                    "Unable to connect to the database (attempt {} of {}), retrying in {:?}: {}",
+                    retry + 1,
+                    retries + 1,
+                    delay,
+                    error
+                );
+                sleep(delay);
+                delay = (delay * 2).min(MAX_STARTUP_BACKOFF);
+                retry += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Checks whether the database is able to serve queries.
+pub trait DatabaseProbe: Send + Sync {
+    /// Runs a trivial query, failing when no connection is available within the given timeout.
+    fn probe(&self, timeout: Duration) -> Result<(), RepositoryError>;
+}
+
+impl DatabaseProbe for PostgresPool {
+    fn probe(&self, timeout: Duration) -> Result<(), RepositoryError> {
+        let mut connection = self.get_timeout(timeout)?;
+        diesel::sql_query("SELECT 1").execute(&mut connection)?;
+        Ok(())
+    }
+}
+
+// Configures the pool builder with the settings from the environment.
+fn pool_builder() -> Builder<ConnectionManager<PgConnection>> {
+    let (max_size, _) = split_max_size(max_size());
+    // Without a minimum the pool keeps `max_size` idle connections, like r2d2 does.
+    let min_idle = env_number("DB_POOL_MIN_IDLE").filter(|min_idle| {
+        let valid = *min_idle <= max_size;
+        if !valid {
+            warn!(
+                "DB_POOL_MIN_IDLE {} exceeds the max size {}, ignoring it",
+                min_idle, max_size
+            );
+        }
+        valid
+    });
+    let connection_timeout = env_number("DB_POOL_CONNECTION_TIMEOUT_SECS")
+        .filter(|seconds| *seconds > 0)
+        .map_or(DEFAULT_CONNECTION_TIMEOUT, |seconds| {
+            Duration::from_secs(seconds.into())
+        });
+
+    let builder = Pool::builder()
+        .max_size(max_size)
+        .min_idle(min_idle)
+        .connection_timeout(connection_timeout);
+
+    // Reap connections that have been idle for too long, so they are re-established
+    // before a server closing idle connections (e.g. serverless Postgres) breaks them.
+    match idle_timeout() {
+        Some(timeout) => builder.idle_timeout(Some(timeout)),
+        None => builder,
+    }
+}
+
+// Reads `DB_POOL_MAX_SIZE`, ignoring values that are not a positive number.
+fn max_size() -> u32 {
+    env_number("DB_POOL_MAX_SIZE")
+        .filter(|size| *size > 0)
+        .unwrap_or(DEFAULT_MAX_SIZE)
+}
+
+// Splits the maximum number of connections between the blocking and the non-blocking pool, so
+// together they don't open more connections than the database is configured for. Both pools are
+// needed until the remaining todo specific queries of `TodoRepository`, which hold locks across
+// several statements, are moved to the non-blocking connections. The non-blocking pool serves the
+// generic operations and `AsyncTodoRepository`, and gets a quarter of them, each pool gets at
+// least one.
+fn split_max_size(max_size: u32) -> (u32, u32) {
+    let async_size = (max_size / ASYNC_POOL_SHARE).max(1);
+    (max_size.saturating_sub(async_size).max(1), async_size)
+}
+
+// Reads `DB_IDLE_TIMEOUT_SECS`, ignoring values that are not a positive number of seconds.
+fn idle_timeout() -> Option<Duration> {
+    env::var("DB_IDLE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs)
+}
+
+// Reads a numeric setting, ignoring values that are not a number.
+fn env_number(name: &str) -> Option<u32> {
+    env::var(name).ok().and_then(|value| value.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_builder_idle_timeout() {
+        env::set_var("DB_IDLE_TIMEOUT_SECS", "30");
+
+        // Build without connecting, we only want to inspect the configuration.
+        let pool = pool_builder().build_unchecked(ConnectionManager::<PgConnection>::new(
+            "postgres://localhost/test",
+        ));
+
+        assert_eq!(pool.idle_timeout(), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_pool_builder_size() {
+        env::set_var("DB_POOL_MAX_SIZE", "4");
+        env::set_var("DB_POOL_MIN_IDLE", "1");
+        env::set_var("DB_POOL_CONNECTION_TIMEOUT_SECS", "5");
+
+        let pool = pool_builder().build_unchecked(ConnectionManager::<PgConnection>::new(
+            "postgres://localhost/test",
+        ));
+
+        // One of the connections is left to the pool of non-blocking connections
+        assert_eq!(pool.max_size(), 3);
+        assert_eq!(pool.min_idle(), Some(1));
+        assert_eq!(pool.connection_timeout(), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_split_max_size() {
+        assert_eq!(split_max_size(10), (8, 2));
+        assert_eq!(split_max_size(4), (3, 1));
+        assert_eq!(split_max_size(2), (1, 1));
+    }
+
+    #[test]
+    fn test_retry_with_backoff() {
+        let mut waits = Vec::new();
+        let mut attempts = 0;
+        let result = retry(
+            5,
+            Duration::from_millis(100),
+            |wait| waits.push(wait),
+            || {
+                attempts += 1;
+                match attempts {
+                    1..=3 => Err("connection refused"),
+                    _ => Ok(attempts),
+                }
+            },
+        );
+
+        assert_eq!(result, Ok(4));
+        assert_eq!(waits, [100, 200, 400].map(Duration::from_millis).to_vec());
+
+        // Gives up once the retries are exhausted
+        let mut attempts = 0;
+        let result: Result<(), _> = retry(
+            2,
+            Duration::from_millis(100),
+            |_| {},
+            || {
+                attempts += 1;
+                Err("connection refused")
+            },
+        );
+        assert_eq!(result, Err("connection refused"));
+        assert_eq!(attempts, 3);
+    }
 }
@@ -1,5 +1,7 @@
+pub mod async_todo_repository;
 pub mod db_context;
 pub mod repository;
+pub mod shard;
 pub mod todo_repository;
 
 use crate::Error;
@@ -12,7 +14,8 @@ pub fn run_migrations() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
     // See the documentation for `MigrationHarness` for
     // all available methods.
 
-    let mut connection = db_context::get_pool().get().unwrap();
+    // Wait for the database to accept connections, it may still be starting up
+    let mut connection = db_context::establish_with_retries()?;
     connection.run_pending_migrations(MIGRATIONS)?;
 
     Ok(())
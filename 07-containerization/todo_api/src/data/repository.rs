@@ -1,33 +1,127 @@
-pub trait Repository<T>: Send + Sync {
+use async_trait::async_trait;
+use diesel_async::pooled_connection::deadpool;
+use std::fmt;
+
+/// Errors returned by a repository.
+#[derive(Debug)]
+pub enum RepositoryError {
+    /// No connection became available in the pool in time
+    PoolTimeout,
+
+    /// No instance exists with the given identifier
+    NotFound,
+
+    /// The instance was changed since the version the change is based on
+    Conflict,
+
+    /// Another instance already has the same title, while titles have to be unique
+    DuplicateTitle,
+
+    /// The instance can't be completed, as this number of its subtasks is incomplete
+    SubtasksIncomplete(i64),
+
+    /// The data store failed to execute the query
+    Database(diesel::result::Error),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepositoryError::PoolTimeout => write!(f, "Timed out waiting for a connection"),
+            RepositoryError::NotFound => write!(f, "Record not found"),
+            RepositoryError::Conflict => write!(f, "Record was changed concurrently"),
+            RepositoryError::DuplicateTitle => write!(f, "Record with the same title exists"),
+            RepositoryError::SubtasksIncomplete(count) => {
+                write!(f, "Record has {} incomplete subtasks", count)
+            }
+            RepositoryError::Database(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+// Convert from the Diesel error, keeping a missing record apart from other failures
+impl From<diesel::result::Error> for RepositoryError {
+    fn from(error: diesel::result::Error) -> Self {
+        match error {
+            diesel::result::Error::NotFound => RepositoryError::NotFound,
+            error => RepositoryError::Database(error),
+        }
+    }
+}
+
+// Getting a connection from the pool only fails when none became available within the timeout
+impl From<diesel::r2d2::PoolError> for RepositoryError {
+    fn from(_: diesel::r2d2::PoolError) -> Self {
+        RepositoryError::PoolTimeout
+    }
+}
+
+// The async pool fails like the blocking one, when no connection could be established in time
+impl From<deadpool::PoolError> for RepositoryError {
+    fn from(_: deadpool::PoolError) -> Self {
+        RepositoryError::PoolTimeout
+    }
+}
+
+/// The generic data store operations on instances of `<T>`, identified by a `Key`, awaited
+/// without blocking a worker.
+///
+/// Handlers await these methods directly, instead of moving the call to the blocking thread pool
+/// with `web::block`. Implementations choose the type of the key, e.g. `Uuid` for the todo items.
+#[async_trait]
+pub trait AsyncRepository<T>: Send + Sync {
+    /// The identifier of an instance of `<T>`
+    type Key;
+
     /// Returns all availble instances of `<T>`
-    fn get_all(&self) -> Vec<T>;
+    async fn get_all(&self) -> Result<Vec<T>, RepositoryError>;
 
     /// Returns a single instance of `<T>` based on the given id
     ///
     ///  # Arguments
-    ///  
+    ///
     ///  * `id` - The identifier of the item to find in the data store.
-    fn get_by_id(&self, id: uuid::Uuid) -> Option<T>;
+    async fn get_by_id(&self, id: Self::Key) -> Result<Option<T>, RepositoryError>;
+
+    /// Indicates whether an instance of `<T>` exists with the given id, without loading it
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The identifier of the item to look for in the data store.
+    async fn exists(&self, id: Self::Key) -> Result<bool, RepositoryError>;
 
     /// Inserts a single instance of `<T>` in the data store
     ///
     ///  # Arguments
-    ///  
+    ///
     ///  * `entity` - The entity to insert.
-    fn insert(&self, entity: T) -> Result<T, String>;
+    async fn insert(&self, entity: T) -> Result<T, RepositoryError>;
 
-    /// Updates a single instance of `<T>` in the data store with the given `id`
+    /// Inserts multiple instances of `<T>` in the data store within a single transaction
+    ///
+    /// Either all instances are inserted, or none of them when one fails.
     ///
     ///  # Arguments
-    ///  
-    ///  * `id` - The unique identifier of the entity to update
-    ///  * `entity` - An updated version of the entity with the latest values.
-    fn update(&self, id: uuid::Uuid, entity: T) -> Result<T, String>;
+    ///
+    ///  * `entities` - The entities to insert.
+    async fn insert_many(&self, entities: Vec<T>) -> Result<Vec<T>, RepositoryError>;
 
     /// Deletes a single instance of `<T>` from the data store with the given `id`
     ///
+    /// Implementations may keep the instance as a (soft-deleted) tombstone, as long as it no
+    /// longer shows up in the other queries.
+    ///
     ///  # Arguments
-    ///  
+    ///
     ///  * `id` - The identifier of the item to delete from the data store.
-    fn delete(&self, id: uuid::Uuid) -> Result<bool, String>;
+    async fn delete(&self, id: Self::Key) -> Result<bool, RepositoryError>;
+
+    /// Deletes all instances of `<T>` with one of the given ids from the data store at once
+    ///
+    /// Returns the ids of the deleted instances, ids that don't exist are ignored.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `ids` - The identifiers of the items to delete from the data store.
+    async fn delete_many(&self, ids: Vec<Self::Key>) -> Result<Vec<Self::Key>, RepositoryError>;
 }
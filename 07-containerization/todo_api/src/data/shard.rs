@@ -0,0 +1,66 @@
+use std::str::FromStr;
+
+/// A single partition of the todo items, used to export disjoint subsets concurrently.
+///
+/// Parsed from `<index>/<count>`, where the (1-based) `index` selects one of `count` partitions.
+/// A row belongs to the partition when its id hashes to `index - 1` modulo `count`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    /// The 1-based index of the shard.
+    pub index: i32,
+
+    /// The total number of shards.
+    pub count: i32,
+}
+
+impl Shard {
+    /// The remainder of the id hash selecting the rows of this shard.
+    pub fn remainder(&self) -> i32 {
+        self.index - 1
+    }
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid shard `{}`, expected `<index>/<count>` with 1 <= index <= count",
+                spec
+            )
+        };
+
+        let (index, count) = spec.split_once('/').ok_or_else(invalid)?;
+        let index = index.trim().parse::<i32>().map_err(|_| invalid())?;
+        let count = count.trim().parse::<i32>().map_err(|_| invalid())?;
+        if index < 1 || index > count {
+            return Err(invalid());
+        }
+
+        Ok(Shard { index, count })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_shard() {
+        assert_eq!("2/5".parse(), Ok(Shard { index: 2, count: 5 }));
+        assert_eq!("1/1".parse(), Ok(Shard { index: 1, count: 1 }));
+        assert_eq!("2/5".parse::<Shard>().unwrap().remainder(), 1);
+    }
+
+    #[test]
+    fn test_parse_invalid_shard() {
+        for spec in ["", "2", "0/5", "6/5", "-1/5", "2/-5", "a/5", "2/5/1"] {
+            assert!(
+                spec.parse::<Shard>().is_err(),
+                "{} should be rejected",
+                spec
+            );
+        }
+    }
+}
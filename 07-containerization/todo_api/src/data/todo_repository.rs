@@ -1,71 +1,1526 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicBool, Ordering};
 use uuid::Uuid;
 
 use crate::data::db_context;
-use crate::data::repository::Repository;
+use crate::data::repository::{AsyncRepository, RepositoryError};
+use crate::data::shard::Shard;
 use crate::diesel::prelude::*;
+use crate::entities::checklist::Checklist;
+use crate::entities::todo_change::{ChangeError, TodoChange};
+use crate::entities::todo_dependency_entity::TodoDependencyEntity;
 use crate::entities::todo_entity::TodoEntity;
+use crate::entities::todo_history_entity::TodoHistoryEntity;
+use crate::entities::todo_patch::{rescheduled_reminder, TodoPatch, TodoUpdate};
+use crate::schema::todo_dependencies;
+use crate::schema::todo_history;
 use crate::schema::todos;
 use crate::schema::todos::dsl::*;
+use crate::services::cursor::Cursor;
+use crate::services::dependency_graph::creates_cycle;
+use crate::settings::Settings;
+use diesel::dsl::{count_star, sql};
+use diesel::expression::BoxableExpression;
+use diesel::pg::{Pg, PgConnection};
+use diesel::query_builder::{BoxedSqlQuery, SqlQuery};
+use diesel::result::Error as DieselError;
+use diesel::sql_types::{Array, BigInt, Bool, Integer, Nullable, Text, Timestamptz};
+use log::warn;
+use todo_shared::{ChecklistItem, SortOrder, TagFilter, TodoSortField};
+
+/// How the writes completing a todo item treat its incomplete subtasks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SubtaskRule {
+    /// Complete the todo item regardless of its subtasks
+    #[default]
+    Ignore,
+
+    /// Complete the incomplete subtasks, and their subtasks, along with the todo item
+    Cascade,
+
+    /// Refuse to complete the todo item while any of its subtasks, or their subtasks, is incomplete
+    RequireDone,
+}
+
+impl SubtaskRule {
+    /// Returns the rule configured by `CASCADE_COMPLETE_SUBTASKS` and `REQUIRE_SUBTASKS_DONE`,
+    /// cascading takes precedence when both are set.
+    pub fn from_settings(settings: &Settings) -> Self {
+        match (
+            settings.cascade_complete_subtasks,
+            settings.require_subtasks_done,
+        ) {
+            (true, _) => SubtaskRule::Cascade,
+            (false, true) => SubtaskRule::RequireDone,
+            (false, false) => SubtaskRule::Ignore,
+        }
+    }
+}
+
+/// A todo item changed by a write, along with the subtasks the write completed.
+pub struct Changed {
+    /// The todo item before the write
+    pub previous: TodoEntity,
+
+    /// The todo item after the write
+    pub updated: TodoEntity,
+
+    /// The identifiers of the subtasks completed along with the todo item, see `SubtaskRule`
+    pub completed_subtasks: Vec<Uuid>,
+}
+
+/// The outcome of applying a list of changes, see `TodoRepository::apply_changes`.
+pub struct AppliedChanges {
+    /// The resulting state for every change, `None` for deletes
+    pub items: Vec<Option<TodoEntity>>,
+
+    /// The identifiers of the subtasks completed along with the todo items, see `SubtaskRule`
+    pub completed_subtasks: Vec<Uuid>,
+}
+
+// The todo item counts of a single tag, as grouped by `tag_stats`.
+#[derive(QueryableByName)]
+struct TagStatsRow {
+    #[diesel(sql_type = Text)]
+    tag: String,
+    #[diesel(sql_type = BigInt)]
+    total_items: i64,
+    #[diesel(sql_type = BigInt)]
+    completed_items: i64,
+    #[diesel(sql_type = BigInt)]
+    overdue_items: i64,
+}
+
+// Whether any of the checked titles is shared by several live todo items, see `duplicate_titles`.
+#[derive(QueryableByName)]
+pub(super) struct DuplicateTitles {
+    #[diesel(sql_type = Bool)]
+    pub(super) duplicated: bool,
+}
+
+// The number of incomplete subtasks of a todo item, as counted by `incomplete_subtasks`.
+#[derive(QueryableByName)]
+pub(super) struct IncompleteSubtasks {
+    #[diesel(sql_type = BigInt)]
+    pub(super) incomplete: i64,
+}
+
+// A subtask completed by `cascade_completion`.
+#[derive(QueryableByName)]
+pub(super) struct CompletedSubtask {
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub(super) completed_id: Uuid,
+}
+
+// The total of a minutes column, NULL when no row has a value. Diesel 2.2 exports its own `sum`
+// ambiguously from `dsl`, along with the helper type of the same name.
+define_sql_function! {
+    #[aggregate]
+    #[sql_name = "sum"]
+    fn sum_minutes(x: Nullable<Integer>) -> Nullable<BigInt>;
+}
+
+/// The completion flag and due date of a todo item.
+pub type StatusFields = (bool, Option<DateTime<Utc>>);
+
+/// The filters and order of a list of todo items, which lists all live todo items by creation
+/// time by default.
+#[derive(Clone, Debug, Default)]
+pub struct ListQuery {
+    /// Only list the todo items that are (not) completed
+    pub completed: Option<bool>,
+
+    /// Only list the todo items with the given tag, or with any tag in the given namespace
+    pub tag: Option<TagFilter>,
+
+    /// List the soft-deleted todo items as well
+    pub include_deleted: bool,
+
+    /// The field to sort the todo items by, their creation time when absent
+    pub sort: Option<TodoSortField>,
+
+    /// The direction to sort the todo items in
+    pub order: SortOrder,
+}
+
+impl ListQuery {
+    /// Indicates whether all live todo items are listed.
+    pub fn is_unfiltered(&self) -> bool {
+        self.completed.is_none() && self.tag.is_none() && !self.include_deleted
+    }
+}
+
+/// The todo specific queries running on the non-blocking connections, like the generic operations
+/// of `AsyncRepository<TodoEntity>`.
+#[async_trait]
+pub trait AsyncTodoRepository: Send + Sync {
+    /// Soft-deletes a todo item, keeping it as a tombstone until it is purged.
+    ///
+    /// Returns `false` when `only_if_completed` is set and the todo item is not completed, or
+    /// `RepositoryError::NotFound` when no (undeleted) todo item exists with the given id.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The identifier of the item to delete.
+    ///  * `only_if_completed` - Only delete the todo item when it is completed.
+    ///  * `at` - The point in time the todo item is deleted at.
+    async fn soft_delete(
+        &self,
+        id: Uuid,
+        only_if_completed: bool,
+        at: DateTime<Utc>,
+    ) -> Result<bool, RepositoryError>;
+
+    /// Returns a single todo item based on the given id, even when it has been soft-deleted.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The identifier of the item to find in the data store.
+    async fn get_by_id_including_deleted(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<TodoEntity>, RepositoryError>;
+
+    /// Returns all dependencies between todo items.
+    async fn get_dependencies(&self) -> Result<Vec<TodoDependencyEntity>, RepositoryError>;
+}
+
+/// Todo specific queries on top of the generic `AsyncRepository<TodoEntity>` and the non-blocking
+/// `AsyncTodoRepository`.
+///
+/// Unlike those, these queries block, so handlers run them with `telemetry::block`. They hold row
+/// and title locks across several statements or run recursive SQL, and haven't been moved to the
+/// non-blocking connections yet. A handler runs all of its queries on the same kind of
+/// connections, so a request never waits for both pools.
+///
+/// When titles have to be unique, the generic and todo specific writes fail with
+/// `RepositoryError::DuplicateTitle` when they would leave another live todo item with the same
+/// title, ignoring case.
+///
+/// Likewise, every write completing a todo item treats its subtasks according to the configured
+/// `SubtaskRule`, within the same transaction. With `SubtaskRule::RequireDone` the write fails with
+/// `RepositoryError::SubtasksIncomplete` while any of them is incomplete.
+pub trait TodoRepository: AsyncRepository<TodoEntity, Key = Uuid> + AsyncTodoRepository {
+    /// Returns only the completion flag and due date of a todo item, used to derive its status.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The identifier of the item to find in the data store.
+    fn get_status_fields(&self, id: Uuid) -> Result<Option<StatusFields>, RepositoryError>;
+
+    /// Returns the todo items matching the given filters, in the given order.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `listing` - The filters and order of the todo items to return.
+    ///  * `page` - The number of items to skip and the maximum number of items to return, all items when absent.
+    fn get_listed(
+        &self,
+        listing: &ListQuery,
+        page: Option<(i64, i64)>,
+    ) -> Result<Vec<TodoEntity>, RepositoryError>;
+
+    /// Returns the number of todo items matching the given filters.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `listing` - The filters of the todo items to count.
+    fn count_listed(&self, listing: &ListQuery) -> Result<i64, RepositoryError>;
+
+    /// Returns a page of the todo items after the given cursor, ordered by creation time and id.
+    ///
+    /// Unlike an offset, the cursor doesn't skip or repeat todo items when earlier ones are
+    /// created or deleted in the meantime, and doesn't get slower for later pages.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `after` - The position after the last todo item of the previous page, the first page when absent.
+    ///  * `limit` - The maximum number of items to return.
+    fn get_after(
+        &self,
+        after: Option<Cursor>,
+        limit: i64,
+    ) -> Result<Vec<TodoEntity>, RepositoryError>;
+
+    /// Returns the total number of todo items.
+    fn count(&self) -> Result<i64, RepositoryError>;
+
+    /// Estimates the number of todo items from the table statistics, without scanning the table.
+    ///
+    /// The estimate includes the soft-deleted todo items and is only as recent as the last
+    /// `ANALYZE` or vacuum. Falls back to an exact count when the table was never analyzed.
+    fn estimated_count(&self) -> Result<i64, RepositoryError>;
+
+    /// Counts the total, completed and overdue todo items per tag, in a single grouped query.
+    ///
+    /// Returns `(tag, total, completed, overdue)` for each of the given tags with any todo items.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `requested` - The tags to count the todo items of.
+    ///  * `overdue_before` - Incomplete todo items due before this point in time are overdue.
+    fn tag_stats(
+        &self,
+        requested: &[String],
+        overdue_before: DateTime<Utc>,
+    ) -> Result<Vec<(String, i64, i64, i64)>, RepositoryError>;
+
+    /// Permanently deletes the todo items that were soft-deleted before the given point in time,
+    /// returning the ids of the purged todo items.
+    fn purge_deleted(&self, before: DateTime<Utc>) -> Result<Vec<Uuid>, RepositoryError>;
+
+    /// Brings a soft-deleted todo item back, returning the restored todo item.
+    ///
+    /// Restoring a todo item that isn't deleted returns it unchanged, `None` is returned when no
+    /// todo item exists with the given id, e.g. because it has been purged.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The identifier of the item to restore.
+    fn restore(&self, id: Uuid) -> Result<Option<TodoEntity>, RepositoryError>;
+
+    /// Returns the todo items whose title or description contains the given term, newest first.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `term` - The (case-insensitive) term to search for.
+    fn search(&self, term: &str) -> Result<Vec<TodoEntity>, RepositoryError>;
+
+    /// Returns a page of the todo items whose title or description contains the given term.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `term` - The (case-insensitive) term to search for.
+    ///  * `offset` - The number of matching items to skip.
+    ///  * `limit` - The maximum number of matching items to return.
+    fn search_paged(
+        &self,
+        term: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<TodoEntity>, RepositoryError>;
+
+    /// Returns the total number of todo items whose title or description contains the given term.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `term` - The (case-insensitive) term to search for.
+    fn search_count(&self, term: &str) -> Result<i64, RepositoryError>;
+
+    /// Returns the todo items to export, ordered by creation time.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `shard` - When given, only the todo items in this partition are returned.
+    fn export(&self, shard: Option<Shard>) -> Result<Vec<TodoEntity>, RepositoryError>;
+
+    /// Returns the number of completed todo items with their total estimated and actual effort.
+    fn effort_summary(&self) -> Result<(i64, i64, i64), RepositoryError>;
+
+    /// Returns the number of todo items, the number of completed ones and the number of those
+    /// completed since the given time.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `completed_since` - The start of the period to count the recently completed todo items in.
+    fn stats(&self, completed_since: DateTime<Utc>) -> Result<(i64, i64, i64), RepositoryError>;
+
+    /// Returns the open todo items whose reminder is due at the given time and wasn't sent yet.
+    ///
+    /// The reminder of a todo item is due `remind_before_minutes` before its due date.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `now` - The point in time to compare the reminder times with.
+    fn due_for_reminder(&self, now: DateTime<Utc>) -> Result<Vec<TodoEntity>, RepositoryError>;
+
+    /// Records that the reminder of a todo item was sent, returns `false` when it already was.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The unique identifier of the todo item.
+    ///  * `at` - The point in time the reminder was sent.
+    fn mark_reminded(&self, id: Uuid, at: DateTime<Utc>) -> Result<bool, RepositoryError>;
+
+    /// Reassigns all todo items of one owner to another owner, recording every reassignment in
+    /// the history.
+    ///
+    /// Returns the number of reassigned todo items.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `from` - The subject of the current owner.
+    ///  * `to` - The subject of the new owner.
+    ///  * `actor` - The subject of the user reassigning the todo items.
+    fn reassign_owner(&self, from: &str, to: &str, actor: &str) -> Result<i64, RepositoryError>;
+
+    /// Marks a todo item as completed or not completed, setting or clearing its completion time.
+    ///
+    /// Returns `None` when no todo item exists with the given id.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The unique identifier of the todo item.
+    ///  * `completed` - Indicates whether the todo item is completed.
+    fn set_completed(
+        &self,
+        id: Uuid,
+        completed: bool,
+    ) -> Result<Option<TodoEntity>, RepositoryError>;
+
+    /// Marks a todo item as completed, treating its subtasks according to the configured rule,
+    /// even when it already is completed.
+    ///
+    /// Returns `None` when no todo item exists with the given id.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The unique identifier of the todo item.
+    fn complete(&self, id: Uuid) -> Result<Option<Changed>, RepositoryError>;
+
+    /// Appends an item, which is not done yet, to the checklist of a todo item.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The unique identifier of the todo item.
+    ///  * `text` - The text of the checklist item.
+    fn add_checklist_item(&self, id: Uuid, text: String) -> Result<TodoEntity, RepositoryError>;
+
+    /// Toggles whether the checklist item at the given index is done.
+    ///
+    /// Returns `RepositoryError::NotFound` when the todo item or checklist item doesn't exist.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The unique identifier of the todo item.
+    ///  * `index` - The (0-based) position of the item in the checklist.
+    fn toggle_checklist_item(&self, id: Uuid, index: usize) -> Result<TodoEntity, RepositoryError>;
+
+    /// Adds a tag to a todo item, adding a tag it already has is a no-op.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The unique identifier of the todo item.
+    ///  * `tag` - The (validated) tag to add.
+    fn add_tag(&self, id: Uuid, tag: String) -> Result<TodoEntity, RepositoryError>;
+
+    /// Removes a tag from a todo item.
+    ///
+    /// Returns `RepositoryError::NotFound` when the todo item doesn't exist or doesn't have the tag.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The unique identifier of the todo item.
+    ///  * `tag` - The tag to remove.
+    fn remove_tag(&self, id: Uuid, tag: &str) -> Result<TodoEntity, RepositoryError>;
+
+    /// Adds a dependency, unless it would create a cycle in the dependency graph.
+    ///
+    /// Returns `false` when the dependency would create a cycle and `RepositoryError::NotFound`
+    /// when either todo item doesn't exist. Adding an existing dependency is a no-op.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The unique identifier of the todo item that is blocked.
+    ///  * `blocked_by` - The unique identifier of the todo item that has to be done first.
+    fn add_dependency(&self, id: Uuid, blocked_by: Uuid) -> Result<bool, RepositoryError>;
+
+    /// Removes a dependency, returns `false` when it doesn't exist.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The unique identifier of the todo item that is blocked.
+    ///  * `blocked_by` - The unique identifier of the todo item that has to be done first.
+    fn remove_dependency(&self, id: Uuid, blocked_by: Uuid) -> Result<bool, RepositoryError>;
+
+    /// Records changes of todo items in the history, all or none of them.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `entries` - The history entries describing the changes.
+    fn record_history(&self, entries: Vec<TodoHistoryEntity>) -> Result<(), RepositoryError>;
+
+    /// Returns a page of the history, newest first.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `actor` - When given, only the changes made by this user are returned.
+    ///  * `offset` - The number of entries to skip.
+    ///  * `limit` - The maximum number of entries to return.
+    fn activity(
+        &self,
+        actor: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<TodoHistoryEntity>, RepositoryError>;
+
+    /// Returns the total number of history entries.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `actor` - When given, only the changes made by this user are counted.
+    fn activity_count(&self, actor: Option<&str>) -> Result<i64, RepositoryError>;
+
+    /// Updates a todo item within a transaction, returning both its previous and updated state
+    /// along with the subtasks completed with it.
+    ///
+    /// Returns `None` when no todo item exists with the given id, and fails with
+    /// `RepositoryError::Conflict` when it was changed since the version the entity is based on.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The unique identifier of the entity to update
+    ///  * `entity` - An updated version of the entity with the latest values.
+    fn update_with_previous(
+        &self,
+        id: Uuid,
+        entity: TodoEntity,
+    ) -> Result<Option<Changed>, RepositoryError>;
+
+    /// Changes only the given columns of a todo item within a transaction, leaving the others
+    /// untouched, returning both its previous and patched state along with the subtasks completed
+    /// with it.
+    ///
+    /// Returns `None` when no todo item exists with the given id, and fails with
+    /// `RepositoryError::Conflict` when it doesn't have the expected version.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `id` - The unique identifier of the entity to patch
+    ///  * `patch` - The columns to change.
+    ///  * `expected_version` - The version the patch is based on, not checked when `None`.
+    fn patch_with_previous(
+        &self,
+        id: Uuid,
+        patch: TodoPatch,
+        expected_version: Option<i32>,
+    ) -> Result<Option<Changed>, RepositoryError>;
+
+    /// Applies the given changes in order within a single transaction.
+    ///
+    /// Returns the resulting state for every change (`None` for deletes). If any change fails,
+    /// none of the changes are persisted and an error with the index of the failing change is
+    /// returned.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `changes` - The changes to apply.
+    fn apply_changes(&self, changes: Vec<TodoChange>) -> Result<AppliedChanges, ChangeError>;
+}
+
+// Filter on the todos table, selecting the items matching a search term.
+type SearchFilter = Box<dyn BoxableExpression<todos::table, Pg, SqlType = Bool>>;
+
+// Makes sure the fallback to the plain search is only logged once.
+static SEARCH_FALLBACK_LOGGED: AtomicBool = AtomicBool::new(false);
 
 pub struct TodoEntityRepository {
-    db_context: db_context::PostgresPool,
+    db_context: db_context::DbContext,
+
+    // The non-blocking connections of the generic operations, see `async_todo_repository`
+    pub(super) async_db_context: db_context::AsyncDbContext,
+
+    // Search using trigram similarity instead of plain substring matching
+    fuzzy_search: bool,
+
+    // Reject writes leaving several live todo items with the same title, ignoring case
+    unique_titles: bool,
+
+    // How the writes completing a todo item treat its incomplete subtasks
+    pub(super) subtask_rule: SubtaskRule,
 }
 
 impl TodoEntityRepository {
-    pub fn new() -> Self {
+    /// Creates the repository, following the search, title and subtask rules of the settings.
+    pub fn new(settings: &Settings) -> Self {
         TodoEntityRepository {
-            db_context: db_context::get_pool(),
+            db_context: db_context::DbContext::new(db_context::get_pool()),
+            async_db_context: db_context::AsyncDbContext::new(db_context::get_async_pool()),
+            fuzzy_search: settings.fuzzy_search,
+            unique_titles: settings.unique_titles,
+            subtask_rule: SubtaskRule::from_settings(settings),
+        }
+    }
+
+    // Returns the written titles to check for duplicates, none when titles don't have to be unique.
+    pub(super) fn checked_titles(&self, written: impl IntoIterator<Item = String>) -> Vec<String> {
+        match self.unique_titles {
+            true => written.into_iter().collect(),
+            false => Vec::new(),
+        }
+    }
+
+    // Runs a write of todo items with the given titles within the current transaction, failing
+    // with `DuplicateTitle` when it leaves several live todo items with one of these titles. See
+    // `lock_titles` for how concurrent writes are kept apart.
+    fn write_unique_titles<T>(
+        &self,
+        connection: &mut PgConnection,
+        written_titles: Vec<String>,
+        write: impl FnOnce(&mut PgConnection) -> Result<T, RepositoryError>,
+    ) -> Result<T, RepositoryError> {
+        let written_titles = self.checked_titles(written_titles);
+        if written_titles.is_empty() {
+            return write(connection);
         }
+
+        lock_titles(written_titles.clone()).execute(connection)?;
+        let written = write(connection)?;
+        let check = duplicate_titles(written_titles).get_result::<DuplicateTitles>(connection)?;
+        match check.duplicated {
+            true => Err(RepositoryError::DuplicateTitle),
+            false => Ok(written),
+        }
+    }
+
+    // Treats the subtasks of a todo item completed within the current transaction according to
+    // the subtask rule, returning the subtasks completed along with it.
+    fn complete_subtasks(
+        &self,
+        connection: &mut PgConnection,
+        todo_id: Uuid,
+        completion_time: DateTime<Utc>,
+    ) -> Result<Vec<Uuid>, RepositoryError> {
+        match self.subtask_rule {
+            SubtaskRule::Ignore => Ok(Vec::new()),
+            SubtaskRule::Cascade => Ok(cascade_completion(todo_id, completion_time)
+                .load::<CompletedSubtask>(connection)?
+                .into_iter()
+                .map(|subtask| subtask.completed_id)
+                .collect()),
+            SubtaskRule::RequireDone => {
+                let check =
+                    incomplete_subtasks(todo_id).get_result::<IncompleteSubtasks>(connection)?;
+                match check.incomplete {
+                    0 => Ok(Vec::new()),
+                    count => Err(RepositoryError::SubtasksIncomplete(count)),
+                }
+            }
+        }
+    }
+
+    // Completes the subtasks of a todo item the write within the current transaction completed,
+    // writes that leave it as it was don't touch them.
+    fn changed(
+        &self,
+        connection: &mut PgConnection,
+        previous: TodoEntity,
+        updated: TodoEntity,
+    ) -> Result<Changed, RepositoryError> {
+        let completed_subtasks = match (previous.completed, updated.completed) {
+            (false, true) => self.complete_subtasks(
+                connection,
+                updated.id,
+                updated.completed_at.unwrap_or(updated.updated_at),
+            )?,
+            _ => Vec::new(),
+        };
+        Ok(Changed {
+            previous,
+            updated,
+            completed_subtasks,
+        })
+    }
+
+    /// Returns the connection pool of the repository, which shares its connections.
+    pub fn pool(&self) -> db_context::PostgresPool {
+        self.db_context.pool().clone()
     }
 }
 
-impl Repository<TodoEntity> for TodoEntityRepository {
-    fn get_all(&self) -> Vec<TodoEntity> {
-        let mut connection = self.db_context.get().unwrap();
+impl TodoRepository for TodoEntityRepository {
+    fn get_status_fields(&self, todo_id: Uuid) -> Result<Option<StatusFields>, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
         todos
+            .find(todo_id)
+            .filter(deleted_at.is_null())
+            .select((completed, due_date))
+            .first::<(bool, Option<DateTime<Utc>>)>(&mut connection)
+            .optional()
+            .map_err(RepositoryError::from)
+    }
+
+    fn count_listed(&self, listing: &ListQuery) -> Result<i64, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        listed(listing)
+            .count()
+            .get_result(&mut connection)
+            .map_err(RepositoryError::from)
+    }
+
+    fn get_after(
+        &self,
+        after: Option<Cursor>,
+        limit: i64,
+    ) -> Result<Vec<TodoEntity>, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        let mut query = todos
+            .filter(deleted_at.is_null())
+            .order((created_at.asc(), id.asc()))
+            .limit(limit)
+            .into_boxed();
+        if let Some(cursor) = after {
+            query = query.filter(
+                created_at
+                    .gt(cursor.created_at)
+                    .or(created_at.eq(cursor.created_at).and(id.gt(cursor.id))),
+            );
+        }
+        query
             .load::<TodoEntity>(&mut connection)
-            .expect("Error loading todo items")
+            .map_err(RepositoryError::from)
     }
 
-    fn get_by_id(&self, todo_id: Uuid) -> Option<TodoEntity> {
-        let mut connection = self.db_context.get().unwrap();
-        let item = todos.find(todo_id).first(&mut connection);
-        if item.is_ok() {
-            Some(item.unwrap())
-        } else {
-            None
+    fn count(&self) -> Result<i64, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        todos
+            .filter(deleted_at.is_null())
+            .count()
+            .get_result(&mut connection)
+            .map_err(RepositoryError::from)
+    }
+
+    fn estimated_count(&self) -> Result<i64, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        let estimate = diesel::select(sql::<BigInt>(
+            "(SELECT reltuples::bigint FROM pg_class WHERE oid = 'todos'::regclass)",
+        ))
+        .get_result::<i64>(&mut connection)?;
+        // Postgres reports -1 until the table has been analyzed for the first time
+        match estimate {
+            estimate if estimate >= 0 => Ok(estimate),
+            _ => self.count(),
         }
     }
 
-    fn insert<'a>(&self, entity: TodoEntity) -> Result<TodoEntity, String> {
-        let mut connection = self.db_context.get().unwrap();
-        let result = diesel::insert_into(todos::table)
-            .values(entity)
-            .get_result::<TodoEntity>(&mut connection)
-            .expect("Unable to insert todo item into database");
-        Ok(result)
+    fn get_listed(
+        &self,
+        listing: &ListQuery,
+        page: Option<(i64, i64)>,
+    ) -> Result<Vec<TodoEntity>, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        let query = listed(listing);
+        let sort = listing.sort.unwrap_or(TodoSortField::CreatedAt);
+        let mut query = match (sort, listing.order) {
+            (TodoSortField::CreatedAt, SortOrder::Asc) => query.order(created_at.asc()),
+            (TodoSortField::CreatedAt, SortOrder::Desc) => query.order(created_at.desc()),
+            (TodoSortField::Title, SortOrder::Asc) => query.order(title.asc()),
+            (TodoSortField::Title, SortOrder::Desc) => query.order(title.desc()),
+            // Open todo items have no completion time, list them last in both directions
+            (TodoSortField::CompletedAt, SortOrder::Asc) => {
+                query.order(completed_at.asc().nulls_last())
+            }
+            (TodoSortField::CompletedAt, SortOrder::Desc) => {
+                query.order(completed_at.desc().nulls_last())
+            }
+        }
+        // Keep the order of equal values stable between requests and pages
+        .then_order_by(id.asc());
+        if let Some((offset, limit)) = page {
+            query = query.offset(offset).limit(limit);
+        }
+        query
+            .load::<TodoEntity>(&mut connection)
+            .map_err(RepositoryError::from)
+    }
+
+    fn tag_stats(
+        &self,
+        requested: &[String],
+        overdue_before: DateTime<Utc>,
+    ) -> Result<Vec<(String, i64, i64, i64)>, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        // The overlap with the GIN indexed tags narrows the todo items down before unnesting them
+        let rows = diesel::sql_query(
+            "SELECT tag, COUNT(*) AS total_items, \
+                COUNT(*) FILTER (WHERE completed) AS completed_items, \
+                COUNT(*) FILTER (WHERE NOT completed AND due_date < $2) AS overdue_items \
+             FROM todos, unnest(tags) AS tag \
+             WHERE deleted_at IS NULL AND tags && $1 AND tag = ANY($1) \
+             GROUP BY tag",
+        )
+        .bind::<Array<Text>, _>(requested)
+        .bind::<Timestamptz, _>(overdue_before)
+        .load::<TagStatsRow>(&mut connection)?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    row.tag,
+                    row.total_items,
+                    row.completed_items,
+                    row.overdue_items,
+                )
+            })
+            .collect())
+    }
+
+    fn purge_deleted(&self, before: DateTime<Utc>) -> Result<Vec<Uuid>, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        let purged = diesel::delete(todos.filter(deleted_at.lt(before)))
+            .returning(id)
+            .get_results(&mut connection)?;
+        Ok(purged)
+    }
+
+    fn restore(&self, todo_id: Uuid) -> Result<Option<TodoEntity>, RepositoryError> {
+        self.db_context.transaction(|connection| {
+            let existing = todos
+                .find(todo_id)
+                .for_update()
+                .first::<TodoEntity>(connection)
+                .optional()?;
+            let existing = match existing {
+                Some(existing) if existing.deleted_at.is_some() => existing,
+                existing => return Ok(existing),
+            };
+
+            // Another todo item may have taken the title while this one was deleted
+            self.write_unique_titles(connection, vec![existing.title], |connection| {
+                Ok(diesel::update(todos.find(todo_id))
+                    .set((
+                        deleted_at.eq(None::<DateTime<Utc>>),
+                        updated_at.eq(Utc::now()),
+                        version.eq(version + 1),
+                    ))
+                    .get_result::<TodoEntity>(connection)?)
+            })
+            .map(Some)
+        })
     }
 
-    fn update(&self, todo_id: Uuid, entity: TodoEntity) -> Result<TodoEntity, String> {
-        let mut connection = self.db_context.get().unwrap();
-        let todo_item = diesel::update(todos.find(todo_id))
+    fn search(&self, term: &str) -> Result<Vec<TodoEntity>, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        with_search_fallback(self.fuzzy_search, |fuzzy| {
+            todos
+                .filter(deleted_at.is_null())
+                .filter(search_filter(term, fuzzy))
+                .order(created_at.desc())
+                .load::<TodoEntity>(&mut connection)
+        })
+        .map_err(RepositoryError::from)
+    }
+
+    fn search_paged(
+        &self,
+        term: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<TodoEntity>, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        with_search_fallback(self.fuzzy_search, |fuzzy| {
+            todos
+                .filter(deleted_at.is_null())
+                .filter(search_filter(term, fuzzy))
+                .order(created_at.asc())
+                .offset(offset)
+                .limit(limit)
+                .load::<TodoEntity>(&mut connection)
+        })
+        .map_err(RepositoryError::from)
+    }
+
+    fn search_count(&self, term: &str) -> Result<i64, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        with_search_fallback(self.fuzzy_search, |fuzzy| {
+            todos
+                .filter(deleted_at.is_null())
+                .filter(search_filter(term, fuzzy))
+                .count()
+                .get_result(&mut connection)
+        })
+        .map_err(RepositoryError::from)
+    }
+
+    fn export(&self, shard: Option<Shard>) -> Result<Vec<TodoEntity>, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        let mut query = todos
+            .filter(deleted_at.is_null())
+            .order(created_at.asc())
+            .into_boxed();
+        if let Some(shard) = shard {
+            // hashtext can be negative, so the absolute remainder is used to select the shard.
+            query = query.filter(
+                sql::<diesel::sql_types::Bool>("abs(hashtext(id::text) % ")
+                    .bind::<diesel::sql_types::Integer, _>(shard.count)
+                    .sql(") = ")
+                    .bind::<diesel::sql_types::Integer, _>(shard.remainder()),
+            );
+        }
+        query
+            .load::<TodoEntity>(&mut connection)
+            .map_err(RepositoryError::from)
+    }
+
+    fn effort_summary(&self) -> Result<(i64, i64, i64), RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        let (count, estimated, actual) = todos
+            .filter(deleted_at.is_null())
+            .filter(completed.eq(true))
+            .select((
+                count_star(),
+                sum_minutes(estimated_minutes),
+                sum_minutes(actual_minutes),
+            ))
+            .first::<(i64, Option<i64>, Option<i64>)>(&mut connection)?;
+        Ok((count, estimated.unwrap_or(0), actual.unwrap_or(0)))
+    }
+
+    fn stats(&self, completed_since: DateTime<Utc>) -> Result<(i64, i64, i64), RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        let live = || todos.filter(deleted_at.is_null());
+        let total = live().count().get_result(&mut connection)?;
+        let done = live()
+            .filter(completed.eq(true))
+            .count()
+            .get_result(&mut connection)?;
+        let recently_done = live()
+            .filter(completed.eq(true))
+            .filter(completed_at.ge(completed_since))
+            .count()
+            .get_result(&mut connection)?;
+        Ok((total, done, recently_done))
+    }
+
+    fn due_for_reminder(&self, now: DateTime<Utc>) -> Result<Vec<TodoEntity>, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        todos
+            .filter(deleted_at.is_null())
+            .filter(completed.eq(false))
+            .filter(reminded_at.is_null())
+            .filter(due_date.is_not_null())
+            .filter(
+                sql::<Bool>(
+                    "due_date - make_interval(mins => COALESCE(remind_before_minutes, 0)) <= ",
+                )
+                .bind::<Timestamptz, _>(now),
+            )
+            .load::<TodoEntity>(&mut connection)
+            .map_err(RepositoryError::from)
+    }
+
+    fn mark_reminded(&self, todo_id: Uuid, at: DateTime<Utc>) -> Result<bool, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        diesel::update(todos.find(todo_id).filter(reminded_at.is_null()))
+            .set(reminded_at.eq(at))
+            .execute(&mut connection)
+            .map(|count| count > 0)
+            .map_err(RepositoryError::from)
+    }
+
+    fn reassign_owner(&self, from: &str, to: &str, actor: &str) -> Result<i64, RepositoryError> {
+        self.db_context.transaction(|connection| {
+            let reassigned = diesel::update(todos.filter(owner.eq(from)))
+                .set((
+                    owner.eq(to),
+                    updated_at.eq(Utc::now()),
+                    version.eq(version + 1),
+                ))
+                .returning(id)
+                .get_results::<Uuid>(connection)?;
+
+            let details = format!("Reassigned from {} to {}", from, to);
+            let entries: Vec<TodoHistoryEntity> = reassigned
+                .iter()
+                .map(|todo_id| {
+                    TodoHistoryEntity::new(*todo_id, actor, "reassign", Some(details.clone()))
+                })
+                .collect();
+            diesel::insert_into(todo_history::table)
+                .values(&entries)
+                .execute(connection)
+                .map(|_| reassigned.len() as i64)
+        })
+    }
+
+    fn set_completed(
+        &self,
+        todo_id: Uuid,
+        is_completed: bool,
+    ) -> Result<Option<TodoEntity>, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        let now = Utc::now();
+        diesel::update(todos.find(todo_id).filter(deleted_at.is_null()))
             .set((
-                completed_at.eq(entity.completed_at),
-                completed.eq(entity.completed),
-                title.eq(entity.title),
-                description.eq(entity.description),
+                completed.eq(is_completed),
+                completed_at.eq(is_completed.then_some(now)),
+                updated_at.eq(now),
+                version.eq(version + 1),
             ))
             .get_result::<TodoEntity>(&mut connection)
-            .expect("Unable to update todo entity");
+            .optional()
+            .map_err(RepositoryError::from)
+    }
+
+    fn complete(&self, todo_id: Uuid) -> Result<Option<Changed>, RepositoryError> {
+        self.db_context.transaction(|connection| {
+            // Lock the todo item, so its subtasks can't be reopened while it completes
+            let previous = match todos
+                .find(todo_id)
+                .filter(deleted_at.is_null())
+                .for_update()
+                .first::<TodoEntity>(connection)
+                .optional()?
+            {
+                Some(previous) => previous,
+                None => return Ok(None),
+            };
 
-        Ok(todo_item)
+            let now = Utc::now();
+            let updated = diesel::update(todos.find(todo_id))
+                .set((
+                    completed.eq(true),
+                    completed_at.eq(Some(now)),
+                    updated_at.eq(now),
+                    version.eq(version + 1),
+                ))
+                .get_result::<TodoEntity>(connection)?;
+            let completed_subtasks = self.complete_subtasks(connection, todo_id, now)?;
+            Ok::<_, RepositoryError>(Some(Changed {
+                previous,
+                updated,
+                completed_subtasks,
+            }))
+        })
     }
 
-    fn delete(&self, todo_id: Uuid) -> Result<bool, String> {
-        let mut connection = self.db_context.get().unwrap();
-        let num_deleted = diesel::delete(todos.find(todo_id))
-            .execute(&mut connection)
-            .expect("Error deleting todo item with id {}");
+    fn add_checklist_item(
+        &self,
+        todo_id: Uuid,
+        text: String,
+    ) -> Result<TodoEntity, RepositoryError> {
+        self.db_context.transaction(|connection| {
+            modify_checklist(connection, todo_id, |items| {
+                items.push(ChecklistItem { text, done: false });
+                true
+            })
+        })
+    }
+
+    fn add_tag(&self, todo_id: Uuid, tag: String) -> Result<TodoEntity, RepositoryError> {
+        self.db_context.transaction(|connection| {
+            modify_tags(connection, todo_id, |item_tags| {
+                if !item_tags.contains(&tag) {
+                    item_tags.push(tag);
+                }
+                true
+            })
+        })
+    }
+
+    fn remove_tag(&self, todo_id: Uuid, tag: &str) -> Result<TodoEntity, RepositoryError> {
+        self.db_context.transaction(|connection| {
+            modify_tags(connection, todo_id, |item_tags| {
+                let count = item_tags.len();
+                item_tags.retain(|item_tag| item_tag != tag);
+                item_tags.len() < count
+            })
+        })
+    }
+
+    fn toggle_checklist_item(
+        &self,
+        todo_id: Uuid,
+        index: usize,
+    ) -> Result<TodoEntity, RepositoryError> {
+        self.db_context.transaction(|connection| {
+            modify_checklist(connection, todo_id, |items| match items.get_mut(index) {
+                Some(item) => {
+                    item.done = !item.done;
+                    true
+                }
+                None => false,
+            })
+        })
+    }
+
+    fn add_dependency(&self, todo_id: Uuid, blocked_by: Uuid) -> Result<bool, RepositoryError> {
+        self.db_context.transaction(|connection| {
+            // Serialize concurrent additions, which could close a cycle together
+            diesel::sql_query("LOCK TABLE todo_dependencies IN SHARE ROW EXCLUSIVE MODE")
+                .execute(connection)?;
+
+            let existing = todos
+                .filter(id.eq_any([todo_id, blocked_by]))
+                .filter(deleted_at.is_null())
+                .count()
+                .get_result::<i64>(connection)?;
+            let expected = if todo_id == blocked_by { 1 } else { 2 };
+            if existing < expected {
+                return Err(DieselError::NotFound);
+            }
+
+            let dependencies = todo_dependencies::table.load::<TodoDependencyEntity>(connection)?;
+            if creates_cycle(&dependencies, todo_id, blocked_by) {
+                return Ok(false);
+            }
+            diesel::insert_into(todo_dependencies::table)
+                .values(TodoDependencyEntity {
+                    todo_id,
+                    blocked_by,
+                })
+                .on_conflict_do_nothing()
+                .execute(connection)?;
+            Ok(true)
+        })
+    }
+
+    fn remove_dependency(&self, todo_id: Uuid, blocked_by: Uuid) -> Result<bool, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        let num_deleted = diesel::delete(todo_dependencies::table.find((todo_id, blocked_by)))
+            .execute(&mut connection)?;
         Ok(num_deleted > 0)
     }
+
+    fn record_history(&self, entries: Vec<TodoHistoryEntity>) -> Result<(), RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        diesel::insert_into(todo_history::table)
+            .values(entries)
+            .execute(&mut connection)?;
+        Ok(())
+    }
+
+    fn activity(
+        &self,
+        actor: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<TodoHistoryEntity>, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        let mut query = todo_history::table.into_boxed();
+        if let Some(actor) = actor {
+            query = query.filter(todo_history::actor.eq(actor));
+        }
+        query
+            .order((todo_history::created_at.desc(), todo_history::id.desc()))
+            .offset(offset)
+            .limit(limit)
+            .load::<TodoHistoryEntity>(&mut connection)
+            .map_err(RepositoryError::from)
+    }
+
+    fn activity_count(&self, actor: Option<&str>) -> Result<i64, RepositoryError> {
+        let mut connection = self.db_context.get_conn()?;
+        let mut query = todo_history::table.into_boxed();
+        if let Some(actor) = actor {
+            query = query.filter(todo_history::actor.eq(actor));
+        }
+        query
+            .count()
+            .get_result(&mut connection)
+            .map_err(RepositoryError::from)
+    }
+
+    fn update_with_previous(
+        &self,
+        todo_id: Uuid,
+        entity: TodoEntity,
+    ) -> Result<Option<Changed>, RepositoryError> {
+        self.db_context.transaction(|connection| {
+            // Lock the row, so the previous state can't change before we update it.
+            let previous = match todos
+                .find(todo_id)
+                .filter(deleted_at.is_null())
+                .for_update()
+                .first::<TodoEntity>(connection)
+                .optional()?
+            {
+                Some(previous) => previous,
+                None => return Ok(None),
+            };
+            if previous.version != entity.version {
+                return Err(RepositoryError::Conflict);
+            }
+
+            let updated =
+                self.write_unique_titles(connection, vec![entity.title.clone()], |connection| {
+                    Ok(update_entity(connection, todo_id, entity)?.1)
+                })?;
+            self.changed(connection, previous, updated).map(Some)
+        })
+    }
+
+    fn patch_with_previous(
+        &self,
+        todo_id: Uuid,
+        patch: TodoPatch,
+        expected_version: Option<i32>,
+    ) -> Result<Option<Changed>, RepositoryError> {
+        self.db_context.transaction(|connection| {
+            let item = todos.find(todo_id).filter(deleted_at.is_null());
+
+            // Lock the row, so the previous state can't change before we patch it.
+            let previous = match item
+                .for_update()
+                .first::<TodoEntity>(connection)
+                .optional()?
+            {
+                Some(previous) => previous,
+                None => return Ok(None),
+            };
+            if expected_version.is_some_and(|expected| expected != previous.version) {
+                return Err(RepositoryError::Conflict);
+            }
+
+            // Diesel refuses an update without changes, so the todo item stays as is.
+            if patch.is_empty() {
+                return self
+                    .changed(connection, previous.clone(), previous)
+                    .map(Some);
+            }
+            let reminder = rescheduled_reminder(
+                patch.due_date.unwrap_or(previous.due_date),
+                patch
+                    .remind_before_minutes
+                    .unwrap_or(previous.remind_before_minutes),
+            );
+            let patched_titles = patch.title.iter().cloned().collect();
+            let patched = self.write_unique_titles(connection, patched_titles, |connection| {
+                Ok(diesel::update(item)
+                    .set((
+                        &patch,
+                        reminded_at.eq(reminder),
+                        updated_at.eq(Utc::now()),
+                        version.eq(version + 1),
+                    ))
+                    .get_result::<TodoEntity>(connection)?)
+            })?;
+            self.changed(connection, previous, patched).map(Some)
+        })
+    }
+
+    fn apply_changes(&self, changes: Vec<TodoChange>) -> Result<AppliedChanges, ChangeError> {
+        let mut connection = self
+            .db_context
+            .get_conn()
+            .map_err(|error| ChangeError::new(0, error))?;
+
+        // Keep track of the change being applied, so we can tell which one failed.
+        let mut current = 0;
+        connection
+            .transaction(|connection| {
+                let mut applied = AppliedChanges {
+                    items: Vec::new(),
+                    completed_subtasks: Vec::new(),
+                };
+                for (index, change) in changes.into_iter().enumerate() {
+                    current = index;
+                    let item = match change {
+                        TodoChange::Create(entity) => Some(self.write_unique_titles(
+                            connection,
+                            vec![entity.title.clone()],
+                            |connection| Ok(insert_entity(connection, entity)?),
+                        )?),
+                        TodoChange::Update(todo_id, entity) => {
+                            let (previous, updated) = self.write_unique_titles(
+                                connection,
+                                vec![entity.title.clone()],
+                                |connection| Ok(update_entity(connection, todo_id, entity)?),
+                            )?;
+                            let changed = self.changed(connection, previous, updated)?;
+                            applied
+                                .completed_subtasks
+                                .extend(changed.completed_subtasks);
+                            Some(changed.updated)
+                        }
+                        TodoChange::Delete(todo_id) => match delete_entity(connection, todo_id)? {
+                            0 => return Err(RepositoryError::NotFound),
+                            _ => None,
+                        },
+                    };
+                    applied.items.push(item);
+                }
+                Ok(applied)
+            })
+            .map_err(|error| ChangeError::new(current, error))
+    }
+}
+
+// Takes a lock per title, ignoring case, held until the end of the transaction. Writes of the same
+// title wait for each other, so they can't both pass the check for duplicates before either is
+// committed. The titles are locked in order, so writes of several titles can't deadlock.
+pub(super) fn lock_titles(locked_titles: Vec<String>) -> BoxedSqlQuery<'static, Pg, SqlQuery> {
+    diesel::sql_query(
+        "SELECT pg_advisory_xact_lock(hashtext(locked.title)) \
+         FROM (SELECT DISTINCT lower(title) AS title FROM unnest($1) AS titles(title) \
+            ORDER BY 1) AS locked",
+    )
+    .into_boxed()
+    .bind::<Array<Text>, _>(locked_titles)
+}
+
+// Checks whether any of the given titles is shared by several live todo items, ignoring case.
+// Uses the `lower(title)` index.
+pub(super) fn duplicate_titles(
+    checked_titles: Vec<String>,
+) -> BoxedSqlQuery<'static, Pg, SqlQuery> {
+    diesel::sql_query(
+        "SELECT EXISTS (\
+            SELECT 1 FROM todos \
+            WHERE deleted_at IS NULL \
+                AND lower(title) IN (SELECT lower(checked.title) FROM unnest($1) AS checked(title)) \
+            GROUP BY lower(title) HAVING COUNT(*) > 1\
+         ) AS duplicated",
+    )
+    .into_boxed()
+    .bind::<Array<Text>, _>(checked_titles)
+}
+
+// Completes the incomplete live subtasks of a todo item, and their subtasks, returning their ids.
+pub(super) fn cascade_completion(
+    todo_id: Uuid,
+    completion_time: DateTime<Utc>,
+) -> BoxedSqlQuery<'static, Pg, SqlQuery> {
+    diesel::sql_query(
+        "WITH RECURSIVE subtasks AS ( \
+            SELECT id FROM todos WHERE parent_id = $1 AND deleted_at IS NULL \
+            UNION SELECT todos.id FROM todos \
+            JOIN subtasks ON todos.parent_id = subtasks.id \
+            WHERE todos.deleted_at IS NULL \
+         ) \
+         UPDATE todos \
+         SET completed = TRUE, completed_at = $2, updated_at = $2, version = version + 1 \
+         WHERE id IN (SELECT id FROM subtasks) AND NOT completed \
+         RETURNING id AS completed_id",
+    )
+    .into_boxed()
+    .bind::<diesel::sql_types::Uuid, _>(todo_id)
+    .bind::<Timestamptz, _>(completion_time)
+}
+
+// Counts the incomplete live subtasks of a todo item, and of their subtasks.
+pub(super) fn incomplete_subtasks(todo_id: Uuid) -> BoxedSqlQuery<'static, Pg, SqlQuery> {
+    diesel::sql_query(
+        "WITH RECURSIVE subtasks AS ( \
+            SELECT id, completed FROM todos WHERE parent_id = $1 AND deleted_at IS NULL \
+            UNION SELECT todos.id, todos.completed FROM todos \
+            JOIN subtasks ON todos.parent_id = subtasks.id \
+            WHERE todos.deleted_at IS NULL \
+         ) \
+         SELECT COUNT(*) AS incomplete FROM subtasks WHERE NOT completed",
+    )
+    .into_boxed()
+    .bind::<diesel::sql_types::Uuid, _>(todo_id)
+}
+
+// Selects the todo items matching the filters of a list.
+fn listed(listing: &ListQuery) -> todos::BoxedQuery<'static, Pg> {
+    let mut query = todos.into_boxed();
+    if !listing.include_deleted {
+        query = query.filter(deleted_at.is_null());
+    }
+    if let Some(value) = listing.completed {
+        query = query.filter(completed.eq(value));
+    }
+    // Both tag columns have a GIN index, so containment is an index lookup
+    match &listing.tag {
+        Some(TagFilter::Exact(tag)) => query.filter(tags.contains(vec![tag.clone()])),
+        Some(TagFilter::Namespace(namespace)) => {
+            query.filter(tag_namespaces.contains(vec![namespace.clone()]))
+        }
+        None => query,
+    }
+}
+
+// Selects the todo items whose title or description matches the given term.
+//
+// The fuzzy search compares trigrams, which requires the `pg_trgm` extension to be installed.
+fn search_filter(term: &str, fuzzy: bool) -> SearchFilter {
+    if fuzzy {
+        Box::new(
+            sql::<Bool>("")
+                .bind::<Text, _>(term.to_string())
+                .sql(" <% (title || ' ' || description)"),
+        )
+    } else {
+        let pattern = contains_pattern(term);
+        Box::new(title.ilike(pattern.clone()).or(description.ilike(pattern)))
+    }
+}
+
+// Runs a search query, falling back to the plain search when the fuzzy search is not available,
+// e.g. while the database is only partially migrated.
+fn with_search_fallback<T>(
+    fuzzy: bool,
+    mut query: impl FnMut(bool) -> QueryResult<T>,
+) -> QueryResult<T> {
+    match query(fuzzy) {
+        Err(error) if fuzzy && is_missing_search_index(&error) => {
+            if !SEARCH_FALLBACK_LOGGED.swap(true, Ordering::Relaxed) {
+                warn!(
+                    "Fuzzy search is unavailable, falling back to plain search: {}",
+                    error
+                );
+            }
+            query(false)
+        }
+        result => result,
+    }
+}
+
+// Indicates whether the error is caused by a missing column, function or operator.
+fn is_missing_search_index(error: &DieselError) -> bool {
+    match error {
+        DieselError::DatabaseError(_, information) => {
+            let message = information.message();
+            ["column ", "function ", "operator "]
+                .iter()
+                .any(|kind| message.starts_with(kind))
+                && message.contains("does not exist")
+        }
+        _ => false,
+    }
+}
+
+// Builds a LIKE pattern matching values containing the given term literally.
+fn contains_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn insert_entity(connection: &mut PgConnection, entity: TodoEntity) -> QueryResult<TodoEntity> {
+    diesel::insert_into(todos::table)
+        .values(entity)
+        .get_result::<TodoEntity>(connection)
+}
+
+// Updates a live todo item, returning both its previous and updated state.
+fn update_entity(
+    connection: &mut PgConnection,
+    todo_id: Uuid,
+    entity: TodoEntity,
+) -> QueryResult<(TodoEntity, TodoEntity)> {
+    let existing = todos
+        .find(todo_id)
+        .filter(deleted_at.is_null())
+        .for_update()
+        .first::<TodoEntity>(connection)?;
+
+    // The given entity is usually converted from a request, never let it change the
+    // identity or creation time of the existing row.
+    let entity = TodoEntity {
+        id: existing.id,
+        created_at: existing.created_at,
+        ..entity
+    };
+
+    let reminder = rescheduled_reminder(entity.due_date, entity.remind_before_minutes);
+    let updated = diesel::update(todos.find(entity.id))
+        .set((
+            TodoUpdate::from(entity),
+            reminded_at.eq(reminder),
+            updated_at.eq(Utc::now()),
+            version.eq(version + 1),
+        ))
+        .get_result::<TodoEntity>(connection)?;
+    Ok((existing, updated))
+}
+
+// Changes the checklist of a live todo item, locking the row so concurrent changes aren't lost.
+// Runs within a transaction, which holds the lock until the change is committed. The change
+// returns false when it doesn't apply, e.g. because the index is out of range.
+fn modify_checklist(
+    connection: &mut PgConnection,
+    todo_id: Uuid,
+    change: impl FnOnce(&mut Vec<ChecklistItem>) -> bool,
+) -> QueryResult<TodoEntity> {
+    let mut items = todos
+        .find(todo_id)
+        .filter(deleted_at.is_null())
+        .select(checklist)
+        .for_update()
+        .first::<Checklist>(connection)?;
+    if !change(&mut items.0) {
+        return Err(DieselError::NotFound);
+    }
+
+    diesel::update(todos.find(todo_id))
+        .set((
+            checklist.eq(items),
+            updated_at.eq(Utc::now()),
+            version.eq(version + 1),
+        ))
+        .get_result::<TodoEntity>(connection)
+}
+
+// Changes the tags of a live todo item along with their namespaces, locking the row so concurrent
+// changes aren't lost. Runs within a transaction, which holds the lock until the change is
+// committed. The change returns false when it doesn't apply, e.g. for a missing tag.
+fn modify_tags(
+    connection: &mut PgConnection,
+    todo_id: Uuid,
+    change: impl FnOnce(&mut Vec<String>) -> bool,
+) -> QueryResult<TodoEntity> {
+    let mut item_tags = todos
+        .find(todo_id)
+        .filter(deleted_at.is_null())
+        .select(tags)
+        .for_update()
+        .first::<Vec<String>>(connection)?;
+    if !change(&mut item_tags) {
+        return Err(DieselError::NotFound);
+    }
+
+    diesel::update(todos.find(todo_id))
+        .set((
+            tag_namespaces.eq(todo_shared::tag_namespaces(&item_tags)),
+            tags.eq(item_tags),
+            updated_at.eq(Utc::now()),
+            version.eq(version + 1),
+        ))
+        .get_result::<TodoEntity>(connection)
+}
+
+// Soft-deletes a todo item, it is kept as a tombstone until it is restored or purged.
+fn delete_entity(connection: &mut PgConnection, todo_id: Uuid) -> QueryResult<usize> {
+    let now = Utc::now();
+    diesel::update(todos.find(todo_id).filter(deleted_at.is_null()))
+        .set((
+            deleted_at.eq(now),
+            updated_at.eq(now),
+            version.eq(version + 1),
+        ))
+        .execute(connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::result::DatabaseErrorKind;
+
+    use super::*;
+
+    fn database_error(message: &str) -> DieselError {
+        DieselError::DatabaseError(DatabaseErrorKind::Unknown, Box::new(message.to_string()))
+    }
+
+    #[test]
+    fn test_search_fallback_on_missing_index() {
+        let mut attempts = Vec::new();
+        let result = with_search_fallback(true, |fuzzy| {
+            attempts.push(fuzzy);
+            match fuzzy {
+                true => Err(database_error("operator does not exist: text <% text")),
+                false => Ok(vec!["Use a mock repository"]),
+            }
+        });
+
+        assert_eq!(result, Ok(vec!["Use a mock repository"]));
+        assert_eq!(attempts, vec![true, false]);
+    }
+
+    #[test]
+    fn test_search_fallback_keeps_other_errors() {
+        let result: QueryResult<Vec<&str>> = with_search_fallback(true, |fuzzy| match fuzzy {
+            true => Err(database_error(
+                "canceling statement due to statement timeout",
+            )),
+            false => Ok(vec![]),
+        });
+
+        assert!(result.is_err());
+    }
 }
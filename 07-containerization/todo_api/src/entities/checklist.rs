@@ -0,0 +1,24 @@
+use diesel::deserialize::{self, FromSql};
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Jsonb;
+use todo_shared::ChecklistItem;
+
+/// The checklist embedded in a todo item, stored as a JSONB array.
+#[derive(AsExpression, FromSqlRow, Debug, Clone, Default, PartialEq)]
+#[diesel(sql_type = Jsonb)]
+pub struct Checklist(pub Vec<ChecklistItem>);
+
+impl FromSql<Jsonb, Pg> for Checklist {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let value = <serde_json::Value as FromSql<Jsonb, Pg>>::from_sql(bytes)?;
+        Ok(Checklist(serde_json::from_value(value)?))
+    }
+}
+
+impl ToSql<Jsonb, Pg> for Checklist {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let value = serde_json::to_value(&self.0)?;
+        <serde_json::Value as ToSql<Jsonb, Pg>>::to_sql(&value, &mut out.reborrow())
+    }
+}
@@ -1 +1,6 @@
+pub mod checklist;
+pub mod todo_change;
+pub mod todo_dependency_entity;
 pub mod todo_entity;
+pub mod todo_history_entity;
+pub mod todo_patch;
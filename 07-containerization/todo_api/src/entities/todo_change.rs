@@ -0,0 +1,127 @@
+use std::fmt;
+use todo_shared::{CreateTodoItemRequest, SyncOperation, SyncOperationKind, UpdateTodoItemRequest};
+use uuid::Uuid;
+
+use crate::data::repository::RepositoryError;
+use crate::entities::todo_entity::TodoEntity;
+
+/// A single change to apply to the todo items in the data store.
+pub enum TodoChange {
+    /// Insert the given entity
+    Create(TodoEntity),
+
+    /// Update the entity with the given id with the values of the given entity
+    Update(Uuid, TodoEntity),
+
+    /// Delete the entity with the given id
+    Delete(Uuid),
+}
+
+impl TodoChange {
+    /// The identifier of the todo item affected by this change
+    pub fn id(&self) -> Uuid {
+        match self {
+            TodoChange::Create(entity) => entity.id,
+            TodoChange::Update(id, _) => *id,
+            TodoChange::Delete(id) => *id,
+        }
+    }
+}
+
+/// A change that could not be applied, none of the changes are persisted.
+#[derive(Debug)]
+pub struct ChangeError {
+    /// The index of the failing change
+    pub index: usize,
+
+    /// The reason the change failed
+    pub error: RepositoryError,
+}
+
+impl ChangeError {
+    pub fn new(index: usize, error: impl Into<RepositoryError>) -> Self {
+        ChangeError {
+            index,
+            error: error.into(),
+        }
+    }
+}
+
+impl fmt::Display for ChangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Operation {} failed: {}", self.index, self.error)
+    }
+}
+
+/// Returns the name of the first effort or reminder lead time field with a negative value, these
+/// are counted in minutes and can't be negative.
+pub fn negative_minutes(
+    estimated: Option<i32>,
+    actual: Option<i32>,
+    remind_before: Option<i32>,
+) -> Option<&'static str> {
+    [
+        ("estimated_minutes", estimated),
+        ("actual_minutes", actual),
+        ("remind_before_minutes", remind_before),
+    ]
+    .into_iter()
+    .find(|(_, value)| matches!(value, Some(minutes) if *minutes < 0))
+    .map(|(field, _)| field)
+}
+
+// Convert from SyncOperation to TodoChange, failing when a required field is missing
+impl TryFrom<SyncOperation> for TodoChange {
+    type Error = String;
+
+    fn try_from(operation: SyncOperation) -> Result<Self, Self::Error> {
+        let missing = |field: &str| format!("missing field `{}`", field);
+
+        if let Some(field) = negative_minutes(
+            operation.estimated_minutes,
+            operation.actual_minutes,
+            operation.remind_before_minutes,
+        ) {
+            return Err(format!("`{}` must not be negative", field));
+        }
+
+        match operation.op {
+            SyncOperationKind::Create => Ok(TodoChange::Create(
+                CreateTodoItemRequest {
+                    title: operation.title.ok_or_else(|| missing("title"))?,
+                    description: operation
+                        .description
+                        .ok_or_else(|| missing("description"))?,
+                    due_date: operation.due_date,
+                    estimated_minutes: operation.estimated_minutes,
+                    actual_minutes: operation.actual_minutes,
+                    remind_before_minutes: operation.remind_before_minutes,
+                    tags: Vec::new(),
+                    parent_id: None,
+                    recurrence: None,
+                }
+                .into(),
+            )),
+            SyncOperationKind::Update => Ok(TodoChange::Update(
+                operation.id.ok_or_else(|| missing("id"))?,
+                UpdateTodoItemRequest {
+                    new_title: operation.title.ok_or_else(|| missing("title"))?,
+                    new_description: operation
+                        .description
+                        .ok_or_else(|| missing("description"))?,
+                    completed: operation.completed.ok_or_else(|| missing("completed"))?,
+                    due_date: operation.due_date,
+                    estimated_minutes: operation.estimated_minutes,
+                    actual_minutes: operation.actual_minutes,
+                    remind_before_minutes: operation.remind_before_minutes,
+                    // Synced changes aren't checked against the version, the last one wins
+                    expected_version: 0,
+                }
+                .into(),
+            )),
+            SyncOperationKind::Delete => Ok(TodoChange::Delete(
+                operation.id.ok_or_else(|| missing("id"))?,
+            )),
+        }
+    }
+}
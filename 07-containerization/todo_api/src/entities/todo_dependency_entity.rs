@@ -0,0 +1,24 @@
+use crate::schema::todo_dependencies;
+use todo_shared::TodoGraphEdge;
+use uuid::Uuid;
+
+#[derive(Queryable, Insertable, Clone, Debug, PartialEq, Eq)]
+#[diesel(table_name = todo_dependencies)]
+#[diesel(primary_key(todo_id, blocked_by))]
+pub struct TodoDependencyEntity {
+    /// The identifier of the todo item that is blocked
+    pub todo_id: Uuid,
+
+    /// The identifier of the todo item that has to be done first
+    pub blocked_by: Uuid,
+}
+
+// Convert from TodoDependencyEntity to TodoGraphEdge
+impl From<TodoDependencyEntity> for TodoGraphEdge {
+    fn from(entity: TodoDependencyEntity) -> Self {
+        TodoGraphEdge {
+            from: entity.blocked_by,
+            to: entity.todo_id,
+        }
+    }
+}
@@ -1,6 +1,9 @@
+use crate::entities::checklist::Checklist;
 use crate::schema::todos;
-use std::time::SystemTime;
-use todo_shared::{CreateTodoItemRequest, TodoItem, UpdateTodoItemRequest};
+use chrono::{DateTime, Utc};
+use todo_shared::{
+    tag_namespaces, ChecklistProgress, CreateTodoItemRequest, TodoItem, UpdateTodoItemRequest,
+};
 use uuid::Uuid;
 
 #[derive(Queryable, Insertable, Clone)]
@@ -20,10 +23,52 @@ pub struct TodoEntity {
     pub completed: bool,
 
     /// Timestamp when the todo item was completed
-    pub completed_at: Option<SystemTime>,
+    pub completed_at: Option<DateTime<Utc>>,
 
     /// Timestamp when the todo item was created
-    pub created_at: SystemTime,
+    pub created_at: DateTime<Utc>,
+
+    /// Timestamp when the todo item is due
+    pub due_date: Option<DateTime<Utc>>,
+
+    /// Timestamp when the todo item was (soft) deleted
+    pub deleted_at: Option<DateTime<Utc>>,
+
+    /// The estimated effort in minutes
+    pub estimated_minutes: Option<i32>,
+
+    /// The actual effort in minutes
+    pub actual_minutes: Option<i32>,
+
+    /// The subject of the user owning the todo item
+    pub owner: Option<String>,
+
+    /// The number of minutes before the due date to send a reminder
+    pub remind_before_minutes: Option<i32>,
+
+    /// Timestamp when the reminder of the todo item was sent
+    pub reminded_at: Option<DateTime<Utc>>,
+
+    /// The checklist embedded in the todo item
+    pub checklist: Checklist,
+
+    /// The tags of the todo item
+    pub tags: Vec<String>,
+
+    /// Every namespace of the tags, e.g. `project` for `project/alpha`
+    pub tag_namespaces: Vec<String>,
+
+    /// The todo item this todo item is a subtask of
+    pub parent_id: Option<Uuid>,
+
+    /// The recurrence rule of the todo item, if it repeats
+    pub recurrence: Option<String>,
+
+    /// Timestamp when the todo item was last changed
+    pub updated_at: DateTime<Utc>,
+
+    /// The version of the todo item, incremented on every change
+    pub version: i32,
 }
 
 // Convert from TodoEntity to TodoItem
@@ -36,6 +81,20 @@ impl From<TodoEntity> for TodoItem {
             completed: entity.completed,
             completed_at: entity.completed_at,
             created_at: entity.created_at,
+            due_date: entity.due_date,
+            deleted_at: entity.deleted_at,
+            estimated_minutes: entity.estimated_minutes,
+            actual_minutes: entity.actual_minutes,
+            owner: entity.owner,
+            remind_before_minutes: entity.remind_before_minutes,
+            reminded_at: entity.reminded_at,
+            checklist_progress: ChecklistProgress::of(&entity.checklist.0),
+            checklist: entity.checklist.0,
+            tags: entity.tags,
+            parent_id: entity.parent_id,
+            recurrence: entity.recurrence,
+            updated_at: entity.updated_at,
+            version: entity.version,
         }
     }
 }
@@ -43,30 +102,58 @@ impl From<TodoEntity> for TodoItem {
 // Convert from CreateTodoItemRequest to TodoEntity
 impl From<CreateTodoItemRequest> for TodoEntity {
     fn from(request: CreateTodoItemRequest) -> Self {
+        let now = Utc::now();
         TodoEntity {
             id: Uuid::new_v4(),
             title: request.title,
             description: request.description,
-            created_at: SystemTime::now(),
+            created_at: now,
             completed_at: None,
             completed: false,
+            due_date: request.due_date,
+            deleted_at: None,
+            estimated_minutes: request.estimated_minutes,
+            actual_minutes: request.actual_minutes,
+            owner: None,
+            remind_before_minutes: request.remind_before_minutes,
+            reminded_at: None,
+            checklist: Checklist::default(),
+            tag_namespaces: tag_namespaces(&request.tags),
+            tags: request.tags,
+            parent_id: request.parent_id,
+            recurrence: request.recurrence,
+            updated_at: now,
+            version: 1,
         }
     }
 }
 
 // Convert from UpdateTodoItemRequest to TodoEntity
+// The request doesn't contain the id and creation time, the repository keeps the existing values.
+// The version is the one the update expects, the repository increments it when it matches.
 impl From<UpdateTodoItemRequest> for TodoEntity {
     fn from(request: UpdateTodoItemRequest) -> Self {
         TodoEntity {
             id: Uuid::new_v4(),
             title: request.new_title,
             description: request.new_description,
-            created_at: SystemTime::now(),
-            completed_at: match request.completed {
-                true => Some(SystemTime::now()),
-                _ => None,
-            },
+            created_at: Utc::now(),
+            completed_at: request.completed.then(Utc::now),
             completed: request.completed,
+            due_date: request.due_date,
+            deleted_at: None,
+            estimated_minutes: request.estimated_minutes,
+            actual_minutes: request.actual_minutes,
+            owner: None,
+            remind_before_minutes: request.remind_before_minutes,
+            reminded_at: None,
+            checklist: Checklist::default(),
+            tags: Vec::new(),
+            tag_namespaces: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+            updated_at: Utc::now(),
+            version: request.expected_version,
         }
     }
 }
@@ -0,0 +1,55 @@
+use crate::schema::todo_history;
+use chrono::{DateTime, Utc};
+use todo_shared::ActivityEntry;
+use uuid::Uuid;
+
+#[derive(Queryable, Insertable, Clone)]
+#[diesel(table_name = todo_history)]
+#[diesel(primary_key(id))]
+pub struct TodoHistoryEntity {
+    /// The unique identifier of the history entry
+    pub id: Uuid,
+
+    /// The identifier of the todo item that was changed
+    pub todo_id: Uuid,
+
+    /// The subject of the user that made the change
+    pub actor: String,
+
+    /// The kind of change, e.g. `create`, `update`, `delete` or `reassign`
+    pub action: String,
+
+    /// A human readable description of the change
+    pub details: Option<String>,
+
+    /// Timestamp when the change was made
+    pub created_at: DateTime<Utc>,
+}
+
+impl TodoHistoryEntity {
+    /// Creates a new history entry for a change made just now.
+    pub fn new(todo_id: Uuid, actor: &str, action: &str, details: Option<String>) -> Self {
+        TodoHistoryEntity {
+            id: Uuid::new_v4(),
+            todo_id,
+            actor: actor.to_string(),
+            action: action.to_string(),
+            details,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+// Convert from TodoHistoryEntity to ActivityEntry
+impl From<TodoHistoryEntity> for ActivityEntry {
+    fn from(entity: TodoHistoryEntity) -> Self {
+        ActivityEntry {
+            id: entity.id,
+            todo_id: entity.todo_id,
+            actor: entity.actor,
+            action: entity.action,
+            details: entity.details,
+            created_at: entity.created_at,
+        }
+    }
+}
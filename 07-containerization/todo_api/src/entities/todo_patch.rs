@@ -0,0 +1,157 @@
+use crate::entities::todo_entity::TodoEntity;
+use crate::schema::todos;
+use crate::schema::todos::dsl::{due_date, remind_before_minutes, reminded_at};
+use chrono::{DateTime, Utc};
+use diesel::dsl::case_when;
+use diesel::BoolExpressionMethods;
+use diesel::PgExpressionMethods;
+use todo_shared::PatchTodoItemRequest;
+
+/// The columns of a todo item replaced by an update, shared by the blocking and non-blocking
+/// repositories. `None` fields clear the column.
+#[derive(AsChangeset)]
+#[diesel(table_name = todos, treat_none_as_null = true)]
+pub struct TodoUpdate {
+    /// The new title of the todo item
+    pub title: String,
+
+    /// The new description of the todo item
+    pub description: String,
+
+    /// Indicates whether the todo item is completed
+    pub completed: bool,
+
+    /// Timestamp when the todo item was completed
+    pub completed_at: Option<DateTime<Utc>>,
+
+    /// Timestamp when the todo item is due
+    pub due_date: Option<DateTime<Utc>>,
+
+    /// The estimated effort in minutes
+    pub estimated_minutes: Option<i32>,
+
+    /// The actual effort in minutes
+    pub actual_minutes: Option<i32>,
+
+    /// The number of minutes before the due date to send a reminder
+    pub remind_before_minutes: Option<i32>,
+}
+
+// Convert from TodoEntity to TodoUpdate, the other columns are kept as they are
+impl From<TodoEntity> for TodoUpdate {
+    fn from(entity: TodoEntity) -> Self {
+        TodoUpdate {
+            title: entity.title,
+            description: entity.description,
+            completed: entity.completed,
+            completed_at: entity.completed_at,
+            due_date: entity.due_date,
+            estimated_minutes: entity.estimated_minutes,
+            actual_minutes: entity.actual_minutes,
+            remind_before_minutes: entity.remind_before_minutes,
+        }
+    }
+}
+
+/// The `reminded_at` column of a todo item changed to the given due date and reminder lead time.
+///
+/// A sent reminder is cleared once either of them changes, so it's sent again for the new time.
+/// Otherwise it's kept, so unrelated changes don't send the reminder twice.
+///
+///  # Arguments
+///
+///  * `new_due_date` - The due date of the changed todo item.
+///  * `new_remind_before_minutes` - The reminder lead time of the changed todo item.
+#[diesel::dsl::auto_type]
+pub fn rescheduled_reminder(
+    new_due_date: Option<DateTime<Utc>>,
+    new_remind_before_minutes: Option<i32>,
+) -> _ {
+    let cleared: Option<DateTime<Utc>> = None;
+    case_when(
+        due_date
+            .is_not_distinct_from(new_due_date)
+            .and(remind_before_minutes.is_not_distinct_from(new_remind_before_minutes)),
+        reminded_at,
+    )
+    .otherwise(cleared)
+}
+
+/// The columns of a todo item to change, `None` fields leave the column untouched.
+#[derive(AsChangeset, Default)]
+#[diesel(table_name = todos)]
+pub struct TodoPatch {
+    /// The new title of the todo item
+    pub title: Option<String>,
+
+    /// The new description of the todo item
+    pub description: Option<String>,
+
+    /// Indicates whether the todo item is completed
+    pub completed: Option<bool>,
+
+    /// Timestamp when the todo item was completed, `Some(None)` clears it
+    pub completed_at: Option<Option<DateTime<Utc>>>,
+
+    /// Timestamp when the todo item is due, `Some(None)` clears it
+    pub due_date: Option<Option<DateTime<Utc>>>,
+
+    /// The estimated effort in minutes, `Some(None)` clears it
+    pub estimated_minutes: Option<Option<i32>>,
+
+    /// The actual effort in minutes, `Some(None)` clears it
+    pub actual_minutes: Option<Option<i32>>,
+
+    /// The number of minutes before the due date to send a reminder, `Some(None)` clears it
+    pub remind_before_minutes: Option<Option<i32>>,
+}
+
+impl TodoPatch {
+    /// Indicates whether the patch doesn't change any column.
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.description.is_none()
+            && self.completed.is_none()
+            && self.completed_at.is_none()
+            && self.due_date.is_none()
+            && self.estimated_minutes.is_none()
+            && self.actual_minutes.is_none()
+            && self.remind_before_minutes.is_none()
+    }
+}
+
+// Convert from PatchTodoItemRequest to TodoPatch
+impl From<PatchTodoItemRequest> for TodoPatch {
+    fn from(request: PatchTodoItemRequest) -> Self {
+        TodoPatch {
+            title: request.title,
+            description: request.description,
+            completed: request.completed,
+            completed_at: request.completed.map(|completed| completed.then(Utc::now)),
+            ..TodoPatch::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::debug_query;
+    use diesel::pg::Pg;
+    use diesel::ExpressionMethods;
+
+    use super::*;
+
+    #[test]
+    fn test_rescheduled_reminder_is_kept_unless_changed() {
+        let query =
+            diesel::update(todos::table).set(reminded_at.eq(rescheduled_reminder(None, Some(30))));
+
+        assert_eq!(
+            debug_query::<Pg, _>(&query).to_string(),
+            "UPDATE \"todos\" SET \"reminded_at\" = CASE WHEN (((\"todos\".\"due_date\" \
+             IS NOT DISTINCT FROM $1) AND (\"todos\".\"remind_before_minutes\" IS NOT DISTINCT \
+             FROM $2))) THEN (\"todos\".\"reminded_at\") ELSE ($3) END \
+             -- binds: [None, Some(30), None]"
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use log::Record;
+use serde_json::json;
+use std::env;
+use std::io::Write;
+
+/// Initializes the logger, filtered by `RUST_LOG` as usual.
+///
+/// With `LOG_FORMAT=json` every log line is a JSON object with the `timestamp`, `level`, `target`
+/// and `message` of the record, so a log aggregator can parse it. Plain text is logged otherwise.
+pub fn init() {
+    let json = env::var("LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json"));
+    if !json {
+        env_logger::init();
+        return;
+    }
+
+    env_logger::Builder::from_default_env()
+        .format(|buf, record| {
+            let line = to_json(&buf.timestamp_millis().to_string(), record);
+            writeln!(buf, "{}", line)
+        })
+        .init();
+}
+
+// Converts a log record to a structured log line.
+fn to_json(timestamp: &str, record: &Record) -> serde_json::Value {
+    json!({
+        "timestamp": timestamp,
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "message": record.args().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn test_record_to_json() {
+        let line = to_json(
+            "2026-10-16T12:00:00.000Z",
+            &Record::builder()
+                .level(Level::Warn)
+                .target("todo_api::api")
+                .args(format_args!("Todo item \"{}\" was not found", 42))
+                .build(),
+        );
+
+        assert_eq!(
+            line,
+            json!({
+                "timestamp": "2026-10-16T12:00:00.000Z",
+                "level": "WARN",
+                "target": "todo_api::api",
+                "message": "Todo item \"42\" was not found",
+            })
+        );
+    }
+}
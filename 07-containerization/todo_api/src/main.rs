@@ -4,41 +4,204 @@ extern crate openssl;
 #[macro_use]
 extern crate diesel;
 
+use actix_web::middleware::{from_fn, Compress, Condition};
+use actix_web::web::Data;
 use actix_web::{App, HttpServer};
 mod api;
+pub mod auth;
 mod data;
 mod entities;
+mod logging;
 pub mod schema;
+mod services;
+mod settings;
+mod telemetry;
+use chrono::Utc;
 use dotenv::dotenv;
 use utoipa_swagger_ui::SwaggerUi;
 
-use std::{error::Error, net::Ipv4Addr};
+use std::{
+    error::Error,
+    net::Ipv4Addr,
+    time::{Duration, Instant},
+};
 
 // Add error and info logging macro usings here.
-use log::{error, info};
+use log::{error, info, warn};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
-    env_logger::init();
+    logging::init();
+    let telemetry = telemetry::init();
+    api::error_handlers::install_panic_hook();
 
     // Apply any ending database migrations upon startup of our application.
     match data::run_migrations() {
         Ok(()) => info!("Succesfully applied pending migrations (if any)"),
-        Err(_) => error!("Unable to apply pending migrations"),
+        Err(error) => error!("Unable to apply pending migrations: {}", error),
     }
 
+    // Scan for due reminders and purge tombstones once, rather than in every worker thread.
+    let settings = settings::Settings::from_env();
+    todo_shared::set_timestamp_precision(settings.timestamp_precision);
+    services::reminders::spawn_scan(&settings);
+    services::purge::spawn_purge(&settings);
+
+    let deprecation_headers = settings.deprecation_headers;
+    let allowed_origins = settings.allowed_origins.clone();
+
+    // Delay readiness from now on, shared by all worker threads.
+    let readiness = Data::new(api::health::Readiness::new(
+        Utc::now(),
+        Duration::from_secs(settings.readiness_delay_secs),
+    ));
+
+    // Authenticate the mutating requests when a secret to validate bearer tokens is configured.
+    let jwt_secret = settings.jwt_secret.clone().unwrap_or_default();
+    let authenticated = settings.jwt_secret.is_some();
+    if !authenticated {
+        warn!("JWT_SECRET is not set, the todo items can be changed without authentication");
+    }
+
+    // Accept requests signed with a shared secret as well, e.g. from webhooks.
+    let signed = settings.hmac_signing_secret.is_some();
+    let signature_verifier = Data::new(api::signature::SignatureVerifier::new(
+        settings.hmac_signing_secret.as_deref().unwrap_or_default(),
+        api::signature::MAX_CLOCK_SKEW,
+    ));
+
+    // Share the rate limit buckets between all worker threads.
+    let rate_limited = settings.rate_limit_requests > 0;
+    let rate_limiter = Data::new(api::rate_limit::RateLimiter::new(
+        settings.rate_limit_requests,
+        Duration::from_secs(settings.rate_limit_window_secs),
+    ));
+
+    // Share the published changes of todo items between all worker threads.
+    let todo_events = Data::new(services::todo_events::TodoEvents::default());
+
+    // Share the idempotency keys of the creates between all worker threads.
+    let idempotency_keys = Data::new(services::idempotency::IdempotencyKeys::new(
+        Duration::from_secs(settings.idempotency_window_secs),
+        settings.idempotency_max_keys,
+    ));
+
+    // Collect the metrics of all worker threads and their connection pools in one registry.
+    let metrics = Data::new(api::metrics::Metrics::new().expect("could not register the metrics"));
+
+    // Trace the requests when the spans are exported.
+    let traced = telemetry.is_some();
+
+    // Build the GraphQL schema once, the repository is attached to every request.
+    let graphql_schema = Data::new(api::graphql::schema());
+
     // Make instance variable of ApiDoc so all worker threads gets the same instance.
     let openapi = api::register_open_api_spec();
+    let postman_collection = Data::new(api::postman::PostmanCollection(
+        api::postman::to_collection(&openapi),
+    ));
 
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         App::new()
-            .configure(api::todo_controller::configure())
+            .app_data(rate_limiter.clone())
+            .app_data(signature_verifier.clone())
+            .app_data(todo_events.clone())
+            .app_data(idempotency_keys.clone())
+            .wrap(api::error_handlers::blocking_error_handlers())
+            .wrap(Condition::new(
+                authenticated,
+                api::auth::JwtAuth::new(&jwt_secret),
+            ))
+            // Outside of the bearer token authentication, which skips signed requests
+            .wrap(Condition::new(
+                signed,
+                from_fn(api::signature::verify_signature),
+            ))
+            .wrap(Condition::new(
+                rate_limited,
+                from_fn(api::rate_limit::rate_limit),
+            ))
+            .wrap(Condition::new(
+                deprecation_headers,
+                from_fn(api::deprecation::deprecation_headers),
+            ))
+            .wrap(from_fn(api::cbor::cbor_responses))
+            // Outside of the CBOR encoding, so both JSON and CBOR bodies are compressed. The event
+            // stream opts out, WebSocket upgrades are never compressed
+            .wrap(Compress::default())
+            .wrap(from_fn(api::request_id::request_id))
+            // Outside of the rate limiting and authentication, so rejected requests are counted
+            .wrap(from_fn(api::metrics::record_metrics))
+            .wrap(Condition::new(traced, from_fn(telemetry::trace_requests)))
+            // Outermost, so preflight requests are answered before any other middleware
+            .wrap(api::cors::cors(&allowed_origins))
+            .configure(api::todo_controller::configure(metrics.clone()))
+            .configure(api::capabilities_controller::configure())
+            .configure(api::health::configure(readiness.clone()))
+            .configure(api::metrics::configure(metrics.clone()))
+            .configure(api::postman::configure(postman_collection.clone()))
+            .configure(api::graphql::configure(graphql_schema.clone()))
             .service(
                 SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-doc/openapi.json", openapi.clone()),
             )
     })
     .bind((Ipv4Addr::UNSPECIFIED, 8080))?
-    .run()
-    .await
+    // Stop on our own signal handling, rather than the built-in one, to log the shutdown
+    .disable_signals()
+    .shutdown_timeout(SHUTDOWN_TIMEOUT_SECS)
+    .run();
+
+    // Let the in-flight requests finish, so database writes aren't cut off during a redeploy
+    let handle = server.handle();
+    actix_web::rt::spawn(async move {
+        shutdown_signal().await;
+        info!("Shutting down, waiting for the in-flight requests to finish");
+        let started = Instant::now();
+        handle.stop(true).await;
+        info!(
+            "Shut down in {:.1} seconds",
+            started.elapsed().as_secs_f64()
+        );
+    });
+
+    let result = server.await;
+    if let Some(telemetry) = telemetry {
+        telemetry.shutdown();
+    }
+    result
+}
+
+// The number of seconds to wait for in-flight requests when shutting down, before aborting them.
+const SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+// Waits for Ctrl+C or, on Unix, SIGTERM, the signal container runtimes stop containers with.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(error) = tokio::signal::ctrl_c().await {
+            error!("Unable to listen for Ctrl+C: {}", error);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(error) => {
+                error!("Unable to listen for SIGTERM: {}", error);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
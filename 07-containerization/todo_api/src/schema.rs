@@ -4,7 +4,41 @@ table! {
         title -> Text,
         description -> Text,
         completed -> Bool,
-        completed_at -> Nullable<Timestamp>,
-        created_at -> Timestamp,
+        completed_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        due_date -> Nullable<Timestamptz>,
+        deleted_at -> Nullable<Timestamptz>,
+        estimated_minutes -> Nullable<Int4>,
+        actual_minutes -> Nullable<Int4>,
+        owner -> Nullable<Text>,
+        remind_before_minutes -> Nullable<Int4>,
+        reminded_at -> Nullable<Timestamptz>,
+        checklist -> Jsonb,
+        tags -> Array<Text>,
+        tag_namespaces -> Array<Text>,
+        parent_id -> Nullable<Uuid>,
+        recurrence -> Nullable<Text>,
+        updated_at -> Timestamptz,
+        version -> Int4,
     }
 }
+
+table! {
+    todo_dependencies (todo_id, blocked_by) {
+        todo_id -> Uuid,
+        blocked_by -> Uuid,
+    }
+}
+
+table! {
+    todo_history (id) {
+        id -> Uuid,
+        todo_id -> Uuid,
+        actor -> Text,
+        action -> Text,
+        details -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(todo_dependencies, todo_history, todos,);
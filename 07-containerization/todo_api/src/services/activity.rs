@@ -0,0 +1,50 @@
+use log::warn;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::data::todo_repository::TodoRepository;
+use crate::entities::todo_history_entity::TodoHistoryEntity;
+use crate::telemetry;
+
+/// The actor of the changes the service makes on its own, e.g. purging the expired tombstones.
+pub const SYSTEM_ACTOR: &str = "system";
+
+/// Records the same change of the given todo items in the history, with a single insert.
+///
+/// Every write path records its changes through here, so the activity feed covers the batch and
+/// bulk endpoints as well as the single todo items. The change itself already succeeded, so
+/// failing to record it is only logged.
+///
+///  # Arguments
+///
+///  * `repository` - The repository to record the history in.
+///  * `actor` - The subject of the user that made the change, or `SYSTEM_ACTOR`.
+///  * `action` - The kind of change, e.g. `create`, `update`, `delete` or `purge`.
+///  * `ids` - The identifiers of the changed todo items.
+pub async fn record(
+    repository: Arc<dyn TodoRepository>,
+    actor: &str,
+    action: &str,
+    ids: impl IntoIterator<Item = Uuid>,
+) {
+    let entries: Vec<TodoHistoryEntity> = ids
+        .into_iter()
+        .map(|id| TodoHistoryEntity::new(id, actor, action, None))
+        .collect();
+    if entries.is_empty() {
+        return;
+    }
+
+    let count = entries.len();
+    match telemetry::block(move || repository.record_history(entries)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(error)) => warn!(
+            "Unable to record the {} of {} todo items: {}",
+            action, count, error
+        ),
+        Err(error) => warn!(
+            "Unable to record the {} of {} todo items: {}",
+            action, count, error
+        ),
+    }
+}
@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+
+pub trait Clock: Send + Sync {
+    /// Returns the current point in time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Clock backed by the system time, used outside of tests.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that always returns the same point in time, so time dependent logic can be tested.
+#[cfg(test)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use uuid::Uuid;
+
+use crate::data::repository::RepositoryError;
+use crate::data::todo_repository::TodoRepository;
+use crate::entities::todo_entity::TodoEntity;
+
+/// The maximum number of todo items waiting to be inserted, further creates wait for a free slot.
+const MAX_QUEUED_ITEMS: usize = 10_000;
+
+/// The maximum number of todo items inserted together. Every row binds a parameter per column of
+/// the todos table, this keeps a batch well below the 65535 bind parameters Postgres accepts.
+const MAX_BATCH_SIZE: usize = 1_000;
+
+// A todo item waiting to be inserted, with the channel to send the outcome to.
+type PendingInsert = (TodoEntity, oneshot::Sender<Result<TodoEntity, BatchError>>);
+
+/// The reasons a queued todo item was not inserted.
+#[derive(Debug)]
+pub enum BatchError {
+    /// The batcher stopped before the todo item was inserted
+    Stopped,
+
+    /// The data store rejected the todo item
+    Repository(RepositoryError),
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::Stopped => write!(f, "The create batcher has stopped"),
+            BatchError::Repository(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// Buffers todo items to insert for a short window and flushes them as a single multi-row insert.
+///
+/// Every caller waits until the batch containing its todo item is committed, which trades a few
+/// milliseconds of latency for a much higher throughput of bursty creates. When a batch fails, its
+/// todo items are inserted one by one, so only the todo items that can't be inserted fail.
+#[derive(Clone)]
+pub struct CreateBatcher {
+    sender: mpsc::Sender<PendingInsert>,
+}
+
+impl CreateBatcher {
+    /// Creates the batcher and spawns its background flusher on the current runtime.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `repository` - The repository to insert the batches into.
+    ///  * `window` - How long to wait for more todo items after the first one arrived.
+    pub fn new(repository: Arc<dyn TodoRepository>, window: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel(MAX_QUEUED_ITEMS);
+        actix_web::rt::spawn(flush(receiver, repository, window));
+        CreateBatcher { sender }
+    }
+
+    /// Queues a todo item for insertion, resolving once the batch containing it is committed.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `entity` - The entity to insert.
+    pub async fn insert(&self, entity: TodoEntity) -> Result<TodoEntity, BatchError> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send((entity, sender))
+            .await
+            .map_err(|_| BatchError::Stopped)?;
+        receiver.await.map_err(|_| BatchError::Stopped)?
+    }
+}
+
+// Collects the todo items arriving within the window and inserts them together, until all
+// batchers are dropped.
+async fn flush(
+    mut receiver: mpsc::Receiver<PendingInsert>,
+    repository: Arc<dyn TodoRepository>,
+    window: Duration,
+) {
+    while let Some(first) = receiver.recv().await {
+        actix_web::rt::time::sleep(window).await;
+        let mut pending = vec![first];
+        while pending.len() < MAX_BATCH_SIZE {
+            match receiver.try_recv() {
+                Ok(next) => pending.push(next),
+                Err(_) => break,
+            }
+        }
+
+        let entities = pending.iter().map(|(entity, _)| entity.clone()).collect();
+        match repository.insert_many(entities).await {
+            Ok(inserted) => {
+                let mut inserted: HashMap<Uuid, TodoEntity> = inserted
+                    .into_iter()
+                    .map(|entity| (entity.id, entity))
+                    .collect();
+                for (entity, sender) in pending {
+                    let result = inserted
+                        .remove(&entity.id)
+                        .ok_or(BatchError::Repository(RepositoryError::NotFound));
+                    let _ = sender.send(result);
+                }
+            }
+            // A single todo item fails the whole batch, retry them separately to find out which
+            Err(_) => {
+                for (entity, sender) in pending {
+                    let result = repository
+                        .insert(entity)
+                        .await
+                        .map_err(BatchError::Repository);
+                    let _ = sender.send(result);
+                }
+            }
+        }
+    }
+}
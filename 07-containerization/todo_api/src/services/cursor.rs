@@ -0,0 +1,79 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::entities::todo_entity::TodoEntity;
+
+/// The position after the last todo item of a page, to continue paging from.
+///
+/// Todo items are paged by creation time, ties are broken by id, so a page stays consistent while
+/// todo items are created or deleted in the meantime. Formatted as the base64 encoding of
+/// `<created_at>/<id>`, clients should treat it as an opaque string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    /// Returns the cursor pointing after the given todo item.
+    pub fn after(entity: &TodoEntity) -> Self {
+        Cursor {
+            created_at: entity.created_at,
+            id: entity.id,
+        }
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = format!(
+            "{}/{}",
+            self.created_at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            self.id
+        );
+        f.write_str(&URL_SAFE_NO_PAD.encode(position))
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = &'static str;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        const INVALID: &str = "The cursor is not valid, use the next_cursor of a previous page";
+        let position = URL_SAFE_NO_PAD.decode(value).map_err(|_| INVALID)?;
+        let position = String::from_utf8(position).map_err(|_| INVALID)?;
+        let (created_at, id) = position.split_once('/').ok_or(INVALID)?;
+        Ok(Cursor {
+            created_at: DateTime::parse_from_rfc3339(created_at)
+                .map_err(|_| INVALID)?
+                .with_timezone(&Utc),
+            id: id.parse().map_err(|_| INVALID)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor {
+            created_at: "2024-03-01T12:30:00.123456Z".parse().unwrap(),
+            id: Uuid::new_v4(),
+        };
+        let encoded = cursor.to_string();
+        assert!(!encoded.contains('/'));
+        assert_eq!(encoded.parse::<Cursor>(), Ok(cursor));
+
+        assert!("not a cursor".parse::<Cursor>().is_err());
+        assert!(URL_SAFE_NO_PAD
+            .encode("yesterday/42")
+            .parse::<Cursor>()
+            .is_err());
+    }
+}
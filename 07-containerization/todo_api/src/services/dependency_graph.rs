@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+use todo_shared::{TodoGraph, TodoGraphEdge, TodoGraphNode};
+use uuid::Uuid;
+
+use crate::entities::todo_dependency_entity::TodoDependencyEntity;
+use crate::entities::todo_entity::TodoEntity;
+
+/// Indicates whether adding a dependency would create a cycle in the dependency graph.
+///
+/// A todo item blocking itself is a cycle as well.
+///
+///  # Arguments
+///
+///  * `dependencies` - The existing dependencies, which form no cycle.
+///  * `todo_id` - The todo item that would be blocked.
+///  * `blocked_by` - The todo item that would have to be done first.
+pub fn creates_cycle(
+    dependencies: &[TodoDependencyEntity],
+    todo_id: Uuid,
+    blocked_by: Uuid,
+) -> bool {
+    // The todo items blocked by each todo item
+    let mut blocks: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for dependency in dependencies {
+        blocks
+            .entry(dependency.blocked_by)
+            .or_default()
+            .push(dependency.todo_id);
+    }
+
+    // The new dependency closes a cycle when `blocked_by` is (indirectly) blocked by `todo_id`
+    let mut visited = HashSet::new();
+    let mut pending = vec![todo_id];
+    while let Some(current) = pending.pop() {
+        if current == blocked_by {
+            return true;
+        }
+        if visited.insert(current) {
+            pending.extend(blocks.get(&current).into_iter().flatten());
+        }
+    }
+    false
+}
+
+/// Builds the dependency graph of the given todo items.
+///
+/// Dependencies on todo items that are not among the given ones, e.g. because they were deleted,
+/// are left out.
+pub fn build_graph(
+    entities: Vec<TodoEntity>,
+    dependencies: Vec<TodoDependencyEntity>,
+) -> TodoGraph {
+    let ids: HashSet<Uuid> = entities.iter().map(|entity| entity.id).collect();
+    let nodes = entities
+        .into_iter()
+        .map(|entity| TodoGraphNode {
+            id: entity.id,
+            title: entity.title,
+            completed: entity.completed,
+        })
+        .collect();
+    let edges = dependencies
+        .into_iter()
+        .filter(|dependency| {
+            ids.contains(&dependency.todo_id) && ids.contains(&dependency.blocked_by)
+        })
+        .map(TodoGraphEdge::from)
+        .collect();
+
+    TodoGraph { nodes, edges }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(todo_id: Uuid, blocked_by: Uuid) -> TodoDependencyEntity {
+        TodoDependencyEntity {
+            todo_id,
+            blocked_by,
+        }
+    }
+
+    #[test]
+    fn test_creates_cycle() {
+        let (a, b, c, d) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        // a blocks b, b blocks c
+        let dependencies = vec![dependency(b, a), dependency(c, b)];
+
+        assert!(creates_cycle(&dependencies, a, c));
+        assert!(creates_cycle(&dependencies, a, b));
+        assert!(creates_cycle(&dependencies, d, d));
+        assert!(!creates_cycle(&dependencies, c, a));
+        assert!(!creates_cycle(&dependencies, d, c));
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use todo_shared::TodoItem;
+
+/// Remembers the todo items created for an `Idempotency-Key`, so a create that is retried after a
+/// timeout returns the original todo item rather than inserting a duplicate.
+///
+/// Shared by all worker threads. Keys expire once the window since their first use has passed, a
+/// zero window disables idempotency keys. At most `capacity` keys are remembered, when it's
+/// reached the oldest key is forgotten before its window has passed.
+pub struct IdempotencyKeys {
+    window: Duration,
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+// The used keys, along with the keys in the order they expire in. Every key has the same window,
+// so that's the order they were first used in.
+#[derive(Default)]
+struct Entries {
+    by_key: HashMap<String, Entry>,
+    expiry: VecDeque<(Instant, String)>,
+}
+
+// A used key, with the todo item it created once the create finished.
+struct Entry {
+    created: Option<TodoItem>,
+    expires_at: Instant,
+}
+
+/// The outcome of claiming a key for a create.
+pub enum Claim<'a> {
+    /// The key wasn't used yet, the create has to be recorded or is released when dropped
+    New(PendingKey<'a>),
+
+    /// The key was used to create the given todo item
+    Created(Box<TodoItem>),
+
+    /// A create with the same key is still in progress
+    InProgress,
+}
+
+/// A key claimed by a create in progress, released when it's dropped before the create finished.
+pub struct PendingKey<'a> {
+    keys: &'a IdempotencyKeys,
+    key: Option<String>,
+}
+
+impl IdempotencyKeys {
+    /// Creates the store of keys.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `window` - The time a key is remembered after its first use.
+    ///  * `capacity` - The maximum number of keys to remember.
+    pub fn new(window: Duration, capacity: usize) -> Self {
+        IdempotencyKeys {
+            window,
+            capacity,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Claims a key for a create, unless it was used within the window.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `key` - The idempotency key, scoped to the caller that sent it.
+    ///  * `now` - The current point in time.
+    pub fn claim(&self, key: &str, now: Instant) -> Claim<'_> {
+        if self.window.is_zero() || self.capacity == 0 {
+            return Claim::New(PendingKey {
+                keys: self,
+                key: None,
+            });
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.evict(now, self.capacity);
+        match entries.by_key.get(key) {
+            Some(Entry {
+                created: Some(todo),
+                ..
+            }) => Claim::Created(Box::new(todo.clone())),
+            Some(_) => Claim::InProgress,
+            None => {
+                let expires_at = now + self.window;
+                entries.by_key.insert(
+                    key.to_string(),
+                    Entry {
+                        created: None,
+                        expires_at,
+                    },
+                );
+                entries.expiry.push_back((expires_at, key.to_string()));
+                Claim::New(PendingKey {
+                    keys: self,
+                    key: Some(key.to_string()),
+                })
+            }
+        }
+    }
+}
+
+impl PendingKey<'_> {
+    /// Records the todo item created for the key, returned to the retries within the window.
+    pub fn complete(mut self, todo: &TodoItem) {
+        if let Some(key) = self.key.take() {
+            let mut entries = self.keys.entries.lock().unwrap();
+            if let Some(entry) = entries.by_key.get_mut(&key) {
+                entry.created = Some(todo.clone());
+            }
+        }
+    }
+}
+
+impl Drop for PendingKey<'_> {
+    fn drop(&mut self) {
+        // The create failed, so it can be retried with the same key
+        if let Some(key) = self.key.take() {
+            self.keys.entries.lock().unwrap().by_key.remove(&key);
+        }
+    }
+}
+
+impl Entries {
+    // Forgets the expired keys, and the oldest keys until there's room for another one. Only the
+    // oldest keys are visited, rather than every key on each claim.
+    fn evict(&mut self, now: Instant, capacity: usize) {
+        while let Some((expires_at, _)) = self.expiry.front() {
+            if *expires_at > now && self.by_key.len() < capacity {
+                break;
+            }
+            let (expires_at, key) = self.expiry.pop_front().unwrap();
+            // A released key may have been claimed again since, with a later expiry
+            if self
+                .by_key
+                .get(&key)
+                .is_some_and(|entry| entry.expires_at == expires_at)
+            {
+                self.by_key.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use todo_shared::CreateTodoItemRequest;
+
+    use crate::entities::todo_entity::TodoEntity;
+
+    fn todo() -> TodoItem {
+        let entity: TodoEntity = CreateTodoItemRequest {
+            title: "Create once".to_string(),
+            description: String::new(),
+            due_date: None,
+            estimated_minutes: None,
+            actual_minutes: None,
+            remind_before_minutes: None,
+            tags: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+        }
+        .into();
+        entity.into()
+    }
+
+    #[test]
+    fn test_claim_replays_created_todo_within_window() {
+        let keys = IdempotencyKeys::new(Duration::from_secs(60), 100);
+        let now = Instant::now();
+        let todo = todo();
+
+        let Claim::New(pending) = keys.claim("user-1:key", now) else {
+            panic!("expected a new key");
+        };
+        assert!(matches!(keys.claim("user-1:key", now), Claim::InProgress));
+        pending.complete(&todo);
+
+        match keys.claim("user-1:key", now + Duration::from_secs(30)) {
+            Claim::Created(replayed) => assert_eq!(replayed.id, todo.id),
+            _ => panic!("expected the created todo item"),
+        }
+
+        // Expired keys can be used again
+        assert!(matches!(
+            keys.claim("user-1:key", now + Duration::from_secs(61)),
+            Claim::New(_)
+        ));
+    }
+
+    #[test]
+    fn test_failed_create_releases_key() {
+        let keys = IdempotencyKeys::new(Duration::from_secs(60), 100);
+        let now = Instant::now();
+
+        drop(keys.claim("key", now));
+        assert!(matches!(keys.claim("key", now), Claim::New(_)));
+    }
+
+    #[test]
+    fn test_claim_forgets_oldest_key_beyond_capacity() {
+        let keys = IdempotencyKeys::new(Duration::from_secs(60), 2);
+        let now = Instant::now();
+        let todo = todo();
+
+        for key in ["first", "second", "third"] {
+            let Claim::New(pending) = keys.claim(key, now) else {
+                panic!("expected a new key");
+            };
+            pending.complete(&todo);
+        }
+
+        // Only the newest keys are remembered
+        assert_eq!(keys.entries.lock().unwrap().by_key.len(), 2);
+        assert!(matches!(keys.claim("third", now), Claim::Created(_)));
+        assert!(matches!(keys.claim("first", now), Claim::New(_)));
+    }
+}
@@ -0,0 +1,11 @@
+pub mod activity;
+pub mod clock;
+pub mod create_batcher;
+pub mod cursor;
+pub mod dependency_graph;
+pub mod idempotency;
+pub mod purge;
+pub mod recurrence;
+pub mod reminders;
+pub mod todo_events;
+pub mod todo_service;
@@ -0,0 +1,48 @@
+use actix_web::web;
+use chrono::Utc;
+use log::{error, info};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::data::todo_repository::{TodoEntityRepository, TodoRepository};
+use crate::services::activity::{self, SYSTEM_ACTOR};
+use crate::settings::Settings;
+
+// The interval to purge the expired tombstones at.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Spawns the task permanently deleting the todo items whose soft-delete retention has expired,
+/// when a retention period is configured.
+///
+/// Called once at startup, rather than per worker, so the purges don't overlap. The purges are
+/// recorded in the history with `SYSTEM_ACTOR` as the actor.
+///
+///  # Arguments
+///
+///  * `settings` - The settings containing the soft-delete retention.
+pub fn spawn_purge(settings: &Settings) {
+    let Some(retention) = settings.soft_delete_retention() else {
+        return;
+    };
+
+    let repository: Arc<dyn TodoRepository> = Arc::new(TodoEntityRepository::new(settings));
+
+    actix_web::rt::spawn(async move {
+        let mut ticks = actix_web::rt::time::interval(PURGE_INTERVAL);
+        loop {
+            ticks.tick().await;
+
+            let purging = repository.clone();
+            let before = Utc::now() - retention;
+            match web::block(move || purging.purge_deleted(before)).await {
+                Ok(Ok(purged)) if purged.is_empty() => {}
+                Ok(Ok(purged)) => {
+                    info!("Purged {} deleted todo items", purged.len());
+                    activity::record(repository.clone(), SYSTEM_ACTOR, "purge", purged).await;
+                }
+                Ok(Err(error)) => error!("Unable to purge deleted todo items: {}", error),
+                Err(error) => error!("Unable to purge deleted todo items: {}", error),
+            }
+        }
+    });
+}
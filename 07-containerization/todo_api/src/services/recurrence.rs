@@ -0,0 +1,82 @@
+use chrono::{DateTime, Days, Months, Utc};
+use todo_shared::{Frequency, Recurrence};
+
+/// Iterates the occurrences of a recurrence rule following a start date, e.g. the current due date
+/// of a todo item.
+///
+/// Every occurrence is computed from the start date rather than from the previous occurrence, so a
+/// monthly rule starting on the 31st falls back to the last day of shorter months without drifting
+/// to the 28th afterwards.
+pub struct Occurrences {
+    rule: Recurrence,
+    start: DateTime<Utc>,
+    index: u32,
+}
+
+impl Occurrences {
+    /// Creates the iterator.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `rule` - The recurrence rule to compute the occurrences of.
+    ///  * `start` - The first occurrence, which the iterator skips.
+    pub fn new(rule: Recurrence, start: DateTime<Utc>) -> Self {
+        Occurrences {
+            rule,
+            start,
+            index: 0,
+        }
+    }
+}
+
+impl Iterator for Occurrences {
+    type Item = DateTime<Utc>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.index = self.index.checked_add(1)?;
+        let steps = u64::from(self.index) * u64::from(self.rule.interval);
+        match self.rule.frequency {
+            Frequency::Daily => add_days(self.start, steps),
+            Frequency::Weekly => add_days(self.start, steps.checked_mul(7)?),
+            Frequency::Monthly => add_months(self.start, steps),
+        }
+    }
+}
+
+// Advances a point in time by a number of whole days.
+fn add_days(time: DateTime<Utc>, days: u64) -> Option<DateTime<Utc>> {
+    time.checked_add_days(Days::new(days))
+}
+
+// Advances a point in time by a number of calendar months, keeping the (UTC) time of day and
+// clamping the day to the length of the resulting month.
+fn add_months(time: DateTime<Utc>, months: u64) -> Option<DateTime<Utc>> {
+    time.checked_add_months(Months::new(u32::try_from(months).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-31 09:30 UTC
+    const JANUARY_31: i64 = 1_706_693_400;
+
+    fn date(days: u64) -> DateTime<Utc> {
+        add_days(DateTime::from_timestamp(JANUARY_31, 0).unwrap(), days).unwrap()
+    }
+
+    #[test]
+    fn test_weekly_occurrences() {
+        let rule: Recurrence = "FREQ=WEEKLY;INTERVAL=2".parse().unwrap();
+        let occurrences: Vec<_> = Occurrences::new(rule, date(0)).take(3).collect();
+        assert_eq!(occurrences, vec![date(14), date(28), date(42)]);
+    }
+
+    #[test]
+    fn test_monthly_occurrences_clamp_to_month_end() {
+        let rule: Recurrence = "FREQ=MONTHLY".parse().unwrap();
+        let occurrences: Vec<_> = Occurrences::new(rule, date(0)).take(3).collect();
+        // 2024-02-29 (leap year), 2024-03-31 and 2024-04-30
+        assert_eq!(occurrences, vec![date(29), date(60), date(90)]);
+    }
+}
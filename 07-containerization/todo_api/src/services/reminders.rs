@@ -0,0 +1,228 @@
+use actix_web::web;
+use chrono::{DateTime, TimeDelta, Utc};
+use log::{error, info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+use todo_shared::TodoItem;
+
+use crate::data::todo_repository::{TodoEntityRepository, TodoRepository};
+use crate::entities::todo_entity::TodoEntity;
+use crate::services::clock::{Clock, SystemClock};
+use crate::settings::Settings;
+
+/// Sends the reminder of a todo item.
+pub trait ReminderNotifier: Send + Sync {
+    /// Notifies about the given todo item, whose reminder is due.
+    fn notify(&self, todo: &TodoItem) -> Result<(), String>;
+}
+
+/// Posts the todo item as JSON to a webhook.
+pub struct WebhookNotifier {
+    url: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String) -> Self {
+        WebhookNotifier { url }
+    }
+}
+
+impl ReminderNotifier for WebhookNotifier {
+    fn notify(&self, todo: &TodoItem) -> Result<(), String> {
+        let request = ureq::post(&self.url);
+        // Continue the trace at the receiver, the span ends once the webhook has responded
+        #[cfg(feature = "telemetry")]
+        let (request, _span) = crate::telemetry::trace_outgoing("POST reminder webhook", request);
+        request
+            .send_json(todo)
+            .map(|_| ())
+            .map_err(|error| error.to_string())
+    }
+}
+
+/// Returns the point in time the reminder of a todo item is due.
+///
+///  # Arguments
+///
+///  * `due_date` - The due date of the todo item.
+///  * `remind_before_minutes` - The lead time of the reminder, at the due date itself when absent.
+pub fn reminder_time(due_date: DateTime<Utc>, remind_before_minutes: Option<i32>) -> DateTime<Utc> {
+    let minutes = remind_before_minutes.unwrap_or(0).max(0);
+    due_date
+        .checked_sub_signed(TimeDelta::minutes(i64::from(minutes)))
+        .unwrap_or(DateTime::UNIX_EPOCH)
+}
+
+/// Indicates whether the reminder of an open todo item is due and wasn't sent yet.
+///
+///  # Arguments
+///
+///  * `entity` - The todo item to check.
+///  * `now` - The current point in time.
+pub fn is_reminder_due(entity: &TodoEntity, now: DateTime<Utc>) -> bool {
+    if entity.completed || entity.deleted_at.is_some() || entity.reminded_at.is_some() {
+        return false;
+    }
+
+    entity
+        .due_date
+        .map(|due_date| reminder_time(due_date, entity.remind_before_minutes) <= now)
+        .unwrap_or(false)
+}
+
+/// Sends the reminders that are due and marks the todo items as reminded.
+///
+/// Returns the number of sent reminders. A todo item whose reminder couldn't be sent is retried
+/// on the next scan.
+///
+///  # Arguments
+///
+///  * `repository` - The repository to look up the todo items in.
+///  * `notifier` - Sends the reminders.
+///  * `now` - The current point in time.
+pub fn send_due_reminders(
+    repository: &dyn TodoRepository,
+    notifier: &dyn ReminderNotifier,
+    now: DateTime<Utc>,
+) -> usize {
+    let mut sent = 0;
+    // The data store selects the todo items, checking them again guards against lead times the
+    // query and this module disagree on, e.g. negative ones written before validation existed.
+    let due = match repository.due_for_reminder(now) {
+        Ok(due) => due,
+        Err(error) => {
+            error!(
+                "Unable to load the todo items due for a reminder: {}",
+                error
+            );
+            return 0;
+        }
+    };
+    let due = due
+        .into_iter()
+        .filter(|entity| is_reminder_due(entity, now));
+
+    for entity in due {
+        let todo_id = entity.id;
+        if let Err(error) = notifier.notify(&entity.into()) {
+            warn!(
+                "Unable to send the reminder of todo item {}: {}",
+                todo_id, error
+            );
+            continue;
+        }
+
+        match repository.mark_reminded(todo_id, now) {
+            Ok(_) => sent += 1,
+            Err(error) => error!(
+                "Unable to mark todo item {} as reminded: {}",
+                todo_id, error
+            ),
+        }
+    }
+    sent
+}
+
+/// Spawns the task scanning for due reminders on the current runtime, when a webhook is configured.
+///
+/// Called once at startup, rather than per worker, so every reminder is sent only once.
+///
+///  # Arguments
+///
+///  * `settings` - The settings containing the webhook and scan interval.
+pub fn spawn_scan(settings: &Settings) {
+    let url = match &settings.reminder_webhook_url {
+        Some(url) if settings.reminder_scan_interval_secs > 0 => url.clone(),
+        _ => return,
+    };
+
+    let repository: Arc<dyn TodoRepository> = Arc::new(TodoEntityRepository::new(settings));
+    let notifier: Arc<dyn ReminderNotifier> = Arc::new(WebhookNotifier::new(url));
+    let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+    let interval = Duration::from_secs(settings.reminder_scan_interval_secs);
+
+    actix_web::rt::spawn(async move {
+        let mut ticks = actix_web::rt::time::interval(interval);
+        loop {
+            ticks.tick().await;
+
+            let repository = repository.clone();
+            let notifier = notifier.clone();
+            let now = clock.now();
+            match web::block(move || {
+                send_due_reminders(repository.as_ref(), notifier.as_ref(), now)
+            })
+            .await
+            {
+                Ok(0) => {}
+                Ok(sent) => info!("Sent {} reminders", sent),
+                Err(error) => error!("Unable to scan for reminders: {}", error),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::entities::checklist::Checklist;
+
+    use super::*;
+
+    // 2022-09-29 12:00:00 UTC
+    fn now() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_664_452_800, 0).unwrap()
+    }
+
+    fn todo_due_in(minutes: u64, remind_before_minutes: Option<i32>) -> TodoEntity {
+        TodoEntity {
+            id: Uuid::new_v4(),
+            title: "Prepare the demo".to_string(),
+            description: "Before the meeting starts".to_string(),
+            completed: false,
+            completed_at: None,
+            created_at: now(),
+            due_date: Some(now() + Duration::from_secs(minutes * 60)),
+            deleted_at: None,
+            estimated_minutes: None,
+            actual_minutes: None,
+            owner: None,
+            remind_before_minutes,
+            reminded_at: None,
+            checklist: Checklist::default(),
+            tags: Vec::new(),
+            tag_namespaces: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+            updated_at: Utc::now(),
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn test_reminder_due_within_lead_time() {
+        assert!(is_reminder_due(&todo_due_in(20, Some(30)), now()));
+    }
+
+    #[test]
+    fn test_reminder_not_due() {
+        // The lead time hasn't started yet
+        assert!(!is_reminder_due(&todo_due_in(40, Some(30)), now()));
+
+        // Without a lead time the reminder is due at the due date
+        assert!(!is_reminder_due(&todo_due_in(20, None), now()));
+
+        // Reminders are sent only once, and never for completed todo items
+        let reminded = TodoEntity {
+            reminded_at: Some(now()),
+            ..todo_due_in(20, Some(30))
+        };
+        assert!(!is_reminder_due(&reminded, now()));
+        let completed = TodoEntity {
+            completed: true,
+            ..todo_due_in(20, Some(30))
+        };
+        assert!(!is_reminder_due(&completed, now()));
+    }
+}
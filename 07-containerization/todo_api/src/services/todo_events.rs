@@ -0,0 +1,64 @@
+use todo_shared::{TodoEvent, TodoEventKind};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// The number of events kept for subscribers that fall behind, older events are skipped for them.
+const CAPACITY: usize = 256;
+
+/// Publishes the changes of todo items to the subscribers of the event stream.
+///
+/// Shared by all worker threads, so a subscriber receives the changes made through any of them.
+/// Events are only kept for the current subscribers, changes made while nobody listens are lost.
+pub struct TodoEvents {
+    sender: broadcast::Sender<TodoEvent>,
+}
+
+impl Default for TodoEvents {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        TodoEvents { sender }
+    }
+}
+
+impl TodoEvents {
+    /// Publishes a change to all current subscribers.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `kind` - The kind of change.
+    ///  * `id` - The unique identifier of the changed todo item.
+    pub fn publish(&self, kind: TodoEventKind, id: Uuid) {
+        // Fails only when nobody is subscribed, which is fine
+        let _ = self.sender.send(TodoEvent { kind, id });
+    }
+
+    /// Subscribes to the changes published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<TodoEvent> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_subscribers_receive_published_events() {
+        let events = TodoEvents::default();
+        let id = Uuid::new_v4();
+
+        // Changes made before subscribing are not received
+        events.publish(TodoEventKind::Created, id);
+        let mut receiver = events.subscribe();
+        events.publish(TodoEventKind::Deleted, id);
+
+        assert_eq!(
+            receiver.recv().await.unwrap(),
+            TodoEvent {
+                kind: TodoEventKind::Deleted,
+                id
+            }
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use todo_shared::{FieldChange, TodoDiff, TodoItem, TodoStatus};
+use uuid::Uuid;
+
+use crate::entities::todo_patch::TodoPatch;
+
+/// Derives the human readable status of a todo item.
+///
+/// Completed items are always `Completed`. Otherwise the (UTC) calendar day of the due date
+/// is compared with the calendar day of `now`.
+///
+///  # Arguments
+///
+///  * `completed` - Indicates whether the todo item is completed.
+///  * `due_date` - The optional due date of the todo item.
+///  * `now` - The current point in time, usually taken from the injected `Clock`.
+pub fn derive_status(
+    completed: bool,
+    due_date: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> TodoStatus {
+    if completed {
+        return TodoStatus::Completed;
+    }
+
+    match due_date {
+        None => TodoStatus::NoDueDate,
+        Some(due_date) => match due_date.date_naive().cmp(&now.date_naive()) {
+            std::cmp::Ordering::Less => TodoStatus::Overdue,
+            std::cmp::Ordering::Equal => TodoStatus::DueToday,
+            std::cmp::Ordering::Greater => TodoStatus::Upcoming,
+        },
+    }
+}
+
+/// Computes the field-level difference between two versions of a todo item.
+///
+/// Fields are compared by their JSON representation, only changed fields are included. The
+/// `updated_at` timestamp and the `version` are left out, as they change with every update.
+///
+///  # Arguments
+///
+///  * `before` - The todo item before it was updated.
+///  * `after` - The todo item after it was updated.
+pub fn diff(before: &TodoItem, after: &TodoItem) -> TodoDiff {
+    let (before, after) = match (serde_json::to_value(before), serde_json::to_value(after)) {
+        (Ok(Value::Object(before)), Ok(Value::Object(after))) => (before, after),
+        _ => return TodoDiff::new(),
+    };
+
+    after
+        .into_iter()
+        .filter(|(field, _)| field != "updated_at" && field != "version")
+        .filter_map(|(field, to)| {
+            let from = before.get(&field).cloned().unwrap_or(Value::Null);
+            (from != to).then_some((field, FieldChange { from, to }))
+        })
+        .collect()
+}
+
+/// Converts a JSON merge patch (RFC 7396) document to the changes it makes to a todo item.
+///
+/// Members of the document set the field to their value, `null` clears the nullable fields
+/// (`due_date`, `estimated_minutes`, `actual_minutes` and `remind_before_minutes`) and absent
+/// members leave the field untouched. Returns the reason the document is rejected otherwise, e.g.
+/// a `null` title or a member that isn't an updatable field.
+///
+///  # Arguments
+///
+///  * `document` - The merge patch document, which has to be an object.
+pub fn merge_patch(document: Value) -> Result<TodoPatch, String> {
+    let Value::Object(members) = document else {
+        return Err("A merge patch must be a JSON object".to_string());
+    };
+
+    let mut patch = TodoPatch::default();
+    for (field, value) in members {
+        match field.as_str() {
+            "title" => patch.title = Some(merge_required(&field, value)?),
+            "description" => patch.description = Some(merge_required(&field, value)?),
+            "completed" => {
+                let completed: bool = merge_required(&field, value)?;
+                patch.completed = Some(completed);
+                patch.completed_at = Some(completed.then(Utc::now));
+            }
+            "due_date" => patch.due_date = Some(merge_nullable(&field, value)?),
+            "estimated_minutes" => patch.estimated_minutes = Some(merge_nullable(&field, value)?),
+            "actual_minutes" => patch.actual_minutes = Some(merge_nullable(&field, value)?),
+            "remind_before_minutes" => {
+                patch.remind_before_minutes = Some(merge_nullable(&field, value)?)
+            }
+            _ => return Err(format!("`{}` is not an updatable field", field)),
+        }
+    }
+    Ok(patch)
+}
+
+// Reads the new value of a field that can't be cleared.
+fn merge_required<T: DeserializeOwned>(field: &str, value: Value) -> Result<T, String> {
+    if value.is_null() {
+        return Err(format!("`{}` can't be cleared", field));
+    }
+    serde_json::from_value(value).map_err(|error| format!("Invalid `{}`: {}", field, error))
+}
+
+// Reads the new value of a field that is cleared by `null`.
+fn merge_nullable<T: DeserializeOwned>(field: &str, value: Value) -> Result<Option<T>, String> {
+    serde_json::from_value(value).map_err(|error| format!("Invalid `{}`: {}", field, error))
+}
+
+/// Returns the new values of the changed fields, along with the id of the todo item.
+///
+///  # Arguments
+///
+///  * `id` - The unique identifier of the todo item.
+///  * `diff` - The changed fields of the todo item.
+pub fn changed_fields(id: Uuid, diff: TodoDiff) -> Map<String, Value> {
+    let mut fields: Map<String, Value> = diff
+        .into_iter()
+        .map(|(field, change)| (field, change.to))
+        .collect();
+    fields.insert("id".to_string(), Value::String(id.to_string()));
+    fields
+}
+
+/// Returns the start of the (UTC) calendar day of the given point in time.
+///
+/// Incomplete todo items due before the start of today are overdue, as `derive_status` does.
+pub fn start_of_day(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.date_naive().and_time(NaiveTime::MIN).and_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::services::clock::{Clock, FixedClock};
+    use todo_shared::ChecklistProgress;
+
+    use super::*;
+
+    const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+    // 2022-09-29 12:00:00 UTC
+    fn clock() -> FixedClock {
+        FixedClock(DateTime::from_timestamp(1_664_452_800, 0).unwrap())
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = TodoItem {
+            id: uuid::Uuid::new_v4(),
+            title: "Before".to_string(),
+            description: "Unchanged".to_string(),
+            completed: false,
+            completed_at: None,
+            created_at: clock().now(),
+            due_date: None,
+            deleted_at: None,
+            estimated_minutes: None,
+            actual_minutes: None,
+            owner: None,
+            remind_before_minutes: None,
+            reminded_at: None,
+            checklist: Vec::new(),
+            checklist_progress: ChecklistProgress::default(),
+            tags: Vec::new(),
+            parent_id: None,
+            recurrence: None,
+            updated_at: Utc::now(),
+            version: 1,
+        };
+        let after = TodoItem {
+            title: "After".to_string(),
+            completed: true,
+            ..before.clone()
+        };
+
+        let diff = diff(&before, &after);
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff["title"].from, "Before");
+        assert_eq!(diff["title"].to, "After");
+        assert_eq!(diff["completed"].from, false);
+        assert_eq!(diff["completed"].to, true);
+    }
+
+    #[test]
+    fn test_completed() {
+        let now = clock().now();
+        let due_date = now - Duration::from_secs(3 * SECONDS_PER_DAY);
+        assert_eq!(
+            derive_status(true, Some(due_date), now),
+            TodoStatus::Completed
+        );
+        assert_eq!(derive_status(true, None, now), TodoStatus::Completed);
+    }
+
+    #[test]
+    fn test_no_due_date() {
+        assert_eq!(
+            derive_status(false, None, clock().now()),
+            TodoStatus::NoDueDate
+        );
+    }
+
+    #[test]
+    fn test_overdue() {
+        let now = clock().now();
+        let due_date = now - Duration::from_secs(SECONDS_PER_DAY);
+        assert_eq!(
+            derive_status(false, Some(due_date), now),
+            TodoStatus::Overdue
+        );
+    }
+
+    #[test]
+    fn test_due_today() {
+        let now = clock().now();
+        let earlier_today = now - Duration::from_secs(60 * 60);
+        let later_today = now + Duration::from_secs(60 * 60);
+        assert_eq!(
+            derive_status(false, Some(earlier_today), now),
+            TodoStatus::DueToday
+        );
+        assert_eq!(
+            derive_status(false, Some(later_today), now),
+            TodoStatus::DueToday
+        );
+    }
+
+    #[test]
+    fn test_upcoming() {
+        let now = clock().now();
+        let due_date = now + Duration::from_secs(SECONDS_PER_DAY);
+        assert_eq!(
+            derive_status(false, Some(due_date), now),
+            TodoStatus::Upcoming
+        );
+    }
+}
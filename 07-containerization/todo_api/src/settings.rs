@@ -0,0 +1,210 @@
+use std::env;
+use std::time::Duration;
+use todo_shared::TimestampPrecision;
+
+/// Deployment specific settings, read from the environment.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// The maximum number of operations a single batch request may contain
+    pub max_batch_size: usize,
+
+    /// The maximum number of todo items to list without paging
+    pub max_unpaged_items: usize,
+
+    /// Indicates whether writing a todo item with the title of another todo item (ignoring case)
+    /// is rejected
+    pub unique_titles: bool,
+
+    /// Indicates whether searching uses trigram similarity, falling back to substring matching
+    /// when the `pg_trgm` extension is not available
+    pub fuzzy_search: bool,
+
+    /// The number of milliseconds to buffer created todo items before inserting them in a single
+    /// batch, 0 disables batching
+    pub create_batch_window_ms: u64,
+
+    /// The URL to post reminders of todo items to, reminders are disabled when absent
+    pub reminder_webhook_url: Option<String>,
+
+    /// The number of seconds between scans for todo items to send a reminder for
+    pub reminder_scan_interval_secs: u64,
+
+    /// The number of requests a client can make within the rate limit window, 0 disables rate
+    /// limiting
+    pub rate_limit_requests: u32,
+
+    /// The number of seconds it takes to refill the rate limit of a client
+    pub rate_limit_window_secs: u64,
+
+    /// The precision timestamps are serialized with in responses
+    pub timestamp_precision: TimestampPrecision,
+
+    /// Indicates whether responses of deprecated endpoints carry the `Deprecation` and `Sunset`
+    /// headers
+    pub deprecation_headers: bool,
+
+    /// The secret bearer tokens are signed with, authentication is disabled when absent
+    pub jwt_secret: Option<String>,
+
+    /// The secret requests can be signed with instead, signature verification is disabled when
+    /// absent
+    pub hmac_signing_secret: Option<String>,
+
+    /// The origins browsers may call the API from, only localhost when empty
+    pub allowed_origins: Vec<String>,
+
+    /// The number of seconds after startup before the instance reports it is ready
+    pub readiness_delay_secs: u64,
+
+    /// Indicates whether completing a todo item completes its incomplete subtasks as well
+    pub cascade_complete_subtasks: bool,
+
+    /// Indicates whether a todo item can only be completed once all of its subtasks are completed
+    pub require_subtasks_done: bool,
+
+    /// The number of days deleted todo items are kept as tombstones before they are purged,
+    /// they are kept until restored when 0
+    pub soft_delete_retention_days: u64,
+
+    /// The maximum number of bytes of an uploaded import file or JSON array
+    pub max_import_bytes: usize,
+
+    /// The maximum number of bytes of a JSON request body
+    pub max_json_body_bytes: usize,
+
+    /// The number of seconds between the keep-alive comments of an idle event stream, and between
+    /// the pings of an event socket
+    pub event_keep_alive_secs: u64,
+
+    /// The number of seconds an idempotency key of a create is remembered, 0 disables them
+    pub idempotency_window_secs: u64,
+
+    /// The maximum number of idempotency keys remembered at once, the oldest are forgotten first
+    pub idempotency_max_keys: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            max_batch_size: 500,
+            max_unpaged_items: 1000,
+            unique_titles: false,
+            fuzzy_search: false,
+            create_batch_window_ms: 0,
+            reminder_webhook_url: None,
+            reminder_scan_interval_secs: 60,
+            rate_limit_requests: 0,
+            rate_limit_window_secs: 60,
+            timestamp_precision: TimestampPrecision::Seconds,
+            deprecation_headers: true,
+            jwt_secret: None,
+            hmac_signing_secret: None,
+            allowed_origins: Vec::new(),
+            readiness_delay_secs: 0,
+            cascade_complete_subtasks: false,
+            require_subtasks_done: false,
+            soft_delete_retention_days: 0,
+            max_import_bytes: 1024 * 1024,
+            max_json_body_bytes: 256 * 1024,
+            event_keep_alive_secs: 15,
+            idempotency_window_secs: 24 * 60 * 60,
+            idempotency_max_keys: 100_000,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings from the environment, falling back to the defaults for missing values.
+    ///
+    /// * `MAX_BATCH_SIZE` - The maximum number of operations in a batch request (default 500).
+    /// * `MAX_UNPAGED_ITEMS` - The maximum number of todo items to list unpaged (default 1000).
+    /// * `UNIQUE_TITLES` - Reject todo items with a duplicate title, ignoring case (default false).
+    /// * `FUZZY_SEARCH` - Search using trigram similarity (default false).
+    /// * `CREATE_BATCH_WINDOW_MS` - Buffer creates for this many milliseconds (default 0, disabled).
+    /// * `REMINDER_WEBHOOK_URL` - Post reminders of todo items to this URL (default none, disabled).
+    /// * `REMINDER_SCAN_INTERVAL_SECS` - Scan for reminders every this many seconds (default 60).
+    /// * `RATE_LIMIT_REQUESTS` - Requests per client within the window (default 0, disabled).
+    /// * `RATE_LIMIT_WINDOW_SECS` - Seconds to refill the rate limit of a client (default 60).
+    /// * `TIMESTAMP_PRECISION` - `seconds`, `millis` or `micros` in responses (default seconds).
+    /// * `DEPRECATION_HEADERS` - Warn clients of deprecated endpoints (default true).
+    /// * `JWT_SECRET` - Require bearer tokens signed with this secret (default none, disabled).
+    /// * `HMAC_SIGNING_SECRET` - Accept requests signed with this secret (default none, disabled).
+    /// * `ALLOWED_ORIGINS` - Comma-separated origins allowed by CORS (default none, localhost only).
+    /// * `READINESS_DELAY_SECS` - Report readiness this many seconds after startup (default 0).
+    /// * `CASCADE_COMPLETE_SUBTASKS` - Complete the subtasks along with a todo item (default false).
+    /// * `REQUIRE_SUBTASKS_DONE` - Only complete todo items without open subtasks (default false).
+    /// * `SOFT_DELETE_RETENTION_DAYS` - Purge deleted todo items after this many days (default 0, never).
+    /// * `MAX_IMPORT_BYTES` - Reject imports larger than this many bytes (default 1 MiB).
+    /// * `MAX_JSON_BODY_BYTES` - Reject JSON bodies larger than this many bytes (default 256 KiB).
+    /// * `EVENT_KEEP_ALIVE_SECS` - Keep idle event streams and sockets open (default 15).
+    /// * `IDEMPOTENCY_WINDOW_SECS` - Replay creates with the same key for this long (default 1 day).
+    /// * `IDEMPOTENCY_MAX_KEYS` - Remember at most this many idempotency keys (default 100000).
+    pub fn from_env() -> Self {
+        let defaults = Settings::default();
+        Settings {
+            max_batch_size: parse_env("MAX_BATCH_SIZE").unwrap_or(defaults.max_batch_size),
+            max_unpaged_items: parse_env("MAX_UNPAGED_ITEMS").unwrap_or(defaults.max_unpaged_items),
+            unique_titles: parse_env("UNIQUE_TITLES").unwrap_or(defaults.unique_titles),
+            fuzzy_search: parse_env("FUZZY_SEARCH").unwrap_or(defaults.fuzzy_search),
+            create_batch_window_ms: parse_env("CREATE_BATCH_WINDOW_MS")
+                .unwrap_or(defaults.create_batch_window_ms),
+            reminder_webhook_url: parse_env("REMINDER_WEBHOOK_URL")
+                .filter(|url: &String| !url.is_empty())
+                .or(defaults.reminder_webhook_url),
+            reminder_scan_interval_secs: parse_env("REMINDER_SCAN_INTERVAL_SECS")
+                .unwrap_or(defaults.reminder_scan_interval_secs),
+            rate_limit_requests: parse_env("RATE_LIMIT_REQUESTS")
+                .unwrap_or(defaults.rate_limit_requests),
+            rate_limit_window_secs: parse_env("RATE_LIMIT_WINDOW_SECS")
+                .unwrap_or(defaults.rate_limit_window_secs),
+            timestamp_precision: parse_env("TIMESTAMP_PRECISION")
+                .unwrap_or(defaults.timestamp_precision),
+            deprecation_headers: parse_env("DEPRECATION_HEADERS")
+                .unwrap_or(defaults.deprecation_headers),
+            jwt_secret: parse_env("JWT_SECRET")
+                .filter(|secret: &String| !secret.is_empty())
+                .or(defaults.jwt_secret),
+            hmac_signing_secret: parse_env("HMAC_SIGNING_SECRET")
+                .filter(|secret: &String| !secret.is_empty())
+                .or(defaults.hmac_signing_secret),
+            allowed_origins: parse_env("ALLOWED_ORIGINS")
+                .map(|origins: String| {
+                    origins
+                        .split(',')
+                        .map(|origin| origin.trim().to_string())
+                        .filter(|origin| !origin.is_empty())
+                        .collect()
+                })
+                .unwrap_or(defaults.allowed_origins),
+            readiness_delay_secs: parse_env("READINESS_DELAY_SECS")
+                .unwrap_or(defaults.readiness_delay_secs),
+            cascade_complete_subtasks: parse_env("CASCADE_COMPLETE_SUBTASKS")
+                .unwrap_or(defaults.cascade_complete_subtasks),
+            require_subtasks_done: parse_env("REQUIRE_SUBTASKS_DONE")
+                .unwrap_or(defaults.require_subtasks_done),
+            soft_delete_retention_days: parse_env("SOFT_DELETE_RETENTION_DAYS")
+                .unwrap_or(defaults.soft_delete_retention_days),
+            max_import_bytes: parse_env("MAX_IMPORT_BYTES").unwrap_or(defaults.max_import_bytes),
+            max_json_body_bytes: parse_env("MAX_JSON_BODY_BYTES")
+                .unwrap_or(defaults.max_json_body_bytes),
+            event_keep_alive_secs: parse_env("EVENT_KEEP_ALIVE_SECS")
+                .unwrap_or(defaults.event_keep_alive_secs),
+            idempotency_window_secs: parse_env("IDEMPOTENCY_WINDOW_SECS")
+                .unwrap_or(defaults.idempotency_window_secs),
+            idempotency_max_keys: parse_env("IDEMPOTENCY_MAX_KEYS")
+                .unwrap_or(defaults.idempotency_max_keys),
+        }
+    }
+
+    /// Returns how long deleted todo items are kept as tombstones, `None` when they are never
+    /// purged.
+    pub fn soft_delete_retention(&self) -> Option<Duration> {
+        (self.soft_delete_retention_days > 0)
+            .then(|| Duration::from_secs(self.soft_delete_retention_days * 24 * 60 * 60))
+    }
+}
+
+// Reads and parses an environment variable, ignoring values that can't be parsed.
+fn parse_env<T: std::str::FromStr>(key: &str) -> Option<T> {
+    env::var(key).ok().and_then(|value| value.parse().ok())
+}
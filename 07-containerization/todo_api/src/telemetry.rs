@@ -0,0 +1,285 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::error::BlockingError;
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+
+#[cfg(feature = "telemetry")]
+pub use otel::*;
+
+/// Runs a blocking call on the thread pool like `web::block`, within the trace of the caller.
+///
+/// Without the `telemetry` feature this is just `web::block`. With it, the database queries of
+/// the call are traced as children of the span of the request running it.
+pub async fn block<F, R>(f: F) -> Result<R, BlockingError>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    #[cfg(feature = "telemetry")]
+    let f = {
+        let context = opentelemetry::Context::current();
+        move || {
+            let _attached = context.attach();
+            f()
+        }
+    };
+    web::block(f).await
+}
+
+/// Starts exporting the spans, never when the `telemetry` feature is disabled.
+#[cfg(not(feature = "telemetry"))]
+pub fn init() -> Option<Telemetry> {
+    None
+}
+
+/// The exporter of the spans, unavailable without the `telemetry` feature.
+#[cfg(not(feature = "telemetry"))]
+pub enum Telemetry {}
+
+#[cfg(not(feature = "telemetry"))]
+impl Telemetry {
+    /// Flushes the pending spans.
+    pub fn shutdown(self) {}
+}
+
+/// Middleware tracing every request, a no-op without the `telemetry` feature.
+#[cfg(not(feature = "telemetry"))]
+pub async fn trace_requests(
+    request: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    next.call(request).await
+}
+
+#[cfg(feature = "telemetry")]
+mod otel {
+    use super::*;
+    use actix_web::http::header::{HeaderMap, HeaderName};
+    use diesel::connection::{set_default_instrumentation, Instrumentation, InstrumentationEvent};
+    use log::{info, warn};
+    use opentelemetry::global::{self, BoxedSpan};
+    use opentelemetry::propagation::Extractor;
+    use opentelemetry::trace::{FutureExt, Span, SpanKind, Status, TraceContextExt, Tracer};
+    use opentelemetry::{Context, KeyValue};
+    use opentelemetry_otlp::SpanExporter;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::Resource;
+    use std::collections::HashMap;
+    use std::env;
+
+    /// The name of the tracer, and the service when `OTEL_SERVICE_NAME` isn't set.
+    const NAME: &str = "todo_api";
+
+    /// The exporter of the spans, which has to be shut down to flush the pending spans.
+    pub struct Telemetry {
+        provider: SdkTracerProvider,
+    }
+
+    impl Telemetry {
+        /// Flushes the pending spans and stops exporting.
+        pub fn shutdown(self) {
+            if let Err(error) = self.provider.shutdown() {
+                warn!("Unable to flush the pending spans: {}", error);
+            }
+        }
+    }
+
+    /// Starts exporting spans via OTLP (HTTP) to the collector at `OTEL_EXPORTER_OTLP_ENDPOINT`.
+    ///
+    /// Registers the W3C trace context propagator and traces the database queries of every
+    /// connection from now on. Tracing stays disabled when the endpoint isn't set.
+    pub fn init() -> Option<Telemetry> {
+        let endpoint = env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+            .ok()
+            .filter(|endpoint| !endpoint.is_empty())?;
+
+        // The exporter reads the endpoint, along with the other OTEL_EXPORTER_OTLP_* variables
+        let exporter = match SpanExporter::builder().with_http().build() {
+            Ok(exporter) => exporter,
+            Err(error) => {
+                warn!("Unable to export traces to {}: {}", endpoint, error);
+                return None;
+            }
+        };
+        let service_name = env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| NAME.to_string());
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(Resource::builder().with_service_name(service_name).build())
+            .build();
+        global::set_tracer_provider(provider.clone());
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        if set_default_instrumentation(query_spans).is_err() {
+            warn!("Unable to trace the database queries");
+        }
+
+        info!("Exporting traces to {}", endpoint);
+        Some(Telemetry { provider })
+    }
+
+    /// Middleware tracing every request as a server span.
+    ///
+    /// Continues the trace of the `traceparent` header of the request, if any. The span is named
+    /// after the matched route, e.g. `GET /todo/{id}`, and records the status of the response.
+    pub async fn trace_requests(
+        request: ServiceRequest,
+        next: Next<impl MessageBody>,
+    ) -> Result<ServiceResponse<impl MessageBody>, Error> {
+        let parent = global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        let method = request.method().to_string();
+        let tracer = global::tracer(NAME);
+        let span = tracer
+            .span_builder(method.clone())
+            .with_kind(SpanKind::Server)
+            .with_attributes([
+                KeyValue::new("http.request.method", method.clone()),
+                KeyValue::new("url.path", request.path().to_string()),
+            ])
+            .start_with_context(&tracer, &parent);
+        let context = parent.with_span(span);
+
+        let result = next.call(request).with_context(context.clone()).await;
+
+        let span = context.span();
+        match &result {
+            Ok(response) => {
+                if let Some(route) = response.request().match_pattern() {
+                    span.update_name(format!("{} {}", method, route));
+                    span.set_attribute(KeyValue::new("http.route", route));
+                }
+                let status = response.status();
+                span.set_attribute(KeyValue::new(
+                    "http.response.status_code",
+                    i64::from(status.as_u16()),
+                ));
+                if status.is_server_error() {
+                    span.set_status(Status::error(status.to_string()));
+                }
+            }
+            Err(error) => span.set_status(Status::error(error.to_string())),
+        }
+        span.end();
+        result
+    }
+
+    /// Starts a client span for an outgoing request and passes it along in its `traceparent`
+    /// header, so the receiver continues the trace.
+    ///
+    /// The span ends once the returned context is dropped.
+    ///
+    ///  # Arguments
+    ///
+    ///  * `name` - The name of the span, e.g. the method and the receiver.
+    ///  * `request` - The outgoing request.
+    pub fn trace_outgoing(name: &'static str, request: ureq::Request) -> (ureq::Request, Context) {
+        let span = global::tracer(NAME)
+            .span_builder(name)
+            .with_kind(SpanKind::Client)
+            .start(&global::tracer(NAME));
+        let context = Context::current().with_span(span);
+        let mut headers = HashMap::new();
+        global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut headers)
+        });
+        let request = headers
+            .iter()
+            .fold(request, |request, (key, value)| request.set(key, value));
+        (request, context)
+    }
+
+    // Reads the trace context from the request headers.
+    struct HeaderExtractor<'a>(&'a HeaderMap);
+
+    impl Extractor for HeaderExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|value| value.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(HeaderName::as_str).collect()
+        }
+    }
+
+    // Traces every query of a connection as a span, a child of the span that runs it.
+    #[derive(Default)]
+    struct QuerySpans {
+        // The spans of the queries in progress, nested ones last
+        spans: Vec<BoxedSpan>,
+    }
+
+    impl Instrumentation for QuerySpans {
+        fn on_connection_event(&mut self, event: InstrumentationEvent<'_>) {
+            match event {
+                InstrumentationEvent::StartQuery { query, .. } => {
+                    // Only the statement, the bound values could contain personal data
+                    let query = query.to_string();
+                    let statement = query
+                        .split(" -- binds:")
+                        .next()
+                        .unwrap_or_default()
+                        .to_string();
+                    let operation = statement
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or("QUERY")
+                        .to_uppercase();
+                    let tracer = global::tracer(NAME);
+                    let span = tracer
+                        .span_builder(operation)
+                        .with_kind(SpanKind::Client)
+                        .with_attributes([
+                            KeyValue::new("db.system.name", "postgresql"),
+                            KeyValue::new("db.query.text", statement),
+                        ])
+                        .start(&tracer);
+                    self.spans.push(span);
+                }
+                InstrumentationEvent::FinishQuery { error, .. } => {
+                    if let Some(mut span) = self.spans.pop() {
+                        if let Some(error) = error {
+                            span.set_status(Status::error(error.to_string()));
+                        }
+                        span.end();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn query_spans() -> Option<Box<dyn Instrumentation>> {
+        Some(Box::new(QuerySpans::default()))
+    }
+}
+
+#[cfg(all(test, feature = "telemetry"))]
+mod tests {
+    use super::*;
+    use opentelemetry::global;
+    use opentelemetry::trace::{
+        SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState,
+    };
+    use opentelemetry::Context;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+
+    #[test]
+    fn test_trace_outgoing_propagates_traceparent() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let parent = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let _attached = Context::new().with_remote_span_context(parent).attach();
+
+        let (request, _span) = trace_outgoing("POST test", ureq::post("http://localhost/"));
+        let traceparent = request.header("traceparent").unwrap();
+        assert!(traceparent.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+        assert!(traceparent.ends_with("-01"));
+    }
+}
@@ -1,4 +1,69 @@
 pub mod models;
+pub use models::capabilities::Capabilities;
+pub use models::capabilities::CapabilityLimits;
+pub use models::error_response::ErrorResponse;
+pub use models::health::HealthStatus;
+pub use models::timestamp::set_timestamp_precision;
+pub use models::timestamp::TimestampPrecision;
+pub use models::todo_activity::ActivityEntry;
+pub use models::todo_checklist::AddChecklistItemRequest;
+pub use models::todo_checklist::ChecklistItem;
+pub use models::todo_checklist::ChecklistProgress;
+pub use models::todo_delete::DeleteTodoItemsRequest;
+pub use models::todo_delete::DeleteTodoItemsResponse;
+pub use models::todo_delete::PurgeDeletedResponse;
+pub use models::todo_dependency::AddDependencyRequest;
+pub use models::todo_dependency::TodoGraph;
+pub use models::todo_dependency::TodoGraphEdge;
+pub use models::todo_dependency::TodoGraphNode;
+pub use models::todo_diff::FieldChange;
+pub use models::todo_diff::TodoDiff;
+pub use models::todo_event::TodoEvent;
+pub use models::todo_event::TodoEventKind;
+pub use models::todo_import::ImportError;
+pub use models::todo_import::ImportSummary;
 pub use models::todo_item::CreateTodoItemRequest;
+pub use models::todo_item::EffortSummary;
+pub use models::todo_item::PatchTodoItemRequest;
+pub use models::todo_item::TodoCount;
 pub use models::todo_item::TodoItem;
+pub use models::todo_item::TodoPage;
+pub use models::todo_item::TodoStats;
 pub use models::todo_item::UpdateTodoItemRequest;
+pub use models::todo_owner::ReassignOwnerRequest;
+pub use models::todo_owner::ReassignOwnerResponse;
+pub use models::todo_query::CompletedQuery;
+pub use models::todo_query::CountMode;
+pub use models::todo_query::CountQuery;
+pub use models::todo_query::CursorQuery;
+pub use models::todo_query::DeleteQuery;
+pub use models::todo_query::ExportQuery;
+pub use models::todo_query::FieldsQuery;
+pub use models::todo_query::ImportQuery;
+pub use models::todo_query::IncludeDeletedQuery;
+pub use models::todo_query::PageQuery;
+pub use models::todo_query::PurgeQuery;
+pub use models::todo_query::ReturnQuery;
+pub use models::todo_query::ReturnRepresentation;
+pub use models::todo_query::SearchQuery;
+pub use models::todo_query::SortOrder;
+pub use models::todo_query::SortQuery;
+pub use models::todo_query::TagQuery;
+pub use models::todo_query::TodoSortField;
+pub use models::todo_query::DEFAULT_PAGE_SIZE;
+pub use models::todo_query::MAX_PAGE_SIZE;
+pub use models::todo_recurrence::validate_recurrence;
+pub use models::todo_recurrence::Frequency;
+pub use models::todo_recurrence::OccurrencesQuery;
+pub use models::todo_recurrence::Recurrence;
+pub use models::todo_recurrence::TodoOccurrences;
+pub use models::todo_status::TodoStatus;
+pub use models::todo_status::TodoStatusResponse;
+pub use models::todo_sync::SyncOperation;
+pub use models::todo_sync::SyncOperationKind;
+pub use models::todo_sync::SyncOperationResult;
+pub use models::todo_tag::tag_namespaces;
+pub use models::todo_tag::AddTagRequest;
+pub use models::todo_tag::TagFilter;
+pub use models::todo_tag::TagStats;
+pub use models::todo_tag::TagStatsRequest;
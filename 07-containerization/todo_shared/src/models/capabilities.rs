@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct Capabilities {
+    // The names of the optional features enabled in this deployment
+    pub features: Vec<String>,
+
+    // The limits enforced by this deployment
+    pub limits: CapabilityLimits,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct CapabilityLimits {
+    // The maximum number of operations in a single batch request
+    pub max_batch_size: usize,
+
+    // The maximum number of todo items in a single page
+    pub max_page_size: i64,
+
+    // The number of days deleted todo items are kept before they are purged, 0 when they are
+    // kept until restored
+    #[serde(default)]
+    pub soft_delete_retention_days: u64,
+}
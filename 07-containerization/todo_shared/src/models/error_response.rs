@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ErrorResponse {
+    // The HTTP status code of the response
+    pub status: u16,
+
+    // A human readable description of the error
+    pub message: String,
+
+    // A stable code of the error, e.g. `NOT_FOUND`, for clients to branch on instead of the message
+    #[serde(default)]
+    pub error_code: String,
+}
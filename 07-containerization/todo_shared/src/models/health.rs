@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct HealthStatus {
+    // The state of the instance, e.g. `ok`, `ready` or `warming_up`
+    pub status: String,
+}
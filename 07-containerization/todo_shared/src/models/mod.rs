@@ -1 +1,18 @@
+pub mod capabilities;
+pub mod error_response;
+pub mod health;
+pub mod timestamp;
+pub mod todo_activity;
+pub mod todo_checklist;
+pub mod todo_delete;
+pub mod todo_dependency;
+pub mod todo_diff;
+pub mod todo_event;
+pub mod todo_import;
 pub mod todo_item;
+pub mod todo_owner;
+pub mod todo_query;
+pub mod todo_recurrence;
+pub mod todo_status;
+pub mod todo_sync;
+pub mod todo_tag;
@@ -0,0 +1,129 @@
+use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
+use serde::Serializer;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// The precision timestamps are serialized with, the microsecond precision of the data store until
+// the application configures it.
+static PRECISION: AtomicU8 = AtomicU8::new(TimestampPrecision::Micros as u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampPrecision {
+    // Whole seconds, without sub-second digits
+    Seconds,
+
+    // Whole milliseconds
+    Millis,
+
+    // Whole microseconds
+    Micros,
+}
+
+impl TimestampPrecision {
+    /// Truncates the given point in time to this precision.
+    pub fn truncate(self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let digits = match self {
+            TimestampPrecision::Seconds => 0,
+            TimestampPrecision::Millis => 3,
+            TimestampPrecision::Micros => 6,
+        };
+        time.trunc_subsecs(digits)
+    }
+
+    /// Formats the given point in time as an RFC 3339 string in UTC, e.g.
+    /// `2022-09-29T12:00:00.123Z`, with the sub-second digits of this precision.
+    pub fn format(self, time: &DateTime<Utc>) -> String {
+        let format = match self {
+            TimestampPrecision::Seconds => SecondsFormat::Secs,
+            TimestampPrecision::Millis => SecondsFormat::Millis,
+            TimestampPrecision::Micros => SecondsFormat::Micros,
+        };
+        time.to_rfc3339_opts(format, true)
+    }
+}
+
+impl FromStr for TimestampPrecision {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "seconds" => Ok(TimestampPrecision::Seconds),
+            "millis" => Ok(TimestampPrecision::Millis),
+            "micros" => Ok(TimestampPrecision::Micros),
+            _ => Err(format!(
+                "Invalid timestamp precision `{}`, expected seconds, millis or micros",
+                value
+            )),
+        }
+    }
+}
+
+/// Sets the precision all timestamps are serialized with.
+pub fn set_timestamp_precision(precision: TimestampPrecision) {
+    PRECISION.store(precision as u8, Ordering::Relaxed);
+}
+
+/// Returns the precision all timestamps are serialized with.
+pub fn timestamp_precision() -> TimestampPrecision {
+    match PRECISION.load(Ordering::Relaxed) {
+        0 => TimestampPrecision::Seconds,
+        1 => TimestampPrecision::Millis,
+        _ => TimestampPrecision::Micros,
+    }
+}
+
+/// Serializes a timestamp as an RFC 3339 string with the configured precision, for
+/// `#[serde(serialize_with)]`.
+pub fn serialize<S: Serializer>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&timestamp_precision().format(time))
+}
+
+/// Serializes an optional timestamp as an RFC 3339 string with the configured precision.
+pub fn serialize_option<S: Serializer>(
+    time: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match time {
+        Some(time) => serializer.serialize_some(&timestamp_precision().format(time)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Serializes a list of timestamps as RFC 3339 strings with the configured precision.
+pub fn serialize_all<S: Serializer>(
+    times: &[DateTime<Utc>],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(times.iter().map(|time| timestamp_precision().format(time)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Stamped {
+        #[serde(serialize_with = "serialize")]
+        at: DateTime<Utc>,
+    }
+
+    #[test]
+    fn test_serialize_without_sub_second_digits() {
+        let time = DateTime::from_timestamp(1_700_000_000, 123_456_789).unwrap();
+
+        set_timestamp_precision(TimestampPrecision::Seconds);
+        let stamped = serde_json::to_value(Stamped { at: time }).unwrap();
+        assert_eq!(stamped["at"], "2023-11-14T22:13:20Z");
+
+        assert_eq!(
+            TimestampPrecision::Millis.format(&time),
+            "2023-11-14T22:13:20.123Z"
+        );
+        let micros = TimestampPrecision::Micros.truncate(time);
+        assert_eq!(
+            micros,
+            DateTime::from_timestamp(1_700_000_000, 123_456_000).unwrap()
+        );
+    }
+}
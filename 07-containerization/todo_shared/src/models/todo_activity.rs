@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::timestamp;
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ActivityEntry {
+    // The unique identifier of the activity entry
+    pub id: Uuid,
+
+    // The identifier of the todo item that was changed
+    pub todo_id: Uuid,
+
+    // The subject of the user that made the change
+    pub actor: String,
+
+    // The kind of change, e.g. `create`, `update`, `delete` or `reassign`
+    pub action: String,
+
+    // A human readable description of the change
+    pub details: Option<String>,
+
+    // Timestamp when the change was made
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct ChecklistItem {
+    // The text of the checklist item
+    pub text: String,
+
+    // Indicates whether the checklist item is done
+    pub done: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+pub struct ChecklistProgress {
+    // The number of checklist items that are done
+    pub done: usize,
+
+    // The total number of checklist items
+    pub total: usize,
+}
+
+impl ChecklistProgress {
+    /// Computes the progress of the given checklist.
+    pub fn of(checklist: &[ChecklistItem]) -> Self {
+        ChecklistProgress {
+            done: checklist.iter().filter(|item| item.done).count(),
+            total: checklist.len(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema, Validate)]
+pub struct AddChecklistItemRequest {
+    // The text of the checklist item to add, which starts out not done
+    #[validate(length(min = 1, max = 255))]
+    pub text: String,
+}
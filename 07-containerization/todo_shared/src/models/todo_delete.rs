@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct DeleteTodoItemsRequest {
+    // The unique identifiers of the todo items to delete
+    pub ids: Vec<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct DeleteTodoItemsResponse {
+    // The number of deleted todo items, lower than the number of ids when some didn't exist
+    pub count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct PurgeDeletedResponse {
+    // The number of soft-deleted todo items that were permanently removed
+    pub count: usize,
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct AddDependencyRequest {
+    // The identifier of the todo item that has to be done first
+    pub blocked_by: Uuid,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct TodoGraphNode {
+    // The unique identifier of the todo item
+    pub id: Uuid,
+
+    // The title of the todo item
+    pub title: String,
+
+    // Indicates whether the todo item is completed
+    pub completed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub struct TodoGraphEdge {
+    // The identifier of the todo item that blocks the other one
+    pub from: Uuid,
+
+    // The identifier of the todo item that is blocked
+    pub to: Uuid,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct TodoGraph {
+    // The todo items
+    pub nodes: Vec<TodoGraphNode>,
+
+    // The dependencies between the todo items, which never form a cycle
+    pub edges: Vec<TodoGraphEdge>,
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    // The value of the field before the update
+    pub from: Value,
+
+    // The value of the field after the update
+    pub to: Value,
+}
+
+// The changed fields of a todo item, keyed by field name
+pub type TodoDiff = BTreeMap<String, FieldChange>;
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TodoEventKind {
+    // A todo item was created, or restored after it was deleted
+    Created,
+
+    // An existing todo item was changed
+    Updated,
+
+    // A todo item was deleted
+    Deleted,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub struct TodoEvent {
+    // The kind of change
+    pub kind: TodoEventKind,
+
+    // The unique identifier of the changed todo item
+    pub id: Uuid,
+}
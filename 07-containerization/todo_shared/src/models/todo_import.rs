@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct ImportError {
+    // The (0-based) position of the invalid todo item in the import
+    pub index: usize,
+
+    // Why the todo item couldn't be imported
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ImportSummary {
+    // The number of imported todo items
+    pub imported: usize,
+
+    // The number of todo items that were skipped because they are invalid
+    pub failed: usize,
+
+    // The reason every skipped todo item couldn't be imported
+    pub errors: Vec<ImportError>,
+}
@@ -1,9 +1,16 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::time::SystemTime;
 use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::models::timestamp;
+use crate::models::todo_recurrence::validate_recurrence;
+use crate::models::todo_tag::validate_tags;
+use crate::{ChecklistItem, ChecklistProgress};
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
 pub struct TodoItem {
     // The unique identifier of the todo item
     pub id: Uuid,
@@ -17,30 +24,219 @@ pub struct TodoItem {
     // Indicates whether the todo item is completed
     pub completed: bool,
 
-    // Epoch timestamp when the todo item was completed
-    pub completed_at: Option<SystemTime>,
+    // Timestamp when the todo item was completed
+    #[serde(serialize_with = "timestamp::serialize_option")]
+    pub completed_at: Option<DateTime<Utc>>,
+
+    // Timestamp when the todo item was created
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub created_at: DateTime<Utc>,
+
+    // Timestamp when the todo item is due, if it has a deadline
+    #[serde(serialize_with = "timestamp::serialize_option")]
+    pub due_date: Option<DateTime<Utc>>,
+
+    // Timestamp when the todo item was deleted, only visible to admins
+    #[serde(serialize_with = "timestamp::serialize_option")]
+    pub deleted_at: Option<DateTime<Utc>>,
+
+    // The estimated effort in minutes
+    pub estimated_minutes: Option<i32>,
+
+    // The actual effort in minutes
+    pub actual_minutes: Option<i32>,
+
+    // The subject of the user owning the todo item
+    pub owner: Option<String>,
+
+    // The number of minutes before the due date to send a reminder, at the due date when absent
+    pub remind_before_minutes: Option<i32>,
+
+    // Timestamp when the reminder of the todo item was sent
+    #[serde(serialize_with = "timestamp::serialize_option")]
+    pub reminded_at: Option<DateTime<Utc>>,
+
+    // The checklist embedded in the todo item
+    #[serde(default)]
+    pub checklist: Vec<ChecklistItem>,
+
+    // The number of checklist items that are done, out of the total
+    #[serde(default)]
+    pub checklist_progress: ChecklistProgress,
+
+    // The tags of the todo item, optionally namespaced like `project/alpha`
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    // The todo item this todo item is a subtask of, if any
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
 
-    // Epoch timestamp when the todo item was created
-    pub created_at: SystemTime,
+    // The recurrence rule of the todo item, e.g. `FREQ=WEEKLY;INTERVAL=1`, if it repeats
+    #[serde(default)]
+    pub recurrence: Option<String>,
+
+    // Timestamp when the todo item was last changed
+    #[serde(serialize_with = "timestamp::serialize")]
+    pub updated_at: DateTime<Utc>,
+
+    // The version of the todo item, incremented on every change
+    pub version: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug, ToSchema)]
+impl TodoItem {
+    // The names of the serialized fields, e.g. to select some of them
+    pub const FIELDS: [&'static str; 20] = [
+        "id",
+        "title",
+        "description",
+        "completed",
+        "completed_at",
+        "created_at",
+        "due_date",
+        "deleted_at",
+        "estimated_minutes",
+        "actual_minutes",
+        "owner",
+        "remind_before_minutes",
+        "reminded_at",
+        "checklist",
+        "checklist_progress",
+        "tags",
+        "parent_id",
+        "recurrence",
+        "updated_at",
+        "version",
+    ];
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema, Validate)]
 pub struct UpdateTodoItemRequest {
     // The new title of the todo item
+    #[validate(length(min = 1, max = 255))]
     pub new_title: String,
 
     // The new description of the todo item
+    #[validate(length(max = 4096))]
     pub new_description: String,
 
     // Indicates whether the todo item is completed
     pub completed: bool,
+
+    // Timestamp when the todo item is due, no deadline when absent
+    pub due_date: Option<DateTime<Utc>>,
+
+    // The estimated effort in minutes
+    pub estimated_minutes: Option<i32>,
+
+    // The actual effort in minutes
+    pub actual_minutes: Option<i32>,
+
+    // The number of minutes before the due date to send a reminder, at the due date when absent
+    pub remind_before_minutes: Option<i32>,
+
+    // The version of the todo item the update is based on, rejected when it has changed since
+    pub expected_version: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[derive(Serialize, Deserialize, Debug, Default, ToSchema, Validate)]
+pub struct PatchTodoItemRequest {
+    // The new title of the todo item, unchanged when absent
+    #[validate(length(min = 1, max = 255))]
+    pub title: Option<String>,
+
+    // The new description of the todo item, unchanged when absent
+    #[validate(length(max = 4096))]
+    pub description: Option<String>,
+
+    // Indicates whether the todo item is completed, unchanged when absent
+    pub completed: Option<bool>,
+
+    // The version of the todo item the patch is based on, not checked when absent
+    pub expected_version: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema, Validate)]
+#[cfg_attr(
+    feature = "graphql",
+    derive(async_graphql::InputObject),
+    graphql(name = "CreateTodoInput")
+)]
 pub struct CreateTodoItemRequest {
     // The title of the todo item
+    #[validate(length(min = 1, max = 255))]
     pub title: String,
 
     // The description of the todo item
+    #[validate(length(max = 4096))]
     pub description: String,
+
+    // Timestamp when the todo item is due, no deadline when absent
+    pub due_date: Option<DateTime<Utc>>,
+
+    // The estimated effort in minutes
+    pub estimated_minutes: Option<i32>,
+
+    // The actual effort in minutes
+    pub actual_minutes: Option<i32>,
+
+    // The number of minutes before the due date to send a reminder, at the due date when absent
+    pub remind_before_minutes: Option<i32>,
+
+    // The tags of the todo item, optionally namespaced like `project/alpha`
+    #[serde(default)]
+    #[cfg_attr(feature = "graphql", graphql(default))]
+    #[validate(custom = "validate_tags")]
+    pub tags: Vec<String>,
+
+    // The todo item to create this todo item as a subtask of, if any
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+
+    // The recurrence rule of the todo item, e.g. `FREQ=WEEKLY;INTERVAL=1`, if it repeats
+    #[serde(default)]
+    #[validate(custom = "validate_recurrence")]
+    pub recurrence: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct TodoPage {
+    // The todo items of the page, ordered by creation time
+    pub items: Vec<TodoItem>,
+
+    // The cursor to request the next page with as `after`, absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct TodoCount {
+    // The number of todo items matching the filter
+    pub count: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct TodoStats {
+    // The number of todo items
+    pub total: i64,
+
+    // The number of completed todo items
+    pub completed: i64,
+
+    // The number of todo items that are not completed yet
+    pub pending: i64,
+
+    // The number of todo items completed within the last 7 days
+    pub completed_last_7_days: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct EffortSummary {
+    // The number of completed todo items
+    pub completed_items: i64,
+
+    // The total estimated effort in minutes of the completed todo items
+    pub total_estimated_minutes: i64,
+
+    // The total actual effort in minutes of the completed todo items
+    pub total_actual_minutes: i64,
 }
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ReassignOwnerRequest {
+    // The subject of the current owner of the todo items
+    pub from: String,
+
+    // The subject of the new owner of the todo items
+    pub to: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ReassignOwnerResponse {
+    // The number of reassigned todo items
+    pub count: i64,
+}
@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct IncludeDeletedQuery {
+    // Include soft-deleted todo items, only allowed for admins
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    // Only return todo items whose title or description contains this term
+    pub q: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CompletedQuery {
+    // Only return todo items that are (not) completed, all todo items when absent
+    pub completed: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FieldsQuery {
+    // Only return these comma-separated fields of the todo items, e.g. `id,title`
+    pub fields: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TagQuery {
+    // Only return todo items with this tag, or with any tag in a namespace as `<namespace>/*`
+    pub tag: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteQuery {
+    // Only delete the todo item when it is completed
+    #[serde(default)]
+    pub only_if_completed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    // Import the valid todo items and report the invalid ones, rather than rejecting the import
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PurgeQuery {
+    // Only purge todo items deleted more than this many days ago, 30 days when absent
+    pub older_than_days: Option<u64>,
+}
+
+/// The number of todo items in a page when no page size is given.
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+
+/// The maximum number of todo items in a page, larger pages are rejected with 400 bad request.
+pub const MAX_PAGE_SIZE: i64 = 200;
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    // The number of todo items to skip, at least 0
+    pub offset: Option<i64>,
+
+    // The maximum number of todo items to return, `DEFAULT_PAGE_SIZE` by default and at most
+    // `MAX_PAGE_SIZE`
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CursorQuery {
+    // Only return the todo items after this cursor, an empty cursor starts at the first todo item
+    pub after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    // Only export a single partition of the todo items, formatted as `<index>/<count>` (e.g. `2/5`)
+    pub shard: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoSortField {
+    // Sort by the time the todo item was created
+    CreatedAt,
+
+    // Sort by the title of the todo item
+    Title,
+
+    // Sort by the time the todo item was completed, open todo items last
+    CompletedAt,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    // Smallest values first
+    #[default]
+    Asc,
+
+    // Largest values first
+    Desc,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SortQuery {
+    // The field to sort the todo items by, unknown fields are rejected
+    #[param(inline)]
+    pub sort: Option<TodoSortField>,
+
+    // The direction to sort the todo items in, ascending by default
+    #[param(inline)]
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CountMode {
+    // Count the matching todo items exactly
+    #[default]
+    Exact,
+
+    // Estimate the total from the table statistics when unfiltered, which is faster on huge tables
+    Estimate,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CountQuery {
+    // How to determine the total number of todo items when paging, exact by default
+    #[param(inline)]
+    #[serde(default)]
+    pub count: CountMode,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReturnRepresentation {
+    // Return the resulting todo item
+    #[default]
+    Item,
+
+    // Return the changed fields of the todo item
+    Diff,
+
+    // Return only the new values of the changed fields, along with the id of the todo item
+    Changed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ReturnQuery {
+    // The representation to return after modifying a todo item
+    #[serde(rename = "return", default)]
+    pub representation: ReturnRepresentation,
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::ValidationError;
+
+use crate::models::timestamp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    // Repeats every given number of days
+    Daily,
+
+    // Repeats every given number of weeks, on the same weekday
+    Weekly,
+
+    // Repeats every given number of months, on the same day of the month or the last day of
+    // shorter months
+    Monthly,
+}
+
+/// A recurrence rule, written as a subset of the iCalendar (RFC 5545) `RRULE` syntax, e.g.
+/// `FREQ=WEEKLY;INTERVAL=2` for every other week.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recurrence {
+    // How often the todo item repeats
+    pub frequency: Frequency,
+
+    // The number of days, weeks or months between occurrences, at least 1
+    pub interval: u32,
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let mut frequency = None;
+        let mut interval = 1;
+        for part in rule.split(';') {
+            match part.split_once('=') {
+                Some(("FREQ", "DAILY")) => frequency = Some(Frequency::Daily),
+                Some(("FREQ", "WEEKLY")) => frequency = Some(Frequency::Weekly),
+                Some(("FREQ", "MONTHLY")) => frequency = Some(Frequency::Monthly),
+                Some(("INTERVAL", value)) => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| format!("Invalid interval `{}` in `{}`", value, rule))?;
+                }
+                _ => return Err(format!("Unsupported part `{}` in `{}`", part, rule)),
+            }
+        }
+
+        frequency
+            .map(|frequency| Recurrence {
+                frequency,
+                interval,
+            })
+            .ok_or_else(|| format!("Missing FREQ in recurrence rule `{}`", rule))
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+        write!(f, "FREQ={};INTERVAL={}", frequency, self.interval)
+    }
+}
+
+/// Validates the recurrence rule of a request, for `#[validate(custom)]`.
+pub fn validate_recurrence(rule: &str) -> Result<(), ValidationError> {
+    rule.parse::<Recurrence>().map(|_| ()).map_err(|reason| {
+        let mut error = ValidationError::new("recurrence");
+        error.message = Some(reason.into());
+        error
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OccurrencesQuery {
+    // The number of upcoming occurrences to return, 5 when absent
+    pub count: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct TodoOccurrences {
+    // The unique identifier of the recurring todo item
+    pub id: Uuid,
+
+    // The recurrence rule the occurrences are computed from
+    pub recurrence: String,
+
+    // Timestamps of the upcoming due dates, following the current due date
+    #[serde(serialize_with = "timestamp::serialize_all")]
+    pub occurrences: Vec<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_recurrence_rule() {
+        let rule: Recurrence = "FREQ=WEEKLY;INTERVAL=2".parse().unwrap();
+        assert_eq!(rule.frequency, Frequency::Weekly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.to_string(), "FREQ=WEEKLY;INTERVAL=2");
+
+        let rule: Recurrence = "FREQ=DAILY".parse().unwrap();
+        assert_eq!(rule.interval, 1);
+
+        for invalid in [
+            "",
+            "FREQ=YEARLY",
+            "INTERVAL=2",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=daily",
+        ] {
+            assert!(invalid.parse::<Recurrence>().is_err(), "{}", invalid);
+        }
+    }
+}
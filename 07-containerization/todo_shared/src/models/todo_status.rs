@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum TodoStatus {
+    // The due date of the todo item has passed
+    #[serde(rename = "overdue")]
+    Overdue,
+
+    // The todo item is due today
+    #[serde(rename = "due today")]
+    DueToday,
+
+    // The todo item is completed
+    #[serde(rename = "completed")]
+    Completed,
+
+    // The todo item is due on a later day
+    #[serde(rename = "upcoming")]
+    Upcoming,
+
+    // The todo item has no due date
+    #[serde(rename = "no due date")]
+    NoDueDate,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct TodoStatusResponse {
+    // The unique identifier of the todo item
+    pub id: Uuid,
+
+    // The status derived from the completion and due date of the todo item
+    pub status: TodoStatus,
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::TodoItem;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncOperationKind {
+    // Create a new todo item
+    Create,
+
+    // Update an existing todo item
+    Update,
+
+    // Delete an existing todo item
+    Delete,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct SyncOperation {
+    // The kind of operation to apply
+    pub op: SyncOperationKind,
+
+    // The identifier of the todo item, required for update and delete
+    pub id: Option<Uuid>,
+
+    // The title of the todo item, required for create and update
+    pub title: Option<String>,
+
+    // The description of the todo item, required for create and update
+    pub description: Option<String>,
+
+    // Indicates whether the todo item is completed, required for update
+    pub completed: Option<bool>,
+
+    // Timestamp when the todo item is due, no deadline when absent
+    pub due_date: Option<DateTime<Utc>>,
+
+    // The estimated effort in minutes
+    pub estimated_minutes: Option<i32>,
+
+    // The actual effort in minutes
+    pub actual_minutes: Option<i32>,
+
+    // The number of minutes before the due date to send a reminder, at the due date when absent
+    pub remind_before_minutes: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct SyncOperationResult {
+    // The kind of operation that was applied
+    pub op: SyncOperationKind,
+
+    // The identifier of the affected todo item
+    pub id: Uuid,
+
+    // The resulting state of the todo item, empty when it was deleted
+    pub item: Option<TodoItem>,
+}
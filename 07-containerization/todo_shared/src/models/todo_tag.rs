@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+// The maximum length of a tag, including its namespaces.
+const MAX_TAG_LENGTH: usize = 255;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagFilter {
+    // Matches todo items with exactly this tag
+    Exact(String),
+
+    // Matches todo items with any tag under this namespace, e.g. `project` for `project/*`
+    Namespace(String),
+}
+
+impl TagFilter {
+    /// Checks whether any of the given tags matches this filter.
+    pub fn matches(&self, tags: &[String]) -> bool {
+        match self {
+            TagFilter::Exact(expected) => tags.iter().any(|tag| tag == expected),
+            TagFilter::Namespace(namespace) => tags.iter().any(|tag| {
+                tag.strip_prefix(namespace.as_str())
+                    .is_some_and(|name| name.starts_with('/'))
+            }),
+        }
+    }
+}
+
+impl FromStr for TagFilter {
+    type Err = String;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        match filter.strip_suffix("/*") {
+            Some(namespace) => {
+                validate_tag(namespace)?;
+                Ok(TagFilter::Namespace(namespace.to_string()))
+            }
+            None => {
+                validate_tag(filter)?;
+                Ok(TagFilter::Exact(filter.to_string()))
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema, Validate)]
+pub struct TagStatsRequest {
+    // The tags to return the stats of, exact tags rather than namespaces
+    #[validate(length(min = 1), custom = "validate_tags")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema, Validate)]
+pub struct AddTagRequest {
+    // The tag to add to the todo item, optionally namespaced like `project/alpha`
+    #[validate(custom = "validate_tag_field")]
+    pub tag: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct TagStats {
+    // The tag the stats are for
+    pub tag: String,
+
+    // The number of todo items with the tag
+    pub total: i64,
+
+    // The number of completed todo items with the tag
+    pub completed: i64,
+
+    // The number of incomplete todo items with the tag that were due before today
+    pub overdue: i64,
+}
+
+/// Checks whether a tag is formatted as namespaces and a name separated by slashes, e.g.
+/// `project/alpha`, where every part consists of lowercase letters, digits, dashes or underscores.
+pub fn validate_tag(tag: &str) -> Result<(), String> {
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+    };
+
+    if tag.len() > MAX_TAG_LENGTH || !tag.split('/').all(valid_part) {
+        return Err(format!(
+            "Invalid tag `{}`, expected parts of lowercase letters, digits, `-` or `_` separated by `/`",
+            tag
+        ));
+    }
+    Ok(())
+}
+
+/// Validates all tags of a request, for `#[validate(custom)]`.
+pub fn validate_tags(tags: &[String]) -> Result<(), ValidationError> {
+    tags.iter().try_for_each(|tag| validate_tag_field(tag))
+}
+
+/// Validates a single tag of a request, for `#[validate(custom)]`.
+pub fn validate_tag_field(tag: &str) -> Result<(), ValidationError> {
+    validate_tag(tag).map_err(|reason| {
+        let mut error = ValidationError::new("tag");
+        error.message = Some(reason.into());
+        error
+    })
+}
+
+/// Returns every namespace the given tags are in, e.g. `project` and `project/alpha` for
+/// `project/alpha/design`, so todo items can be found by namespace using an index.
+pub fn tag_namespaces(tags: &[String]) -> Vec<String> {
+    let mut namespaces: Vec<String> = tags
+        .iter()
+        .flat_map(|tag| {
+            tag.match_indices('/')
+                .map(|(index, _)| tag[..index].to_string())
+        })
+        .collect();
+    namespaces.sort();
+    namespaces.dedup();
+    namespaces
+}